@@ -0,0 +1,281 @@
+//! Snake body storage compared: the `TinyDeque` bodies used to be, the inline ring
+//! `Body` they are now, and a shared arena with one fixed-stride ring per snake.
+//!
+//! Each representation holds `SNAKES` bodies and runs three operations over all of them:
+//! move (new head, drop tail), grow (new head only) and tail queries (read head and
+//! tail). Short snakes fit `Body`'s inline ring; long ones spill it to the heap. On Linux,
+//! with perf events allowed, every case also prints last-level cache accesses and misses
+//! per snake for one pass.
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use high_frequency_snake::game::body::Body;
+use high_frequency_snake::game::types::{Direction, Point};
+use std::hint::black_box;
+use tinydeque::TinyDeque;
+
+const SNAKES: usize = 1000;
+/// (name, cells per snake): inside and far past `body::INLINE_CELLS`
+const LENGTHS: [(&str, usize); 2] = [("short", 4), ("long", 200)];
+/// Cells each snake gains in the grow case
+const GROWTH: usize = 8;
+
+/// One storage scheme for a population of bodies
+trait Bodies {
+    const NAME: &'static str;
+
+    /// `SNAKES` bodies of `len` cells, laid out in rows, heads last
+    fn with_bodies(len: usize) -> Self;
+
+    /// Deep copy for setups
+    fn duplicate(&self) -> Self;
+
+    fn head(&self, snake: usize) -> Point;
+
+    fn tail(&self, snake: usize) -> Point;
+
+    fn push_head(&mut self, snake: usize, point: Point);
+
+    fn pop_tail(&mut self, snake: usize);
+
+    /// Step every snake one cell right
+    fn advance(&mut self) {
+        for snake in 0..SNAKES {
+            let next = self.head(snake).step(Direction::Right);
+            self.push_head(snake, next);
+            self.pop_tail(snake);
+        }
+    }
+
+    /// Give every snake a new head, keeping its tail
+    fn grow(&mut self) {
+        for snake in 0..SNAKES {
+            let next = self.head(snake).step(Direction::Right);
+            self.push_head(snake, next);
+        }
+    }
+
+    /// Sum of head and tail coordinates, so no read is optimised out
+    fn query_ends(&self) -> u64 {
+        (0..SNAKES)
+            .map(|snake| {
+                let (head, tail) = (self.head(snake), self.tail(snake));
+                head.x as u64 + head.y as u64 + tail.x as u64 + tail.y as u64
+            })
+            .sum()
+    }
+}
+
+fn start_cell(snake: usize, index: usize) -> Point {
+    Point { x: index as u16, y: (snake * 2) as u16 }
+}
+
+impl Bodies for Vec<TinyDeque<[Point; 16]>> {
+    const NAME: &'static str = "tinydeque";
+
+    fn with_bodies(len: usize) -> Self {
+        (0..SNAKES)
+            .map(|snake| {
+                let mut body = TinyDeque::new();
+                (0..len).for_each(|index| body.push_back(start_cell(snake, index)));
+                body
+            })
+            .collect()
+    }
+
+    // TinyDeque 0.1 clones inline deques through its broken iterator, which empties them
+    fn duplicate(&self) -> Self {
+        self.iter()
+            .map(|body| {
+                let mut copy = TinyDeque::new();
+                (0..body.len()).for_each(|index| copy.push_back(*body.get(index).unwrap()));
+                copy
+            })
+            .collect()
+    }
+
+    // TinyDeque 0.1 has no front/back and iterates inline deques as empty; get() is sound
+    fn head(&self, snake: usize) -> Point {
+        let body = &self[snake];
+        *body.get(body.len() - 1).unwrap()
+    }
+
+    fn tail(&self, snake: usize) -> Point {
+        *self[snake].get(0).unwrap()
+    }
+
+    fn push_head(&mut self, snake: usize, point: Point) {
+        self[snake].push_back(point);
+    }
+
+    fn pop_tail(&mut self, snake: usize) {
+        self[snake].pop_front();
+    }
+}
+
+impl Bodies for Vec<Body> {
+    const NAME: &'static str = "ring_body";
+
+    fn with_bodies(len: usize) -> Self {
+        (0..SNAKES).map(|snake| (0..len).map(|index| start_cell(snake, index)).collect()).collect()
+    }
+
+    fn duplicate(&self) -> Self {
+        self.clone()
+    }
+
+    fn head(&self, snake: usize) -> Point {
+        *self[snake].back().unwrap()
+    }
+
+    fn tail(&self, snake: usize) -> Point {
+        *self[snake].front().unwrap()
+    }
+
+    fn push_head(&mut self, snake: usize, point: Point) {
+        self[snake].push_back(point);
+    }
+
+    fn pop_tail(&mut self, snake: usize) {
+        self[snake].pop_front();
+    }
+}
+
+/// Every body in one allocation: snake `i` owns the ring at `cells[i * stride..][..stride]`,
+/// with `stride` a power of two fixed up front. Heads and tails of all snakes sit in one
+/// array, at the price of reserving the longest allowed body for every snake.
+#[derive(Clone)]
+struct Arena {
+    cells: Vec<Point>,
+    stride: usize,
+    starts: Vec<u32>,
+    lens: Vec<u32>,
+}
+
+impl Arena {
+    #[inline(always)]
+    fn slot(&self, snake: usize, index: usize) -> usize {
+        snake * self.stride + ((self.starts[snake] as usize + index) & (self.stride - 1))
+    }
+}
+
+impl Bodies for Arena {
+    const NAME: &'static str = "shared_arena";
+
+    fn with_bodies(len: usize) -> Self {
+        let stride = (len + GROWTH).next_power_of_two();
+        let mut arena = Arena {
+            cells: vec![Point::default(); SNAKES * stride],
+            stride,
+            starts: vec![0; SNAKES],
+            lens: vec![len as u32; SNAKES],
+        };
+        for snake in 0..SNAKES {
+            for index in 0..len {
+                let slot = arena.slot(snake, index);
+                arena.cells[slot] = start_cell(snake, index);
+            }
+        }
+        arena
+    }
+
+    fn duplicate(&self) -> Self {
+        self.clone()
+    }
+
+    fn head(&self, snake: usize) -> Point {
+        self.cells[self.slot(snake, self.lens[snake] as usize - 1)]
+    }
+
+    fn tail(&self, snake: usize) -> Point {
+        self.cells[self.slot(snake, 0)]
+    }
+
+    fn push_head(&mut self, snake: usize, point: Point) {
+        assert!((self.lens[snake] as usize) < self.stride, "arena slot full");
+        let slot = self.slot(snake, self.lens[snake] as usize);
+        self.cells[slot] = point;
+        self.lens[snake] += 1;
+    }
+
+    fn pop_tail(&mut self, snake: usize) {
+        self.starts[snake] = ((self.starts[snake] as usize + 1) & (self.stride - 1)) as u32;
+        self.lens[snake] -= 1;
+    }
+}
+
+fn compare<B: Bodies>(c: &mut Criterion) {
+    for (scenario, len) in LENGTHS {
+        let mut group = c.benchmark_group(format!("body_{}", scenario));
+        let bodies = B::with_bodies(len);
+
+        group.bench_function(format!("{}_move", B::NAME), |b| {
+            let mut bodies = bodies.duplicate();
+            b.iter(|| black_box(&mut bodies).advance());
+        });
+        group.bench_function(format!("{}_grow", B::NAME), |b| {
+            b.iter_batched_ref(
+                || bodies.duplicate(),
+                |bodies| (0..GROWTH).for_each(|_| black_box(&mut *bodies).grow()),
+                BatchSize::SmallInput,
+            );
+        });
+        group.bench_function(format!("{}_tail_query", B::NAME), |b| {
+            b.iter(|| black_box(black_box(&bodies).query_ends()));
+        });
+
+        group.finish();
+
+        counters::report(&format!("body_{}/{}_move", scenario, B::NAME), &mut bodies.duplicate(), |bodies| bodies.advance());
+        counters::report(&format!("body_{}/{}_grow", scenario, B::NAME), &mut bodies.duplicate(), |bodies| bodies.grow());
+        counters::report(&format!("body_{}/{}_tail_query", scenario, B::NAME), &mut bodies.duplicate(), |bodies| {
+            black_box(bodies.query_ends());
+        });
+    }
+}
+
+fn body_bench(c: &mut Criterion) {
+    compare::<Vec<TinyDeque<[Point; 16]>>>(c);
+    compare::<Vec<Body>>(c);
+    compare::<Arena>(c);
+}
+
+// Last-level cache counters come from perf_event_open, which only exists on Linux
+#[cfg(target_os = "linux")]
+mod counters {
+    use super::SNAKES;
+    use perf_event_open::config::{Cpu, Opts, Proc};
+    use perf_event_open::count::Counter;
+    use perf_event_open::event::hw::Hardware;
+
+    /// Run `pass` once on warm `bodies` with cache counters on and print them per snake.
+    /// Silent when counters are unavailable (containers, perf_event_paranoid).
+    pub fn report<B>(name: &str, bodies: &mut B, mut pass: impl FnMut(&mut B)) {
+        let target = (Proc::CURRENT, Cpu::ALL);
+        let (Ok(accesses), Ok(misses)) = (
+            Counter::new(Hardware::CacheAccess, target, Opts::default()),
+            Counter::new(Hardware::CacheMiss, target, Opts::default()),
+        ) else {
+            return;
+        };
+        pass(bodies);
+        let (Ok(()), Ok(())) = (accesses.enable(), misses.enable()) else { return };
+        pass(bodies);
+        let _ = (accesses.disable(), misses.disable());
+        if let (Ok(accesses), Ok(misses)) = (accesses.stat(), misses.stat()) {
+            println!(
+                "{}: {:.2} cache accesses, {:.2} misses per snake",
+                name,
+                accesses.count as f64 / SNAKES as f64,
+                misses.count as f64 / SNAKES as f64
+            );
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod counters {
+    pub fn report<B>(_name: &str, _bodies: &mut B, _pass: impl FnMut(&mut B)) {}
+}
+
+criterion_group!(benches, body_bench);
+criterion_main!(benches);
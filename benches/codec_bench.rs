@@ -0,0 +1,66 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use high_frequency_snake::game::{
+    codec::{GridCodec, decode_grid, encode_grid},
+    grid::{Cell, Grid, GRID_HEIGHT, GRID_WIDTH},
+    types::Point,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::hint::black_box;
+
+// Occupied cells per million: 0.1%, 1% and 10% of the grid
+const DENSITIES_PPM: [u32; 3] = [1_000, 10_000, 100_000];
+
+/// Grid with roughly `ppm` occupied cells per million, nine snake cells to one apple,
+/// scattered uniformly (the worst case for run-length encoding)
+fn filled_grid(ppm: u32) -> Grid {
+    let mut grid = Grid::new();
+    let mut rng = StdRng::seed_from_u64(ppm as u64);
+    let cells = (GRID_WIDTH * GRID_HEIGHT) as u64 * ppm as u64 / 1_000_000;
+    for i in 0..cells {
+        let point = Point {
+            x: rng.random_range(0..GRID_WIDTH as u16),
+            y: rng.random_range(0..GRID_HEIGHT as u16),
+        };
+        grid.set_cell(point, if i % 10 == 0 { Cell::Apple } else { Cell::Snake });
+    }
+    grid
+}
+
+/// Encode and decode time of every codec at several fill densities; encoded sizes are printed
+fn grid_codec_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("grid_codec");
+    // Each iteration walks all 100M cells
+    group.sample_size(10);
+
+    for ppm in DENSITIES_PPM {
+        let grid = filled_grid(ppm);
+        for &codec in GridCodec::ALL {
+            let mut encoded = Vec::new();
+            encode_grid(&grid, codec, &mut encoded).unwrap();
+            println!(
+                "{} at {} ppm: {} bytes ({} bytes raw)",
+                codec.name(),
+                ppm,
+                encoded.len(),
+                GRID_WIDTH * GRID_HEIGHT
+            );
+
+            group.bench_function(format!("encode_{}_{}ppm", codec.name(), ppm), |b| {
+                let mut out = Vec::with_capacity(encoded.len());
+                b.iter(|| {
+                    out.clear();
+                    encode_grid(black_box(&grid), codec, &mut out).unwrap();
+                });
+            });
+            group.bench_function(format!("decode_{}_{}ppm", codec.name(), ppm), |b| {
+                b.iter(|| decode_grid(codec, &mut black_box(encoded.as_slice())).unwrap());
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, grid_codec_bench);
+criterion_main!(benches);
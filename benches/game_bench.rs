@@ -1,13 +1,25 @@
 use criterion::{Criterion, criterion_group, criterion_main, BatchSize};
 use high_frequency_snake::game::{
-    engine::GameState,
-    generator::{DeterministicGenerator, DeterministicConfig},
-    types::{Direction, Input},
+    config::GameConfig,
+    engine::{GameState, MovementRecord, NUM_BUCKETS, bucket_index},
+    fixedpoint::Fixed,
+    generator::{DeterministicGenerator, DeterministicConfig, LayoutPattern, RandomGenerator},
+    grid::{Cell, Grid, GRID_HEIGHT, GRID_WIDTH},
+    scatter::RecordScatter,
+    scenario::Scenario,
+    snake::GridAwareSnake,
+    spawn::SpawnConfig,
+    tick_engine::{DirectReads, GatheredReads, Legacy, TickEngine},
+    types::{Direction, Input, Point},
 };
+use high_frequency_snake::game::view::view_tensor_len;
+use high_frequency_snake::input::bot::{DifficultyMix, TieredBots};
+use high_frequency_snake::input::source::from_fn;
 use rand::Rng;
 use std::hint::black_box;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-const NUM_TICKS: usize = 1000;
 const MIN_SNAKES: usize = 100;
 const MAX_SNAKES: usize = 1000;
 const SNAKE_STEP: usize = 100;
@@ -30,16 +42,16 @@ fn game_tick_no_inputs_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("game_tick_no_inputs");
 
     for num_snakes in (MIN_SNAKES..=MAX_SNAKES).step_by(SNAKE_STEP) {
-        group.bench_function(&format!("{}_snakes", num_snakes), |b| {
+        group.bench_function(format!("{}_snakes", num_snakes), |b| {
             // Setup outside measurement
-            let mut game_state = GameState::random();
-            while game_state.snakes.len() < num_snakes {
-                game_state = GameState::random();
-            }
+            let mut game_state = GameState::random_with_config(GameConfig {
+                snake_capacity: num_snakes,
+                ..GameConfig::default()
+            });
             
             // Measure only the game.tick() call
             b.iter(|| {
-                black_box(game_state.tick(&[]));
+                game_state.tick(black_box(&[]));
             });
         });
     }
@@ -52,19 +64,19 @@ fn game_tick_light_inputs_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("game_tick_light_inputs");
 
     for num_snakes in (MIN_SNAKES..=MAX_SNAKES).step_by(SNAKE_STEP) {
-        group.bench_function(&format!("{}_snakes", num_snakes), |b| {
+        group.bench_function(format!("{}_snakes", num_snakes), |b| {
             // Setup outside measurement
-            let mut game_state = GameState::random();
-            while game_state.snakes.len() < num_snakes {
-                game_state = GameState::random();
-            }
+            let mut game_state = GameState::random_with_config(GameConfig {
+                snake_capacity: num_snakes,
+                ..GameConfig::default()
+            });
             
             // Generate inputs outside measurement
             let inputs = generate_random_inputs(num_snakes, 0.1);
             
             // Measure only the game.tick() call
             b.iter(|| {
-                black_box(game_state.tick(&inputs));
+                game_state.tick(black_box(&inputs));
             });
         });
     }
@@ -77,19 +89,19 @@ fn game_tick_heavy_inputs_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("game_tick_heavy_inputs");
 
     for num_snakes in (MIN_SNAKES..=MAX_SNAKES).step_by(SNAKE_STEP) {
-        group.bench_function(&format!("{}_snakes", num_snakes), |b| {
+        group.bench_function(format!("{}_snakes", num_snakes), |b| {
             // Setup outside measurement
-            let mut game_state = GameState::random();
-            while game_state.snakes.len() < num_snakes {
-                game_state = GameState::random();
-            }
+            let mut game_state = GameState::random_with_config(GameConfig {
+                snake_capacity: num_snakes,
+                ..GameConfig::default()
+            });
             
             // Generate inputs outside measurement
             let inputs = generate_random_inputs(num_snakes, 0.5);
             
             // Measure only the game.tick() call
             b.iter(|| {
-                black_box(game_state.tick(&inputs));
+                game_state.tick(black_box(&inputs));
             });
         });
     }
@@ -102,19 +114,19 @@ fn game_tick_max_inputs_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("game_tick_max_inputs");
 
     for num_snakes in (MIN_SNAKES..=MAX_SNAKES).step_by(SNAKE_STEP) {
-        group.bench_function(&format!("{}_snakes", num_snakes), |b| {
+        group.bench_function(format!("{}_snakes", num_snakes), |b| {
             // Setup outside measurement
-            let mut game_state = GameState::random();
-            while game_state.snakes.len() < num_snakes {
-                game_state = GameState::random();
-            }
+            let mut game_state = GameState::random_with_config(GameConfig {
+                snake_capacity: num_snakes,
+                ..GameConfig::default()
+            });
             
             // Generate inputs outside measurement
             let inputs = generate_random_inputs(num_snakes, 1.0);
             
             // Measure only the game.tick() call
             b.iter(|| {
-                black_box(game_state.tick(&inputs));
+                game_state.tick(black_box(&inputs));
             });
         });
     }
@@ -127,19 +139,17 @@ fn game_tick_latency_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("game_tick_latency");
 
     for num_snakes in [100, 500, 1000] {
-        group.bench_function(&format!("{}_snakes_single_tick", num_snakes), |b| {
-            let mut game_state = GameState::random();
-            
-            // Ensure we have the target number of snakes
-            while game_state.snakes.len() < num_snakes {
-                game_state = GameState::random();
-            }
+        group.bench_function(format!("{}_snakes_single_tick", num_snakes), |b| {
+            let mut game_state = GameState::random_with_config(GameConfig {
+                snake_capacity: num_snakes,
+                ..GameConfig::default()
+            });
             
             // Generate inputs for 25% of snakes
             let inputs = generate_random_inputs(num_snakes, 0.25);
             
             b.iter(|| {
-                black_box(game_state.tick(&inputs));
+                game_state.tick(black_box(&inputs));
             });
         });
     }
@@ -166,6 +176,32 @@ fn game_state_init_bench(c: &mut Criterion) {
     group.finish();
 }
 
+/// Setup cost of the generators across snake counts and layouts
+fn generator_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generator");
+    group.sample_size(20);
+
+    for num_snakes in [MIN_SNAKES, MAX_SNAKES, 10 * MAX_SNAKES] {
+        for (layout, pattern) in [("grid", LayoutPattern::Grid), ("concentric", LayoutPattern::Concentric)] {
+            let config = DeterministicConfig {
+                layout_pattern: pattern,
+                game: GameConfig { snake_capacity: num_snakes, ..GameConfig::default() },
+                ..DeterministicConfig::default()
+            };
+            group.bench_function(format!("deterministic_{}/{}_snakes", layout, num_snakes), |b| {
+                b.iter(|| black_box(DeterministicGenerator::generate(num_snakes, config)));
+            });
+        }
+
+        let config = GameConfig { snake_capacity: num_snakes, ..GameConfig::default() };
+        group.bench_function(format!("random/{}_snakes", num_snakes), |b| {
+            b.iter(|| black_box(RandomGenerator::generate_parallel(config, SpawnConfig::default(), 42)));
+        });
+    }
+
+    group.finish();
+}
+
 /// Generate deterministic inputs for predictable outcomes
 fn generate_deterministic_inputs(num_snakes: usize, num_ticks: usize) -> Vec<Input> {
     let mut inputs = Vec::new();
@@ -215,7 +251,7 @@ fn hot_path_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("hot_path");
 
     for num_snakes in (MIN_SNAKES..=MAX_SNAKES).step_by(SNAKE_STEP) {
-        group.bench_function(&format!("{}_snakes", num_snakes), |b| {
+        group.bench_function(format!("{}_snakes", num_snakes), |b| {
             // Generate deterministic inputs outside measurement
             let inputs = generate_deterministic_inputs(num_snakes, 1);
             
@@ -227,7 +263,7 @@ fn hot_path_bench(c: &mut Criterion) {
                 },
                 |game_state| {
                     // Benchmark function - IS measured
-                    black_box(game_state.tick(&inputs));
+                    game_state.tick(black_box(&inputs));
                 },
                 BatchSize::LargeInput,
             );
@@ -237,6 +273,332 @@ fn hot_path_bench(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark bot decisions plus tick() for different difficulty mixes. Smarter bots live
+/// longer and eat more, so the mix shapes the population the tick works on.
+fn bot_mix_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bot_mix");
+    let mixes = [
+        ("random", DifficultyMix { random: 1, greedy: 0, lookahead: 0 }),
+        ("greedy", DifficultyMix { random: 1, greedy: 3, lookahead: 0 }),
+        ("lookahead", DifficultyMix { random: 1, greedy: 1, lookahead: 2 }),
+    ];
+
+    for (name, mix) in mixes {
+        group.bench_function(format!("{}_{}_snakes", name, MAX_SNAKES), |b| {
+            let mut game_state = GameState::seeded(GameConfig {
+                snake_capacity: MAX_SNAKES,
+                ..GameConfig::default()
+            });
+            let mut bots = TieredBots::new(0..MAX_SNAKES as u32, mix, Fixed::from_ratio(1, 10), 42);
+            let mut inputs = Vec::new();
+
+            b.iter(|| {
+                inputs.clear();
+                bots.decide(game_state.view(), &mut inputs);
+                game_state.tick(black_box(&inputs));
+                game_state.respawn_dead(MAX_SNAKES);
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark encoding one view tensor per snake, as the RL env does every step
+fn view_tensor_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("view_tensor");
+    let game_state = GameState::seeded(GameConfig {
+        snake_capacity: MAX_SNAKES,
+        ..GameConfig::default()
+    });
+
+    for radius in [3u16, 7, 15] {
+        group.bench_function(format!("radius_{}_{}_snakes", radius, MAX_SNAKES), |b| {
+            let mut out = vec![0u8; view_tensor_len(radius)];
+            b.iter(|| {
+                for snake in &game_state.snakes {
+                    if let Some(head) = snake.head() {
+                        game_state.extract_view_tensor(*head, radius, snake.id(), &mut out);
+                    }
+                }
+                black_box(&out);
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark tick_n against the same ticks driven one tick() call at a time
+fn game_tick_n_bench(c: &mut Criterion) {
+    const TICKS: u64 = 100;
+    let mut group = c.benchmark_group("game_tick_n");
+    let inputs = generate_random_inputs(MAX_SNAKES, 0.1);
+
+    group.bench_function(format!("loop_{}_ticks_{}_snakes", TICKS, MAX_SNAKES), |b| {
+        let mut game_state = GameState::random_with_config(GameConfig {
+            snake_capacity: MAX_SNAKES,
+            ..GameConfig::default()
+        });
+        let mut batch = Vec::new();
+        b.iter(|| {
+            for _ in 0..TICKS {
+                batch.clear();
+                batch.extend_from_slice(&inputs);
+                game_state.tick(black_box(&batch));
+            }
+        });
+    });
+
+    group.bench_function(format!("tick_n_{}_ticks_{}_snakes", TICKS, MAX_SNAKES), |b| {
+        let mut game_state = GameState::random_with_config(GameConfig {
+            snake_capacity: MAX_SNAKES,
+            ..GameConfig::default()
+        });
+        let mut source = from_fn(|_, buf: &mut Vec<Input>| buf.extend_from_slice(&inputs));
+        b.iter(|| game_state.tick_n(TICKS, 0, black_box(&mut source)));
+    });
+
+    group.finish();
+}
+
+/// Benchmark the tick that follows speculate() against a plain tick with the same inputs
+fn speculative_tick_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("speculative_tick");
+    let inputs = generate_random_inputs(MAX_SNAKES, 0.1);
+
+    for speculate in [false, true] {
+        let name = if speculate { "speculated" } else { "plain" };
+        group.bench_function(format!("{}_{}_snakes", name, MAX_SNAKES), |b| {
+            let mut game_state = GameState::random_with_config(GameConfig {
+                snake_capacity: MAX_SNAKES,
+                ..GameConfig::default()
+            });
+            // Speculation happens while idle, so only the tick itself is measured
+            b.iter_custom(|iters| {
+                let mut total = std::time::Duration::ZERO;
+                for _ in 0..iters {
+                    if speculate {
+                        game_state.speculate();
+                    }
+                    let start = std::time::Instant::now();
+                    game_state.tick(black_box(&inputs));
+                    total += start.elapsed();
+                }
+                total
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark clearing one tick's worth of tails cell by cell against the tile-batched
+/// clear_cells. Tails are random cells in one bucket's row band, like a tick's tail bucket.
+fn tail_clear_bench(c: &mut Criterion) {
+    const BAND_ROWS: u16 = 256;
+    let mut group = c.benchmark_group("tail_clear");
+    let mut grid = Grid::new();
+    let mut rng = rand::rng();
+
+    for tails in [32usize, 256, 2048] {
+        let points: Vec<Point> = (0..tails)
+            .map(|_| Point {
+                x: rng.random_range(0..GRID_WIDTH as u16),
+                y: rng.random_range(0..BAND_ROWS.min(GRID_HEIGHT as u16)),
+            })
+            .collect();
+        let mut sorted = points.clone();
+
+        group.bench_function(format!("set_cell_{}_tails", tails), |b| {
+            b.iter_custom(|iters| {
+                let mut total = std::time::Duration::ZERO;
+                for _ in 0..iters {
+                    points.iter().for_each(|point| grid.set_cell(*point, Cell::Snake));
+                    let start = std::time::Instant::now();
+                    for point in black_box(&points) {
+                        grid.set_cell(*point, Cell::Empty);
+                    }
+                    total += start.elapsed();
+                }
+                total
+            });
+        });
+
+        // Sorting is part of the cost, as in tick()
+        group.bench_function(format!("clear_cells_{}_tails", tails), |b| {
+            b.iter_custom(|iters| {
+                let mut total = std::time::Duration::ZERO;
+                for _ in 0..iters {
+                    points.iter().for_each(|point| grid.set_cell(*point, Cell::Snake));
+                    sorted.copy_from_slice(&points);
+                    let start = std::time::Instant::now();
+                    sorted.sort_unstable_by_key(Grid::write_order);
+                    black_box(grid.clear_cells(black_box(&sorted)));
+                    total += start.elapsed();
+                }
+                total
+            });
+        });
+    }
+
+    // The whole tick, where the grid competes for cache with everything else
+    let inputs = generate_random_inputs(MAX_SNAKES, 0.1);
+    for combine in [false, true] {
+        let name = if combine { "tick_combined" } else { "tick_per_cell" };
+        group.bench_function(format!("{}_{}_snakes", name, MAX_SNAKES), |b| {
+            let mut game_state = GameState::random_with_config(GameConfig {
+                snake_capacity: MAX_SNAKES,
+                ..GameConfig::default()
+            });
+            game_state.set_tail_write_combining(combine);
+            b.iter(|| {
+                game_state.tick(black_box(&inputs));
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark building the movement records (phase 2, run alone by speculate()) from the
+/// dense motion table against the snake structs, and the whole tick both ways
+fn motion_table_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("motion_table");
+    let inputs = generate_random_inputs(MAX_SNAKES, 0.1);
+
+    for dense in [false, true] {
+        let name = if dense { "dense" } else { "structs" };
+        let mut game_state = GameState::random_with_config(GameConfig {
+            snake_capacity: MAX_SNAKES,
+            ..GameConfig::default()
+        });
+        game_state.set_dense_motion(dense);
+        group.bench_function(format!("records_{}_{}_snakes", name, MAX_SNAKES), |b| {
+            b.iter(|| {
+                game_state.discard_speculation();
+                game_state.speculate();
+            });
+        });
+        game_state.discard_speculation();
+        group.bench_function(format!("tick_{}_{}_snakes", name, MAX_SNAKES), |b| {
+            b.iter(|| {
+                game_state.tick(black_box(&inputs));
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark one tick engine per snake count, each from the same starting state
+fn bench_engine<E: TickEngine>(group: &mut criterion::BenchmarkGroup<criterion::measurement::WallTime>, snakes: usize, inputs: &[Input]) {
+    group.bench_function(format!("{}_{}_snakes", E::NAME, snakes), |b| {
+        let mut game_state = GameState::random_with_config(GameConfig {
+            snake_capacity: snakes,
+            ..GameConfig::default()
+        });
+        E::prepare(&mut game_state);
+        b.iter(|| E::tick(&mut game_state, black_box(inputs)));
+    });
+}
+
+/// Benchmark phase 3 reading cells one record at a time against gathering a bucket's
+/// cells first, with the legacy loop as a baseline
+fn cell_reads_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cell_reads");
+    for snakes in [MAX_SNAKES, 10 * MAX_SNAKES] {
+        let inputs = generate_random_inputs(snakes, 0.1);
+        bench_engine::<DirectReads>(&mut group, snakes, &inputs);
+        bench_engine::<GatheredReads>(&mut group, snakes, &inputs);
+        bench_engine::<Legacy>(&mut group, snakes, &inputs);
+    }
+    group.finish();
+}
+
+/// Baseline for `RecordScatter`: each worker pushes into the bucket `Vec`s under a mutex
+/// per bucket, and the buckets are then sorted by id to get the same order
+fn collect_with_mutexes(snakes: &[GridAwareSnake], workers: usize, buckets: &[Mutex<Vec<MovementRecord>>]) {
+    buckets.iter().for_each(|bucket| bucket.lock().unwrap().clear());
+    std::thread::scope(|scope| {
+        for chunk in snakes.chunks(snakes.len().div_ceil(workers)) {
+            scope.spawn(move || {
+                for snake in chunk.iter().filter(|snake| snake.is_alive()) {
+                    let new_head = snake.calculate_new_head();
+                    let record = MovementRecord::new(snake.id(), new_head, Cell::Empty);
+                    buckets[bucket_index(&new_head)].lock().unwrap().push(record);
+                }
+            });
+        }
+    });
+    buckets.iter().for_each(|bucket| bucket.lock().unwrap().sort_unstable_by_key(|record| record.snake_id()));
+}
+
+/// Benchmark collecting movement records on several threads: the two-pass prefix scatter
+/// against a mutex per bucket. Both spawn their threads inside the measurement.
+fn record_scatter_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("record_scatter");
+    for snakes in [10 * MAX_SNAKES, 100 * MAX_SNAKES] {
+        let game_state = GameState::random_with_config(GameConfig {
+            snake_capacity: snakes,
+            ..GameConfig::default()
+        });
+        for workers in [1, 2, 4] {
+            let mut scatter = RecordScatter::new();
+            group.bench_function(format!("prefix_{}_snakes_{}_workers", snakes, workers), |b| {
+                b.iter(|| scatter.collect(black_box(&game_state.snakes), workers));
+            });
+            let buckets: Vec<Mutex<Vec<MovementRecord>>> = (0..NUM_BUCKETS).map(|_| Mutex::new(Vec::new())).collect();
+            group.bench_function(format!("mutex_{}_snakes_{}_workers", snakes, workers), |b| {
+                b.iter(|| collect_with_mutexes(black_box(&game_state.snakes), workers, &buckets));
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Recorded by `examples/record_workload.rs`: 1000 mixed-difficulty bots after a 2000-tick
+/// warm-up, then 2000 ticks of their inputs with a respawn wave every 50 ticks
+const BOT_RUN: &str = include_str!("data/bot_run.scenario");
+
+/// Benchmark tick() over a recorded bot-driven run. Unlike the synthetic groups the inputs
+/// follow the bots' real turn pattern and the state evolves with them: bodies grow, snakes
+/// die and respawn. Each iteration is the next tick of the run; at the end it restarts
+/// from the recorded state (outside measurement).
+fn replay_workload_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("replay_workload");
+    let scenario = Scenario::read_from(&mut BOT_RUN.as_bytes()).expect("bad bot_run.scenario");
+    let ticks = scenario.ticks as usize;
+    let mut frames: Vec<&[Input]> = vec![&[]; ticks];
+    for frame in &scenario.replay.frames {
+        frames[frame.tick as usize] = &frame.inputs;
+    }
+
+    group.bench_function(format!("bot_run_{}_snakes", scenario.snakes.len()), |b| {
+        let mut game_state = scenario.build();
+        let mut events = scenario.events.clone();
+        let mut tick = 0;
+        b.iter_custom(|iters| {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                if tick == ticks {
+                    game_state = scenario.build();
+                    events = scenario.events.clone();
+                    tick = 0;
+                }
+                events.fire_due(&mut game_state);
+                let start = Instant::now();
+                game_state.tick(black_box(frames[tick]));
+                elapsed += start.elapsed();
+                tick += 1;
+            }
+            elapsed
+        });
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     game_tick_no_inputs_bench,
@@ -245,6 +607,16 @@ criterion_group!(
     game_tick_max_inputs_bench,
     game_tick_latency_bench,
     game_state_init_bench,
-    hot_path_bench
+    generator_bench,
+    hot_path_bench,
+    bot_mix_bench,
+    view_tensor_bench,
+    game_tick_n_bench,
+    speculative_tick_bench,
+    tail_clear_bench,
+    motion_table_bench,
+    cell_reads_bench,
+    record_scatter_bench,
+    replay_workload_bench
 );
 criterion_main!(benches);
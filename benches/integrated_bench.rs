@@ -1,13 +1,15 @@
 use criterion::{Criterion, criterion_group, criterion_main};
 use high_frequency_snake::game::{
+    apple::AppleScaling,
+    config::GameConfig,
     engine::GameState,
-    types::{Direction, Input},
+    types::Input,
 };
+use high_frequency_snake::input::source::{InputSource, QueueSource};
 use high_frequency_snake::ipc::spsc::Spsc;
+use high_frequency_snake::runtime::corescan::{self, CoreScanConfig};
 use rand::Rng;
 use std::hint::black_box;
-use std::sync::mpsc::channel;
-use std::sync::{Arc, Mutex};
 use std::thread;
 
 const QUEUE_CAPACITY: usize = 1024;
@@ -39,17 +41,37 @@ fn hot_path_bench(c: &mut Criterion) {
         println!("Skipping hot path benchmark: at least 2 CPU cores required.");
         return;
     }
-    let core_a = core_ids.as_ref().unwrap()[0];
-    let core_b = core_ids.as_ref().unwrap()[1];
+    // Pin the measuring thread to the game core, as main.rs does: the quietest core of a
+    // scan, or the second listed one with CORE_SCAN=0
+    let game_core = if std::env::var_os("CORE_SCAN").is_some_and(|value| value == "0") {
+        core_ids.as_ref().unwrap()[1]
+    } else {
+        let probe = corescan::tick_probe(GameConfig { snake_capacity: MAX_SNAKES, ..GameConfig::default() }, 100);
+        let scan = corescan::scan(CoreScanConfig::default(), "ns", probe);
+        println!("{}", scan);
+        scan.quietest(1).first().copied().unwrap_or(core_ids.as_ref().unwrap()[1])
+    };
+    println!("Game core: {}", game_core.id);
+    core_affinity::set_for_current(game_core);
+    // APPLE_SCALING=<snakes>,<cells>,<min>,<max>,<every> keeps food pressure steady over long runs
+    let apple_scaling = std::env::var("APPLE_SCALING").ok().map(|spec| {
+        AppleScaling::from_spec(&spec).unwrap_or_else(|| panic!("invalid APPLE_SCALING: {}", spec))
+    });
+    // LEADERBOARD=<size> measures ticks with the standings kept up to date
+    let leaderboard = std::env::var("LEADERBOARD").ok().map(|size| {
+        size.parse::<usize>().unwrap_or_else(|_| panic!("invalid LEADERBOARD: {}", size))
+    });
 
     for num_snakes in (MIN_SNAKES..=MAX_SNAKES).step_by(SNAKE_STEP) {
-        group.bench_function(&format!("{}_snakes", num_snakes), |b| {
+        group.bench_function(format!("{}_snakes", num_snakes), |b| {
             // Setup: Create queue and game state (outside of measurement)
-            let queue = Arc::new(Spsc::<Input, QUEUE_CAPACITY>::new());
-            let mut game_state = GameState::random();
-            while game_state.snakes.len() < num_snakes {
-                game_state = GameState::random();
-            }
+            let (mut queue, consumer) = Spsc::<Input, QUEUE_CAPACITY>::new().split();
+            let mut game_state = GameState::random_with_config(GameConfig {
+                snake_capacity: num_snakes,
+                ..GameConfig::default()
+            });
+            game_state.set_apple_scaling(apple_scaling);
+            game_state.set_leaderboard(leaderboard);
             
             // Pre-fill queue with inputs to simulate continuous operation
             let inputs = generate_random_inputs(num_snakes, 0.25);
@@ -59,22 +81,22 @@ fn hot_path_bench(c: &mut Criterion) {
                 }
             }
             
+            let mut input_source = QueueSource::new(consumer);
             let mut inputs_buffer = Vec::with_capacity(QUEUE_CAPACITY);
+            let mut tick = 0u64;
             
             // Measure only the hot path: consume + tick
             b.iter(|| {
-                let mut input_count = 0;
                 let start = std::time::Instant::now();
                 
                 // Drain the queue (SPSC consume) - this is the hot path
-                while let Some(input) = queue.consume() {
-                    inputs_buffer.push(input);
-                    input_count += 1;
-                }
+                input_source.next_batch(tick, &mut inputs_buffer);
+                let input_count = inputs_buffer.len() as u32;
+                tick += 1;
                 
                 // Process the collected inputs (game.tick()) - this is the hot path
                 if !inputs_buffer.is_empty() {
-                    black_box(game_state.tick(&inputs_buffer));
+                    game_state.tick(black_box(&inputs_buffer));
                     inputs_buffer.clear();
                 }
                 
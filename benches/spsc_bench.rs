@@ -1,6 +1,7 @@
 use criterion::{Criterion, criterion_group, criterion_main};
 use high_frequency_snake::game::types::{Direction, Input};
 use high_frequency_snake::ipc::spsc::Spsc;
+use high_frequency_snake::runtime::corescan::{self, CoreScanConfig};
 use std::hint::black_box;
 use std::sync::mpsc::channel;
 use std::sync::{Arc, Mutex};
@@ -25,12 +26,11 @@ fn spsc_throughput_bench(c: &mut Criterion) {
     group.throughput(criterion::Throughput::Elements(NUM_MESSAGES as u64));
 
     group.bench_function("multi_threaded_throughput", |b| {
-        let queue = Arc::new(Spsc::<Input, QUEUE_CAPACITY>::new());
+        let (mut producer_queue, mut queue) = Spsc::<Input, QUEUE_CAPACITY>::new().split();
         let (start_tx, start_rx) = channel::<()>();
         let (done_tx, done_rx) = channel::<()>();
         let start_rx = Arc::new(Mutex::new(start_rx));
 
-        let producer_queue = Arc::clone(&queue);
         let producer_done_tx = done_tx.clone();
         let producer_start_rx = Arc::clone(&start_rx);
         thread::spawn(move || {
@@ -78,6 +78,24 @@ fn spsc_throughput_bench(c: &mut Criterion) {
     group.finish();
 }
 
+// Two cores with the least run-to-run variance on separate physical cores, unless
+// CORE_SCAN=0
+fn quietest_pair() -> Option<(core_affinity::CoreId, core_affinity::CoreId)> {
+    if std::env::var_os("CORE_SCAN").is_some_and(|value| value == "0") {
+        return None;
+    }
+    let scan = corescan::scan(CoreScanConfig::default(), "ns", || {
+        let start = Instant::now();
+        busy_spin(100_000);
+        start.elapsed().as_nanos() as u64
+    });
+    println!("{}", scan);
+    match scan.quietest(2)[..] {
+        [a, b] => Some((a, b)),
+        _ => None,
+    }
+}
+
 // Prolly more accurate
 fn spsc_latency_bench(c: &mut Criterion) {
     let mut group = c.benchmark_group("spsc_latency");
@@ -87,15 +105,15 @@ fn spsc_latency_bench(c: &mut Criterion) {
         println!("Skipping latency test: at least 2 CPU cores required.");
         return;
     }
-    let core_a = core_ids.as_ref().unwrap()[0];
-    let core_b = core_ids.as_ref().unwrap()[1];
+    // The two quietest cores of a scan, or the first two listed with CORE_SCAN=0
+    let (core_a, core_b) = match quietest_pair() {
+        Some(pair) => pair,
+        None => (core_ids.as_ref().unwrap()[0], core_ids.as_ref().unwrap()[1]),
+    };
 
     group.bench_function("ping_pong_rtt", |b| {
-        let ping_queue = Arc::new(Spsc::<Instant, QUEUE_CAPACITY>::new());
-        let pong_queue = Arc::new(Spsc::<Instant, QUEUE_CAPACITY>::new());
-
-        let pong_ping_queue = Arc::clone(&ping_queue);
-        let pong_pong_queue = Arc::clone(&pong_queue);
+        let (mut ping_queue, mut pong_ping_queue) = Spsc::<Instant, QUEUE_CAPACITY>::new().split();
+        let (mut pong_pong_queue, mut pong_queue) = Spsc::<Instant, QUEUE_CAPACITY>::new().split();
         thread::spawn(move || {
             core_affinity::set_for_current(core_b);
             loop {
@@ -136,12 +154,11 @@ fn spsc_contention_bench(c: &mut Criterion) {
 
     // --- Producer is faster than the consumer (queue is often full) ---
     group.bench_function("producer_faster_consumer_slower", |b| {
-        let queue = Arc::new(Spsc::<Input, QUEUE_CAPACITY>::new());
+        let (mut producer_queue, mut queue) = Spsc::<Input, QUEUE_CAPACITY>::new().split();
         let (start_tx, start_rx) = channel::<()>();
         let (done_tx, done_rx) = channel::<()>();
         let start_rx = Arc::new(Mutex::new(start_rx));
 
-        let producer_queue = Arc::clone(&queue);
         let producer_done_tx = done_tx.clone();
         let producer_start_rx = Arc::clone(&start_rx);
         thread::spawn(move || {
@@ -189,12 +206,11 @@ fn spsc_contention_bench(c: &mut Criterion) {
 
     // --- Consumer is faster than the producer (queue is often empty) ---
     group.bench_function("consumer_faster_producer_slower", |b| {
-        let queue = Arc::new(Spsc::<Input, QUEUE_CAPACITY>::new());
+        let (mut producer_queue, mut queue) = Spsc::<Input, QUEUE_CAPACITY>::new().split();
         let (start_tx, start_rx) = channel::<()>();
         let (done_tx, done_rx) = channel::<()>();
         let start_rx = Arc::new(Mutex::new(start_rx));
 
-        let producer_queue = Arc::clone(&queue);
         let producer_done_tx = done_tx.clone();
         let producer_start_rx = Arc::clone(&start_rx);
         thread::spawn(move || {
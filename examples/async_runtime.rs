@@ -0,0 +1,69 @@
+//! Embeds the engine in a tokio service: the game loop runs on a pinned core while
+//! simulated network clients, a metrics logger and an admin endpoint run as async tasks.
+//!
+//! cargo run --release --example async_runtime --features tokio-runtime
+//! curl http://127.0.0.1:7878/
+
+use high_frequency_snake::game::types::Input;
+use high_frequency_snake::runtime::async_runtime::{AsyncEngineConfig, render_stats, serve_admin, spawn_engine};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+const ADMIN_ADDR: &str = "127.0.0.1:7878";
+const CLIENTS: u64 = 4;
+const RUN_SECS: u64 = 10;
+
+fn main() -> std::io::Result<()> {
+    // Last core for the game loop, the rest for the async workers
+    let game_core = core_affinity::get_core_ids().and_then(|cores| cores.last().copied());
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_all()
+        .build()?;
+
+    runtime.block_on(async {
+        let engine = spawn_engine(AsyncEngineConfig {
+            game_core,
+            ..AsyncEngineConfig::default()
+        })?;
+        let num_snakes = AsyncEngineConfig::default().game.snake_capacity as u32;
+
+        let listener = TcpListener::bind(ADMIN_ADDR).await?;
+        println!("Admin endpoint on http://{}/", ADMIN_ADDR);
+        tokio::spawn(serve_admin(listener, engine.stats()));
+
+        // Stand-ins for network connections, each steering random snakes
+        for client in 0..CLIENTS {
+            let inputs = engine.inputs();
+            tokio::spawn(async move {
+                let mut rng = StdRng::seed_from_u64(client);
+                loop {
+                    let input = Input {
+                        snake_id: rng.random_range(0..num_snakes),
+                        direction: rng.random(),
+                    };
+                    if inputs.send(input).await.is_err() {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_micros(100)).await;
+                }
+            });
+        }
+
+        let mut stats = engine.stats();
+        for _ in 0..RUN_SECS {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            print!("{}", render_stats(&stats.borrow_and_update()));
+        }
+
+        match tokio::task::spawn_blocking(move || engine.shutdown()).await {
+            Ok(Err(failure)) => eprintln!("{} thread panicked: {}", failure.worker, failure.message),
+            Err(error) => eprintln!("shutdown task failed: {}", error),
+            Ok(Ok(())) => {}
+        }
+        Ok(())
+    })
+}
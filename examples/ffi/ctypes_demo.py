@@ -0,0 +1,61 @@
+"""Drive the engine from Python through the C ABI (src/ffi.rs).
+
+    cargo build --release
+    python3 examples/ffi/ctypes_demo.py target/release/libhigh_frequency_snake.so
+"""
+import ctypes
+import random
+import sys
+
+
+class HfsPoint(ctypes.Structure):
+    _fields_ = [("x", ctypes.c_uint16), ("y", ctypes.c_uint16)]
+
+
+class HfsSnakeInfo(ctypes.Structure):
+    _fields_ = [
+        ("alive", ctypes.c_uint8),
+        ("direction", ctypes.c_uint8),
+        ("length", ctypes.c_uint32),
+        ("head", HfsPoint),
+    ]
+
+
+class HfsPopulation(ctypes.Structure):
+    _fields_ = [
+        ("alive", ctypes.c_uint32),
+        ("total_body_cells", ctypes.c_uint64),
+        ("total_births", ctypes.c_uint64),
+        ("total_deaths", ctypes.c_uint64),
+        ("num_apples", ctypes.c_uint64),
+    ]
+
+
+def main(library_path):
+    lib = ctypes.CDLL(library_path)
+    lib.hfs_game_create.restype = ctypes.c_void_p
+    lib.hfs_game_create.argtypes = [ctypes.c_uint32, ctypes.c_uint32, ctypes.c_uint64]
+    for name in ("hfs_game_destroy", "hfs_game_tick"):
+        getattr(lib, name).argtypes = [ctypes.c_void_p]
+    lib.hfs_game_push_input.argtypes = [ctypes.c_void_p, ctypes.c_uint32, ctypes.c_uint8]
+    lib.hfs_game_snake_info.argtypes = [ctypes.c_void_p, ctypes.c_uint32, ctypes.POINTER(HfsSnakeInfo)]
+    lib.hfs_game_population.argtypes = [ctypes.c_void_p, ctypes.POINTER(HfsPopulation)]
+
+    game = lib.hfs_game_create(64, 16, 42)
+    rng = random.Random(42)
+    for _ in range(1000):
+        for _ in range(8):
+            lib.hfs_game_push_input(game, rng.randrange(64), rng.randrange(4))
+        lib.hfs_game_tick(game)
+
+    info = HfsSnakeInfo()
+    lib.hfs_game_snake_info(game, 0, ctypes.byref(info))
+    population = HfsPopulation()
+    lib.hfs_game_population(game, ctypes.byref(population))
+    print(f"snake 0: alive={info.alive} length={info.length} head=({info.head.x}, {info.head.y})")
+    print(f"alive={population.alive} deaths={population.total_deaths} apples={population.num_apples}")
+    lib.hfs_game_destroy(game)
+
+
+if __name__ == "__main__":
+    main(sys.argv[1] if len(sys.argv) > 1 else "target/release/libhigh_frequency_snake.so")
@@ -0,0 +1,77 @@
+//! Records the bot-driven workload `game_bench`'s `replay_workload` group replays.
+//!
+//! Runs mixed-difficulty bots until the population settles, then captures the state and
+//! the next stretch of bot inputs as a scenario. Respawns in the recorded stretch become
+//! scheduled respawn waves, so the scenario alone reproduces the run.
+//!
+//! cargo run --release --example record_workload [-- <out>]
+
+use high_frequency_snake::game::config::GameConfig;
+use high_frequency_snake::game::engine::GameState;
+use high_frequency_snake::game::events::{EventSchedule, ScheduledEvent, WorldEvent};
+use high_frequency_snake::game::fixedpoint::Fixed;
+use high_frequency_snake::game::scenario::Scenario;
+use high_frequency_snake::game::types::Input;
+use high_frequency_snake::input::bot::{DifficultyMix, TieredBots};
+use high_frequency_snake::input::replay::Replay;
+
+const OUT: &str = "benches/data/bot_run.scenario";
+const SNAKES: usize = 1000;
+const WARMUP_TICKS: u64 = 2000;
+const RECORDED_TICKS: u64 = 2000;
+const RESPAWN_EVERY: u64 = 50;
+
+fn main() -> std::io::Result<()> {
+    let out = std::env::args().nth(1).unwrap_or_else(|| OUT.to_string());
+    let mut state = GameState::seeded(GameConfig {
+        snake_capacity: SNAKES,
+        ..GameConfig::default()
+    });
+    let mix = DifficultyMix { random: 1, greedy: 1, lookahead: 2 };
+    let mut bots = TieredBots::new(0..SNAKES as u32, mix, Fixed::from_ratio(1, 10), 42);
+    let mut inputs = Vec::new();
+
+    for tick in 0..WARMUP_TICKS {
+        step(&mut state, &mut bots, &mut inputs, tick);
+    }
+
+    let mut scenario = Scenario::from_state(&state, 0, &Replay::new(), RECORDED_TICKS);
+    let mut replay = Replay::new();
+    let mut events = Vec::new();
+    for tick in 0..RECORDED_TICKS {
+        if tick.is_multiple_of(RESPAWN_EVERY) {
+            events.push(ScheduledEvent { tick, event: WorldEvent::RespawnWave { count: SNAKES as u32 } });
+        }
+        step(&mut state, &mut bots, &mut inputs, tick);
+        replay.record(tick, &inputs);
+    }
+
+    scenario.replay = replay;
+    scenario.events = EventSchedule::new(events);
+    let replayed = Scenario::from_state(&scenario.state_after(RECORDED_TICKS), 0, &Replay::new(), 0);
+    let recorded = Scenario::from_state(&state, 0, &Replay::new(), 0);
+    assert!(
+        replayed.snakes == recorded.snakes && replayed.apples == recorded.apples,
+        "the scenario does not reproduce the recorded run"
+    );
+    scenario.save(&out)?;
+    println!(
+        "{}: {} snakes ({} alive at the start), {} inputs over {} ticks",
+        out,
+        scenario.snakes.len(),
+        scenario.snakes.iter().filter(|snake| snake.alive).count(),
+        scenario.replay.input_count(),
+        RECORDED_TICKS
+    );
+    Ok(())
+}
+
+// One tick as the scenario will replay it: respawn wave due this tick, then bot inputs
+fn step(state: &mut GameState, bots: &mut TieredBots, inputs: &mut Vec<Input>, tick: u64) {
+    if tick.is_multiple_of(RESPAWN_EVERY) {
+        state.respawn_dead(SNAKES);
+    }
+    inputs.clear();
+    bots.decide(state.view(), inputs);
+    state.tick(inputs);
+}
@@ -0,0 +1,144 @@
+#ifndef HIGH_FREQUENCY_SNAKE_H
+#define HIGH_FREQUENCY_SNAKE_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdint.h>
+#include <stddef.h>
+
+#define HFS_OK 0
+
+#define HFS_ERR_NULL -1
+
+#define HFS_ERR_INVALID_ARGUMENT -2
+
+#define GRID_WIDTH 10000
+
+#define GRID_HEIGHT 10000
+
+/**
+ * Opaque game handle
+ */
+typedef struct HfsGame HfsGame;
+
+typedef struct HfsPoint {
+  uint16_t x;
+  uint16_t y;
+} HfsPoint;
+
+typedef struct HfsSnakeInfo {
+  uint8_t alive;
+  /**
+   * 0 up, 1 down, 2 left, 3 right
+   */
+  uint8_t direction;
+  uint32_t length;
+  struct HfsPoint head;
+} HfsSnakeInfo;
+
+typedef struct HfsPopulation {
+  uint32_t alive;
+  uint64_t total_body_cells;
+  uint64_t total_births;
+  uint64_t total_deaths;
+  uint64_t num_apples;
+} HfsPopulation;
+
+
+
+
+
+
+
+/**
+ * Create a game with `snake_capacity` randomly placed snakes and up to `apple_capacity` apples.
+ * Placement and the engine RNG are seeded with `seed`, so two games created with the same
+ * arguments and fed the same inputs stay identical. Free with `hfs_game_destroy`.
+ * Returns null if `snake_capacity` exceeds what the engine can tick.
+ */
+struct HfsGame *hfs_game_create(uint32_t snake_capacity, uint32_t apple_capacity, uint64_t seed);
+
+/**
+ * # Safety
+ * `game` must come from `hfs_game_create` and not be used afterwards. Null is ignored.
+ */
+void hfs_game_destroy(struct HfsGame *game);
+
+/**
+ * Queue a direction change (0 up, 1 down, 2 left, 3 right) for the next tick.
+ *
+ * # Safety
+ * `game` must be a live handle from `hfs_game_create` or null.
+ */
+int32_t hfs_game_push_input(struct HfsGame *game, uint32_t snake_id, uint8_t direction);
+
+/**
+ * Apply the queued inputs and advance one tick.
+ *
+ * # Safety
+ * `game` must be a live handle from `hfs_game_create` or null.
+ */
+int32_t hfs_game_tick(struct HfsGame *game);
+
+/**
+ * Ticks simulated so far (0 for null)
+ *
+ * # Safety
+ * `game` must be a live handle from `hfs_game_create` or null.
+ */
+uint64_t hfs_game_tick_count(const struct HfsGame *game);
+
+/**
+ * Number of snake slots; valid ids are `0..count` (0 for null)
+ *
+ * # Safety
+ * `game` must be a live handle from `hfs_game_create` or null.
+ */
+uint32_t hfs_game_snake_count(const struct HfsGame *game);
+
+/**
+ * # Safety
+ * `game` must be a live handle from `hfs_game_create` or null; `out` must be writable or null.
+ */
+int32_t hfs_game_snake_info(const struct HfsGame *game,
+                            uint32_t snake_id,
+                            struct HfsSnakeInfo *out);
+
+/**
+ * Copy up to `capacity` body segments, tail first, into `out`.
+ * Returns the full body length (which may exceed `capacity`) or a negative error code.
+ *
+ * # Safety
+ * `game` must be a live handle from `hfs_game_create` or null; `out` must have room for
+ * `capacity` points (it may be null when `capacity` is 0).
+ */
+int64_t hfs_game_snake_body(const struct HfsGame *game,
+                            uint32_t snake_id,
+                            struct HfsPoint *out,
+                            size_t capacity);
+
+/**
+ * Cell at `(x, y)`: 0 empty, 1 snake, 2 apple, 3 split power-up, or a negative error code
+ *
+ * # Safety
+ * `game` must be a live handle from `hfs_game_create` or null.
+ */
+int32_t hfs_game_cell(const struct HfsGame *game, uint16_t x, uint16_t y);
+
+/**
+ * # Safety
+ * `game` must be a live handle from `hfs_game_create` or null; `out` must be writable or null.
+ */
+int32_t hfs_game_population(const struct HfsGame *game, struct HfsPopulation *out);
+
+/**
+ * Serialize the full state (the `game::snapshot` format) into a buffer owned by the game.
+ * Returns a pointer to the bytes and stores their count in `out_len`, or null on error.
+ * The buffer stays valid until the next `hfs_game_snapshot` or `hfs_game_destroy`.
+ *
+ * # Safety
+ * `game` must be a live handle from `hfs_game_create` or null; `out_len` must be writable.
+ */
+const uint8_t *hfs_game_snapshot(struct HfsGame *game, size_t *out_len);
+
+#endif  /* HIGH_FREQUENCY_SNAKE_H */
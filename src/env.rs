@@ -0,0 +1,243 @@
+//! Gym-style environment over [`GameState`] for agent training.
+//!
+//! The first `agents` snakes are controlled by the caller, all in one `step`: actions go in
+//! as one byte per agent and observations, rewards and done flags come back as flat,
+//! agent-major buffers that are reused between steps. The other snakes just move on.
+//!
+//! There is no scoring system in the engine, so rewards are computed here from what
+//! happened to each agent during the tick (see [`RewardWeights`]).
+
+use crate::game::config::GameConfig;
+use crate::game::engine::GameState;
+use crate::game::types::{Direction, Input, Rect};
+use crate::game::view::view_tensor_len;
+
+/// Action that keeps the current direction; 0..=3 are the `Direction` encoding
+pub const ACTION_NOOP: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RewardWeights {
+    /// Per body cell grown (apples and kill growth)
+    pub growth: f32,
+    /// Per snake this agent killed; needs kill attribution in the game config
+    pub kill: f32,
+    /// On the tick the agent dies
+    pub death: f32,
+    /// Every tick the agent is alive at the end of
+    pub alive: f32,
+}
+
+impl Default for RewardWeights {
+    fn default() -> Self {
+        Self {
+            growth: 1.0,
+            kill: 0.0,
+            death: -1.0,
+            alive: 0.0,
+        }
+    }
+}
+
+/// How each agent's view is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Observation {
+    /// One cell code per cell (`Cell::to_u8`), row-major
+    #[default]
+    Cells,
+    /// 0/1 planes from `GameState::extract_view_tensor`, separating the agent from others
+    Tensor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvConfig {
+    /// Game each episode starts from (placement seeded with `reset`'s seed)
+    pub game: GameConfig,
+    /// Snakes `0..agents` are controlled; must not exceed `game.snake_capacity`
+    pub agents: u32,
+    /// Cells seen in every direction from the head; views are `2 * radius + 1` square
+    pub view_radius: u16,
+    pub observation: Observation,
+    /// Episode length after which every agent is truncated; 0 for none
+    pub max_ticks: u64,
+    pub rewards: RewardWeights,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            game: GameConfig::default(),
+            agents: 1,
+            view_radius: 7,
+            observation: Observation::Cells,
+            max_ticks: 1000,
+            rewards: RewardWeights::default(),
+        }
+    }
+}
+
+/// Buffers returned by [`SnakeEnv::step`] and [`SnakeEnv::reset`], one entry per agent
+#[derive(Debug, Clone, Copy)]
+pub struct Step<'a> {
+    /// `agents` views of `view_len` bytes each, centred on the head and encoded as
+    /// configured. A dead agent's view is all zeros.
+    pub observations: &'a [u8],
+    pub rewards: &'a [f32],
+    /// The agent is dead; its actions are ignored until the next reset
+    pub dones: &'a [bool],
+    /// The episode hit `max_ticks`
+    pub truncated: bool,
+}
+
+pub struct SnakeEnv {
+    config: EnvConfig,
+    state: GameState,
+    tick: u64,
+    observations: Vec<u8>,
+    rewards: Vec<f32>,
+    dones: Vec<bool>,
+    lengths: Vec<usize>,
+    inputs: Vec<Input>,
+    // One view, before it is copied into `observations`
+    view: Vec<u8>,
+}
+
+impl SnakeEnv {
+    /// Environment with a first episode seeded from `config.game.rng_seed`
+    pub fn new(config: EnvConfig) -> Self {
+        let agents = config.agents as usize;
+        assert!(agents <= config.game.snake_capacity, "more agents than snakes");
+        let mut env = Self {
+            config,
+            state: GameState::seeded(config.game),
+            tick: 0,
+            observations: vec![0; agents * view_len(&config)],
+            rewards: vec![0.0; agents],
+            dones: vec![false; agents],
+            lengths: vec![0; agents],
+            inputs: Vec::with_capacity(agents),
+            view: Vec::with_capacity(view_len(&config)),
+        };
+        env.begin_episode();
+        env
+    }
+
+    /// Start a new episode with placement and engine RNG seeded by `seed`
+    pub fn reset(&mut self, seed: u64) -> Step<'_> {
+        self.state = GameState::seeded(GameConfig {
+            rng_seed: seed,
+            ..self.config.game
+        });
+        self.begin_episode();
+        self.current()
+    }
+
+    /// Apply one action per agent (0..=3 a direction, anything else [`ACTION_NOOP`]) and
+    /// advance one tick
+    pub fn step(&mut self, actions: &[u8]) -> Step<'_> {
+        assert_eq!(actions.len(), self.dones.len(), "one action per agent");
+        self.inputs.clear();
+        for (snake_id, &action) in actions.iter().enumerate() {
+            if let Some(direction) = Direction::from_u8(action)
+                && !self.dones[snake_id]
+            {
+                self.inputs.push(Input {
+                    snake_id: snake_id as u32,
+                    direction,
+                });
+            }
+        }
+        self.state.tick(&self.inputs);
+        self.tick += 1;
+
+        let weights = self.config.rewards;
+        self.rewards.fill(0.0);
+        for kill in &self.state.kills {
+            if let Some(reward) = self.rewards.get_mut(kill.killer as usize) {
+                *reward += weights.kill;
+            }
+        }
+        for agent in 0..self.dones.len() {
+            if self.dones[agent] {
+                continue;
+            }
+            let snake = &self.state.snakes[agent];
+            if snake.is_alive() {
+                let length = snake.body().len();
+                self.rewards[agent] += weights.growth * length.saturating_sub(self.lengths[agent]) as f32 + weights.alive;
+                self.lengths[agent] = length;
+            } else {
+                self.rewards[agent] += weights.death;
+                self.dones[agent] = true;
+            }
+        }
+        self.observe();
+        self.current()
+    }
+
+    /// Bytes in one agent's view
+    pub fn view_len(&self) -> usize {
+        view_len(&self.config)
+    }
+
+    /// Ticks since the last reset
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    fn begin_episode(&mut self) {
+        self.tick = 0;
+        self.rewards.fill(0.0);
+        for agent in 0..self.dones.len() {
+            let snake = &self.state.snakes[agent];
+            self.dones[agent] = !snake.is_alive();
+            self.lengths[agent] = snake.body().len();
+        }
+        self.observe();
+    }
+
+    fn observe(&mut self) {
+        let radius = self.config.view_radius;
+        let side = radius as usize * 2 + 1;
+        for (agent, out) in self.observations.chunks_exact_mut(view_len(&self.config)).enumerate() {
+            match self.state.snakes[agent].head().filter(|_| !self.dones[agent]) {
+                Some(head) if self.config.observation == Observation::Tensor => {
+                    self.state.extract_view_tensor(*head, radius, agent as u32, out);
+                }
+                Some(head) => {
+                    self.view.clear();
+                    let rect = Rect::around(*head, radius);
+                    self.state.grid.write_view(rect.x as usize, rect.y as usize, side, side, &mut self.view);
+                    out.copy_from_slice(&self.view);
+                }
+                None => out.fill(0),
+            }
+        }
+    }
+
+    fn current(&self) -> Step<'_> {
+        Step {
+            observations: &self.observations,
+            rewards: &self.rewards,
+            dones: &self.dones,
+            truncated: self.config.max_ticks > 0 && self.tick >= self.config.max_ticks,
+        }
+    }
+}
+
+fn view_len(config: &EnvConfig) -> usize {
+    match config.observation {
+        Observation::Cells => {
+            let side = config.view_radius as usize * 2 + 1;
+            side * side
+        }
+        Observation::Tensor => view_tensor_len(config.view_radius),
+    }
+}
@@ -0,0 +1,244 @@
+//! C ABI for driving the engine from other languages (C, C++, Python via ctypes, ...).
+//!
+//! Every function takes an `HfsGame` pointer obtained from `hfs_game_create` and must
+//! not be called concurrently on the same game. The header lives in
+//! `include/high_frequency_snake.h` and is generated with cbindgen (see `cbindgen.toml`).
+
+use crate::game::config::GameConfig;
+use crate::game::engine::GameState;
+use crate::game::grid::{GRID_HEIGHT, GRID_WIDTH};
+use crate::game::snapshot::write_snapshot;
+use crate::game::types::{Direction, Input, Point};
+use std::ptr;
+
+pub const HFS_OK: i32 = 0;
+pub const HFS_ERR_NULL: i32 = -1;
+pub const HFS_ERR_INVALID_ARGUMENT: i32 = -2;
+
+/// Opaque game handle
+pub struct HfsGame {
+    state: GameState,
+    // Inputs pushed since the last tick
+    inputs: Vec<Input>,
+    // Backing storage for the pointer returned by hfs_game_snapshot
+    snapshot: Vec<u8>,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HfsPoint {
+    pub x: u16,
+    pub y: u16,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HfsSnakeInfo {
+    pub alive: u8,
+    /// 0 up, 1 down, 2 left, 3 right
+    pub direction: u8,
+    pub length: u32,
+    pub head: HfsPoint,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HfsPopulation {
+    pub alive: u32,
+    pub total_body_cells: u64,
+    pub total_births: u64,
+    pub total_deaths: u64,
+    pub num_apples: u64,
+}
+
+impl From<Point> for HfsPoint {
+    fn from(point: Point) -> Self {
+        Self { x: point.x, y: point.y }
+    }
+}
+
+/// Create a game with `snake_capacity` randomly placed snakes and up to `apple_capacity` apples.
+/// Placement and the engine RNG are seeded with `seed`, so two games created with the same
+/// arguments and fed the same inputs stay identical. Free with `hfs_game_destroy`.
+/// Returns null if `snake_capacity` exceeds what the engine can tick.
+#[unsafe(no_mangle)]
+pub extern "C" fn hfs_game_create(snake_capacity: u32, apple_capacity: u32, seed: u64) -> *mut HfsGame {
+    let config = GameConfig {
+        snake_capacity: snake_capacity as usize,
+        apple_capacity: apple_capacity as usize,
+        rng_seed: seed,
+        ..GameConfig::default()
+    };
+    // GameState panics on an invalid config, and a panic cannot unwind into C
+    if config.validate().is_err() {
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(HfsGame {
+        state: GameState::seeded(config),
+        inputs: Vec::new(),
+        snapshot: Vec::new(),
+    }))
+}
+
+/// # Safety
+/// `game` must come from `hfs_game_create` and not be used afterwards. Null is ignored.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hfs_game_destroy(game: *mut HfsGame) {
+    if !game.is_null() {
+        drop(unsafe { Box::from_raw(game) });
+    }
+}
+
+/// Queue a direction change (0 up, 1 down, 2 left, 3 right) for the next tick.
+///
+/// # Safety
+/// `game` must be a live handle from `hfs_game_create` or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hfs_game_push_input(game: *mut HfsGame, snake_id: u32, direction: u8) -> i32 {
+    let Some(game) = (unsafe { game.as_mut() }) else {
+        return HFS_ERR_NULL;
+    };
+    let Some(direction) = Direction::from_u8(direction) else {
+        return HFS_ERR_INVALID_ARGUMENT;
+    };
+    if snake_id as usize >= game.state.snakes.len() {
+        return HFS_ERR_INVALID_ARGUMENT;
+    }
+    game.inputs.push(Input { snake_id, direction });
+    HFS_OK
+}
+
+/// Apply the queued inputs and advance one tick.
+///
+/// # Safety
+/// `game` must be a live handle from `hfs_game_create` or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hfs_game_tick(game: *mut HfsGame) -> i32 {
+    let Some(game) = (unsafe { game.as_mut() }) else {
+        return HFS_ERR_NULL;
+    };
+    game.state.tick(&game.inputs);
+    game.inputs.clear();
+    HFS_OK
+}
+
+/// Ticks simulated so far (0 for null)
+///
+/// # Safety
+/// `game` must be a live handle from `hfs_game_create` or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hfs_game_tick_count(game: *const HfsGame) -> u64 {
+    unsafe { game.as_ref() }.map_or(0, |game| game.state.now())
+}
+
+/// Number of snake slots; valid ids are `0..count` (0 for null)
+///
+/// # Safety
+/// `game` must be a live handle from `hfs_game_create` or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hfs_game_snake_count(game: *const HfsGame) -> u32 {
+    unsafe { game.as_ref() }.map_or(0, |game| game.state.snakes.len() as u32)
+}
+
+/// # Safety
+/// `game` must be a live handle from `hfs_game_create` or null; `out` must be writable or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hfs_game_snake_info(game: *const HfsGame, snake_id: u32, out: *mut HfsSnakeInfo) -> i32 {
+    let (Some(game), Some(out)) = (unsafe { game.as_ref() }, unsafe { out.as_mut() }) else {
+        return HFS_ERR_NULL;
+    };
+    let Some(snake) = game.state.snakes.get(snake_id as usize) else {
+        return HFS_ERR_INVALID_ARGUMENT;
+    };
+    *out = HfsSnakeInfo {
+        alive: snake.is_alive() as u8,
+        direction: snake.snake().direction.to_u8(),
+        length: snake.body().len() as u32,
+        head: snake.head().copied().map(HfsPoint::from).unwrap_or_default(),
+    };
+    HFS_OK
+}
+
+/// Copy up to `capacity` body segments, tail first, into `out`.
+/// Returns the full body length (which may exceed `capacity`) or a negative error code.
+///
+/// # Safety
+/// `game` must be a live handle from `hfs_game_create` or null; `out` must have room for
+/// `capacity` points (it may be null when `capacity` is 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hfs_game_snake_body(
+    game: *const HfsGame,
+    snake_id: u32,
+    out: *mut HfsPoint,
+    capacity: usize,
+) -> i64 {
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return HFS_ERR_NULL as i64;
+    };
+    let Some(snake) = game.state.snakes.get(snake_id as usize) else {
+        return HFS_ERR_INVALID_ARGUMENT as i64;
+    };
+    let body = snake.body();
+    if capacity > 0 {
+        if out.is_null() {
+            return HFS_ERR_NULL as i64;
+        }
+        for (i, point) in snake.segments().take(capacity).enumerate() {
+            unsafe { out.add(i).write(point.into()) };
+        }
+    }
+    body.len() as i64
+}
+
+/// Cell at `(x, y)`: 0 empty, 1 snake, 2 apple, 3 split power-up, or a negative error code
+///
+/// # Safety
+/// `game` must be a live handle from `hfs_game_create` or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hfs_game_cell(game: *const HfsGame, x: u16, y: u16) -> i32 {
+    let Some(game) = (unsafe { game.as_ref() }) else {
+        return HFS_ERR_NULL;
+    };
+    if x as usize >= GRID_WIDTH || y as usize >= GRID_HEIGHT {
+        return HFS_ERR_INVALID_ARGUMENT;
+    }
+    game.state.grid.get_cell(&Point { x, y }).to_u8() as i32
+}
+
+/// # Safety
+/// `game` must be a live handle from `hfs_game_create` or null; `out` must be writable or null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hfs_game_population(game: *const HfsGame, out: *mut HfsPopulation) -> i32 {
+    let (Some(game), Some(out)) = (unsafe { game.as_ref() }, unsafe { out.as_mut() }) else {
+        return HFS_ERR_NULL;
+    };
+    let population = &game.state.population;
+    *out = HfsPopulation {
+        alive: population.alive,
+        total_body_cells: population.total_body_cells,
+        total_births: population.total_births,
+        total_deaths: population.total_deaths,
+        num_apples: game.state.num_apples,
+    };
+    HFS_OK
+}
+
+/// Serialize the full state (the `game::snapshot` format) into a buffer owned by the game.
+/// Returns a pointer to the bytes and stores their count in `out_len`, or null on error.
+/// The buffer stays valid until the next `hfs_game_snapshot` or `hfs_game_destroy`.
+///
+/// # Safety
+/// `game` must be a live handle from `hfs_game_create` or null; `out_len` must be writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hfs_game_snapshot(game: *mut HfsGame, out_len: *mut usize) -> *const u8 {
+    let (Some(game), Some(out_len)) = (unsafe { game.as_mut() }, unsafe { out_len.as_mut() }) else {
+        return ptr::null();
+    };
+    game.snapshot.clear();
+    if write_snapshot(&game.state, game.state.now(), &mut game.snapshot).is_err() {
+        *out_len = 0;
+        return ptr::null();
+    }
+    *out_len = game.snapshot.len();
+    game.snapshot.as_ptr()
+}
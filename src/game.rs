@@ -1,6 +1,68 @@
+// Gameplay modules must stay float-free for cross-platform determinism (see fixedpoint)
+#[deny(clippy::float_arithmetic)]
 pub mod apple;
+#[deny(clippy::float_arithmetic)]
+pub mod audit;
+#[deny(clippy::float_arithmetic)]
+pub mod body;
+#[deny(clippy::float_arithmetic)]
+pub mod budget;
+#[deny(clippy::float_arithmetic)]
+pub mod cellmeta;
+#[deny(clippy::float_arithmetic)]
+pub mod codec;
+#[deny(clippy::float_arithmetic)]
+pub mod collision;
+#[deny(clippy::float_arithmetic)]
+pub mod config;
+#[deny(clippy::float_arithmetic)]
+pub mod delta;
+#[deny(clippy::float_arithmetic)]
 pub mod engine;
+#[deny(clippy::float_arithmetic)]
+pub mod events;
+#[deny(clippy::float_arithmetic)]
+pub mod fixedpoint;
+#[deny(clippy::float_arithmetic)]
 pub mod generator;
+#[deny(clippy::float_arithmetic)]
 pub mod grid;
+#[deny(clippy::float_arithmetic)]
+pub mod heads;
+#[deny(clippy::float_arithmetic)]
+pub mod layers;
+#[deny(clippy::float_arithmetic)]
+pub mod leaderboard;
+pub mod metrics;
+#[deny(clippy::float_arithmetic)]
+pub mod motion;
+#[deny(clippy::float_arithmetic)]
+pub mod powerup;
+#[deny(clippy::float_arithmetic)]
+pub mod repair;
+#[deny(clippy::float_arithmetic)]
+pub mod report;
+#[deny(clippy::float_arithmetic)]
+pub mod rng;
+#[deny(clippy::float_arithmetic)]
+pub mod scatter;
+#[deny(clippy::float_arithmetic)]
+pub mod scenario;
+#[deny(clippy::float_arithmetic)]
 pub mod snake;
+#[deny(clippy::float_arithmetic)]
+pub mod snakemeta;
+#[deny(clippy::float_arithmetic)]
+pub mod snapshot;
+#[deny(clippy::float_arithmetic)]
+pub mod spawn;
+#[deny(clippy::float_arithmetic)]
+pub mod textmap;
+#[deny(clippy::float_arithmetic)]
+pub mod systems;
+#[deny(clippy::float_arithmetic)]
+pub mod tick_engine;
+#[deny(clippy::float_arithmetic)]
 pub mod types;
+#[deny(clippy::float_arithmetic)]
+pub mod view;
@@ -1,7 +1,15 @@
-use crate::game::grid::{Cell, Grid};
-use crate::game::types::Point;
+use crate::game::grid::{Cell, GRID_HEIGHT, GRID_WIDTH, Grid};
+use crate::game::metrics::PopulationMetrics;
+use crate::game::rng::SplitMix64;
+use crate::game::types::{Point, Rect};
+use rand::Rng;
+use std::collections::HashMap;
 
 pub const APPLE_CAPACITY: usize = 128;
+// Slots of the expiry wheel; deadlines further out wait in their slot for another lap
+pub const EXPIRY_WHEEL_SLOTS: usize = 256;
+// Slots of the respawn wheel, the same way
+pub const RESPAWN_WHEEL_SLOTS: usize = 256;
 
 pub struct Apple {
     pub position: Point,
@@ -92,3 +100,327 @@ impl GridAwareApple {
     }
     
 }
+
+/// Apple count that follows the population (see `GameState::set_apple_scaling`), so food
+/// pressure stays the same as snakes multiply and grow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AppleScaling {
+    /// One apple per this many live snakes; 0 ignores the snake count
+    pub snakes_per_apple: u32,
+    /// One apple per this many body cells of live snakes; 0 ignores length
+    pub cells_per_apple: u32,
+    /// Bounds of the target
+    pub min_apples: usize,
+    pub max_apples: usize,
+    /// Recompute the target on every this many ticks
+    pub every_ticks: u64,
+}
+
+impl AppleScaling {
+    /// Apples to keep on the grid for `population`
+    pub fn target(&self, population: &PopulationMetrics) -> usize {
+        let per = |count: u64, divisor: u32| if divisor == 0 { 0 } else { count / divisor as u64 };
+        let target = per(population.alive as u64, self.snakes_per_apple) + per(population.total_body_cells, self.cells_per_apple);
+        (target as usize).clamp(self.min_apples, self.max_apples.max(self.min_apples))
+    }
+
+    /// Parse `<snakes per apple>,<cells per apple>,<min>,<max>,<every ticks>`, the form
+    /// the runner's `--apple-scaling` flag and the benches' `APPLE_SCALING` take
+    pub fn from_spec(spec: &str) -> Option<Self> {
+        let fields: Vec<&str> = spec.split(',').map(str::trim).collect();
+        let [snakes, cells, min, max, every] = fields[..] else {
+            return None;
+        };
+        Some(Self {
+            snakes_per_apple: snakes.parse().ok()?,
+            cells_per_apple: cells.parse().ok()?,
+            min_apples: min.parse().ok()?,
+            max_apples: max.parse().ok()?,
+            every_ticks: every.parse().ok()?,
+        })
+    }
+}
+
+/// Spawn policy concentrating apples around a few centers that drift across the grid,
+/// so snakes converge on the same spots instead of spreading out after uniform food.
+///
+/// Centers are a pure function of the game seed and the tick, so nothing beyond the
+/// config needs saving to resume a game with them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AppleHotspots {
+    /// Number of centers; 0 spawns apples uniformly
+    pub count: u8,
+    /// Apples land at most this many cells from a center on either axis
+    pub radius: u16,
+    /// Ticks a center takes to move one cell; 0 keeps centers still
+    pub drift_interval: u32,
+}
+
+// Headings a center may drift in: the four directions and the four diagonals
+const DRIFT_HEADINGS: [(i64, i64); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+impl AppleHotspots {
+    /// Uniform spawns
+    pub const OFF: AppleHotspots = AppleHotspots {
+        count: 0,
+        radius: 0,
+        drift_interval: 0,
+    };
+
+    pub fn is_enabled(&self) -> bool {
+        self.count > 0
+    }
+
+    /// Center of hotspot `index` on tick `now` in a game seeded with `seed`
+    pub fn center(&self, seed: u64, index: u8, now: u64) -> Point {
+        let mut rng = SplitMix64::new(seed ^ (index as u64 + 1).wrapping_mul(0xa076_1d64_78bd_642f));
+        let start = rng.random::<Point>();
+        let (dx, dy) = DRIFT_HEADINGS[rng.random_range(0..DRIFT_HEADINGS.len())];
+        let steps = if self.drift_interval == 0 { 0 } else { now / self.drift_interval as u64 };
+        Point {
+            x: wrap(start.x as i64 + dx * (steps % GRID_WIDTH as u64) as i64, GRID_WIDTH),
+            y: wrap(start.y as i64 + dy * (steps % GRID_HEIGHT as u64) as i64, GRID_HEIGHT),
+        }
+    }
+
+    /// A spawn position on tick `now`: a random cell within `radius` of a random center,
+    /// wrapping around the grid edges
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R, seed: u64, now: u64) -> Point {
+        let center = self.center(seed, rng.random_range(0..self.count.max(1)), now);
+        let radius = self.radius as i64;
+        Point {
+            x: wrap(center.x as i64 + rng.random_range(-radius..=radius), GRID_WIDTH),
+            y: wrap(center.y as i64 + rng.random_range(-radius..=radius), GRID_HEIGHT),
+        }
+    }
+}
+
+fn wrap(coordinate: i64, size: usize) -> u16 {
+    coordinate.rem_euclid(size as i64) as u16
+}
+
+/// Apple deadlines for `GameConfig::apple_ttl`, kept in a hashed timing wheel so each tick
+/// only looks at the apples that could be due.
+///
+/// Keeps its own clock, advanced once per engine tick. Eaten apples are forgotten right
+/// away; their wheel entries are dropped lazily when their slot comes round.
+#[derive(Debug, Clone, Default)]
+pub struct AppleExpiry {
+    ttl: u64,
+    now: u64,
+    // (position, deadline) by deadline % EXPIRY_WHEEL_SLOTS
+    slots: Vec<Vec<(Point, u64)>>,
+    // Live apples and their deadlines; wheel entries not matching this are stale
+    deadlines: HashMap<Point, u64>,
+}
+
+impl AppleExpiry {
+    /// `ttl` 0 disables expiry; every method is then a no-op
+    pub fn new(ttl: u32) -> Self {
+        Self {
+            ttl: ttl as u64,
+            now: 0,
+            slots: if ttl > 0 { vec![Vec::new(); EXPIRY_WHEEL_SLOTS] } else { Vec::new() },
+            deadlines: HashMap::new(),
+        }
+    }
+
+    /// Rebuild the wheel from [`AppleExpiry::entries`] and the clock they were taken at
+    pub fn restore(ttl: u32, now: u64, entries: impl IntoIterator<Item = (Point, u64)>) -> Self {
+        let mut expiry = Self::new(ttl);
+        expiry.now = now;
+        if expiry.is_enabled() {
+            for (position, deadline) in entries {
+                expiry.insert(position, deadline);
+            }
+        }
+        expiry
+    }
+
+    #[inline(always)]
+    pub fn is_enabled(&self) -> bool {
+        self.ttl > 0
+    }
+
+    /// Ticks advanced so far
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Number of apples with a deadline
+    pub fn len(&self) -> usize {
+        self.deadlines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deadlines.is_empty()
+    }
+
+    /// Start the clock of an apple just placed at `position`
+    pub fn track(&mut self, position: Point) {
+        if self.is_enabled() {
+            self.insert(position, self.now + self.ttl);
+        }
+    }
+
+    /// The apple at `position` was eaten
+    #[inline(always)]
+    pub fn forget(&mut self, position: &Point) {
+        if self.is_enabled() {
+            self.deadlines.remove(position);
+        }
+    }
+
+    /// Advance the clock one tick and return the apples due, in row-major order so the
+    /// result does not depend on the order they were tracked in
+    pub fn advance(&mut self) -> Vec<Point> {
+        if !self.is_enabled() {
+            return Vec::new();
+        }
+        self.now += 1;
+        let now = self.now;
+        let deadlines = &mut self.deadlines;
+        let mut expired = Vec::new();
+        self.slots[(now % EXPIRY_WHEEL_SLOTS as u64) as usize].retain(|&(position, deadline)| {
+            if deadlines.get(&position) != Some(&deadline) {
+                return false;
+            }
+            if deadline == now {
+                deadlines.remove(&position);
+                expired.push(position);
+                return false;
+            }
+            true
+        });
+        expired.sort_unstable_by_key(|position| (position.y, position.x));
+        expired
+    }
+
+    /// Live `(position, deadline)` pairs in row-major order
+    pub fn entries(&self) -> Vec<(Point, u64)> {
+        let mut entries: Vec<_> = self.deadlines.iter().map(|(position, deadline)| (*position, *deadline)).collect();
+        entries.sort_unstable_by_key(|(position, _)| (position.y, position.x));
+        entries
+    }
+
+    fn insert(&mut self, position: Point, deadline: u64) {
+        self.deadlines.insert(position, deadline);
+        self.slots[(deadline % EXPIRY_WHEEL_SLOTS as u64) as usize].push((position, deadline));
+    }
+}
+
+/// When and where eaten apples are replaced (see `GameConfig::apple_respawn`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AppleRespawn {
+    /// Ticks between an apple being eaten and its replacement being placed; 0 replaces it
+    /// on the tick it was eaten
+    pub delay: u32,
+    /// Most replacements placed in one tick; the rest wait for the next ticks, so a tick
+    /// where a crowd eats does not also pay for every spawn. 0 places every one due.
+    pub max_per_tick: u32,
+    /// Area replacements are placed in, wrapping around the grid edges; `None` places
+    /// them anywhere (around hotspots when those are on)
+    pub region: Option<Rect>,
+}
+
+impl AppleRespawn {
+    /// Every apple replaced on the tick it is eaten, anywhere
+    pub const IMMEDIATE: AppleRespawn = AppleRespawn {
+        delay: 0,
+        max_per_tick: 0,
+        region: None,
+    };
+}
+
+/// Apple replacements waiting for their tick, in a hashed timing wheel like [`AppleExpiry`]
+///
+/// The engine schedules replacements as apples are eaten and takes those due once per
+/// tick; replacements held back by `AppleRespawn::max_per_tick` stay in a backlog taken
+/// first on the next tick.
+#[derive(Debug, Clone, Default)]
+pub struct RespawnWheel {
+    // (due tick, count) by due % RESPAWN_WHEEL_SLOTS
+    slots: Vec<Vec<(u64, u64)>>,
+    // Due but not placed yet
+    backlog: u64,
+    // Scheduled in the wheel, backlog excluded
+    scheduled: u64,
+    // Ticks before this one have been taken
+    next: u64,
+}
+
+impl RespawnWheel {
+    /// An empty wheel whose first tick is `now`
+    pub fn new(now: u64) -> Self {
+        Self {
+            slots: vec![Vec::new(); RESPAWN_WHEEL_SLOTS],
+            backlog: 0,
+            scheduled: 0,
+            next: now,
+        }
+    }
+
+    /// Rebuild the wheel from [`RespawnWheel::backlog`], [`RespawnWheel::entries`] and the
+    /// tick they were taken at
+    pub fn restore(now: u64, backlog: u64, entries: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        let mut wheel = Self::new(now);
+        wheel.backlog = backlog;
+        for (due, count) in entries {
+            wheel.schedule(due, count);
+        }
+        wheel
+    }
+
+    /// Replacements waiting, due or not
+    pub fn pending(&self) -> u64 {
+        self.backlog + self.scheduled
+    }
+
+    /// Replacements due but held back by the per-tick limit
+    pub fn backlog(&self) -> u64 {
+        self.backlog
+    }
+
+    /// Place `count` replacements on tick `due`; ticks already taken count as the next one
+    pub fn schedule(&mut self, due: u64, count: u64) {
+        if count == 0 {
+            return;
+        }
+        let due = due.max(self.next);
+        self.slots[(due % RESPAWN_WHEEL_SLOTS as u64) as usize].push((due, count));
+        self.scheduled += count;
+    }
+
+    /// Take the replacements to place on tick `now`: the backlog, then everything due by
+    /// now, at most `limit` of them (0: no limit). The rest joins the backlog.
+    pub fn take_due(&mut self, now: u64, limit: u32) -> u64 {
+        // Every slot at most once, even when the clock jumped a lap or more
+        let ticks = now.saturating_sub(self.next).saturating_add(1).min(RESPAWN_WHEEL_SLOTS as u64);
+        for tick in now + 1 - ticks..=now {
+            let backlog = &mut self.backlog;
+            let scheduled = &mut self.scheduled;
+            self.slots[(tick % RESPAWN_WHEEL_SLOTS as u64) as usize].retain(|&(due, count)| {
+                if due > now {
+                    return true;
+                }
+                *backlog += count;
+                *scheduled -= count;
+                false
+            });
+        }
+        self.next = self.next.max(now + 1);
+        let taken = match limit {
+            0 => self.backlog,
+            limit => self.backlog.min(limit as u64),
+        };
+        self.backlog -= taken;
+        taken
+    }
+
+    /// Scheduled `(due, count)` pairs in due order, the backlog excluded
+    pub fn entries(&self) -> Vec<(u64, u64)> {
+        let mut entries: Vec<_> = self.slots.iter().flatten().copied().collect();
+        entries.sort_unstable();
+        entries
+    }
+}
@@ -0,0 +1,56 @@
+use crate::game::types::Input;
+
+/// Why an input was dropped without effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// The snake was heading the opposite way and may not reverse onto itself
+    Reversal,
+    /// The snake was dead
+    DeadSnake,
+    /// The snake changed direction less than `GameConfig::turn_interval` ticks ago
+    Throttled,
+}
+
+impl Rejection {
+    /// Lowercase name used by text formats
+    pub fn name(self) -> &'static str {
+        match self {
+            Rejection::Reversal => "reversal",
+            Rejection::DeadSnake => "dead_snake",
+            Rejection::Throttled => "throttled",
+        }
+    }
+}
+
+/// An input of the last tick that did not change its snake's direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RejectedInput {
+    pub input: Input,
+    pub reason: Rejection,
+}
+
+/// Rejected inputs of one tick, by reason
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InputAudit {
+    pub reversals: u32,
+    pub dead_snakes: u32,
+    pub throttled: u32,
+}
+
+impl InputAudit {
+    pub fn of(rejected: &[RejectedInput]) -> Self {
+        let mut audit = Self::default();
+        for rejection in rejected {
+            match rejection.reason {
+                Rejection::Reversal => audit.reversals += 1,
+                Rejection::DeadSnake => audit.dead_snakes += 1,
+                Rejection::Throttled => audit.throttled += 1,
+            }
+        }
+        audit
+    }
+
+    pub fn total(&self) -> u32 {
+        self.reversals + self.dead_snakes + self.throttled
+    }
+}
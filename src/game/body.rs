@@ -0,0 +1,206 @@
+//! Snake body storage.
+//!
+//! [`Body`] keeps the first [`INLINE_CELLS`] cells in a ring inside the snake and
+//! spills to a `VecDeque` past that, like the `TinyDeque` it replaces. It exists because
+//! `tinydeque` 0.1 iterates inline deques as empty and returns empty slices for a full
+//! inline ring, which left only `get(i)` loops safe to use.
+
+use crate::game::types::Point;
+use std::collections::VecDeque;
+
+/// Cells held without a heap allocation
+pub const INLINE_CELLS: usize = 16;
+
+/// Cells from tail (front) to head (back)
+#[derive(Debug, Clone)]
+pub enum Body {
+    Inline { cells: [Point; INLINE_CELLS], start: u8, len: u8 },
+    Heap(VecDeque<Point>),
+}
+
+impl Body {
+    pub fn new() -> Self {
+        Body::Inline { cells: [Point::default(); INLINE_CELLS], start: 0, len: 0 }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        match self {
+            Body::Inline { len, .. } => *len as usize,
+            Body::Heap(cells) => cells.len(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cell `index` counted from the tail
+    #[inline(always)]
+    pub fn get(&self, index: usize) -> Option<&Point> {
+        match self {
+            Body::Inline { cells, start, len } => {
+                (index < *len as usize).then(|| &cells[(*start as usize + index) % INLINE_CELLS])
+            }
+            Body::Heap(cells) => cells.get(index),
+        }
+    }
+
+    /// Tail cell
+    #[inline(always)]
+    pub fn front(&self) -> Option<&Point> {
+        self.get(0)
+    }
+
+    /// Head cell
+    #[inline(always)]
+    pub fn back(&self) -> Option<&Point> {
+        self.get(self.len().wrapping_sub(1))
+    }
+
+    /// Add a new head
+    #[inline(always)]
+    pub fn push_back(&mut self, point: Point) {
+        match self {
+            Body::Inline { cells, start, len } if (*len as usize) < INLINE_CELLS => {
+                cells[(*start as usize + *len as usize) % INLINE_CELLS] = point;
+                *len += 1;
+            }
+            Body::Inline { .. } => {
+                let mut spilled = VecDeque::with_capacity(INLINE_CELLS * 2);
+                spilled.extend(self.iter());
+                spilled.push_back(point);
+                *self = Body::Heap(spilled);
+            }
+            Body::Heap(cells) => cells.push_back(point),
+        }
+    }
+
+    /// Remove the tail
+    #[inline(always)]
+    pub fn pop_front(&mut self) -> Option<Point> {
+        match self {
+            Body::Inline { len: 0, .. } => None,
+            Body::Inline { cells, start, len } => {
+                let point = cells[*start as usize];
+                *start = ((*start as usize + 1) % INLINE_CELLS) as u8;
+                *len -= 1;
+                Some(point)
+            }
+            Body::Heap(cells) => cells.pop_front(),
+        }
+    }
+
+    /// Remove the head
+    #[inline(always)]
+    pub fn pop_back(&mut self) -> Option<Point> {
+        match self {
+            Body::Inline { len: 0, .. } => None,
+            Body::Inline { cells, start, len } => {
+                *len -= 1;
+                Some(cells[(*start as usize + *len as usize) % INLINE_CELLS])
+            }
+            Body::Heap(cells) => cells.pop_back(),
+        }
+    }
+
+    /// The cells as at most two contiguous runs, tail first
+    #[inline]
+    pub fn as_slices(&self) -> (&[Point], &[Point]) {
+        match self {
+            Body::Inline { cells, start, len } => {
+                let (start, len) = (*start as usize, *len as usize);
+                if start + len <= INLINE_CELLS {
+                    (&cells[start..start + len], &[])
+                } else {
+                    (&cells[start..], &cells[..start + len - INLINE_CELLS])
+                }
+            }
+            Body::Heap(cells) => cells.as_slices(),
+        }
+    }
+
+    /// Cells from tail to head
+    #[inline]
+    pub fn iter(&self) -> Iter<'_> {
+        let (front, back) = self.as_slices();
+        Iter { front: front.iter(), back: back.iter() }
+    }
+
+    #[inline]
+    pub fn contains(&self, point: &Point) -> bool {
+        let (front, back) = self.as_slices();
+        front.contains(point) || back.contains(point)
+    }
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for Body {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Body {}
+
+impl FromIterator<Point> for Body {
+    fn from_iter<I: IntoIterator<Item = Point>>(iter: I) -> Self {
+        let mut body = Body::new();
+        body.extend(iter);
+        body
+    }
+}
+
+impl Extend<Point> for Body {
+    fn extend<I: IntoIterator<Item = Point>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|point| self.push_back(point));
+    }
+}
+
+impl<'a> IntoIterator for &'a Body {
+    type Item = &'a Point;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over a [`Body`], tail to head
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    front: std::slice::Iter<'a, Point>,
+    back: std::slice::Iter<'a, Point>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Point;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a Point> {
+        self.front.next().or_else(|| self.back.next())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.front.len() + self.back.len();
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.back.next_back().or_else(|| self.front.next_back())
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl std::iter::FusedIterator for Iter<'_> {}
@@ -0,0 +1,52 @@
+//! Per-tick time budget.
+//!
+//! With a [`TickBudget`] set, `tick` compares its running time with the limit at every
+//! phase boundary. The first boundary past the limit records a [`TickOverrun`] for that
+//! tick, and the next tick runs with the budget's [`Degradations`] to catch up; it then
+//! either fits, ending the degraded run, or overruns again.
+//!
+//! Degrading changes what the game does (fewer inputs, later apples) on wall-clock
+//! timing, so runs with a budget are not reproducible from their inputs.
+
+/// Work the tick after an overrun leaves out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Degradations {
+    /// Skip the optional bookkeeping: phase timings and death marks in `cell_meta`
+    pub skip_metrics: bool,
+    /// Apply at most this many inputs, dropping the rest (see `GameState::capped_inputs`)
+    pub max_inputs: Option<usize>,
+    /// Leave apples eaten during the tick to be replaced by the next tick that runs in
+    /// full. Deferred apples are not part of snapshots.
+    pub defer_apples: bool,
+}
+
+impl Degradations {
+    /// Every degradation, inputs capped at `max_inputs`
+    pub fn all(max_inputs: usize) -> Self {
+        Self {
+            skip_metrics: true,
+            max_inputs: Some(max_inputs),
+            defer_apples: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickBudget {
+    /// Longest acceptable tick, in `platform::TIMESTAMP_UNIT`s
+    pub limit: u64,
+    pub degradations: Degradations,
+}
+
+/// A tick that ran past its budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickOverrun {
+    /// `GameState::now()` when the tick started
+    pub tick: u64,
+    /// Phase (one of `metrics::TICK_PHASES`) whose end was the first past the limit
+    pub phase: &'static str,
+    /// Time from the start of the tick to the end of that phase
+    pub elapsed: u64,
+    /// Whether the tick itself already ran degraded
+    pub degraded: bool,
+}
@@ -0,0 +1,138 @@
+//! Per-cell scalar state beside the grid.
+//!
+//! [`CellMeta`] keeps one `u8` per cell for effects the three-way [`Cell`] enum cannot
+//! hold: "recently died here" cooldowns, zone damage levels, pheromone trails for bots.
+//! Storage is allocated per grid tile (see `grid::TILE_SIZE`) on first write, so a layer
+//! that only touches a few areas costs a few tiles rather than a second grid.
+//!
+//! Values fade by `decay` per tick. Nothing runs every tick: a tile remembers when it was
+//! last brought up to date and reads subtract the elapsed decay on the fly. A write
+//! settles its tile first, so its cost is bounded by the tile size, never the grid size.
+//!
+//! [`Cell`]: crate::game::grid::Cell
+
+use crate::game::grid::{GRID_WIDTH, TILE_BITS, TILE_SIZE, TILES_X, TILES_Y};
+use crate::game::types::Point;
+
+const TILE_CELLS: usize = TILE_SIZE * TILE_SIZE;
+
+#[derive(Clone)]
+struct Tile {
+    values: Box<[u8]>,
+    // Tick the values were last decayed to
+    updated: u64,
+}
+
+#[derive(Clone)]
+pub struct CellMeta {
+    decay: u8,
+    tiles: Vec<Option<Tile>>,
+    active: usize,
+}
+
+impl CellMeta {
+    /// Empty layer whose values drop by `decay` every tick; 0 keeps them until overwritten
+    pub fn new(decay: u8) -> Self {
+        Self {
+            decay,
+            tiles: (0..TILES_X * TILES_Y).map(|_| None).collect(),
+            active: 0,
+        }
+    }
+
+    pub fn decay(&self) -> u8 {
+        self.decay
+    }
+
+    /// Tiles holding storage
+    pub fn active_tiles(&self) -> usize {
+        self.active
+    }
+
+    /// Value of the cell at tick `now`
+    #[inline]
+    pub fn get(&self, point: &Point, now: u64) -> u8 {
+        let (tile, offset) = locate(point);
+        match &self.tiles[tile] {
+            Some(tile) => faded(tile.values[offset], self.fade(now.saturating_sub(tile.updated))),
+            None => 0,
+        }
+    }
+
+    pub fn set(&mut self, point: Point, value: u8, now: u64) {
+        let (tile, offset) = locate(&point);
+        if value == 0 && self.tiles[tile].is_none() {
+            return;
+        }
+        self.settled(tile, now).values[offset] = value;
+    }
+
+    /// Add `amount` to the cell, saturating at `u8::MAX`. Returns the new value.
+    pub fn raise(&mut self, point: Point, amount: u8, now: u64) -> u8 {
+        let (tile, offset) = locate(&point);
+        let value = &mut self.settled(tile, now).values[offset];
+        *value = value.saturating_add(amount);
+        *value
+    }
+
+    /// Bring every tile up to tick `now` and release the ones that faded to zero
+    pub fn settle(&mut self, now: u64) {
+        for index in 0..self.tiles.len() {
+            if self.tiles[index].is_none() {
+                continue;
+            }
+            if self.settled(index, now).values.iter().all(|&value| value == 0) {
+                self.tiles[index] = None;
+                self.active -= 1;
+            }
+        }
+    }
+
+    /// Drop every value and release all tiles
+    pub fn clear(&mut self) {
+        self.tiles.iter_mut().for_each(|tile| *tile = None);
+        self.active = 0;
+    }
+
+    // Tile `index` with its values decayed to `now`, allocated if it had none
+    fn settled(&mut self, index: usize, now: u64) -> &mut Tile {
+        let fade = match &self.tiles[index] {
+            Some(tile) => self.fade(now.saturating_sub(tile.updated)),
+            None => {
+                self.active += 1;
+                0
+            }
+        };
+        let tile = self.tiles[index].get_or_insert_with(|| Tile {
+            values: vec![0; TILE_CELLS].into_boxed_slice(),
+            updated: now,
+        });
+        if fade > 0 {
+            tile.values.iter_mut().for_each(|value| *value = faded(*value, fade));
+        }
+        tile.updated = tile.updated.max(now);
+        tile
+    }
+
+    // Total decay over `ticks`, capped where every value has reached zero
+    #[inline]
+    fn fade(&self, ticks: u64) -> u8 {
+        (self.decay as u64).saturating_mul(ticks).min(u8::MAX as u64) as u8
+    }
+}
+
+#[inline(always)]
+fn faded(value: u8, fade: u8) -> u8 {
+    value.saturating_sub(fade)
+}
+
+// Tile index and offset within the tile. Edge tiles are allocated whole; their cells
+// past the grid edge are never addressed.
+#[inline(always)]
+fn locate(point: &Point) -> (usize, usize) {
+    let (x, y) = (point.x as usize, point.y as usize);
+    debug_assert!(x < GRID_WIDTH);
+    let tile = (y >> TILE_BITS) * TILES_X + (x >> TILE_BITS);
+    let offset = ((y & (TILE_SIZE - 1)) << TILE_BITS) | (x & (TILE_SIZE - 1));
+    (tile, offset)
+}
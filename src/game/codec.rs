@@ -0,0 +1,274 @@
+use crate::game::grid::{Cell, GRID_HEIGHT, GRID_WIDTH, Grid, TILE_SIZE, TILES_X, TILES_Y};
+use crate::game::types::Point;
+use std::io::{self, Read, Write};
+
+/// How the grid section of a snapshot is compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridCodec {
+    /// Row-major runs `run_count:u64` x `(cell:u8 len:u32)`; runs may span rows
+    #[default]
+    Rle,
+    /// A bitmask of non-empty tiles, then per marked tile `count:varint` and `count` x
+    /// `(gap:varint cell:u8)`, gaps counting empty cells row-major inside the tile. Small
+    /// when the grid is sparse.
+    Bitmask,
+    /// The raw cell codes as an LZ4 frame, `len:u64` then the frame
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+impl GridCodec {
+    /// Every codec this build supports
+    pub const ALL: &[GridCodec] = &[
+        GridCodec::Rle,
+        GridCodec::Bitmask,
+        #[cfg(feature = "lz4")]
+        GridCodec::Lz4,
+    ];
+
+    /// Encoding used in snapshot headers
+    pub fn to_u8(self) -> u8 {
+        match self {
+            GridCodec::Rle => 0,
+            GridCodec::Bitmask => 1,
+            #[cfg(feature = "lz4")]
+            GridCodec::Lz4 => 2,
+        }
+    }
+
+    /// Inverse of [`GridCodec::to_u8`]. Codes of codecs left out of this build are errors.
+    pub fn from_u8(value: u8) -> io::Result<Self> {
+        match value {
+            0 => Ok(GridCodec::Rle),
+            1 => Ok(GridCodec::Bitmask),
+            #[cfg(feature = "lz4")]
+            2 => Ok(GridCodec::Lz4),
+            #[cfg(not(feature = "lz4"))]
+            2 => Err(invalid_data("grid is LZ4 compressed; rebuild with --features lz4")),
+            _ => Err(invalid_data("unknown grid codec")),
+        }
+    }
+
+    /// Lowercase name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            GridCodec::Rle => "rle",
+            GridCodec::Bitmask => "bitmask",
+            #[cfg(feature = "lz4")]
+            GridCodec::Lz4 => "lz4",
+        }
+    }
+
+    /// Inverse of [`GridCodec::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|codec| codec.name() == name)
+    }
+}
+
+/// Write `grid` compressed with `codec`
+pub fn encode_grid<W: Write>(grid: &Grid, codec: GridCodec, writer: &mut W) -> io::Result<()> {
+    match codec {
+        GridCodec::Rle => encode_rle(grid, writer),
+        GridCodec::Bitmask => encode_bitmask(grid, writer),
+        #[cfg(feature = "lz4")]
+        GridCodec::Lz4 => encode_lz4(grid, writer),
+    }
+}
+
+/// Read a grid written by [`encode_grid`] with the same codec
+pub fn decode_grid<R: Read>(codec: GridCodec, reader: &mut R) -> io::Result<Grid> {
+    match codec {
+        GridCodec::Rle => decode_rle(reader),
+        GridCodec::Bitmask => decode_bitmask(reader),
+        #[cfg(feature = "lz4")]
+        GridCodec::Lz4 => decode_lz4(reader),
+    }
+}
+
+fn encode_rle<W: Write>(grid: &Grid, writer: &mut W) -> io::Result<()> {
+    let runs = grid_runs(grid);
+    writer.write_all(&(runs.len() as u64).to_le_bytes())?;
+    for (cell, len) in runs {
+        writer.write_all(&[cell.to_u8()])?;
+        writer.write_all(&len.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn decode_rle<R: Read>(reader: &mut R) -> io::Result<Grid> {
+    let mut grid = Grid::new();
+    let run_count = u64::from_le_bytes(read_array(reader)?);
+    let mut offset = 0usize;
+    for _ in 0..run_count {
+        let [cell] = read_array::<_, 1>(reader)?;
+        let cell = Cell::from_u8(cell).ok_or_else(|| invalid_data("invalid cell"))?;
+        let len = u32::from_le_bytes(read_array(reader)?) as usize;
+        if offset + len > GRID_WIDTH * GRID_HEIGHT {
+            return Err(invalid_data("grid runs exceed grid size"));
+        }
+        // A fresh grid is all empty, so only occupied runs need writing
+        if cell != Cell::Empty {
+            for index in offset..offset + len {
+                let point = Point {
+                    x: (index % GRID_WIDTH) as u16,
+                    y: (index / GRID_WIDTH) as u16,
+                };
+                grid.set_cell(point, cell);
+            }
+        }
+        offset += len;
+    }
+    if offset != GRID_WIDTH * GRID_HEIGHT {
+        return Err(invalid_data("grid runs do not cover the grid"));
+    }
+    Ok(grid)
+}
+
+// Row-major run-length encoding of the grid; runs may span rows
+fn grid_runs(grid: &Grid) -> Vec<(Cell, u32)> {
+    let mut runs: Vec<(Cell, u32)> = Vec::new();
+    for y in 0..GRID_HEIGHT {
+        for &cell in grid.row(y) {
+            match runs.last_mut() {
+                Some((last, len)) if *last == cell => *len += 1,
+                _ => runs.push((cell, 1)),
+            }
+        }
+    }
+    runs
+}
+
+const TILE_MASK_BYTES: usize = (TILES_X * TILES_Y).div_ceil(8);
+
+fn encode_bitmask<W: Write>(grid: &Grid, writer: &mut W) -> io::Result<()> {
+    let mut mask = [0u8; TILE_MASK_BYTES];
+    let mut body = Vec::new();
+    // Non-empty cells of the current tile as (index inside the tile, cell)
+    let mut cells: Vec<(u32, Cell)> = Vec::new();
+    for tile_y in 0..TILES_Y {
+        for tile_x in 0..TILES_X {
+            cells.clear();
+            let x_start = tile_x * TILE_SIZE;
+            let x_end = (x_start + TILE_SIZE).min(GRID_WIDTH);
+            for y in tile_y * TILE_SIZE..((tile_y + 1) * TILE_SIZE).min(GRID_HEIGHT) {
+                let row_start = (y - tile_y * TILE_SIZE) * TILE_SIZE;
+                for (dx, &cell) in grid.row(y)[x_start..x_end].iter().enumerate() {
+                    if cell != Cell::Empty {
+                        cells.push(((row_start + dx) as u32, cell));
+                    }
+                }
+            }
+            if cells.is_empty() {
+                continue;
+            }
+
+            let tile = tile_y * TILES_X + tile_x;
+            mask[tile / 8] |= 1 << (tile % 8);
+            write_varint(&mut body, cells.len() as u64);
+            let mut next = 0;
+            for &(index, cell) in &cells {
+                write_varint(&mut body, (index - next) as u64);
+                body.push(cell.to_u8());
+                next = index + 1;
+            }
+        }
+    }
+    writer.write_all(&mask)?;
+    writer.write_all(&body)
+}
+
+fn decode_bitmask<R: Read>(reader: &mut R) -> io::Result<Grid> {
+    let mask: [u8; TILE_MASK_BYTES] = read_array(reader)?;
+    let mut grid = Grid::new();
+    for tile in 0..TILES_X * TILES_Y {
+        if mask[tile / 8] & (1 << (tile % 8)) == 0 {
+            continue;
+        }
+        let (tile_x, tile_y) = (tile % TILES_X, tile / TILES_X);
+        let count = read_varint(reader)?;
+        let mut index = 0u64;
+        for _ in 0..count {
+            index = index.saturating_add(read_varint(reader)?);
+            let [cell] = read_array::<_, 1>(reader)?;
+            let cell = Cell::from_u8(cell).ok_or_else(|| invalid_data("invalid cell"))?;
+            let x = tile_x * TILE_SIZE + (index as usize % TILE_SIZE);
+            let y = tile_y * TILE_SIZE + (index as usize / TILE_SIZE);
+            if x >= GRID_WIDTH || y >= GRID_HEIGHT || y >= (tile_y + 1) * TILE_SIZE {
+                return Err(invalid_data("cell outside its tile"));
+            }
+            grid.set_cell(Point { x: x as u16, y: y as u16 }, cell);
+            index += 1;
+        }
+    }
+    Ok(grid)
+}
+
+#[cfg(feature = "lz4")]
+fn encode_lz4<W: Write>(grid: &Grid, writer: &mut W) -> io::Result<()> {
+    let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+    let mut row = vec![0u8; GRID_WIDTH];
+    for y in 0..GRID_HEIGHT {
+        for (code, cell) in row.iter_mut().zip(grid.row(y)) {
+            *code = cell.to_u8();
+        }
+        encoder.write_all(&row)?;
+    }
+    let frame = encoder.finish().map_err(io::Error::other)?;
+    writer.write_all(&(frame.len() as u64).to_le_bytes())?;
+    writer.write_all(&frame)
+}
+
+#[cfg(feature = "lz4")]
+fn decode_lz4<R: Read>(reader: &mut R) -> io::Result<Grid> {
+    let len = u64::from_le_bytes(read_array(reader)?);
+    let mut frame = Vec::new();
+    reader.take(len).read_to_end(&mut frame)?;
+    if frame.len() as u64 != len {
+        return Err(invalid_data("truncated LZ4 grid"));
+    }
+
+    let mut decoder = lz4_flex::frame::FrameDecoder::new(frame.as_slice());
+    let mut grid = Grid::new();
+    let mut row = vec![0u8; GRID_WIDTH];
+    for y in 0..GRID_HEIGHT {
+        decoder.read_exact(&mut row)?;
+        for (x, &code) in row.iter().enumerate() {
+            let cell = Cell::from_u8(code).ok_or_else(|| invalid_data("invalid cell"))?;
+            if cell != Cell::Empty {
+                grid.set_cell(Point { x: x as u16, y: y as u16 }, cell);
+            }
+        }
+    }
+    Ok(grid)
+}
+
+// LEB128: 7 bits per byte, low groups first, high bit set on all but the last byte
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let [byte] = read_array::<_, 1>(reader)?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("varint too long"))
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
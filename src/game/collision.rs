@@ -0,0 +1,178 @@
+use crate::game::grid::Cell;
+use crate::game::types::Point;
+
+/// What happens to a snake whose move was contested
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fate {
+    /// Move into the target cell as if it were free
+    Move,
+    /// Stay in place this tick (no head write, no tail clear)
+    Stay,
+    /// Die where it stands
+    Die,
+}
+
+/// Decision returned by a [`CollisionPolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub mover: Fate,
+    /// Also kill the contender that claimed the cell earlier this tick
+    pub kill_contender: bool,
+}
+
+impl Resolution {
+    pub const fn mover(fate: Fate) -> Self {
+        Self {
+            mover: fate,
+            kill_contender: false,
+        }
+    }
+}
+
+/// A death attributed to another snake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Kill {
+    pub killer: u32,
+    pub victim: u32,
+    /// Victim length at death
+    pub victim_length: usize,
+}
+
+/// Who eats an apple that several heads reach in the same tick. The others then hit the
+/// winner's fresh head and are resolved by the [`CollisionPolicy`] as usual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AppleTieBreak {
+    /// The snake processed first: lowest id within the apple's spatial bucket
+    #[default]
+    BucketOrder,
+    /// A uniform pick among the contenders, from the engine's contest stream
+    Random,
+    /// The longest contender; equal lengths fall back to bucket order
+    Longest,
+    /// The contender whose input came first in the tick's input order (see
+    /// `StampedInput::sort_batch`); snakes without an input go last, ties fall back to
+    /// bucket order
+    FirstInput,
+}
+
+impl AppleTieBreak {
+    pub const ALL: [AppleTieBreak; 4] = [
+        AppleTieBreak::BucketOrder,
+        AppleTieBreak::Random,
+        AppleTieBreak::Longest,
+        AppleTieBreak::FirstInput,
+    ];
+
+    /// Compact encoding used by snapshots
+    pub fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Inverse of [`AppleTieBreak::to_u8`]
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    /// Name used by scenario files
+    pub fn name(self) -> &'static str {
+        match self {
+            AppleTieBreak::BucketOrder => "bucket",
+            AppleTieBreak::Random => "random",
+            AppleTieBreak::Longest => "longest",
+            AppleTieBreak::FirstInput => "first_input",
+        }
+    }
+
+    /// Inverse of [`AppleTieBreak::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tie_break| tie_break.name() == name)
+    }
+}
+
+/// An apple two or more heads reached in the same tick, one entry per snake that missed it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContestedApple {
+    pub position: Point,
+    /// Snake that ate the apple
+    pub eater: u32,
+    /// Snake that reached the cell after it
+    pub rival: u32,
+}
+
+/// A snake taking part in a collision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contestant {
+    pub snake_id: u32,
+    /// Body length at the time of resolution
+    pub length: usize,
+}
+
+/// Decides the outcome of contested moves in the resolve phase of `tick()`.
+///
+/// Only consulted when the target cell holds a snake or another snake already moved
+/// into the same cell this tick; uncontested moves never reach the policy.
+/// The engine applies the returned resolution as-is, so a policy answering `Move`
+/// for a body cell is responsible for the overlap it creates.
+pub trait CollisionPolicy: Send + Sync {
+    fn resolve(&mut self, mover: &Contestant, target: Cell, contender: Option<&Contestant>) -> Resolution;
+}
+
+/// Classic rules: hitting any snake cell kills the mover; on a head-on tie the snake
+/// processed first keeps the cell.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClassicPolicy;
+
+impl CollisionPolicy for ClassicPolicy {
+    #[inline(always)]
+    fn resolve(&mut self, _mover: &Contestant, _target: Cell, _contender: Option<&Contestant>) -> Resolution {
+        Resolution::mover(Fate::Die)
+    }
+}
+
+/// Head-on collisions kill both snakes; body collisions kill the mover.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BothDiePolicy;
+
+impl CollisionPolicy for BothDiePolicy {
+    fn resolve(&mut self, _mover: &Contestant, _target: Cell, contender: Option<&Contestant>) -> Resolution {
+        Resolution {
+            mover: Fate::Die,
+            kill_contender: contender.is_some(),
+        }
+    }
+}
+
+/// Head-on collisions are won by the longer snake (ties kill both); body collisions kill the mover.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BiggerWinsPolicy;
+
+impl CollisionPolicy for BiggerWinsPolicy {
+    fn resolve(&mut self, mover: &Contestant, target: Cell, contender: Option<&Contestant>) -> Resolution {
+        match contender {
+            // The contender's fresh head is the only snake in the cell
+            Some(contender) if mover.length > contender.length => Resolution {
+                mover: Fate::Move,
+                kill_contender: true,
+            },
+            Some(contender) if mover.length == contender.length => Resolution {
+                mover: Fate::Die,
+                kill_contender: true,
+            },
+            Some(_) => Resolution::mover(Fate::Die),
+            None => {
+                debug_assert_eq!(target, Cell::Snake);
+                Resolution::mover(Fate::Die)
+            }
+        }
+    }
+}
+
+/// Blocked snakes stay where they are for the tick instead of dying.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BounceBackPolicy;
+
+impl CollisionPolicy for BounceBackPolicy {
+    fn resolve(&mut self, _mover: &Contestant, _target: Cell, _contender: Option<&Contestant>) -> Resolution {
+        Resolution::mover(Fate::Stay)
+    }
+}
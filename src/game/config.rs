@@ -0,0 +1,117 @@
+use crate::game::apple::{APPLE_CAPACITY, AppleHotspots, AppleRespawn};
+use crate::game::collision::AppleTieBreak;
+use crate::game::engine::{MAX_RECORD_SNAKES, NUM_BUCKETS};
+use crate::game::snake::{ReversalRule, SNAKE_CAPACITY};
+use std::fmt;
+
+/// Runtime sizing of a game. Defaults match the historical compile-time constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameConfig {
+    /// Number of snakes spawned by the random generators and reserved up front
+    pub snake_capacity: usize,
+    /// Maximum number of apples alive on the grid at once; `GameState::set_apple_scaling`
+    /// retargets it from the population
+    pub apple_capacity: usize,
+    /// Work out who caused each death in tick() and report it in `GameState::kills`
+    pub attribute_kills: bool,
+    /// Percentage of a victim's length its killer grows by over the following ticks.
+    /// Non-zero implies kill attribution.
+    pub kill_growth_percent: u8,
+    /// Seed of the engine RNG (apple spawns, respawns)
+    pub rng_seed: u64,
+    /// Split power-ups kept on the grid; 0 disables the mechanic
+    pub split_powerups: usize,
+    /// Shortest snake a power-up splits. Shorter snakes eat it without effect.
+    pub split_min_length: usize,
+    /// Ticks an apple lasts before it despawns and respawns elsewhere; 0 keeps apples forever
+    pub apple_ttl: u32,
+    /// Fairness policy for apple spawns: keep new apples off this many cells straight
+    /// ahead of every head, so nobody gets fed just for happening to face the spawn.
+    /// 0 lets apples spawn anywhere.
+    pub apple_head_clearance: u8,
+    /// Who eats an apple several heads reach in the same tick
+    pub apple_tie_break: AppleTieBreak,
+    /// Which snakes may turn straight back on themselves
+    pub reversal_rule: ReversalRule,
+    /// Fewest ticks between two direction changes of one snake; turns that come sooner
+    /// are dropped and counted in `GameState::throttled_inputs`. 0 leaves turns unlimited.
+    pub turn_interval: u32,
+    /// Spawn apples around drifting hotspots instead of uniformly; off by default
+    pub apple_hotspots: AppleHotspots,
+    /// Most cells the whole population may grow by in one tick; growth past it, from
+    /// apples or kills, stays queued for later ticks. 0 leaves growth unlimited.
+    pub max_growth_per_tick: u32,
+    /// Longest a snake may get: growing past it drops the tail instead. 0 leaves length
+    /// unlimited.
+    pub max_snake_length: u32,
+    /// Turn tails dropped by `max_snake_length` into apples, within `apple_capacity`
+    pub length_cap_apples: bool,
+    /// Delay, per-tick limit and region of eaten apples' replacements; by default each
+    /// is replaced anywhere on the tick it is eaten
+    pub apple_respawn: AppleRespawn,
+}
+
+impl GameConfig {
+    /// Whether tick() needs to attribute deaths to killers
+    #[inline(always)]
+    pub fn tracks_kills(&self) -> bool {
+        self.attribute_kills || self.kill_growth_percent > 0
+    }
+
+    /// Initial capacity of each spatial bucket.
+    /// 50% overestimate of the even split to prevent frequent reallocations.
+    pub fn expected_snakes_per_bucket(&self) -> usize {
+        (self.snake_capacity.div_ceil(NUM_BUCKETS) * 3) / 2
+    }
+
+    /// Check the config describes a game the engine can build and tick
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.snake_capacity > MAX_RECORD_SNAKES {
+            return Err(ConfigError::TooManySnakes(self.snake_capacity));
+        }
+        Ok(())
+    }
+}
+
+/// Why `GameConfig::validate` rejected a config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// `snake_capacity` above [`MAX_RECORD_SNAKES`], the most ids a movement record holds
+    TooManySnakes(usize),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::TooManySnakes(capacity) => {
+                write!(f, "snake capacity {} exceeds the maximum of {}", capacity, MAX_RECORD_SNAKES)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            snake_capacity: SNAKE_CAPACITY,
+            apple_capacity: APPLE_CAPACITY,
+            attribute_kills: false,
+            kill_growth_percent: 0,
+            rng_seed: 0x5eed,
+            split_powerups: 0,
+            split_min_length: 4,
+            apple_ttl: 0,
+            apple_head_clearance: 0,
+            apple_tie_break: AppleTieBreak::BucketOrder,
+            reversal_rule: ReversalRule::Never,
+            turn_interval: 0,
+            apple_hotspots: AppleHotspots::OFF,
+            max_growth_per_tick: 0,
+            max_snake_length: 0,
+            length_cap_apples: false,
+            apple_respawn: AppleRespawn::IMMEDIATE,
+        }
+    }
+}
@@ -0,0 +1,462 @@
+//! Differences between two states of one game.
+//!
+//! [`SnapshotDelta::between`] lists what a snapshot of `next` holds that one of `prev`
+//! does not: the grid cells that changed (from [`Grid::diff`](crate::game::grid::Grid::diff),
+//! so unchanged tiles cost nothing), the snakes whose direction, life, growth or body
+//! changed, the snakes whose viewer metadata (see [`crate::game::snakemeta`]) changed, and
+//! the counters and clocks, which are carried whole, the apple expiry and respawn
+//! wheels when they changed, and the leaderboard scores that moved. [`SnapshotDelta::apply`]
+//! turns `prev` into `next`. A body that continues the old one, as after a tick, travels
+//! as the tail cells dropped and the head cells added.
+//!
+//! Both states must share a config, which is not part of a delta, except for the apple
+//! capacity that apple scaling retargets as the game runs. Per-tick reports
+//! (kills, splits, contested apples) and cell metadata are not snapshot state and are
+//! left alone.
+
+use crate::game::apple::{AppleExpiry, RespawnWheel};
+use crate::game::body::Body;
+use crate::game::engine::GameState;
+use crate::game::grid::Cell;
+use crate::game::leaderboard::Leaderboard;
+use crate::game::metrics::PopulationMetrics;
+use crate::game::rng::RngStreams;
+use crate::game::snake::{GridAwareSnake, Snake};
+use crate::game::snakemeta::{SnakeMeta, read_meta, write_meta};
+use crate::game::snapshot::{invalid_data, read_array, read_population, read_rng, write_population, write_rng};
+use crate::game::types::{Direction, Point};
+use std::collections::BTreeSet;
+use std::io::{self, Read, Write};
+
+pub const DELTA_MAGIC: [u8; 4] = *b"HFSD";
+pub const DELTA_VERSION: u16 = 6;
+
+/// How a snake's body got from one state to the next
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BodyChange {
+    /// Drop `trimmed` tail cells, then add `grown` as heads, oldest first
+    Shift { trimmed: u32, grown: Vec<Point> },
+    /// A body unrelated to the old one, tail first
+    Replace(Vec<Point>),
+}
+
+/// A snake's state in `next`, for a snake that changed or is new
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnakeChange {
+    pub id: u32,
+    pub direction: Direction,
+    pub alive: bool,
+    pub pending_growth: u32,
+    /// See `GameState::turn_ready`
+    pub turn_ready: u64,
+    pub body: BodyChange,
+}
+
+/// Everything that turns a snapshot of one state into a snapshot of a later one.
+///
+/// Encoded layout (little endian):
+/// `magic[4] version:u16 now:u64 cell_count:u32` then `cell_count` x `(x:u16 y:u16 cell:u8)`,
+/// `snake_count:u32 change_count:u32` then per change
+/// `id:u32 direction:u8 alive:u8 pending_growth:u32 turn_ready:u64 kind:u8` and either (kind 0)
+/// `trimmed:u32 len:u32` or (kind 1) `len:u32`, followed by `len` x `(x:u16 y:u16)`,
+/// `num_apples:u64 num_powerups:u64`, the population counters and RNG streams as in a
+/// snapshot, and `has_expiry:u8` followed, if set, by
+/// `expiry_now:u64 deadline_count:u32` and that many `(x:u16 y:u16 deadline:u64)`, then
+/// `meta_count:u32` and per entry `id:u32 present:u8` followed, if set, by the metadata as
+/// in [`crate::game::snakemeta::SnakeMetaTable::write_to`], then `has_capacity:u8` followed,
+/// if set, by `apple_capacity:u64`, then `has_respawns:u8` followed, if set, by
+/// `backlog:u64 entry_count:u32` and that many `(due:u64 count:u64)`, then
+/// `score_count:u32` and that many `(snake_id:u32 score:u64)`.
+///
+/// Version 1 predates `turn_ready`, which reads back as 0; versions before 3 carry no
+/// metadata changes, versions before 4 no apple capacity, versions before 5 no
+/// respawn wheel and versions before 6 no leaderboard scores.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotDelta {
+    /// Clock of `next`
+    pub now: u64,
+    /// Cells that differ, with their value in `next`
+    pub cells: Vec<(Point, Cell)>,
+    /// Length of `next.snakes`
+    pub snake_count: u32,
+    /// Snakes that changed or are new, by id
+    pub snakes: Vec<SnakeChange>,
+    pub num_apples: u64,
+    pub num_powerups: u64,
+    pub population: PopulationMetrics,
+    pub rng: RngStreams,
+    /// Apple expiry clock and deadlines, when they changed
+    pub expiry: Option<(u64, Vec<(Point, u64)>)>,
+    /// Viewer metadata that changed, by id; `None` where an entry was removed
+    pub meta: Vec<(u32, Option<SnakeMeta>)>,
+    /// `config.apple_capacity` of `next`, when apple scaling changed it
+    pub apple_capacity: Option<u64>,
+    /// Apple respawn backlog and scheduled `(due, count)` pairs, when they changed
+    pub respawns: Option<(u64, Vec<(u64, u64)>)>,
+    /// Leaderboard scores of `next` that differ, by id; 0 where a score was cleared.
+    /// Applied only to a state keeping a board.
+    pub scores: Vec<(u32, u64)>,
+}
+
+impl SnapshotDelta {
+    pub fn between(prev: &GameState, next: &GameState) -> Self {
+        let cells = prev.grid.diff(&next.grid).into_iter().map(|(point, _, cell)| (point, cell)).collect();
+
+        let snakes = next
+            .snakes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, snake)| {
+                let snake = snake.snake();
+                let old = prev.snakes.get(index).map(GridAwareSnake::snake);
+                let body = match old {
+                    Some(old) => body_change(&old.body, &snake.body),
+                    None => Some(BodyChange::Replace(snake.body.iter().copied().collect())),
+                };
+                let turn_ready = next.turn_ready(snake.id);
+                let flags_changed = old.is_none_or(|old| {
+                    (old.direction, old.is_alive, old.pending_growth, prev.turn_ready(old.id))
+                        != (snake.direction, snake.is_alive, snake.pending_growth, turn_ready)
+                });
+                (flags_changed || body.is_some()).then(|| SnakeChange {
+                    id: snake.id,
+                    direction: snake.direction,
+                    alive: snake.is_alive,
+                    pending_growth: snake.pending_growth,
+                    turn_ready,
+                    body: body.unwrap_or(BodyChange::Shift { trimmed: 0, grown: Vec::new() }),
+                })
+            })
+            .collect();
+
+        let ids: BTreeSet<u32> = prev.snake_meta.iter().chain(next.snake_meta.iter()).map(|(id, _)| id).collect();
+        let meta = ids
+            .into_iter()
+            .filter(|&id| prev.snake_meta.get(id) != next.snake_meta.get(id))
+            .map(|id| (id, next.snake_meta.get(id).cloned()))
+            .collect();
+
+        let (prev_expiry, next_expiry) = (expiry(&prev.apple_expiry), expiry(&next.apple_expiry));
+        let (prev_respawns, next_respawns) = (respawns(&prev.apple_respawns), respawns(&next.apple_respawns));
+        let scores = match (prev.leaderboard(), next.leaderboard()) {
+            (_, None) => Vec::new(),
+            (prev_board, Some(board)) => {
+                let prev_score = |id| prev_board.map_or(0, |prev| prev.score(id));
+                let ids: BTreeSet<u32> =
+                    prev_board.into_iter().flat_map(Leaderboard::scores).chain(board.scores()).map(|standing| standing.snake_id).collect();
+                ids.into_iter().filter(|&id| prev_score(id) != board.score(id)).map(|id| (id, board.score(id))).collect()
+            }
+        };
+        Self {
+            now: next.now(),
+            cells,
+            snake_count: next.snakes.len() as u32,
+            snakes,
+            num_apples: next.num_apples,
+            num_powerups: next.num_powerups,
+            population: next.population,
+            rng: next.rng,
+            expiry: (prev_expiry != next_expiry).then_some(next_expiry),
+            meta,
+            apple_capacity: (prev.config.apple_capacity != next.config.apple_capacity).then_some(next.config.apple_capacity as u64),
+            respawns: (prev_respawns != next_respawns).then_some(next_respawns),
+            scores,
+        }
+    }
+
+    /// Turn `prev`, the state this delta was taken from, into `next`
+    pub fn apply(&self, state: &mut GameState) {
+        state.discard_speculation();
+        for (point, cell) in &self.cells {
+            state.grid.set_cell(*point, *cell);
+        }
+
+        state.snakes.truncate(self.snake_count as usize);
+        state.free_ids.split_off(&self.snake_count);
+        for change in &self.snakes {
+            let index = change.id as usize;
+            if index == state.snakes.len() {
+                state.snakes.push(GridAwareSnake::from_placed(Snake {
+                    id: change.id,
+                    body: Body::new(),
+                    direction: change.direction,
+                    is_alive: change.alive,
+                    pending_growth: 0,
+                }));
+            }
+            state.set_turn_ready(change.id, change.turn_ready);
+            let snake = state.snakes[index].snake_mut();
+            snake.direction = change.direction;
+            snake.is_alive = change.alive;
+            snake.pending_growth = change.pending_growth;
+            match &change.body {
+                BodyChange::Shift { trimmed, grown } => {
+                    for _ in 0..*trimmed {
+                        snake.body.pop_front();
+                    }
+                    snake.body.extend(grown.iter().copied());
+                }
+                BodyChange::Replace(cells) => snake.body = cells.iter().copied().collect(),
+            }
+            if change.alive {
+                state.free_ids.remove(&change.id);
+            } else {
+                state.free_ids.insert(change.id);
+            }
+        }
+
+        state.num_apples = self.num_apples;
+        state.num_powerups = self.num_powerups;
+        state.population = self.population;
+        state.rng = self.rng;
+        if let Some((now, deadlines)) = &self.expiry {
+            state.apple_expiry = AppleExpiry::restore(state.config.apple_ttl, *now, deadlines.iter().copied());
+        }
+        for (id, meta) in &self.meta {
+            state.set_snake_meta(*id, meta.clone());
+        }
+        if let Some(capacity) = self.apple_capacity {
+            state.config.apple_capacity = capacity as usize;
+        }
+        if let Some((backlog, entries)) = &self.respawns {
+            state.apple_respawns = RespawnWheel::restore(self.now, *backlog, entries.iter().copied());
+        }
+        if let Some(board) = &mut state.leaderboard {
+            for &(id, score) in &self.scores {
+                board.set_score(id, score);
+            }
+            board.end_tick();
+        }
+        state.reindex_heads();
+        state.set_now(self.now);
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&DELTA_MAGIC)?;
+        writer.write_all(&DELTA_VERSION.to_le_bytes())?;
+        writer.write_all(&self.now.to_le_bytes())?;
+        writer.write_all(&(self.cells.len() as u32).to_le_bytes())?;
+        for (point, cell) in &self.cells {
+            write_point(writer, *point)?;
+            writer.write_all(&[cell.to_u8()])?;
+        }
+
+        writer.write_all(&self.snake_count.to_le_bytes())?;
+        writer.write_all(&(self.snakes.len() as u32).to_le_bytes())?;
+        for change in &self.snakes {
+            writer.write_all(&change.id.to_le_bytes())?;
+            writer.write_all(&[change.direction.to_u8(), change.alive as u8])?;
+            writer.write_all(&change.pending_growth.to_le_bytes())?;
+            writer.write_all(&change.turn_ready.to_le_bytes())?;
+            let cells = match &change.body {
+                BodyChange::Shift { trimmed, grown } => {
+                    writer.write_all(&[0])?;
+                    writer.write_all(&trimmed.to_le_bytes())?;
+                    grown
+                }
+                BodyChange::Replace(cells) => {
+                    writer.write_all(&[1])?;
+                    cells
+                }
+            };
+            writer.write_all(&(cells.len() as u32).to_le_bytes())?;
+            for point in cells {
+                write_point(writer, *point)?;
+            }
+        }
+
+        writer.write_all(&self.num_apples.to_le_bytes())?;
+        writer.write_all(&self.num_powerups.to_le_bytes())?;
+        write_population(&self.population, writer)?;
+        write_rng(&self.rng, writer)?;
+        writer.write_all(&[self.expiry.is_some() as u8])?;
+        if let Some((now, deadlines)) = &self.expiry {
+            writer.write_all(&now.to_le_bytes())?;
+            writer.write_all(&(deadlines.len() as u32).to_le_bytes())?;
+            for (point, deadline) in deadlines {
+                write_point(writer, *point)?;
+                writer.write_all(&deadline.to_le_bytes())?;
+            }
+        }
+        writer.write_all(&(self.meta.len() as u32).to_le_bytes())?;
+        for (id, meta) in &self.meta {
+            writer.write_all(&id.to_le_bytes())?;
+            writer.write_all(&[meta.is_some() as u8])?;
+            if let Some(meta) = meta {
+                write_meta(writer, meta)?;
+            }
+        }
+        writer.write_all(&[self.apple_capacity.is_some() as u8])?;
+        if let Some(capacity) = self.apple_capacity {
+            writer.write_all(&capacity.to_le_bytes())?;
+        }
+        writer.write_all(&[self.respawns.is_some() as u8])?;
+        if let Some((backlog, entries)) = &self.respawns {
+            writer.write_all(&backlog.to_le_bytes())?;
+            writer.write_all(&(entries.len() as u32).to_le_bytes())?;
+            for (due, count) in entries {
+                writer.write_all(&due.to_le_bytes())?;
+                writer.write_all(&count.to_le_bytes())?;
+            }
+        }
+        writer.write_all(&(self.scores.len() as u32).to_le_bytes())?;
+        for (id, score) in &self.scores {
+            writer.write_all(&id.to_le_bytes())?;
+            writer.write_all(&score.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != DELTA_MAGIC {
+            return Err(invalid_data("not a snapshot delta"));
+        }
+        let version = u16::from_le_bytes(read_array(reader)?);
+        if !(1..=DELTA_VERSION).contains(&version) {
+            return Err(invalid_data("unsupported snapshot delta version"));
+        }
+        let now = u64::from_le_bytes(read_array(reader)?);
+        let cell_count = u32::from_le_bytes(read_array(reader)?);
+        let mut cells = Vec::with_capacity(cell_count as usize);
+        for _ in 0..cell_count {
+            let point = read_point(reader)?;
+            let [cell] = read_array(reader)?;
+            cells.push((point, Cell::from_u8(cell).ok_or_else(|| invalid_data("invalid cell"))?));
+        }
+
+        let snake_count = u32::from_le_bytes(read_array(reader)?);
+        let change_count = u32::from_le_bytes(read_array(reader)?);
+        let mut snakes = Vec::with_capacity(change_count as usize);
+        for _ in 0..change_count {
+            let id = u32::from_le_bytes(read_array(reader)?);
+            let [direction, alive] = read_array(reader)?;
+            let direction = Direction::from_u8(direction).ok_or_else(|| invalid_data("invalid direction"))?;
+            let pending_growth = u32::from_le_bytes(read_array(reader)?);
+            let turn_ready = if version >= 2 { u64::from_le_bytes(read_array(reader)?) } else { 0 };
+            let [kind] = read_array(reader)?;
+            let trimmed = match kind {
+                0 => Some(u32::from_le_bytes(read_array(reader)?)),
+                1 => None,
+                _ => return Err(invalid_data("invalid body change")),
+            };
+            let len = u32::from_le_bytes(read_array(reader)?);
+            let points = (0..len).map(|_| read_point(reader)).collect::<io::Result<Vec<_>>>()?;
+            let body = match trimmed {
+                Some(trimmed) => BodyChange::Shift { trimmed, grown: points },
+                None => BodyChange::Replace(points),
+            };
+            snakes.push(SnakeChange { id, direction, alive: alive != 0, pending_growth, turn_ready, body });
+        }
+
+        let num_apples = u64::from_le_bytes(read_array(reader)?);
+        let num_powerups = u64::from_le_bytes(read_array(reader)?);
+        let population = read_population(reader)?;
+        let rng = read_rng(reader)?;
+        let [has_expiry] = read_array(reader)?;
+        let expiry = if has_expiry != 0 {
+            let expiry_now = u64::from_le_bytes(read_array(reader)?);
+            let count = u32::from_le_bytes(read_array(reader)?);
+            let mut deadlines = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let point = read_point(reader)?;
+                deadlines.push((point, u64::from_le_bytes(read_array(reader)?)));
+            }
+            Some((expiry_now, deadlines))
+        } else {
+            None
+        };
+        let mut meta = Vec::new();
+        if version >= 3 {
+            let count = u32::from_le_bytes(read_array(reader)?);
+            for _ in 0..count {
+                let id = u32::from_le_bytes(read_array(reader)?);
+                let [present] = read_array(reader)?;
+                meta.push((id, if present != 0 { Some(read_meta(reader)?) } else { None }));
+            }
+        }
+        let mut apple_capacity = None;
+        if version >= 4 {
+            let [has_capacity] = read_array(reader)?;
+            if has_capacity != 0 {
+                apple_capacity = Some(u64::from_le_bytes(read_array(reader)?));
+            }
+        }
+        let mut respawns = None;
+        if version >= 5 {
+            let [has_respawns] = read_array(reader)?;
+            if has_respawns != 0 {
+                let backlog = u64::from_le_bytes(read_array(reader)?);
+                let count = u32::from_le_bytes(read_array(reader)?);
+                let mut entries = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let due = u64::from_le_bytes(read_array(reader)?);
+                    entries.push((due, u64::from_le_bytes(read_array(reader)?)));
+                }
+                respawns = Some((backlog, entries));
+            }
+        }
+        let mut scores = Vec::new();
+        if version >= 6 {
+            let count = u32::from_le_bytes(read_array(reader)?);
+            for _ in 0..count {
+                let id = u32::from_le_bytes(read_array(reader)?);
+                scores.push((id, u64::from_le_bytes(read_array(reader)?)));
+            }
+        }
+
+        Ok(Self {
+            now,
+            cells,
+            snake_count,
+            snakes,
+            num_apples,
+            num_powerups,
+            population,
+            rng,
+            expiry,
+            meta,
+            apple_capacity,
+            respawns,
+            scores,
+        })
+    }
+}
+
+// None when the bodies are equal; a shift when `next` starts with what is left of `prev`
+// after dropping some tail cells
+fn body_change(prev: &Body, next: &Body) -> Option<BodyChange> {
+    if prev == next {
+        return None;
+    }
+    for trimmed in 0..prev.len() {
+        let kept = prev.len() - trimmed;
+        if kept <= next.len() && (0..kept).all(|index| prev.get(trimmed + index) == next.get(index)) {
+            return Some(BodyChange::Shift {
+                trimmed: trimmed as u32,
+                grown: next.iter().skip(kept).copied().collect(),
+            });
+        }
+    }
+    Some(BodyChange::Replace(next.iter().copied().collect()))
+}
+
+fn expiry(expiry: &AppleExpiry) -> (u64, Vec<(Point, u64)>) {
+    (expiry.now(), expiry.entries())
+}
+
+fn respawns(wheel: &RespawnWheel) -> (u64, Vec<(u64, u64)>) {
+    (wheel.backlog(), wheel.entries())
+}
+
+fn write_point<W: Write>(writer: &mut W, point: Point) -> io::Result<()> {
+    writer.write_all(&point.x.to_le_bytes())?;
+    writer.write_all(&point.y.to_le_bytes())
+}
+
+fn read_point<R: Read>(reader: &mut R) -> io::Result<Point> {
+    let x = u16::from_le_bytes(read_array(reader)?);
+    let y = u16::from_le_bytes(read_array(reader)?);
+    Ok(Point { x, y })
+}
@@ -1,148 +1,821 @@
 use crate::game::{
-    apple::{APPLE_CAPACITY, Apple},
-    grid::{self, Grid},
-    snake::{SNAKE_CAPACITY, Snake, GridAwareSnake},
-    types::{Input, Point},
+    apple::{Apple, AppleExpiry, AppleScaling, RespawnWheel},
+    audit::{InputAudit, RejectedInput, Rejection},
+    budget::{Degradations, TickBudget, TickOverrun},
+    cellmeta::CellMeta,
+    collision::{AppleTieBreak, ClassicPolicy, CollisionPolicy, ContestedApple, Contestant, Fate, Kill},
+    config::GameConfig,
+    generator::RandomGenerator,
+    grid::{self, GRID_HEIGHT, GRID_WIDTH, Grid},
+    heads::HeadIndex,
+    leaderboard::Leaderboard,
+    metrics::{BucketStats, PhaseClock, PhaseTimings, PopulationMetrics, TICK_PHASES},
+    motion::MotionTable,
+    powerup::{self, Split},
+    report::TickReport,
+    rng::{RngStreams, SplitMix64, Stream},
+    snake::{SNAKE_CAPACITY, Snake, GridAwareSnake, next_head},
+    snakemeta::{SnakeMeta, SnakeMetaTable},
+    spawn::{JoinConfig, SpawnConfig, SpawnError},
+    systems::SystemRegistry,
+    types::{Input, Point, Rect, StampedInput},
 };
+use crate::input::source::InputSource;
+use crate::runtime::platform::{PAGE_SIZE, lock_vec};
+#[cfg(feature = "phase-sampler")]
+use crate::runtime::sampler::PhaseBeacon;
 use grid::Cell;
 use rand::Rng;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+use std::io;
+#[cfg(feature = "phase-sampler")]
+use std::sync::Arc;
 
 // Bucket partitioning constants for cache-aware processing
 pub const BUCKET_BITS: usize = 8;
 pub const NUM_BUCKETS: usize = 1 << BUCKET_BITS; // 128
-// 50% overestimate to prevent frequent reallocations
-pub const EXPECTED_SNAKES_PER_BUCKET: usize = ((SNAKE_CAPACITY + NUM_BUCKETS - 1) / NUM_BUCKETS * 3) / 2; 
-
-#[derive(Debug, Clone, Copy)]
-pub struct MovementRecord {
-    pub snake_id: u32,
-    pub new_head: Point,
-    pub cell_at_new_head: Cell,
+// 50% overestimate to prevent frequent reallocations (default config; see GameConfig)
+pub const EXPECTED_SNAKES_PER_BUCKET: usize = (SNAKE_CAPACITY.div_ceil(NUM_BUCKETS) * 3) / 2;
+
+/// Snake ids a [`MovementRecord`] can hold
+pub const MAX_RECORD_SNAKES: usize = 1 << RECORD_ID_BITS;
+
+const RECORD_ID_BITS: u32 = 20;
+// The grid is 10,000 cells across, so coordinates take 14 bits rather than 12
+const RECORD_COORD_BITS: u32 = 14;
+const RECORD_X_SHIFT: u32 = RECORD_ID_BITS;
+const RECORD_Y_SHIFT: u32 = RECORD_X_SHIFT + RECORD_COORD_BITS;
+const RECORD_CELL_SHIFT: u32 = RECORD_Y_SHIFT + RECORD_COORD_BITS;
+const RECORD_ID_MASK: u64 = (1 << RECORD_ID_BITS) - 1;
+const RECORD_COORD_MASK: u64 = (1 << RECORD_COORD_BITS) - 1;
+const _: () = assert!(GRID_WIDTH <= 1 << RECORD_COORD_BITS && GRID_HEIGHT <= 1 << RECORD_COORD_BITS);
+
+/// One snake's move this tick, packed into 8 bytes so a cache line holds 8 records
+/// instead of 5: snake id in bits 0-19, new head x in 20-33 and y in 34-47, and the cell
+/// phase 3 read at the new head in 48-49. The top 14 bits are zero.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct MovementRecord(u64);
+
+impl MovementRecord {
+    /// Record of `snake_id` moving to `new_head`, which must be on the grid; `snake_id`
+    /// must be below [`MAX_RECORD_SNAKES`]
+    #[inline(always)]
+    pub const fn new(snake_id: u32, new_head: Point, cell_at_new_head: Cell) -> Self {
+        debug_assert!((snake_id as usize) < MAX_RECORD_SNAKES);
+        Self(
+            snake_id as u64
+                | (new_head.x as u64) << RECORD_X_SHIFT
+                | (new_head.y as u64) << RECORD_Y_SHIFT
+                | (cell_at_new_head as u64) << RECORD_CELL_SHIFT,
+        )
+    }
+
+    #[inline(always)]
+    pub const fn snake_id(self) -> u32 {
+        (self.0 & RECORD_ID_MASK) as u32
+    }
+
+    #[inline(always)]
+    pub const fn new_head(self) -> Point {
+        Point {
+            x: (self.0 >> RECORD_X_SHIFT & RECORD_COORD_MASK) as u16,
+            y: (self.0 >> RECORD_Y_SHIFT & RECORD_COORD_MASK) as u16,
+        }
+    }
+
+    #[inline(always)]
+    pub const fn cell_at_new_head(self) -> Cell {
+        match self.0 >> RECORD_CELL_SHIFT & 0b11 {
+            0 => Cell::Empty,
+            1 => Cell::Snake,
+            2 => Cell::Apple,
+            _ => Cell::Split,
+        }
+    }
+
+    #[inline(always)]
+    pub fn set_new_head(&mut self, new_head: Point) {
+        *self = Self::new(self.snake_id(), new_head, self.cell_at_new_head());
+    }
+
+    #[inline(always)]
+    pub fn set_cell_at_new_head(&mut self, cell: Cell) {
+        self.0 = self.0 & !(0b11 << RECORD_CELL_SHIFT) | (cell as u64) << RECORD_CELL_SHIFT;
+    }
+}
+
+impl std::fmt::Debug for MovementRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MovementRecord")
+            .field("snake_id", &self.snake_id())
+            .field("new_head", &self.new_head())
+            .field("cell_at_new_head", &self.cell_at_new_head())
+            .finish()
+    }
 }
 
 
 pub struct GameState {
+    // Sizing this state was built with
+    pub config: GameConfig,
     // Using wrapper types that automatically manage grid updates
     pub snakes: Vec<GridAwareSnake>,
     pub num_apples: u64,
+    // Split power-ups on the grid, topped up to config.split_powerups after every tick
+    pub num_powerups: u64,
+    // Apple deadlines, only tracked when config.apple_ttl is set
+    pub apple_expiry: AppleExpiry,
+    // Replacements of eaten apples waiting for their tick (see config.apple_respawn) or
+    // for a tick that runs in full
+    pub apple_respawns: RespawnWheel,
     pub grid: Grid,
     // Pre-allocated buckets for cache-aware processing - reused every tick
     pub buckets: Vec<Vec<MovementRecord>>,
     // Pre-allocated buckets for tail clearing - reused every tick
     pub tail_buckets: Vec<Vec<Point>>,
+    // Incrementally maintained population statistics
+    pub population: PopulationMetrics,
+    // Decides contested moves in tick(); ClassicPolicy unless replaced
+    pub collision_policy: Box<dyn CollisionPolicy>,
+    // Deaths attributed during the last tick (only filled when the config tracks kills)
+    pub kills: Vec<Kill>,
+    // Engine-side randomness, one stream per subsystem, seeded from the config and advanced
+    // at the end of every tick; snapshots capture it
+    pub rng: RngStreams,
+    // Power-ups eaten during the last tick
+    pub splits: Vec<Split>,
+    // Apples several heads reached during the last tick, one entry per snake that missed out
+    pub contested_apples: Vec<ContestedApple>,
+    // Apples eaten during the current tick and by whom, to spot contested ones
+    eaten_apples: Vec<(Point, u32)>,
+    // (target, bucket position) of records heading for an apple; tie-break scratch
+    apple_claims: Vec<(Point, usize)>,
+    // Ids of dead snakes, handed to split fragments lowest first
+    pub free_ids: BTreeSet<u32>,
+    // Color, name and team of snakes for viewers (see snakemeta); set on join, cleared on
+    // despawn, never touched by tick()
+    pub snake_meta: SnakeMetaTable,
+    // Inputs of the last tick that changed nothing (only filled while auditing inputs)
+    pub rejected_inputs: Vec<RejectedInput>,
+    audit_inputs: bool,
+    // Turns dropped during the last tick for coming within config.turn_interval of the
+    // snake's previous one
+    pub throttled_inputs: u32,
+    // Inputs of the last tick that could not take effect: for a dead snake, or reversing
+    // a snake config.reversal_rule keeps from reversing
+    pub refused_inputs: u32,
+    // Inputs the last tick dropped to its degraded input cap (see set_tick_budget)
+    pub capped_inputs: u32,
+    // Time limit per tick and what the tick after an overrun leaves out
+    budget: Option<TickBudget>,
+    // The coming tick runs with budget.degradations, the last one having overrun
+    degraded: bool,
+    // First phase boundary of the last tick that was past the budget
+    overrun: Option<TickOverrun>,
+    // First tick each snake id may change direction again; only written while
+    // config.turn_interval is set, missing entries mean now
+    turn_ready: Vec<u64>,
+    // Duration of each tick() phase in the last tick (only filled while timing phases)
+    pub phase_timings: PhaseTimings,
+    time_phases: bool,
+    // Phase 6 sorts each tail bucket into tiles and clears it with Grid::clear_cells
+    combine_tail_writes: bool,
+    // Phase 3 reads every cell of a bucket into cell_scratch before processing its records
+    gather_reads: bool,
+    cell_scratch: Vec<Cell>,
+    // Heads claimed so far in the current bucket with the snake that moved there, latest
+    // last. A cell has one bucket, so every rival for it is found here; gathered cells of
+    // these are stale. Buckets hold a handful of records, so a scan beats hashing.
+    head_claims: Vec<(Point, u32)>,
+    // Snakes that died during the current tick; freed once its splits are done
+    dead_this_tick: Vec<u32>,
+    // Per-tick input batch of tick_n and tick_stamped, kept between calls so its capacity stays warm
+    input_scratch: Vec<Input>,
+    // `buckets` already hold the next tick's records assuming no inputs (see speculate)
+    speculative: bool,
+    // Ticks completed, see now()
+    now: u64,
+    // Cells within config.apple_head_clearance ahead of a head, filled before phase 8 spawns.
+    // Sorted by (y, x) for binary search, so the tick stays hash-free; reused across ticks.
+    head_paths: Vec<Point>,
+    // Optional per-cell values (see cellmeta); when set, tick() marks the head cell of every
+    // snake that dies with u8::MAX. Not part of snapshots.
+    pub cell_meta: Option<CellMeta>,
+    // Live heads by block, for heads_in_rect. Kept in step by every engine method that moves,
+    // kills or places a snake; direct edits of `snakes` need reindex_heads.
+    pub(crate) heads: HeadIndex,
+    // Direction, liveness and head of every snake in parallel arrays, read by phases 1 and
+    // 2 instead of the snake structs (see motion). Kept in step like `heads`.
+    pub(crate) motion: MotionTable,
+    // Build phase 2's records from `motion` rather than the snake structs
+    dense_motion: bool,
+    // Top-K standings fed by every tick's eats, kills and deaths (see set_leaderboard)
+    pub(crate) leaderboard: Option<Leaderboard>,
+    // Spread of the last tick's records over the buckets, while enabled (see set_bucket_stats)
+    bucket_stats: Option<BucketStats>,
+    // Retargets config.apple_capacity from the population (see set_apple_scaling)
+    apple_scaling: Option<AppleScaling>,
+    // Mechanics run after the core phases of every tick (see systems)
+    systems: SystemRegistry,
+    // What the last tick did, filled by the core phases and the systems
+    report: TickReport,
+    // Beacon marked at the phase boundaries of every n-th tick (see set_phase_beacon)
+    #[cfg(feature = "phase-sampler")]
+    phase_beacon: Option<(Arc<PhaseBeacon>, u64)>,
 }
 
 impl GameState {
     // clean this shit up
     pub fn random() -> Self {
-        let mut random_snakes = Vec::< GridAwareSnake>::with_capacity(SNAKE_CAPACITY);
-        let mut grid = Grid::new();
-        let mut rng = rand::rng();
-        let mut num_apples = 0;
-
-        // Spawn snakes with collision detection
-        for index in 0..SNAKE_CAPACITY {
-            let mut attempts = 0;
-            let snake = loop {
-                let start_pos = rng.random::<Point>();
-
-                // Check if the starting position is empty
-                if grid.get_cell(&start_pos) == Cell::Empty {
-                    let mut snake = Snake::new(index as u32, start_pos, rng.random());
-
-                    // Grow the snake and check each new segment
-                    let mut valid_growth = true;
-                    for _ in 0..3 {
-                        snake.move_forward(true); // Move forward with growth
-                        // Check if the new tail position is valid
-                        if let Some(tail) = snake.body.get(0) {
-                            if grid.get_cell(tail) != Cell::Empty {
-                                valid_growth = false;
-                                break;
-                            }
-                        }
-                    }
+        Self::random_with_config(GameConfig::default())
+    }
 
-                    if valid_growth {
-                        break snake;
-                    }
-                }
+    /// Random state with `config.snake_capacity` snakes and up to `config.apple_capacity` apples,
+    /// built on several threads (see `RandomGenerator::generate_parallel`).
+    /// Panics if `config` fails [`GameConfig::validate`], like every constructor.
+    pub fn random_with_config(config: GameConfig) -> Self {
+        RandomGenerator::generate_with_spawn(config, SpawnConfig::default())
+    }
 
-                attempts += 1;
-                if attempts > 1000 {
-                    // Fallback: create a minimal snake if we can't find space
-                    let start_pos = Point { x: 0, y: 0 };
-                    let snake = Snake::new(index as u32, start_pos, rng.random());
-                    break snake;
-                }
-            };
+    /// Like `random_with_config`, but placement draws from a generator seeded with
+    /// `config.rng_seed`, so equal configs always produce equal states
+    pub fn seeded(config: GameConfig) -> Self {
+        Self::random_with_rng(config, &mut SplitMix64::new(config.rng_seed))
+    }
 
-            // Add snake to the game state using wrapper
-            let grid_aware_snake = GridAwareSnake::new(snake, &mut grid);
-            random_snakes.push(grid_aware_snake);
-        }
+    fn random_with_rng<R: Rng>(config: GameConfig, rng: &mut R) -> Self {
+        RandomGenerator::generate_with_rng(config, SpawnConfig::default(), rng)
+    }
+    
+    pub fn new() -> Self {
+        Self::with_config(GameConfig::default())
+    }
 
-        // Spawn apples in empty spaces
-        for _ in 0..APPLE_CAPACITY {
-            let mut attempts = 0;
-            loop {
-                let apple = Apple::new(rng.random());
-                if grid.get_cell(&apple.position) == Cell::Empty {
-                    grid.set_cell(apple.position, Cell::Apple);
-                    num_apples += 1;
-                    break;
-                }
-                attempts += 1;
-                if attempts > 1000 {
-                    // Skip this apple if we can't find space
-                    break;
-                }
-            }
-        }
+    /// Empty state sized for `config`
+    pub fn with_config(config: GameConfig) -> Self {
+        Self::from_parts(
+            config,
+            Vec::<GridAwareSnake>::with_capacity(config.snake_capacity),
+            0,
+            Grid::new(),
+        )
+    }
 
+    /// Assemble a state from already-placed entities, pre-allocating the tick scratch buckets
+    pub(crate) fn from_parts(
+        config: GameConfig,
+        snakes: Vec<GridAwareSnake>,
+        num_apples: u64,
+        grid: Grid,
+    ) -> Self {
+        // Fail here rather than on the first tick; entry points taking outside sizes validate first
+        if let Err(error) = config.validate() {
+            panic!("{}", error);
+        }
         // Pre-allocate buckets for cache-aware processing
+        let expected_per_bucket = config.expected_snakes_per_bucket();
         let buckets = (0..NUM_BUCKETS)
-            .map(|_| Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET))
+            .map(|_| Vec::with_capacity(expected_per_bucket))
             .collect();
         let tail_buckets = (0..NUM_BUCKETS)
-            .map(|_| Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET))
+            .map(|_| Vec::with_capacity(expected_per_bucket))
             .collect();
 
+        let population = PopulationMetrics::from_snakes(&snakes);
+        // Generators place apples straight on the grid, so find them to start their clocks
+        let mut apple_expiry = AppleExpiry::new(config.apple_ttl);
+        if apple_expiry.is_enabled() {
+            for y in 0..grid::GRID_HEIGHT {
+                for (x, cell) in grid.row(y).iter().enumerate() {
+                    if *cell == Cell::Apple {
+                        apple_expiry.track(Point { x: x as u16, y: y as u16 });
+                    }
+                }
+            }
+        }
+        let free_ids = snakes
+            .iter()
+            .filter(|snake| !snake.is_alive())
+            .map(|snake| snake.id())
+            .collect();
+        let heads = HeadIndex::from_snakes(&snakes);
+        let motion = MotionTable::from_snakes(&snakes);
+
         Self {
-            snakes: random_snakes,
-            num_apples: num_apples,
+            config,
+            snakes,
+            num_apples,
+            num_powerups: 0,
+            apple_expiry,
+            apple_respawns: RespawnWheel::new(0),
             grid,
             buckets,
             tail_buckets,
+            population,
+            collision_policy: Box::new(ClassicPolicy),
+            kills: Vec::new(),
+            rng: RngStreams::new(config.rng_seed),
+            splits: Vec::new(),
+            contested_apples: Vec::new(),
+            eaten_apples: Vec::new(),
+            apple_claims: Vec::new(),
+            free_ids,
+            snake_meta: SnakeMetaTable::new(),
+            rejected_inputs: Vec::new(),
+            audit_inputs: false,
+            throttled_inputs: 0,
+            refused_inputs: 0,
+            capped_inputs: 0,
+            budget: None,
+            degraded: false,
+            overrun: None,
+            turn_ready: Vec::new(),
+            phase_timings: PhaseTimings::default(),
+            time_phases: false,
+            combine_tail_writes: false,
+            gather_reads: false,
+            cell_scratch: Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET),
+            head_claims: Vec::new(),
+            dead_this_tick: Vec::new(),
+            input_scratch: Vec::new(),
+            speculative: false,
+            now: 0,
+            head_paths: Vec::new(),
+            cell_meta: None,
+            heads,
+            motion,
+            dense_motion: true,
+            leaderboard: None,
+            bucket_stats: None,
+            apple_scaling: None,
+            systems: SystemRegistry::builtin(),
+            report: TickReport::default(),
+            #[cfg(feature = "phase-sampler")]
+            phase_beacon: None,
         }
     }
-    
-    pub fn new() -> Self {
-        // Pre-allocate buckets for cache-aware processing
-        let buckets = (0..NUM_BUCKETS)
-            .map(|_| Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET))
-            .collect();
-        let tail_buckets = (0..NUM_BUCKETS)
-            .map(|_| Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET))
-            .collect();
 
-        Self {
-            snakes: Vec::<GridAwareSnake>::with_capacity(SNAKE_CAPACITY),
-            num_apples: 0,
-            grid: Grid::new(),
-            buckets,
-            tail_buckets,
+    /// Replace the collision rules used by `tick()`
+    pub fn set_collision_policy<P: CollisionPolicy + 'static>(&mut self, policy: P) {
+        self.collision_policy = Box::new(policy);
+    }
+
+    /// Record the inputs each tick drops (reversals, dead snakes, throttled turns) in `rejected_inputs`.
+    /// Off by default: it costs a branch per input.
+    pub fn set_input_audit(&mut self, enabled: bool) {
+        self.audit_inputs = enabled;
+        self.rejected_inputs.clear();
+    }
+
+    /// Rejected inputs of the last tick by reason; all zero unless auditing inputs
+    pub fn input_audit(&self) -> InputAudit {
+        InputAudit::of(&self.rejected_inputs)
+    }
+
+    /// First tick snake `id` may change direction again under `config.turn_interval`
+    pub fn turn_ready(&self, id: u32) -> u64 {
+        self.turn_ready.get(id as usize).copied().unwrap_or(0)
+    }
+
+    /// Set when snake `id` may next change direction, e.g. when restoring a snapshot
+    pub fn set_turn_ready(&mut self, id: u32, tick: u64) {
+        let index = id as usize;
+        if self.turn_ready.len() <= index {
+            self.turn_ready.resize(index + 1, 0);
+        }
+        self.turn_ready[index] = tick;
+    }
+
+    /// The simulation clock: ticks completed since the state was created, or since the
+    /// tick it was restored at. Ticks are numbered from 0, so this is also the number of
+    /// the next tick.
+    #[inline(always)]
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Set the simulation clock, e.g. to the tick a state was saved at
+    pub fn set_now(&mut self, tick: u64) {
+        self.now = tick;
+    }
+
+    /// Time every phase of `tick` into `phase_timings` (see `metrics::TICK_PHASES`).
+    /// Off by default: it reads the timestamp counter six times per tick.
+    pub fn set_phase_timing(&mut self, enabled: bool) {
+        self.time_phases = enabled;
+        self.phase_timings = PhaseTimings::default();
+    }
+
+    /// Mark `beacon` with the running phase throughout every `every_ticks`-th tick, for a
+    /// `PhaseSampler` to read (see [`crate::runtime::sampler`]); none to stop
+    #[cfg(feature = "phase-sampler")]
+    pub fn set_phase_beacon(&mut self, beacon: Option<Arc<PhaseBeacon>>, every_ticks: u64) {
+        if let Some((old, _)) = &self.phase_beacon {
+            old.mark(None);
+        }
+        self.phase_beacon = beacon.map(|beacon| (beacon, every_ticks.max(1)));
+    }
+
+    #[inline(always)]
+    fn mark_phase(&self, _tick: u64, _phase: Option<usize>) {
+        #[cfg(feature = "phase-sampler")]
+        if let Some((beacon, every_ticks)) = &self.phase_beacon
+            && _tick.is_multiple_of(*every_ticks)
+        {
+            beacon.mark(_phase);
+        }
+    }
+
+    /// Keep standings of the best `size` snakes, or none (the default). Scores start from
+    /// zero; every tick then reports how the top changed in the board's `changes` (see
+    /// [`crate::game::leaderboard`]). `tick_legacy` leaves the board alone. Snapshots and
+    /// deltas carry the board and its scores.
+    pub fn set_leaderboard(&mut self, size: Option<usize>) {
+        self.leaderboard = size.map(Leaderboard::new);
+    }
+
+    pub fn leaderboard(&self) -> Option<&Leaderboard> {
+        self.leaderboard.as_ref()
+    }
+
+    /// Work out how every tick's movement records spread over the spatial buckets (see
+    /// [`BucketStats`]); off by default. Costs one pass over the bucket lengths per tick.
+    pub fn set_bucket_stats(&mut self, enabled: bool) {
+        self.bucket_stats = enabled.then(BucketStats::default);
+    }
+
+    /// Bucket occupancy of the last tick, while enabled
+    pub fn bucket_stats(&self) -> Option<&BucketStats> {
+        self.bucket_stats.as_ref()
+    }
+
+    /// Let the population set the apple count: every `scaling.every_ticks` ticks,
+    /// `config.apple_capacity` becomes `scaling.target` of the live snakes and apples are
+    /// topped up to it at once. Above the target, eaten apples go unreplaced until the
+    /// count is back down. None (the default) keeps the capacity fixed.
+    ///
+    /// Snapshots keep the scaling along with the capacity it last set.
+    pub fn set_apple_scaling(&mut self, scaling: Option<AppleScaling>) {
+        self.apple_scaling = scaling;
+    }
+
+    pub fn apple_scaling(&self) -> Option<AppleScaling> {
+        self.apple_scaling
+    }
+
+    /// Systems run after the core phases of every tick, in order (see
+    /// [`crate::game::systems`]); `SystemRegistry::builtin` unless changed
+    pub fn systems(&self) -> &SystemRegistry {
+        &self.systems
+    }
+
+    /// Add, reorder or remove systems. `tick_legacy` runs none of them.
+    pub fn systems_mut(&mut self) -> &mut SystemRegistry {
+        &mut self.systems
+    }
+
+    /// What the last `tick` did
+    pub fn report(&self) -> &TickReport {
+        &self.report
+    }
+
+    /// Apples eaten during the last `tick` as (position, eater)
+    pub fn eaten_apples(&self) -> &[(Point, u32)] {
+        &self.eaten_apples
+    }
+
+    /// Give every tick a time budget, or none (the default). A tick still running at a
+    /// phase boundary past `budget.limit` is reported by `overrun`, and the next tick runs
+    /// with `budget.degradations` (see [`crate::game::budget`]).
+    pub fn set_tick_budget(&mut self, budget: Option<TickBudget>) {
+        self.budget = budget;
+        self.degraded = false;
+        self.overrun = None;
+    }
+
+    pub fn tick_budget(&self) -> Option<TickBudget> {
+        self.budget
+    }
+
+    /// The last tick's overrun of its budget, if it had one
+    pub fn overrun(&self) -> Option<TickOverrun> {
+        self.overrun
+    }
+
+    /// Whether the coming tick runs degraded
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Eaten apples not replaced yet, because their ticks ran degraded or their
+    /// replacements wait per `GameConfig::apple_respawn`
+    pub fn deferred_apples(&self) -> u64 {
+        self.apple_respawns.pending()
+    }
+
+    /// Clear tails tile by tile (sorted, one tile hash update per tile) instead of one cell
+    /// at a time. Same grid either way; off by default because the sort costs more than it
+    /// saves while a tail bucket's rows stay cached (see `tail_clear` in game_bench).
+    pub fn set_tail_write_combining(&mut self, enabled: bool) {
+        self.combine_tail_writes = enabled;
+    }
+
+    /// Read the grid cells of a whole bucket into a contiguous scratch array before
+    /// processing its records, instead of one read per record in between the processing.
+    /// Splits phase 3 into a memory-bound and a compute-bound loop; same results either
+    /// way (see `cell_reads` in game_bench).
+    pub fn set_gather_reads(&mut self, enabled: bool) {
+        self.gather_reads = enabled;
+    }
+
+    /// Touch every page a tick writes to, the grid and the spare capacity of the tick
+    /// buckets, so no tick takes a first-touch page fault. Call once after setup; the
+    /// state is unchanged.
+    pub fn prefault(&mut self) {
+        self.grid.prefault();
+        let record = MovementRecord::new(0, Point { x: 0, y: 0 }, Cell::Empty);
+        for bucket in &mut self.buckets {
+            prefault_spare(bucket, record);
+        }
+        for bucket in &mut self.tail_buckets {
+            prefault_spare(bucket, Point { x: 0, y: 0 });
+        }
+    }
+
+    /// Lock the grid, the snake slots and the tick buckets into RAM so they are never
+    /// swapped out (see `platform::lock_memory`). Returns the bytes locked. The grid alone
+    /// is ~100MB, well over the usual `RLIMIT_MEMLOCK`.
+    pub fn lock_memory(&self) -> io::Result<usize> {
+        let mut locked = self.grid.lock_memory()? + lock_vec(&self.snakes)?;
+        for bucket in &self.buckets {
+            locked += lock_vec(bucket)?;
+        }
+        for bucket in &self.tail_buckets {
+            locked += lock_vec(bucket)?;
+        }
+        Ok(locked)
+    }
+
+    /// Place a snake on the grid and register it, keeping population metrics in sync.
+    /// Pushing into `snakes` directly bypasses the metrics; call `recount_population` afterwards.
+    pub fn add_snake(&mut self, snake: Snake) {
+        self.speculative = false;
+        let grid_aware_snake = GridAwareSnake::new(snake, &mut self.grid);
+        if grid_aware_snake.is_alive() {
+            self.population.on_birth(grid_aware_snake.body().len());
+        } else {
+            self.free_ids.insert(grid_aware_snake.id());
+        }
+        self.heads.update(grid_aware_snake.id(), grid_aware_snake.head().copied().filter(|_| grid_aware_snake.is_alive()));
+        self.motion.sync(&grid_aware_snake);
+        self.snakes.push(grid_aware_snake);
+    }
+
+    /// Bring up to `limit` dead snakes back as fresh length-1 snakes at random empty cells,
+    /// reusing their ids. Corpses are cleared from the grid first. Returns the number respawned.
+    ///
+    /// Placement draws from the engine's respawn stream, so respawns are reproducible from
+    /// a snapshot.
+    pub fn respawn_dead(&mut self, limit: usize) -> usize {
+        self.speculative = false;
+        let mut respawned = 0;
+        for index in 0..self.snakes.len() {
+            if respawned >= limit {
+                break;
+            }
+            if self.snakes[index].is_alive() {
+                continue;
+            }
+
+            self.snakes[index].die(&mut self.grid);
+            for _attempts in 0..100 {
+                let mut rng = self.rng.stream(Stream::Respawns);
+                let position = rng.random::<Point>();
+                if self.grid.get_cell(&position) == Cell::Empty {
+                    let id = self.snakes[index].id();
+                    let snake = Snake::new(id, position, rng.random());
+                    self.snakes[index] = GridAwareSnake::new(snake, &mut self.grid);
+                    self.heads.update(id, Some(position));
+                    self.motion.sync(&self.snakes[index]);
+                    self.free_ids.remove(&id);
+                    self.population.on_birth(1);
+                    respawned += 1;
+                    break;
+                }
+            }
+        }
+        respawned
+    }
+
+    /// Place a new snake in a running game, e.g. a player joining: random spots are tried
+    /// until `join.body` fits on empty cells with no other snake within `join.clearance`
+    /// steps of any of them. The id is the lowest free one (a dead or despawned slot, its
+    /// corpse cleared), else a new one below `config.snake_capacity`. Returns the id.
+    ///
+    /// Placement draws from the engine's respawn stream, like `respawn_dead`.
+    pub fn spawn_snake(&mut self, join: &JoinConfig) -> Result<u32, SpawnError> {
+        if self.free_ids.is_empty() && self.snakes.len() >= self.config.snake_capacity {
+            return Err(SpawnError::Full);
+        }
+        let mut snake = None;
+        for _attempts in 0..join.attempts {
+            let mut rng = self.rng.stream(Stream::Respawns);
+            let (tail, direction) = (rng.random::<Point>(), rng.random());
+            snake = join.body.try_place(&self.grid, 0, tail, direction).filter(|snake| {
+                snake.segments().all(|cell| self.grid.nearest(cell, join.clearance, Cell::Snake).is_none())
+            });
+            if snake.is_some() {
+                break;
+            }
+        }
+        let mut snake = snake.ok_or(SpawnError::NoRoom)?;
+
+        // Only stale free ids let the check above pass with every slot in use
+        let id = self.allocate_id();
+        if id as usize == self.snakes.len() && self.snakes.len() >= self.config.snake_capacity {
+            return Err(SpawnError::Full);
+        }
+        self.speculative = false;
+        snake.id = id;
+        // A reused id must not show the name of whoever held it before
+        self.snake_meta.remove(id);
+        self.population.on_birth(snake.body.len());
+        let snake = GridAwareSnake::new(snake, &mut self.grid);
+        self.heads.update(id, snake.head().copied());
+        self.motion.sync(&snake);
+        if id as usize == self.snakes.len() {
+            self.snakes.push(snake);
+        } else {
+            self.snakes[id as usize] = snake;
+        }
+        Ok(id)
+    }
+
+    /// [`GameState::spawn_snake`], giving the new snake `meta` for viewers
+    pub fn spawn_snake_with_meta(&mut self, join: &JoinConfig, meta: SnakeMeta) -> Result<u32, SpawnError> {
+        let id = self.spawn_snake(join)?;
+        self.snake_meta.set(id, meta);
+        Ok(id)
+    }
+
+    /// Give snake `id` its color, name and team for viewers, or take them away with `None`
+    pub fn set_snake_meta(&mut self, id: u32, meta: Option<SnakeMeta>) {
+        match meta {
+            Some(meta) => self.snake_meta.set(id, meta),
+            None => {
+                self.snake_meta.remove(id);
+            }
+        }
+    }
+
+    /// Color, name and team viewers show for snake `id`, see [`SnakeMeta::fallback`]
+    pub fn snake_meta(&self, id: u32) -> SnakeMeta {
+        self.snake_meta.get_or_fallback(id)
+    }
+
+    /// Remove snake `id` for good, e.g. when its player disconnects: its cells (live body or
+    /// corpse) leave the grid, its body storage is released and its id joins `free_ids`, to be
+    /// reused by `respawn_dead` and split fragments. A live snake counts as a death in the
+    /// population metrics but credits no kill. Returns false if there is no such snake or it
+    /// was already despawned.
+    pub fn despawn(&mut self, id: u32) -> bool {
+        let Some(snake) = self.snakes.get_mut(id as usize).filter(|snake| !snake.body().is_empty()) else {
+            return false;
+        };
+        self.speculative = false;
+        if snake.is_alive() {
+            self.population.on_death(snake.body().len());
+        }
+        snake.die(&mut self.grid);
+        snake.release_body();
+        self.motion.sync(snake);
+        self.heads.update(id, None);
+        if let Some(board) = &mut self.leaderboard {
+            board.on_death(id);
+        }
+        self.free_ids.insert(id);
+        self.snake_meta.remove(id);
+        true
+    }
+
+    /// Kill snake `id` where it stands, e.g. from a [`crate::game::systems::System`]: its
+    /// body stays on the grid as a corpse, as after a collision, and its id joins
+    /// `free_ids`. Counts as a death but credits no kill. Returns false if it was not alive.
+    pub fn kill_snake(&mut self, id: u32) -> bool {
+        let Some(snake) = self.snakes.get_mut(id as usize).filter(|snake| snake.is_alive()) else {
+            return false;
+        };
+        self.speculative = false;
+        self.population.on_death(snake.body().len());
+        snake.mark_dead();
+        self.motion.kill(id);
+        self.heads.update(id, None);
+        if let Some(board) = &mut self.leaderboard {
+            board.on_death(id);
+        }
+        self.free_ids.insert(id);
+        true
+    }
+
+    /// Rebuild population metrics by scanning every snake
+    pub fn recount_population(&mut self) {
+        self.population = PopulationMetrics::from_snakes(&self.snakes);
+    }
+
+    /// Rebuild the head index behind `heads_in_rect` and the motion table the first
+    /// phases of a tick read by scanning every snake, after editing `snakes` directly
+    pub fn reindex_heads(&mut self) {
+        self.heads.rebuild(&self.snakes);
+        self.motion.rebuild(&self.snakes);
+    }
+
+    /// Build each tick's movement records from the dense motion table (the default) or
+    /// from the snake structs, to compare the two with the perf counters
+    pub fn set_dense_motion(&mut self, enabled: bool) {
+        self.dense_motion = enabled;
+    }
+
+    /// ASCII picture of `rect` for test failures and debugging, followed by a legend.
+    ///
+    /// Snake cells show their owner as a letter (`a` + id mod 26, uppercase for the head),
+    /// apples `*`, empty cells `.`. Disagreements between snakes and grid are flagged:
+    /// `?` is a grid snake cell no snake owns, `!` a snake segment the grid has no snake on.
+    pub fn debug_dump(&self, rect: Rect) -> String {
+        let mut owners: HashMap<Point, (u32, bool)> = HashMap::new();
+        let mut shown = Vec::new();
+        for snake in &self.snakes {
+            let len = snake.body().len();
+            let mut in_rect = false;
+            for (i, point) in snake.segments().enumerate() {
+                if rect.contains(&point) {
+                    owners.insert(point, (snake.id(), i + 1 == len));
+                    in_rect = true;
+                }
+            }
+            if in_rect {
+                shown.push(snake);
+            }
         }
+
+        let mut out = format!("rect ({}, {}) {}x{}\n", rect.x, rect.y, rect.width, rect.height);
+        for dy in 0..rect.height {
+            for dx in 0..rect.width {
+                let point = rect.at(dx, dy);
+                let cell = self.grid.get_cell(&point);
+                out.push(match (owners.get(&point), cell) {
+                    (Some(_), cell) if cell != Cell::Snake => '!',
+                    (Some(&(id, head)), _) => snake_letter(id, head),
+                    (None, Cell::Snake) => '?',
+                    (None, cell) => cell.to_char(),
+                });
+            }
+            out.push('\n');
+        }
+        for snake in shown {
+            let head = snake.head().copied().unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{} snake {} {} len {} {} head ({}, {})",
+                snake_letter(snake.id(), true),
+                snake.id(),
+                if snake.is_alive() { "alive" } else { "dead" },
+                snake.body().len(),
+                snake.snake().direction.name(),
+                head.x,
+                head.y
+            );
+        }
+        out
     }
 
-    /// The legacy game loop (pre cache-aware)
+    /// The legacy game loop (pre cache-aware). Does not fill [`GameState::report`], which
+    /// keeps describing the last `tick`; `throttled_inputs` and `refused_inputs` do count
+    /// this tick's inputs.
+    #[allow(deprecated)]
     pub fn tick_legacy(&mut self, inputs: &[Input]) {
+        self.population.begin_tick();
+        self.rejected_inputs.clear();
+        self.throttled_inputs = 0;
+        self.refused_inputs = 0;
+        self.sync_motion_length();
+
         // Process inputs and update snake directions
         // TODO: Wonder if sorting inputs will be faster for cache?
         for input in inputs {
             // Processing dead snakes as well, do not want to add a branch.
             // TODO: Bounds check?
-            self.snakes[input.snake_id as usize].change_direction(input.direction);
+            if self.audit_inputs {
+                self.audit_input(input);
+            }
+            if self.throttle(input) {
+                continue;
+            }
+            let snake = &mut self.snakes[input.snake_id as usize];
+            snake.turn(input.direction, self.config.reversal_rule);
+            self.motion.set_direction(input.snake_id, snake.snake().direction);
         }
 
         let mut consumed_apples = 0;
@@ -154,23 +827,35 @@ impl GameState {
             }
 
             // Check for apple consumption before moving
-            let will_eat_apple = if snake.head().is_some() {
-                let new_head = snake.snake().calculate_new_head();
-                self.grid.get_cell(&new_head) == Cell::Apple
+            let target = if snake.head().is_some() {
+                self.grid.get_cell(&snake.snake().calculate_new_head())
             } else {
-                false
+                Cell::Empty
             };
+            let will_eat_apple = target == Cell::Apple;
             
             // Move snake (collision detection happens automatically)
-            if snake.move_forward(&mut self.grid, will_eat_apple) {
+            let length = snake.body().len();
+            let moved = snake.move_forward(&mut self.grid, will_eat_apple);
+            if !moved {
+                self.population.on_death(length);
+                self.free_ids.insert(snake.id());
+            } else if target == Cell::Split {
+                // The legacy loop has no splits; the power-up is just trampled
+                self.num_powerups -= 1;
+            } else if will_eat_apple {
+                self.population.on_growth(length);
+            }
+            if moved {
                 // If snake was going to eat an apple, handle it now
-                if will_eat_apple {
-                    if let Some(head) = snake.head().copied() {
-                        self.grid.set_cell(head, Cell::Empty);
-                        self.num_apples -= 1;
-                        consumed_apples += 1;
-                        break;
-                    }
+                if will_eat_apple
+                    && let Some(head) = snake.head().copied()
+                {
+                    self.grid.set_cell(head, Cell::Empty);
+                    self.apple_expiry.forget(&head);
+                    self.num_apples -= 1;
+                    consumed_apples += 1;
+                    break;
                 }
             }
             // If snake.move_forward() returned false, snake is already dead
@@ -182,123 +867,788 @@ impl GameState {
                 self.spawn_apple();
             }
         }
+        self.expire_apples();
+        // The legacy loop is only a baseline, so it rescans rather than track every move
+        self.heads.rebuild(&self.snakes);
+        self.motion.rebuild(&self.snakes);
+        self.rng.advance();
+        self.now += 1;
     }
 
-    /// The main game loop (cache-aware)
-    pub fn tick(&mut self, inputs: &[Input]) {
-        // Phase 1: Process inputs (unchanged)
-        for input in inputs {
-            self.snakes[input.snake_id as usize].change_direction(input.direction);
+    /// Precompute the next tick's movement records as if it had no inputs, e.g. while the
+    /// game thread waits for input. The next `tick` then only redoes the records of snakes
+    /// its inputs actually turn, with the same result as without speculating.
+    ///
+    /// `add_snake` and `respawn_dead` discard the speculation; anything else changing snake
+    /// heads or directions outside `tick` must call [`GameState::discard_speculation`].
+    pub fn speculate(&mut self) {
+        if !self.speculative {
+            self.collect_records();
+            self.speculative = true;
         }
+    }
 
-        // Phase 1: Clear pre-allocated buckets (reuse capacity, no allocation)
-        for bucket in &mut self.buckets {
-            bucket.clear();
+    pub fn discard_speculation(&mut self) {
+        self.speculative = false;
+    }
+
+    /// Whether the next tick starts from speculated records
+    pub fn is_speculative(&self) -> bool {
+        self.speculative
+    }
+
+    /// The main game loop (cache-aware). Returns what the tick did, which `report` keeps
+    /// until the next tick.
+    pub fn tick(&mut self, inputs: &[Input]) -> &TickReport {
+        let degradations = match self.budget {
+            Some(budget) if self.degraded => budget.degradations,
+            _ => Degradations::default(),
+        };
+        let mut clock = PhaseClock::start(self.time_phases && !degradations.skip_metrics, self.budget.is_some());
+        let tick = self.now;
+        self.mark_phase(tick, Some(0));
+        self.overrun = None;
+        self.population.begin_tick();
+        self.report.begin(tick);
+        self.rejected_inputs.clear();
+        self.throttled_inputs = 0;
+        self.refused_inputs = 0;
+        let applied = degradations.max_inputs.map_or(inputs.len(), |cap| cap.min(inputs.len()));
+        self.capped_inputs = (inputs.len() - applied) as u32;
+        let inputs = &inputs[..applied];
+        self.sync_motion_length();
+
+        // Phase 1: Process inputs, patching speculated records where a head moved
+        if self.speculative {
+            for input in inputs {
+                if self.audit_inputs {
+                    self.audit_input(input);
+                }
+                if self.throttle(input) {
+                    continue;
+                }
+                self.apply_speculated_input(input);
+            }
+        } else {
+            for input in inputs {
+                if self.audit_inputs {
+                    self.audit_input(input);
+                }
+                if self.throttle(input) {
+                    continue;
+                }
+                // Only an actual turn touches the snake itself
+                let id = input.snake_id;
+                if self.motion.direction(id) != input.direction {
+                    let snake = &mut self.snakes[id as usize];
+                    snake.turn(input.direction, self.config.reversal_rule);
+                    self.motion.set_direction(id, snake.snake().direction);
+                }
+            }
         }
+
+        self.report.inputs_applied = inputs.len() as u32 - self.throttled_inputs - self.refused_inputs;
+        self.report.inputs_dropped = self.capped_inputs + self.throttled_inputs + self.refused_inputs;
+
+        // Phase 1: Clear pre-allocated buckets (reuse capacity, no allocation)
         for tail_bucket in &mut self.tail_buckets {
             tail_bucket.clear();
         }
 
-        // Phase 2: Collect records directly into spatial buckets
-        for snake in &self.snakes {
-            if !snake.is_alive() { continue; }
-
-            let new_head = snake.calculate_new_head();
-            let bucket_idx = (new_head.y >> (16 - BUCKET_BITS)) as usize;
+        clock.lap(&mut self.phase_timings, 0);
+        self.check_budget(&clock, 0, tick);
+        self.mark_phase(tick, Some(1));
 
-            self.buckets[bucket_idx].push(MovementRecord {
-                snake_id: snake.id(),
-                new_head,
-                cell_at_new_head: Cell::Empty, // Will be filled in Phase 3
-            });
+        // Phase 2: Collect records directly into spatial buckets
+        if !self.speculative {
+            self.collect_records();
         }
+        self.speculative = false;
+        if let Some(stats) = &mut self.bucket_stats {
+            stats.observe(self.buckets.iter().map(Vec::len));
+        }
+        if self.config.apple_tie_break != AppleTieBreak::BucketOrder {
+            self.order_apple_claims(inputs);
+        }
+        clock.lap(&mut self.phase_timings, 1);
+        self.check_budget(&clock, 1, tick);
+        self.mark_phase(tick, Some(2));
 
         // Phase 3-5: Combined Loop (Read, Process, Write Immediately)
         let mut consumed_apples: u64 = 0;
-        let mut previous_new_head: Option<Point> = None;
+        self.kills.clear();
+        self.splits.clear();
+        self.contested_apples.clear();
+        self.eaten_apples.clear();
+        self.dead_this_tick.clear();
+        // Power-up eaters, split once every tail is cleared
+        let mut split_parents: Vec<u32> = Vec::new();
+        let track_kills = self.config.tracks_kills();
+        let kill_growth_percent = self.config.kill_growth_percent;
+        // Cells the population may still grow by this tick, and tails dropped at the length cap
+        let mut growth_left = match self.config.max_growth_per_tick {
+            0 => u32::MAX,
+            budget => budget,
+        };
+        let max_length = self.config.max_snake_length as usize;
+        let mut capped_tails: Vec<Point> = Vec::new();
 
         for bucket in &mut self.buckets {
             if bucket.is_empty() { continue; }
 
-            for record in bucket {
-                // Phase 3: Read cell value (cache-friendly since records are sorted)
-                record.cell_at_new_head = self.grid.get_cell(&record.new_head);
+            if self.gather_reads {
+                self.cell_scratch.clear();
+                // SAFETY: every record's new head was checked to be on the grid when the
+                // record was made (collect_records, apply_speculated_input)
+                self.cell_scratch.extend(bucket.iter().map(|record| unsafe { self.grid.get_cell_unchecked(&record.new_head()) }));
+            }
+            self.head_claims.clear();
 
-                if record.cell_at_new_head == Cell::Snake {
-                    self.snakes[record.snake_id as usize].mark_dead();
-                    continue; // Skip this snake
-                }
+            for (index, record) in bucket.iter_mut().enumerate() {
+                let (snake_id, new_head) = (record.snake_id(), record.new_head());
+                // Phase 3: Read cell value (cache-friendly since records are sorted). A gathered
+                // cell is stale once an earlier record of the bucket moved its head there.
+                let claimant = self.head_claims.iter().rev().find(|(head, _)| *head == new_head).map(|&(_, id)| id);
+                let cell_at_new_head = if self.gather_reads && claimant.is_none() {
+                    self.cell_scratch[index]
+                } else {
+                    // SAFETY: new_head was checked when the record was made
+                    unsafe { self.grid.get_cell_unchecked(&new_head) }
+                };
+                record.set_cell_at_new_head(cell_at_new_head);
+
+                // A claimant killed earlier this tick is just a body to later rivals
+                let contender_id = if cell_at_new_head == Cell::Snake {
+                    claimant.filter(|&id| self.snakes[id as usize].is_alive())
+                } else {
+                    None
+                };
 
-                if let Some(prev_pos) = previous_new_head {
-                    if record.new_head == prev_pos {
-                        self.snakes[record.snake_id as usize].mark_dead();
-                        continue; // Skip this snake
+                // Phase 4: Contested moves are resolved by the collision policy
+                if cell_at_new_head == Cell::Snake {
+                    if let Some(&(position, eater)) = self.eaten_apples.iter().find(|(position, _)| *position == new_head) {
+                        self.contested_apples.push(ContestedApple { position, eater, rival: snake_id });
+                    }
+                    let mover = Contestant {
+                        snake_id,
+                        length: self.snakes[snake_id as usize].body().len(),
+                    };
+                    let contender = contender_id.map(|snake_id| Contestant {
+                        snake_id,
+                        length: self.snakes[snake_id as usize].body().len(),
+                    });
+                    let resolution = self.collision_policy.resolve(
+                        &mover,
+                        cell_at_new_head,
+                        contender.as_ref(),
+                    );
+
+                    if resolution.kill_contender
+                        && let Some(contender) = contender
+                    {
+                        let snake = &mut self.snakes[contender.snake_id as usize];
+                        if snake.is_alive() {
+                            self.population.on_death(contender.length);
+                            snake.mark_dead();
+                            self.motion.kill(contender.snake_id);
+                            self.dead_this_tick.push(contender.snake_id);
+                            if track_kills {
+                                credit_kill(&mut self.snakes, &mut self.kills, kill_growth_percent, Kill {
+                                    killer: mover.snake_id,
+                                    victim: contender.snake_id,
+                                    victim_length: contender.length,
+                                });
+                            }
+                        }
+                    }
+
+                    match resolution.mover {
+                        Fate::Move => {}
+                        Fate::Stay => continue,
+                        Fate::Die => {
+                            self.population.on_death(mover.length);
+                            self.snakes[snake_id as usize].mark_dead();
+                            self.motion.kill(snake_id);
+                            self.dead_this_tick.push(mover.snake_id);
+                            if track_kills {
+                                // Head-on losers credit the contender, body hits the body's owner
+                                let killer = contender_id.or_else(|| {
+                                    find_occupant(&self.snakes, &new_head, snake_id)
+                                });
+                                if let Some(killer) = killer {
+                                    credit_kill(&mut self.snakes, &mut self.kills, kill_growth_percent, Kill {
+                                        killer,
+                                        victim: mover.snake_id,
+                                        victim_length: mover.length,
+                                    });
+                                }
+                            }
+                            continue; // Skip this snake
+                        }
                     }
                 }
 
-                previous_new_head = Some(record.new_head);
+                self.head_claims.push((new_head, snake_id));
 
-                let will_grow = record.cell_at_new_head == Cell::Apple;
-                if will_grow {
+                let ate_apple = cell_at_new_head == Cell::Apple;
+                if ate_apple {
                     consumed_apples += 1;
+                    self.num_apples -= 1;
+                    self.apple_expiry.forget(&new_head);
+                    self.eaten_apples.push((new_head, snake_id));
+                } else if cell_at_new_head == Cell::Split {
+                    self.num_powerups -= 1;
+                    split_parents.push(snake_id);
+                }
+                // At most one cell of growth per tick: apples first, then queued growth.
+                // Past the tick's growth budget an apple's cell is queued instead.
+                let snake = &mut self.snakes[snake_id as usize];
+                let mut will_grow = if growth_left > 0 {
+                    ate_apple || snake.take_queued_growth()
+                } else {
+                    if ate_apple {
+                        snake.queue_growth(1);
+                    }
+                    false
+                };
+                if will_grow && max_length > 0 && snake.body().len() >= max_length {
+                    will_grow = false;
+                    if self.config.length_cap_apples
+                        && let Some(tail_pos) = snake.tail_position()
+                    {
+                        capped_tails.push(tail_pos);
+                    }
+                }
+                if will_grow {
+                    growth_left -= 1;
+                    self.population.on_growth(snake.body().len());
                 }
 
                 // Write new head
-                self.grid.set_cell(record.new_head, Cell::Snake);
+                // SAFETY: new_head was checked when the record was made
+                unsafe { self.grid.set_cell_unchecked(new_head, Cell::Snake) };
 
                 // Collect tail position for spatial clearing (only if not growing)
-                if !will_grow {
-                    if let Some(tail_pos) = self.snakes[record.snake_id as usize].tail_position() {
-                        let tail_bucket_idx = (tail_pos.y >> (16 - BUCKET_BITS)) as usize;
-                        self.tail_buckets[tail_bucket_idx].push(tail_pos);
-                    }
+                if !will_grow
+                    && let Some(tail_pos) = self.snakes[snake_id as usize].tail_position()
+                {
+                    self.tail_buckets[bucket_index(&tail_pos)].push(tail_pos);
                 }
 
                 // Update snake body (no grid access)
-                self.snakes[record.snake_id as usize].update_body(will_grow);
+                self.snakes[snake_id as usize].update_body(will_grow);
+                self.heads.update(snake_id, Some(new_head));
+                self.motion.set_head(snake_id, new_head);
             }
         }
 
+        clock.lap(&mut self.phase_timings, 2);
+        self.check_budget(&clock, 2, tick);
+        self.mark_phase(tick, Some(3));
+
         // Phase 6: Clear tails with spatial locality
-        for tail_bucket in &mut self.tail_buckets {
-            for tail_pos in tail_bucket {
-                self.grid.set_cell(*tail_pos, Cell::Empty);
+        if self.combine_tail_writes {
+            for tail_bucket in &mut self.tail_buckets {
+                tail_bucket.sort_unstable_by_key(Grid::write_order);
+                self.grid.clear_cells(tail_bucket);
+            }
+        } else {
+            for tail_bucket in &mut self.tail_buckets {
+                for tail_pos in tail_bucket {
+                    self.grid.set_cell(*tail_pos, Cell::Empty);
+                }
             }
         }
 
-        // Phase 7: Spawn new apples to replace consumed ones
-        if consumed_apples > 0 {
-            for _ in 0..consumed_apples {
+        // Tails dropped at the length cap come back as apples where nothing moved in
+        for tail_pos in capped_tails {
+            if self.num_apples < self.config.apple_capacity as u64 && self.grid.get_cell(&tail_pos) == Cell::Empty {
+                self.grid.set_cell(tail_pos, Cell::Apple);
+                self.apple_expiry.track(tail_pos);
+                self.num_apples += 1;
+            }
+        }
+
+        clock.lap(&mut self.phase_timings, 3);
+        self.check_budget(&clock, 3, tick);
+        self.mark_phase(tick, Some(4));
+
+        // Phase 7: Split power-up eaters. Snakes that died this tick only become free
+        // afterwards, so a fragment never lands on a corpse sharing a cell with a live head.
+        for parent in split_parents {
+            self.split_snake(parent);
+        }
+        if !degradations.skip_metrics
+            && let Some(meta) = &mut self.cell_meta
+        {
+            for &id in &self.dead_this_tick {
+                if let Some(&head) = self.snakes[id as usize].head() {
+                    meta.set(head, u8::MAX, self.now);
+                }
+            }
+        }
+        for &id in &self.dead_this_tick {
+            self.heads.update(id, None);
+        }
+        if let Some(board) = &mut self.leaderboard {
+            self.eaten_apples.iter().for_each(|&(_, eater)| board.on_eat(eater));
+            self.kills.iter().for_each(|kill| board.on_kill(kill.killer));
+            self.dead_this_tick.iter().for_each(|&id| board.on_death(id));
+            board.end_tick();
+        }
+        self.report.deaths.extend_from_slice(&self.dead_this_tick);
+        self.report.apples_eaten.extend_from_slice(&self.eaten_apples);
+        self.free_ids.extend(self.dead_this_tick.drain(..));
+        clock.lap(&mut self.phase_timings, 4);
+        self.check_budget(&clock, 4, tick);
+        self.mark_phase(tick, Some(5));
+
+        // Phase 8: Spawn new apples and power-ups to replace consumed ones. Replacements go
+        // through the respawn wheel, which hands back those due now, within the per-tick
+        // limit; degraded ticks leave them all waiting.
+        let respawn = self.config.apple_respawn;
+        self.apple_respawns.schedule(self.now + respawn.delay as u64, consumed_apples);
+        let replaced_apples = if degradations.defer_apples {
+            0
+        } else {
+            self.apple_respawns.take_due(self.now, respawn.max_per_tick)
+        };
+        self.head_paths.clear();
+        if self.config.apple_head_clearance > 0 && (replaced_apples > 0 || self.apple_expiry.is_enabled() || self.apple_scaling.is_some()) {
+            self.mark_head_paths();
+        }
+        let mut unplaced = 0;
+        for _ in 0..replaced_apples {
+            if !self.spawn_apple_in(respawn.region) && respawn.region.is_some() && self.num_apples < self.config.apple_capacity as u64 {
+                unplaced += 1;
+            }
+        }
+        // A full region keeps its replacements for the next tick rather than dropping them
+        self.apple_respawns.schedule(self.now + 1, unplaced);
+        if let Some(scaling) = self.apple_scaling
+            && tick.is_multiple_of(scaling.every_ticks.max(1))
+        {
+            self.config.apple_capacity = scaling.target(&self.population);
+            if !degradations.defer_apples {
+                for _ in self.num_apples..self.config.apple_capacity as u64 {
+                    self.spawn_apple();
+                }
+            }
+        }
+        // Power-ups and apple expiry are systems too, the first two by default
+        let mut systems = std::mem::take(&mut self.systems);
+        let mut report = std::mem::take(&mut self.report);
+        systems.run(self, &mut report);
+        self.systems = systems;
+        self.report = report;
+        self.rng.advance();
+        self.now += 1;
+        clock.lap(&mut self.phase_timings, 5);
+        self.check_budget(&clock, 5, tick);
+        self.mark_phase(tick, None);
+        self.degraded = self.overrun.is_some();
+        &self.report
+    }
+
+    /// Record an overrun if `clock` passed the budget at the end of `phase`, the first time
+    /// in the tick
+    #[inline(always)]
+    fn check_budget(&mut self, clock: &PhaseClock, phase: usize, tick: u64) {
+        if let Some(budget) = self.budget
+            && self.overrun.is_none()
+            && clock.elapsed() > budget.limit
+        {
+            self.overrun = Some(TickOverrun {
+                tick,
+                phase: TICK_PHASES[phase],
+                elapsed: clock.elapsed(),
+                degraded: self.degraded,
+            });
+            log::debug!("tick {} overran its budget in {}", tick, TICK_PHASES[phase]);
+        }
+    }
+
+    /// Move apples whose TTL ran out this tick to random empty cells; returns how many
+    pub(crate) fn expire_apples(&mut self) -> u32 {
+        if !self.apple_expiry.is_enabled() {
+            return 0;
+        }
+        let mut expired = 0;
+        for position in self.apple_expiry.advance() {
+            if self.grid.get_cell(&position) == Cell::Apple {
+                self.grid.set_cell(position, Cell::Empty);
+                self.num_apples -= 1;
                 self.spawn_apple();
+                expired += 1;
+            }
+        }
+        expired
+    }
+
+    // Record why `input` will have no effect, if it won't; call before applying it
+    fn audit_input(&mut self, input: &Input) {
+        let snake = &self.snakes[input.snake_id as usize];
+        let reason = if !snake.is_alive() {
+            Rejection::DeadSnake
+        } else if input.direction == snake.snake().direction.opposite()
+            && !self.config.reversal_rule.allows(snake.body().len())
+        {
+            Rejection::Reversal
+        } else if self.config.turn_interval > 0
+            && input.direction != snake.snake().direction
+            && self.now < self.turn_ready(input.snake_id)
+        {
+            Rejection::Throttled
+        } else {
+            return;
+        };
+        self.rejected_inputs.push(RejectedInput { input: *input, reason });
+    }
+
+    // Whether `input` must be dropped under config.turn_interval: it would turn a live
+    // snake that last turned too recently. A turn let through starts a new interval.
+    // Inputs that cannot take effect either way are counted in refused_inputs.
+    #[inline(always)]
+    fn throttle(&mut self, input: &Input) -> bool {
+        // Liveness and direction come from the motion table; only a reversal, which the
+        // rule may allow for short snakes, loads the snake itself
+        let id = input.snake_id;
+        let direction = self.motion.direction(id);
+        if !self.motion.is_alive(id)
+            || (input.direction == direction.opposite()
+                && !self.config.reversal_rule.allows(self.snakes[id as usize].snake().body.len()))
+        {
+            self.refused_inputs += 1;
+            return false;
+        }
+        let interval = self.config.turn_interval;
+        if interval == 0 || input.direction == direction {
+            return false;
+        }
+        if self.now < self.turn_ready(input.snake_id) {
+            self.throttled_inputs += 1;
+            return true;
+        }
+        self.set_turn_ready(input.snake_id, self.now + interval as u64);
+        false
+    }
+
+    // Within each bucket, move the tie-break winner of every apple several heads are about
+    // to reach ahead of the other contenders, so it is the one that eats (see AppleTieBreak)
+    fn order_apple_claims(&mut self, inputs: &[Input]) {
+        let tie_break = self.config.apple_tie_break;
+        for bucket in &mut self.buckets {
+            self.apple_claims.clear();
+            self.apple_claims.extend(
+                bucket
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, record)| self.grid.get_cell(&record.new_head()) == Cell::Apple)
+                    .map(|(index, record)| (record.new_head(), index)),
+            );
+            if self.apple_claims.len() < 2 {
+                continue;
+            }
+            self.apple_claims.sort_unstable_by_key(|&(target, index)| (target.y, target.x, index));
+
+            for group in self.apple_claims.chunk_by(|a, b| a.0 == b.0).filter(|group| group.len() > 1) {
+                // Positions ascend within a group, so group[0] is processed first
+                let winner = match tie_break {
+                    AppleTieBreak::BucketOrder => 0,
+                    AppleTieBreak::Random => self.rng.stream(Stream::Contests).random_range(0..group.len()),
+                    AppleTieBreak::Longest => (0..group.len())
+                        .max_by_key(|&i| (self.snakes[bucket[group[i].1].snake_id() as usize].body().len(), Reverse(i)))
+                        .unwrap_or(0),
+                    AppleTieBreak::FirstInput => (0..group.len())
+                        .min_by_key(|&i| {
+                            let snake_id = bucket[group[i].1].snake_id();
+                            (inputs.iter().position(|input| input.snake_id == snake_id).unwrap_or(usize::MAX), i)
+                        })
+                        .unwrap_or(0),
+                };
+                bucket.swap(group[0].1, group[winner].1);
+            }
+        }
+    }
+
+    // Fill the buckets with one record per alive snake, in id order within each bucket
+    fn collect_records(&mut self) {
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+        assert!(self.snakes.len() <= MAX_RECORD_SNAKES, "more snakes than movement records can hold");
+        if self.dense_motion {
+            self.sync_motion_length();
+            for snake_id in 0..self.motion.len() as u32 {
+                if !self.motion.is_alive(snake_id) {
+                    continue;
+                }
+                let new_head = next_head(self.motion.head(snake_id), self.motion.direction(snake_id));
+                // The one bounds check phase 3 relies on to use the unchecked grid accessors
+                assert!(Grid::contains(&new_head), "snake {} moves off the grid", snake_id);
+                // The cell will be filled in Phase 3
+                self.buckets[bucket_index(&new_head)].push(MovementRecord::new(snake_id, new_head, Cell::Empty));
             }
+            return;
+        }
+        for snake in &self.snakes {
+            if !snake.is_alive() { continue; }
+
+            let new_head = snake.calculate_new_head();
+            // The one bounds check phase 3 relies on to use the unchecked grid accessors
+            assert!(Grid::contains(&new_head), "snake {} moves off the grid", snake.id());
+            // The cell will be filled in Phase 3
+            self.buckets[bucket_index(&new_head)].push(MovementRecord::new(snake.id(), new_head, Cell::Empty));
+        }
+    }
+
+    // Turn a snake and move its speculated record if its next head changed. Keeps buckets
+    // sorted by id so they match what collect_records would have built.
+    fn apply_speculated_input(&mut self, input: &Input) {
+        let snake = &mut self.snakes[input.snake_id as usize];
+        if !snake.is_alive() {
+            snake.turn(input.direction, self.config.reversal_rule);
+            self.motion.set_direction(input.snake_id, snake.snake().direction);
+            return;
+        }
+        let before = snake.calculate_new_head();
+        snake.turn(input.direction, self.config.reversal_rule);
+        self.motion.set_direction(input.snake_id, snake.snake().direction);
+        let after = snake.calculate_new_head();
+        assert!(Grid::contains(&after), "snake {} moves off the grid", snake.id());
+        if before == after {
+            return;
         }
+
+        let snake_id = snake.id();
+        let bucket = &mut self.buckets[bucket_index(&before)];
+        let index = bucket
+            .binary_search_by_key(&snake_id, |record| record.snake_id())
+            .expect("alive snake has a speculated record");
+        let mut record = bucket.remove(index);
+        record.set_new_head(after);
+        let bucket = &mut self.buckets[bucket_index(&after)];
+        let index = bucket.binary_search_by_key(&snake_id, |record| record.snake_id()).unwrap_or_else(|index| index);
+        bucket.insert(index, record);
+    }
+
+    /// Hand the rear half of `parent` to a new snake facing the other way. The cells stay
+    /// on the grid; only their owner changes. Dead or too short parents are left alone.
+    fn split_snake(&mut self, parent: u32) {
+        let snake = &self.snakes[parent as usize];
+        let length = snake.body().len();
+        if !snake.is_alive() || length < self.config.split_min_length.max(2) {
+            return;
+        }
+
+        let fragment_length = powerup::fragment_length(length);
+        let fragment = self.allocate_id();
+        let rear = self.snakes[parent as usize].take_rear(fragment_length);
+        let joint = self.snakes[parent as usize].tail_point();
+        let (cells, direction) = powerup::fragment_body(&rear, joint);
+        let snake = GridAwareSnake::from_placed(Snake {
+            id: fragment,
+            body: cells.into_iter().collect(),
+            direction,
+            is_alive: true,
+            pending_growth: 0,
+        });
+        self.heads.update(fragment, snake.head().copied());
+        self.motion.sync(&snake);
+        if fragment as usize == self.snakes.len() {
+            self.snakes.push(snake);
+        } else {
+            self.snakes[fragment as usize] = snake;
+        }
+
+        self.population.on_split(length, fragment_length);
+        self.splits.push(Split {
+            parent,
+            fragment,
+            fragment_length,
+        });
+    }
+
+    // Snakes pushed into `snakes` directly leave the motion table short; catch up by a rescan
+    #[inline(always)]
+    fn sync_motion_length(&mut self) {
+        if self.motion.len() != self.snakes.len() {
+            self.motion.rebuild(&self.snakes);
+        }
+    }
+
+    /// Lowest free dead id, its corpse cleared from the grid, or a new id past the end
+    fn allocate_id(&mut self) -> u32 {
+        while let Some(id) = self.free_ids.pop_first() {
+            // Entries go stale when dead snakes are replaced from outside the engine
+            if let Some(snake) = self.snakes.get_mut(id as usize)
+                && !snake.is_alive()
+            {
+                snake.die(&mut self.grid);
+                return id;
+            }
+        }
+        self.snakes.len() as u32
+    }
+
+    /// Run `n` ticks numbered from `first_tick`, asking `source` for each tick's inputs.
+    ///
+    /// Same result as calling `tick` in a loop, without the caller managing a batch buffer;
+    /// the buffer is reused across ticks and calls. Wrap a closure with
+    /// `input::source::from_fn` to compute inputs on the fly.
+    pub fn tick_n<S: InputSource>(&mut self, n: u64, first_tick: u64, source: &mut S) {
+        let mut inputs = std::mem::take(&mut self.input_scratch);
+        for tick in first_tick..first_tick + n {
+            inputs.clear();
+            source.next_batch(tick, &mut inputs);
+            self.tick(&inputs);
+        }
+        self.input_scratch = inputs;
+    }
+
+    /// Tick with inputs merged from several sources: `batch` is sorted into application
+    /// order (see [`StampedInput::sort_batch`]) before the inputs are applied. Returns the
+    /// inputs as applied, e.g. for recording a replay.
+    pub fn tick_stamped(&mut self, batch: &mut [StampedInput]) -> &[Input] {
+        StampedInput::sort_batch(batch);
+        let mut inputs = std::mem::take(&mut self.input_scratch);
+        inputs.clear();
+        inputs.extend(batch.iter().map(|stamped| stamped.input));
+        self.tick(&inputs);
+        self.input_scratch = inputs;
+        &self.input_scratch
     }
 
     /// Add an apple to the game state (grid update happens automatically)
     pub fn add_apple(&mut self, apple: Apple) {
-        if self.num_apples < APPLE_CAPACITY as u64 {
+        if self.num_apples < self.config.apple_capacity as u64 {
             self.grid.set_cell(apple.position, Cell::Apple);
+            self.apple_expiry.track(apple.position);
             self.num_apples += 1;
         }
     }
 
-    /// Spawn a new apple at a random empty position
+    /// Place a split power-up, if below `config.split_powerups`
+    pub fn add_split_powerup(&mut self, position: Point) {
+        if self.num_powerups < self.config.split_powerups as u64 {
+            self.grid.set_cell(position, Cell::Split);
+            self.num_powerups += 1;
+        }
+    }
+
+    /// Top power-ups up to `config.split_powerups` at random empty positions; returns how
+    /// many were placed. Draws nothing from the RNG while the mechanic is off.
+    pub(crate) fn spawn_powerups(&mut self) -> u32 {
+        let mut spawned = 0;
+        while self.num_powerups < self.config.split_powerups as u64 {
+            let Some(position) = (0..100)
+                .map(|_| self.rng.stream(Stream::PowerUps).random::<Point>())
+                .find(|position| self.grid.get_cell(position) == Cell::Empty)
+            else {
+                break;
+            };
+            self.grid.set_cell(position, Cell::Split);
+            self.num_powerups += 1;
+            spawned += 1;
+        }
+        spawned
+    }
+
+    // Mark the cells ahead of every head that moved this tick, from this tick's records
+    fn mark_head_paths(&mut self) {
+        let clearance = self.config.apple_head_clearance;
+        for record in self.buckets.iter().flatten() {
+            let snake = &self.snakes[record.snake_id() as usize];
+            if !snake.is_alive() {
+                continue;
+            }
+            let direction = snake.snake().direction;
+            let mut cell = record.new_head();
+            for _ in 0..clearance {
+                cell = cell.step(direction);
+                self.head_paths.push(cell);
+            }
+        }
+        self.head_paths.sort_unstable_by_key(|cell| (cell.y, cell.x));
+    }
+
+    /// Spawn a new apple at a random empty position, off the head paths during a tick
+    /// (see `GameConfig::apple_head_clearance`) and near a hotspot when those are on
+    /// (see `GameConfig::apple_hotspots`)
     fn spawn_apple(&mut self) {
-        if self.num_apples >= APPLE_CAPACITY as u64 {
-            return; // Don't spawn if at capacity
+        self.spawn_apple_in(None);
+    }
+
+    /// [`GameState::spawn_apple`] inside `region` when given; false when no apple was placed
+    fn spawn_apple_in(&mut self, region: Option<Rect>) -> bool {
+        if self.num_apples >= self.config.apple_capacity as u64 {
+            return false; // Don't spawn if at capacity
+        }
+        if region.is_some_and(|region| region.width == 0 || region.height == 0) {
+            return false;
         }
 
-        let mut rng = rand::rng();
         for _attempts in 0..100 {
             // Limit attempts to avoid infinite loop
-            let position = rng.random::<Point>();
-            if self.grid.get_cell(&position) == Cell::Empty {
+            let hotspots = self.config.apple_hotspots;
+            let position = if let Some(region) = region {
+                let mut rng = self.rng.stream(Stream::Apples);
+                region.at(rng.random_range(0..region.width), rng.random_range(0..region.height))
+            } else if hotspots.is_enabled() {
+                hotspots.sample(&mut self.rng.stream(Stream::Apples), self.config.rng_seed, self.now)
+            } else {
+                self.rng.stream(Stream::Apples).random::<Point>()
+            };
+            if self.grid.get_cell(&position) == Cell::Empty
+                && self.head_paths.binary_search_by_key(&(position.y, position.x), |cell| (cell.y, cell.x)).is_err()
+            {
                 self.grid.set_cell(position, Cell::Apple);
+                self.apple_expiry.track(position);
                 self.num_apples += 1;
-                break;
+                return true;
             }
         }
+        false
+    }
+}
+
+// Spatial bucket of a cell: its row band
+#[inline(always)]
+pub fn bucket_index(point: &Point) -> usize {
+    (point.y >> (16 - BUCKET_BITS)) as usize
+}
+
+// Write `value` into one spare slot per page of `vec`'s capacity; the length is unchanged
+fn prefault_spare<T: Copy>(vec: &mut Vec<T>, value: T) {
+    let step = (PAGE_SIZE / std::mem::size_of::<T>()).max(1);
+    for slot in vec.spare_capacity_mut().iter_mut().step_by(step) {
+        slot.write(value);
+    }
+}
+
+/// Snake (other than `exclude`) with a body segment at `point`.
+/// Linear scan, only used on deaths when kills are tracked.
+fn find_occupant(snakes: &[GridAwareSnake], point: &Point, exclude: u32) -> Option<u32> {
+    snakes
+        .iter()
+        .find(|snake| snake.id() != exclude && snake.occupies(point))
+        .map(|snake| snake.id())
+}
+
+// Letter identifying a snake in ASCII dumps
+fn snake_letter(id: u32, head: bool) -> char {
+    let letter = (b'a' + (id % 26) as u8) as char;
+    if head { letter.to_ascii_uppercase() } else { letter }
+}
+
+/// Record a kill and queue the killer's share of the victim's length
+fn credit_kill(snakes: &mut [GridAwareSnake], kills: &mut Vec<Kill>, growth_percent: u8, kill: Kill) {
+    let growth = (kill.victim_length * growth_percent as usize / 100) as u32;
+    let killer = &mut snakes[kill.killer as usize];
+    if growth > 0 && killer.is_alive() {
+        killer.queue_growth(growth);
     }
+    kills.push(kill);
 }
 
 impl Default for GameState {
@@ -0,0 +1,102 @@
+use crate::game::apple::Apple;
+use crate::game::engine::GameState;
+use crate::game::grid::Cell;
+use crate::game::rng::Stream;
+use crate::game::types::Rect;
+use rand::Rng;
+
+/// Something done to the world at a set tick, apart from player inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldEvent {
+    /// Up to `count` apples at random empty cells of `area`, within `config.apple_capacity`
+    SpawnApples { count: u32, area: Rect },
+    /// Bring back up to `count` dead snakes at once (see `GameState::respawn_dead`)
+    RespawnWave { count: u32 },
+}
+
+/// A [`WorldEvent`] due before tick `tick` runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledEvent {
+    pub tick: u64,
+    pub event: WorldEvent,
+}
+
+/// Events in tick order, fired against the state's clock (`GameState::now`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventSchedule {
+    events: Vec<ScheduledEvent>,
+    // Events before this one have fired
+    cursor: usize,
+}
+
+impl EventSchedule {
+    /// Sort `events` by tick; events of the same tick keep their order
+    pub fn new(mut events: Vec<ScheduledEvent>) -> Self {
+        events.sort_by_key(|event| event.tick);
+        Self { events, cursor: 0 }
+    }
+
+    pub fn events(&self) -> &[ScheduledEvent] {
+        &self.events
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Apply every event due at `state.now()`, skipping any left over from earlier ticks.
+    /// Call right before each tick. Returns the number of events applied.
+    pub fn fire_due(&mut self, state: &mut GameState) -> usize {
+        let now = state.now();
+        while self.events.get(self.cursor).is_some_and(|event| event.tick < now) {
+            self.cursor += 1;
+        }
+        let mut fired = 0;
+        while let Some(event) = self.events.get(self.cursor).filter(|event| event.tick == now) {
+            state.apply_event(&event.event);
+            self.cursor += 1;
+            fired += 1;
+        }
+        fired
+    }
+
+    /// Events with `start <= tick < start + len`, renumbered so `start` becomes tick 0
+    pub fn window(&self, start: u64, len: u64) -> EventSchedule {
+        let events = self
+            .events
+            .iter()
+            .filter(|event| event.tick >= start && event.tick - start < len)
+            .map(|event| ScheduledEvent { tick: event.tick - start, ..*event })
+            .collect();
+        EventSchedule { events, cursor: 0 }
+    }
+}
+
+impl GameState {
+    /// Apply `event` now. Returns how many apples or snakes it placed.
+    ///
+    /// Placement draws from the engine's event stream, so events never shift the apple
+    /// and respawn sequences of the ticks around them.
+    pub fn apply_event(&mut self, event: &WorldEvent) -> usize {
+        match *event {
+            WorldEvent::SpawnApples { count, area } => {
+                let mut placed = 0;
+                for _ in 0..count {
+                    if self.num_apples >= self.config.apple_capacity as u64 || area.width == 0 || area.height == 0 {
+                        break;
+                    }
+                    let mut rng = self.rng.stream(Stream::Events);
+                    let position = (0..100)
+                        .map(|_| area.at(rng.random_range(0..area.width), rng.random_range(0..area.height)))
+                        .find(|position| self.grid.get_cell(position) == Cell::Empty);
+                    if let Some(position) = position {
+                        self.add_apple(Apple { position });
+                        placed += 1;
+                    }
+                }
+                placed
+            }
+            WorldEvent::RespawnWave { count } => self.respawn_dead(count as usize),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Fractional bits of [`Fixed`]
+pub const FRAC_BITS: u32 = 16;
+
+// pi / 2 in Q2.30, the internal precision of the trig helpers
+const HALF_PI_Q30: i64 = 1_686_629_713;
+const Q30_BITS: u32 = 30;
+// One quarter turn in Fixed raw units
+const QUARTER_TURN: i32 = 1 << (FRAC_BITS - 2);
+
+/// Q16.16 signed fixed-point number.
+///
+/// Gameplay code uses this instead of floats so every platform computes bit-identical
+/// results, which lockstep simulation and replays depend on. Arithmetic truncates like
+/// integer arithmetic and overflows like `i32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Fixed(i32);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << FRAC_BITS);
+    pub const HALF: Fixed = Fixed(1 << (FRAC_BITS - 1));
+
+    pub const fn from_raw(raw: i32) -> Self {
+        Self(raw)
+    }
+
+    pub const fn raw(self) -> i32 {
+        self.0
+    }
+
+    pub const fn from_int(value: i32) -> Self {
+        Self(value << FRAC_BITS)
+    }
+
+    /// `numerator / denominator`, truncated toward zero
+    pub const fn from_ratio(numerator: i64, denominator: i64) -> Self {
+        Self(((numerator << FRAC_BITS) / denominator) as i32)
+    }
+
+    /// Largest integer not greater than the value
+    pub const fn floor(self) -> i32 {
+        self.0 >> FRAC_BITS
+    }
+
+    /// Integer part, rounding toward zero (like a float `as` cast)
+    pub const fn trunc(self) -> i32 {
+        self.0 / Self::ONE.0
+    }
+
+    /// Nearest integer, halves rounding up
+    pub const fn round(self) -> i32 {
+        (self.0 + Self::HALF.0) >> FRAC_BITS
+    }
+
+    /// Sine of an angle given in turns (1.0 = full circle)
+    pub fn sin_turns(self) -> Self {
+        // Fold the angle into the first quadrant, remembering the sign
+        let angle = self.0 & (Self::ONE.0 - 1);
+        let quadrant = angle / QUARTER_TURN;
+        let offset = angle % QUARTER_TURN;
+        let offset = if quadrant % 2 == 1 { QUARTER_TURN - offset } else { offset };
+        let sine = sin_quadrant(offset);
+        Self(if quadrant >= 2 { -sine } else { sine })
+    }
+
+    /// Cosine of an angle given in turns (1.0 = full circle)
+    pub fn cos_turns(self) -> Self {
+        Self(self.0.wrapping_add(QUARTER_TURN)).sin_turns()
+    }
+}
+
+// Sine of `offset` quarter-turn units in [0, QUARTER_TURN], as raw Q16.16.
+// Taylor series to the x^9 term in Q2.30; the truncation error (< 4e-6) is below Q16.16 resolution.
+fn sin_quadrant(offset: i32) -> i32 {
+    let x = offset as i64 * HALF_PI_Q30 / QUARTER_TURN as i64;
+    let x2 = (x * x) >> Q30_BITS;
+    let mut term = x;
+    let mut sum = x;
+    for k in [2i64, 4, 6, 8] {
+        term = -((term * x2) >> Q30_BITS) / (k * (k + 1));
+        sum += term;
+    }
+    let shift = Q30_BITS - FRAC_BITS;
+    ((sum + (1 << (shift - 1))) >> shift) as i32
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    #[inline(always)]
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    #[inline(always)]
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0 - rhs.0)
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    #[inline(always)]
+    fn mul(self, rhs: Fixed) -> Fixed {
+        Fixed(((self.0 as i64 * rhs.0 as i64) >> FRAC_BITS) as i32)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Fixed;
+
+    #[inline(always)]
+    fn div(self, rhs: Fixed) -> Fixed {
+        Fixed((((self.0 as i64) << FRAC_BITS) / rhs.0 as i64) as i32)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    #[inline(always)]
+    fn neg(self) -> Fixed {
+        Fixed(-self.0)
+    }
+}
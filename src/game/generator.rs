@@ -1,17 +1,24 @@
 use crate::game::{
-    apple::{APPLE_CAPACITY, Apple},
-    engine::{GameState, NUM_BUCKETS, EXPECTED_SNAKES_PER_BUCKET},
-    grid::{Cell, GRID_HEIGHT, GRID_WIDTH, Grid},
-    snake::{SNAKE_CAPACITY, Snake, GridAwareSnake},
+    apple::Apple,
+    config::GameConfig,
+    engine::GameState,
+    fixedpoint::Fixed,
+    grid::{Cell, GRID_HEIGHT, GRID_WIDTH, Grid, GridBand, TILES_Y},
+    rng::SplitMix64,
+    snake::{Snake, GridAwareSnake},
+    spawn::{BodyShape, SpawnConfig},
     types::{Direction, Point},
 };
-use rand::Rng;
+use rand::{Rng, RngCore};
 
 #[derive(Clone, Copy)]
 pub struct DeterministicConfig {
     pub seed: u64,
     pub layout_pattern: LayoutPattern,
     pub initial_snake_length: usize,
+    /// Body layout in `generate`; `generate_predictable_outcomes` always uses straight bodies
+    pub body_shape: BodyShape,
+    pub game: GameConfig,
 }
 
 #[derive(Clone, Copy)]
@@ -26,6 +33,8 @@ impl Default for DeterministicConfig {
             seed: 42, // Default seed for reproducibility
             layout_pattern: LayoutPattern::Grid,
             initial_snake_length: 3,
+            body_shape: BodyShape::Straight,
+            game: GameConfig::default(),
         }
     }
 }
@@ -47,37 +56,30 @@ impl DeterministicGenerator {
             LayoutPattern::Concentric => Self::calculate_concentric_positions(num_snakes),
         };
         
-        // Place snakes
+        // Place snakes, tail at the layout position. A body that would overlap an earlier
+        // one (tight layouts, long coils) shrinks to its tail cell.
+        let spawn = SpawnConfig {
+            initial_length: config.initial_snake_length,
+            shape: config.body_shape,
+        };
         for (i, pos) in snake_positions.iter().enumerate() {
-            let mut snake = Snake::new(i as u32, *pos, Direction::Right);
-            // Grow to initial length
-            for _ in 0..config.initial_snake_length - 1 {
-                snake.move_forward(true);
-            }
+            let snake = spawn
+                .try_place(&grid, i as u32, *pos, Direction::Right)
+                .unwrap_or_else(|| Snake::new(i as u32, *pos, Direction::Right));
             let grid_aware_snake = GridAwareSnake::new(snake, &mut grid);
             snakes.push(grid_aware_snake);
         }
         
         // Place apples in remaining spaces
-        let apple_positions = Self::calculate_apple_positions(&grid, config.seed);
+        let apple_positions = Self::calculate_apple_positions(&grid, config.seed, config.game.apple_capacity);
         for pos in apple_positions {
-            if num_apples < APPLE_CAPACITY as u64 {
+            if num_apples < config.game.apple_capacity as u64 {
                 grid.set_cell(pos, Cell::Apple);
                 num_apples += 1;
             }
         }
         
-        GameState {
-            snakes,
-            num_apples,
-            grid,
-            buckets: (0..NUM_BUCKETS)
-                .map(|_| Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET))
-                .collect(),
-            tail_buckets: (0..NUM_BUCKETS)
-                .map(|_| Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET))
-                .collect(),
-        }
+        GameState::from_parts(config.game, snakes, num_apples, grid)
     }
     
     /// Generate a deterministic game state with predictable outcomes in a single tick
@@ -120,7 +122,8 @@ impl DeterministicGenerator {
         // Place apples first, then place apple group snakes next to them
         let apple_start_x = 200;
         let apple_start_y = 100;
-        let apples_to_place = apple_group_size.min(APPLE_CAPACITY);
+        let apple_capacity = config.game.apple_capacity;
+        let apples_to_place = apple_group_size.min(apple_capacity);
         
         // Place apples
         for i in 0..apples_to_place {
@@ -170,32 +173,22 @@ impl DeterministicGenerator {
         }
         
         // Add some additional random apples if we have capacity
-        if num_apples < APPLE_CAPACITY as u64 {
-            let additional_apple_positions = Self::calculate_apple_positions(&grid, config.seed);
-            for pos in additional_apple_positions.iter().take((APPLE_CAPACITY as u64 - num_apples) as usize) {
+        if num_apples < apple_capacity as u64 {
+            let additional_apple_positions = Self::calculate_apple_positions(&grid, config.seed, apple_capacity);
+            for pos in additional_apple_positions.iter().take((apple_capacity as u64 - num_apples) as usize) {
                 grid.set_cell(*pos, Cell::Apple);
                 num_apples += 1;
             }
         }
         
-        GameState {
-            snakes,
-            num_apples,
-            grid,
-            buckets: (0..NUM_BUCKETS)
-                .map(|_| Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET))
-                .collect(),
-            tail_buckets: (0..NUM_BUCKETS)
-                .map(|_| Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET))
-                .collect(),
-        }
+        GameState::from_parts(config.game, snakes, num_apples, grid)
     }
     
     /// Validate that the generated game state is reasonable
     pub fn validate_game_state(game_state: &GameState, expected_snakes: usize) -> bool {
         // Check if we have the expected number of snakes
         if game_state.snakes.len() != expected_snakes {
-            println!("❌ Expected {} snakes, got {}", expected_snakes, game_state.snakes.len());
+            log::warn!("invalid game state: expected {} snakes, got {}", expected_snakes, game_state.snakes.len());
             return false;
         }
         
@@ -208,8 +201,8 @@ impl DeterministicGenerator {
         let mut min_distance = u32::MAX;
         for i in 0..positions.len() {
             for j in i+1..positions.len() {
-                let dx = (positions[i].x as i32 - positions[j].x as i32).abs() as u32;
-                let dy = (positions[i].y as i32 - positions[j].y as i32).abs() as u32;
+                let dx = (positions[i].x as i32 - positions[j].x as i32).unsigned_abs();
+                let dy = (positions[i].y as i32 - positions[j].y as i32).unsigned_abs();
                 let distance = dx + dy; // Manhattan distance
                 min_distance = min_distance.min(distance);
             }
@@ -217,22 +210,23 @@ impl DeterministicGenerator {
         
         // Snakes should be at least 2 cells apart
         if min_distance < 2 {
-            println!("❌ Snakes too close together: minimum distance = {}", min_distance);
+            log::warn!("invalid game state: snakes too close together, minimum distance = {}", min_distance);
             return false;
         }
         
         // Check if we have reasonable number of apples
         if game_state.num_apples == 0 {
-            println!("❌ No apples in game state");
+            log::warn!("invalid game state: no apples");
             return false;
         }
         
-        if game_state.num_apples > APPLE_CAPACITY as u64 {
-            println!("❌ Too many apples: {} > {}", game_state.num_apples, APPLE_CAPACITY);
+        let apple_capacity = game_state.config.apple_capacity;
+        if game_state.num_apples > apple_capacity as u64 {
+            log::warn!("invalid game state: too many apples, {} > {}", game_state.num_apples, apple_capacity);
             return false;
         }
         
-        println!("✅ Valid game state: {} snakes, {} apples, min distance = {}", 
+        log::debug!("valid game state: {} snakes, {} apples, min distance = {}", 
                 game_state.snakes.len(), game_state.num_apples, min_distance);
         true
     }
@@ -242,7 +236,7 @@ impl DeterministicGenerator {
         // If we want snakes to be reasonably spaced:
         let total_cells = GRID_WIDTH * GRID_HEIGHT;
         let available_cells = total_cells / 2; // Leave space for apples and snake bodies
-        let spacing = (available_cells as f64 / num_snakes as f64).sqrt() as usize;
+        let spacing = (available_cells / num_snakes.max(1)).isqrt();
         spacing.max(2) // Minimum 2 cells between snakes
     }
     
@@ -272,12 +266,15 @@ impl DeterministicGenerator {
             y: (GRID_HEIGHT / 2) as u16 
         };
         let mut radius = 2;
-        let angle_step = 2.0 * std::f64::consts::PI / num_snakes as f64;
+        // Offsets truncate toward zero and negative offsets clamp to zero
+        let offset = |radius: i32, component: Fixed| (Fixed::from_int(radius) * component).trunc().max(0) as u16;
         
         for i in 0..num_snakes {
-            let angle = i as f64 * angle_step;
-            let x = center.x + (radius as f64 * angle.cos()) as u16;
-            let y = center.y + (radius as f64 * angle.sin()) as u16;
+            // Angles are in turns so the geometry stays in fixed point
+            let angle = Fixed::from_ratio(i as i64, num_snakes as i64);
+            let (sin, cos) = (angle.sin_turns(), angle.cos_turns());
+            let x = center.x + offset(radius, cos);
+            let y = center.y + offset(radius, sin);
             
             if x < GRID_WIDTH as u16 && y < GRID_HEIGHT as u16 {
                 positions.push(Point { x, y });
@@ -286,8 +283,8 @@ impl DeterministicGenerator {
                 // Recalculate this position
                 if i > 0 {
                     // Try again with larger radius
-                    let x = center.x + (radius as f64 * angle.cos()) as u16;
-                    let y = center.y + (radius as f64 * angle.sin()) as u16;
+                    let x = center.x + offset(radius, cos);
+                    let y = center.y + offset(radius, sin);
                     if x < GRID_WIDTH as u16 && y < GRID_HEIGHT as u16 {
                         positions.push(Point { x, y });
                     }
@@ -297,13 +294,13 @@ impl DeterministicGenerator {
         positions
     }
     
-    fn calculate_apple_positions(grid: &Grid, _seed: u64) -> Vec<Point> {
+    fn calculate_apple_positions(grid: &Grid, _seed: u64, apple_capacity: usize) -> Vec<Point> {
         let mut positions = Vec::new();
         // TODO: Use seeded RNG for true determinism
         
         // Calculate how many apples we want (reasonable ratio to empty space)
         let empty_cells = GRID_WIDTH * GRID_HEIGHT - 100; // Approximate empty cells after snakes
-        let target_apples = (empty_cells / 1000).min(APPLE_CAPACITY); // 1 apple per 1000 empty cells, max 128
+        let target_apples = (empty_cells / 1000).min(apple_capacity); // 1 apple per 1000 empty cells, capped by config
         
         // Place apples with reasonable spacing
         let mut count = 0;
@@ -345,51 +342,135 @@ impl DeterministicGenerator {
     
 }
 
+/// Horizontal bands `RandomGenerator::generate_parallel` fills at once. Fixed rather than
+/// one per core, so a seed builds the same state on any machine.
+pub const GENERATOR_BANDS: usize = 16;
+
 pub struct RandomGenerator;
 
 impl RandomGenerator {
     pub fn generate() -> GameState {
-        let mut random_snakes = Vec::<GridAwareSnake>::with_capacity(SNAKE_CAPACITY);
-        let mut grid = Grid::new();
-        let mut rng = rand::rng();
+        Self::generate_with_config(GameConfig::default())
+    }
+
+    pub fn generate_with_config(config: GameConfig) -> GameState {
+        Self::generate_with_spawn(config, SpawnConfig::default())
+    }
+
+    /// Random placement with `spawn` bodies. Every body cell lands on an empty cell.
+    pub fn generate_with_spawn(config: GameConfig, spawn: SpawnConfig) -> GameState {
+        Self::generate_parallel(config, spawn, rand::rng().next_u64())
+    }
+
+    /// Random placement built band by band: the grid is cut into [`GENERATOR_BANDS`]
+    /// horizontal bands of whole tiles, allocated and populated on up to one thread per core
+    /// and then joined. Most of the time goes into writing the ~100MB of empty rows, which
+    /// the bands spread over the cores.
+    ///
+    /// Each band places its share of snakes (a contiguous id range) and apples from its own
+    /// generator derived from `seed`, keeping bodies inside the band, so the same seed
+    /// always builds the same state. The layout differs from `generate_with_rng`.
+    pub fn generate_parallel(config: GameConfig, spawn: SpawnConfig, seed: u64) -> GameState {
+        // One band after another without spare cores (or threads, in the browser)
+        let workers = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+        Self::generate_banded(config, spawn, seed, workers)
+    }
+
+    /// `generate_parallel` on `workers` threads; any count builds the same state
+    pub(crate) fn generate_banded(config: GameConfig, spawn: SpawnConfig, seed: u64, workers: usize) -> GameState {
+        let workers = workers.clamp(1, GENERATOR_BANDS);
+        let mut seeds = SplitMix64::new(seed);
+        let jobs: Vec<(usize, u64)> = (0..GENERATOR_BANDS).map(|band| (band, seeds.next_u64())).collect();
+        let mut bands: Vec<_> = if workers <= 1 {
+            jobs.iter().map(|&(band, seed)| (band, Self::fill_band(band, &config, &spawn, seed))).collect()
+        } else {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = (0..workers)
+                    .map(|worker| {
+                        let jobs = &jobs;
+                        scope.spawn(move || {
+                            jobs.iter()
+                                .skip(worker)
+                                .step_by(workers)
+                                .map(|&(band, seed)| (band, Self::fill_band(band, &config, &spawn, seed)))
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles.into_iter().flat_map(|handle| handle.join().expect("generator band panicked")).collect()
+            })
+        };
+        bands.sort_unstable_by_key(|(band, _)| *band);
+
+        let mut snakes = Vec::with_capacity(config.snake_capacity);
         let mut num_apples = 0;
+        let mut grid_bands = Vec::with_capacity(GENERATOR_BANDS);
+        for (_, (grid_band, band_snakes, band_apples)) in bands {
+            snakes.extend(band_snakes.into_iter().map(GridAwareSnake::from_placed));
+            num_apples += band_apples;
+            grid_bands.push(grid_band);
+        }
+        GameState::from_parts(config, snakes, num_apples, Grid::from_bands(grid_bands))
+    }
 
-        // Spawn snakes with collision detection
-        for index in 0..SNAKE_CAPACITY {
-            let mut attempts = 0;
-            let snake = loop {
-                let start_pos = rng.random::<Point>();
+    // Band `band` of `generate_parallel`: its rows, its snakes in id order and its apple count
+    fn fill_band(band: usize, config: &GameConfig, spawn: &SpawnConfig, seed: u64) -> (GridBand, Vec<Snake>, u64) {
+        let share = |total: usize| total * band / GENERATOR_BANDS..total * (band + 1) / GENERATOR_BANDS;
+        let mut grid = GridBand::new(share(TILES_Y));
+        let rows = grid.rows();
+        let mut rng = SplitMix64::new(seed);
 
-                // Check if the starting position is empty
-                if grid.get_cell(&start_pos) == Cell::Empty {
-                    let mut snake = Snake::new(index as u32, start_pos, rng.random());
+        let mut snakes = Vec::with_capacity(share(config.snake_capacity).len());
+        for id in share(config.snake_capacity) {
+            let snake = (0..1000)
+                .find_map(|_| {
+                    let tail = Point {
+                        x: rng.random_range(0..GRID_WIDTH) as u16,
+                        y: rng.random_range(rows.clone()) as u16,
+                    };
+                    let direction = rng.random();
+                    let cells = spawn.body_cells(tail, direction);
+                    // Cells wrapping out of the band read as None
+                    cells.iter().all(|cell| grid.get_cell(cell) == Some(Cell::Empty)).then(|| Snake {
+                        body: cells.into_iter().collect(),
+                        ..Snake::new(id as u32, tail, direction)
+                    })
+                })
+                // Fallback: create a minimal snake if we can't find space
+                .unwrap_or_else(|| Snake::new(id as u32, Point { x: 0, y: rows.start as u16 }, rng.random()));
+            for cell in snake.segments() {
+                grid.set_cell(cell, Cell::Snake);
+            }
+            snakes.push(snake);
+        }
 
-                    // Grow the snake and check each new segment
-                    let mut valid_growth = true;
-                    for _ in 0..3 {
-                        snake.move_forward(true);
-                        // Check if the new tail position is valid
-                        if let Some(tail) = snake.body.get(0) {
-                            if grid.get_cell(tail) != Cell::Empty {
-                                valid_growth = false;
-                                break;
-                            }
-                        }
-                    }
+        let mut num_apples = 0;
+        for _ in share(config.apple_capacity) {
+            let position = (0..1000)
+                .map(|_| Point {
+                    x: rng.random_range(0..GRID_WIDTH) as u16,
+                    y: rng.random_range(rows.clone()) as u16,
+                })
+                .find(|position| grid.get_cell(position) == Some(Cell::Empty));
+            if let Some(position) = position {
+                grid.set_cell(position, Cell::Apple);
+                num_apples += 1;
+            }
+        }
+        (grid, snakes, num_apples)
+    }
 
-                    if valid_growth {
-                        break snake;
-                    }
-                }
+    pub(crate) fn generate_with_rng<R: Rng>(config: GameConfig, spawn: SpawnConfig, rng: &mut R) -> GameState {
+        let mut random_snakes = Vec::<GridAwareSnake>::with_capacity(config.snake_capacity);
+        let mut grid = Grid::new();
+        let mut num_apples = 0;
 
-                attempts += 1;
-                if attempts > 1000 {
-                    // Fallback: create a minimal snake if we can't find space
-                    let start_pos = Point { x: 0, y: 0 };
-                    let snake = Snake::new(index as u32, start_pos, rng.random());
-                    break snake;
-                }
-            };
+        // Spawn snakes with collision detection on every body cell
+        for index in 0..config.snake_capacity {
+            let snake = (0..1000)
+                .find_map(|_| spawn.try_place(&grid, index as u32, rng.random(), rng.random()))
+                // Fallback: create a minimal snake if we can't find space
+                .unwrap_or_else(|| Snake::new(index as u32, Point { x: 0, y: 0 }, rng.random()));
 
             // Add snake to the game state using wrapper
             let grid_aware_snake = GridAwareSnake::new(snake, &mut grid);
@@ -397,7 +478,7 @@ impl RandomGenerator {
         }
 
         // Spawn apples in empty spaces
-        for _ in 0..APPLE_CAPACITY {
+        for _ in 0..config.apple_capacity {
             let mut attempts = 0;
             loop {
                 let apple = Apple::new(rng.random());
@@ -414,16 +495,6 @@ impl RandomGenerator {
             }
         }
 
-        GameState {
-            snakes: random_snakes,
-            num_apples,
-            grid,
-            buckets: (0..NUM_BUCKETS)
-                .map(|_| Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET))
-                .collect(),
-            tail_buckets: (0..NUM_BUCKETS)
-                .map(|_| Vec::with_capacity(EXPECTED_SNAKES_PER_BUCKET))
-                .collect(),
-        }
+        GameState::from_parts(config, random_snakes, num_apples, grid)
     }
 }
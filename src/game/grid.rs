@@ -1,23 +1,70 @@
-use crate::game::types::Point;
+use crate::game::types::{Point, Rect};
+use crate::runtime::platform::{PAGE_SIZE, lock_vec};
+use std::io;
 
 pub const GRID_WIDTH: usize = 10_000;
 pub const GRID_HEIGHT: usize = 10_000;
 
+// Tile partitioning used for change tracking (128x128 cells per tile)
+pub const TILE_BITS: usize = 7;
+pub const TILE_SIZE: usize = 1 << TILE_BITS;
+pub const TILES_X: usize = GRID_WIDTH.div_ceil(TILE_SIZE);
+pub const TILES_Y: usize = GRID_HEIGHT.div_ceil(TILE_SIZE);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Cell {
     Empty,
     Snake,
     Apple,
+    /// Split power-up (see `game::powerup`)
+    Split,
+}
+
+impl Cell {
+    /// Compact encoding used by snapshots
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Cell::Empty => 0,
+            Cell::Snake => 1,
+            Cell::Apple => 2,
+            Cell::Split => 3,
+        }
+    }
+
+    /// Symbol used by ASCII dumps
+    pub fn to_char(self) -> char {
+        match self {
+            Cell::Empty => '.',
+            Cell::Snake => '#',
+            Cell::Apple => '*',
+            Cell::Split => '+',
+        }
+    }
+
+    /// Inverse of [`Cell::to_u8`]
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Cell::Empty),
+            1 => Some(Cell::Snake),
+            2 => Some(Cell::Apple),
+            3 => Some(Cell::Split),
+            _ => None,
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct Grid {
     cells: Vec<Vec<Cell>>,
+    // XOR of the hashes of every non-empty cell in each tile, maintained on every write
+    tile_hashes: Vec<u64>,
 }
 
 impl Grid {
     pub fn new() -> Self {
         Self {
             cells: vec![vec![Cell::Empty; GRID_WIDTH]; GRID_HEIGHT],
+            tile_hashes: vec![0; TILES_X * TILES_Y],
         }
     }
 
@@ -28,7 +75,276 @@ impl Grid {
 
     #[inline(always)]
     pub fn set_cell(&mut self, point: Point, cell: Cell) {
-        self.cells[point.y as usize][point.x as usize] = cell;
+        let slot = &mut self.cells[point.y as usize][point.x as usize];
+        let old = *slot;
+        *slot = cell;
+        self.tile_hashes[Self::tile_index(&point)] ^= cell_hash(&point, old) ^ cell_hash(&point, cell);
+    }
+
+    /// Whether `point` lies on the grid
+    #[inline(always)]
+    pub fn contains(point: &Point) -> bool {
+        (point.x as usize) < GRID_WIDTH && (point.y as usize) < GRID_HEIGHT
+    }
+
+    /// [`Grid::get_cell`] without the row and column bounds checks (with them under the
+    /// `safe-fallbacks` feature).
+    ///
+    /// # Safety
+    ///
+    /// `point` must be on the grid (see [`Grid::contains`]).
+    #[inline(always)]
+    pub unsafe fn get_cell_unchecked(&self, point: &Point) -> Cell {
+        debug_assert!(Self::contains(point), "({}, {}) is off the grid", point.x, point.y);
+        #[cfg(feature = "safe-fallbacks")]
+        return self.cells[point.y as usize][point.x as usize];
+        // SAFETY: the caller guarantees point is on the grid; every row is GRID_WIDTH long
+        #[cfg(not(feature = "safe-fallbacks"))]
+        unsafe {
+            *self.cells.get_unchecked(point.y as usize).get_unchecked(point.x as usize)
+        }
+    }
+
+    /// [`Grid::set_cell`] without the row, column and tile bounds checks (with them under
+    /// the `safe-fallbacks` feature).
+    ///
+    /// # Safety
+    ///
+    /// `point` must be on the grid (see [`Grid::contains`]).
+    #[inline(always)]
+    pub unsafe fn set_cell_unchecked(&mut self, point: Point, cell: Cell) {
+        debug_assert!(Self::contains(&point), "({}, {}) is off the grid", point.x, point.y);
+        let tile = Self::tile_index(&point);
+        debug_assert!(tile < self.tile_hashes.len());
+        #[cfg(feature = "safe-fallbacks")]
+        {
+            let slot = &mut self.cells[point.y as usize][point.x as usize];
+            let old = *slot;
+            *slot = cell;
+            self.tile_hashes[tile] ^= cell_hash(&point, old) ^ cell_hash(&point, cell);
+        }
+        // SAFETY: the caller guarantees point is on the grid, and every on-grid point
+        // falls in one of the TILES_X * TILES_Y tiles
+        #[cfg(not(feature = "safe-fallbacks"))]
+        unsafe {
+            let slot = self.cells.get_unchecked_mut(point.y as usize).get_unchecked_mut(point.x as usize);
+            let old = *slot;
+            *slot = cell;
+            *self.tile_hashes.get_unchecked_mut(tile) ^= cell_hash(&point, old) ^ cell_hash(&point, cell);
+        }
+    }
+
+    /// Empty every cell in `points` and return the number of tile batches written.
+    ///
+    /// Consecutive points in the same tile are written as one batch: the tile hash is
+    /// updated once per batch instead of once per cell. Sorting `points` by
+    /// [`Grid::write_order`] first makes every tile one batch, row-major inside it, so
+    /// neighbouring cells share cache lines. Any order gives the same grid.
+    pub fn clear_cells(&mut self, points: &[Point]) -> usize {
+        let mut batches = 0;
+        let mut i = 0;
+        while i < points.len() {
+            let tile = Self::tile_index(&points[i]);
+            let mut hash = 0;
+            while let Some(point) = points.get(i).filter(|point| Self::tile_index(point) == tile) {
+                let slot = &mut self.cells[point.y as usize][point.x as usize];
+                hash ^= cell_hash(point, *slot);
+                *slot = Cell::Empty;
+                i += 1;
+            }
+            self.tile_hashes[tile] ^= hash;
+            batches += 1;
+        }
+        batches
+    }
+
+    /// Sort key grouping points by tile, then row-major within the tile (see `clear_cells`)
+    #[inline(always)]
+    pub fn write_order(point: &Point) -> u64 {
+        ((Self::tile_index(point) as u64) << 32) | ((point.y as u64) << 16) | point.x as u64
+    }
+
+    /// Write one cell per page of every row so the OS maps the whole grid now rather than
+    /// on first touch inside a tick. Cells and hashes are left as they were.
+    pub fn prefault(&mut self) {
+        for row in &mut self.cells {
+            for x in (0..row.len()).step_by(PAGE_SIZE) {
+                // A volatile rewrite of the current value: a plain one is optimised away
+                #[cfg(feature = "safe-fallbacks")]
+                {
+                    row[x] = std::hint::black_box(row[x]);
+                }
+                // SAFETY: x is in bounds of row
+                #[cfg(not(feature = "safe-fallbacks"))]
+                unsafe {
+                    let cell = row.as_mut_ptr().add(x);
+                    cell.write_volatile(cell.read_volatile());
+                }
+            }
+        }
+    }
+
+    /// Lock every row and the tile hashes into RAM (see `platform::lock_memory`).
+    /// Returns the bytes locked.
+    pub fn lock_memory(&self) -> io::Result<usize> {
+        let mut locked = lock_vec(&self.tile_hashes)?;
+        for row in &self.cells {
+            locked += lock_vec(row)?;
+        }
+        Ok(locked)
+    }
+
+    /// One full row of cells
+    #[inline(always)]
+    pub fn row(&self, y: usize) -> &[Cell] {
+        &self.cells[y]
+    }
+
+    /// Append the cell codes (see [`Cell::to_u8`]) of a `width` x `height` window at `(x, y)`
+    /// to `out`, row-major and wrapping at the grid edges like snake movement does
+    pub fn write_view(&self, x: usize, y: usize, width: usize, height: usize, out: &mut Vec<u8>) {
+        out.reserve(width * height);
+        for dy in 0..height {
+            let row = &self.cells[(y + dy) % GRID_HEIGHT];
+            out.extend((0..width).map(|dx| row[(x + dx) % GRID_WIDTH].to_u8()));
+        }
+    }
+
+    /// ASCII picture of `rect`, one line per row: `.` empty, `#` snake, `*` apple, `+` split power-up
+    pub fn render_region(&self, rect: Rect) -> String {
+        let mut out = String::with_capacity((rect.width as usize + 1) * rect.height as usize);
+        for dy in 0..rect.height {
+            for dx in 0..rect.width {
+                out.push(self.get_cell(&rect.at(dx, dy)).to_char());
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Closest `cell` to `from` by wrapped Manhattan distance, at most `max_radius` steps
+    /// away. Searches ring by ring outwards and stops at the first ring holding one, so
+    /// the cost grows with the distance found rather than the radius. Ties go to the
+    /// first cell of the ring in row-major order. Radii past half the grid are clamped.
+    pub fn nearest(&self, from: Point, max_radius: u16, cell: Cell) -> Option<Point> {
+        let max_radius = (max_radius as usize).min(GRID_WIDTH.min(GRID_HEIGHT) / 2 - 1);
+        if self.get_cell(&from) == cell {
+            return Some(from);
+        }
+        for radius in 1..=max_radius as isize {
+            for dy in -radius..=radius {
+                let dx = radius - dy.abs();
+                let y = wrap(from.y as usize, dy, GRID_HEIGHT);
+                let row = &self.cells[y];
+                let left = wrap(from.x as usize, -dx, GRID_WIDTH);
+                if row[left] == cell {
+                    return Some(Point { x: left as u16, y: y as u16 });
+                }
+                let right = wrap(from.x as usize, dx, GRID_WIDTH);
+                if dx != 0 && row[right] == cell {
+                    return Some(Point { x: right as u16, y: y as u16 });
+                }
+            }
+        }
+        None
+    }
+
+    /// Hash of a tile's contents. Equal hashes mean (with overwhelming probability) equal tiles.
+    #[inline(always)]
+    pub fn tile_hash(&self, tile_x: usize, tile_y: usize) -> u64 {
+        self.tile_hashes[tile_y * TILES_X + tile_x]
+    }
+
+    /// List every cell that differs between `self` and `other` as `(point, ours, theirs)`.
+    ///
+    /// Tiles whose hashes match are skipped without being scanned, so the cost is
+    /// proportional to the number of changed tiles rather than the grid size.
+    /// Results are ordered tile by tile, row-major within each tile.
+    pub fn diff(&self, other: &Grid) -> Vec<(Point, Cell, Cell)> {
+        let mut changes = Vec::new();
+        for tile_y in 0..TILES_Y {
+            for tile_x in 0..TILES_X {
+                let index = tile_y * TILES_X + tile_x;
+                if self.tile_hashes[index] == other.tile_hashes[index] {
+                    continue;
+                }
+
+                let y_end = ((tile_y + 1) * TILE_SIZE).min(GRID_HEIGHT);
+                let x_end = ((tile_x + 1) * TILE_SIZE).min(GRID_WIDTH);
+                for y in tile_y * TILE_SIZE..y_end {
+                    let ours = &self.cells[y];
+                    let theirs = &other.cells[y];
+                    for x in tile_x * TILE_SIZE..x_end {
+                        if ours[x] != theirs[x] {
+                            changes.push((Point { x: x as u16, y: y as u16 }, ours[x], theirs[x]));
+                        }
+                    }
+                }
+            }
+        }
+        changes
+    }
+
+    /// Join bands covering every row in order, e.g. built on several threads.
+    /// Panics if they leave a gap or overlap.
+    pub fn from_bands(bands: Vec<GridBand>) -> Self {
+        let mut grid = Self { cells: Vec::with_capacity(GRID_HEIGHT), tile_hashes: Vec::with_capacity(TILES_X * TILES_Y) };
+        for band in bands {
+            assert_eq!(band.top, grid.cells.len(), "grid bands must cover the rows in order");
+            grid.cells.extend(band.cells);
+            grid.tile_hashes.extend(band.tile_hashes);
+        }
+        assert_eq!(grid.cells.len(), GRID_HEIGHT, "grid bands must cover every row");
+        grid
+    }
+
+    #[inline(always)]
+    fn tile_index(point: &Point) -> usize {
+        (point.y as usize >> TILE_BITS) * TILES_X + (point.x as usize >> TILE_BITS)
+    }
+}
+
+/// Full-width rows of a grid starting and ending on tile boundaries, so it owns the hashes
+/// of its tiles outright. Bands are filled independently and joined with [`Grid::from_bands`].
+pub struct GridBand {
+    top: usize,
+    cells: Vec<Vec<Cell>>,
+    tile_hashes: Vec<u64>,
+}
+
+impl GridBand {
+    /// Empty band over tile rows `tile_rows` (the last one may be partial)
+    pub fn new(tile_rows: std::ops::Range<usize>) -> Self {
+        assert!(tile_rows.start < tile_rows.end && tile_rows.end <= TILES_Y, "tile rows out of range");
+        let top = tile_rows.start * TILE_SIZE;
+        let bottom = (tile_rows.end * TILE_SIZE).min(GRID_HEIGHT);
+        Self {
+            top,
+            cells: (top..bottom).map(|_| vec![Cell::Empty; GRID_WIDTH]).collect(),
+            tile_hashes: vec![0; tile_rows.len() * TILES_X],
+        }
+    }
+
+    /// Grid rows the band holds
+    pub fn rows(&self) -> std::ops::Range<usize> {
+        self.top..self.top + self.cells.len()
+    }
+
+    /// The cell at `point`, or `None` when its row is outside the band
+    #[inline(always)]
+    pub fn get_cell(&self, point: &Point) -> Option<Cell> {
+        let row = (point.y as usize).checked_sub(self.top)?;
+        self.cells.get(row).map(|row| row[point.x as usize])
+    }
+
+    /// Panics when the row of `point` is outside the band
+    #[inline(always)]
+    pub fn set_cell(&mut self, point: Point, cell: Cell) {
+        let slot = &mut self.cells[point.y as usize - self.top][point.x as usize];
+        let old = *slot;
+        *slot = cell;
+        let tile = Grid::tile_index(&point) - (self.top >> TILE_BITS) * TILES_X;
+        self.tile_hashes[tile] ^= cell_hash(&point, old) ^ cell_hash(&point, cell);
     }
 }
 
@@ -37,3 +353,22 @@ impl Default for Grid {
         Self::new()
     }
 }
+
+// `base + delta` on an axis of `len` cells that wraps around
+#[inline(always)]
+fn wrap(base: usize, delta: isize, len: usize) -> usize {
+    (base as isize + delta).rem_euclid(len as isize) as usize
+}
+
+/// Position-dependent hash of a cell value; empty cells hash to zero so a fresh grid has all-zero tiles.
+#[inline(always)]
+fn cell_hash(point: &Point, cell: Cell) -> u64 {
+    if cell == Cell::Empty {
+        return 0;
+    }
+    // splitmix64 finalizer over (cell index, cell value)
+    let mut z = ((point.y as u64 * GRID_WIDTH as u64 + point.x as u64) << 2) | cell as u64;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
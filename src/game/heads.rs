@@ -0,0 +1,113 @@
+//! Spatial index of live snake heads.
+//!
+//! The grid is cut into [`HEAD_BLOCK`]-cell squares, each holding the ids of the snakes
+//! whose head is inside. Heads move one cell per tick, so a snake changes block only
+//! every [`HEAD_BLOCK`] ticks or so; the engine reports every move and the lists are
+//! only edited on those crossings. A rect query then visits the blocks it overlaps
+//! instead of every snake.
+
+use crate::game::grid::{GRID_HEIGHT, GRID_WIDTH};
+use crate::game::snake::GridAwareSnake;
+use crate::game::types::{Point, Rect};
+
+/// Side of an index block in cells
+pub const HEAD_BLOCK: usize = 128;
+
+const BLOCK_COLUMNS: usize = GRID_WIDTH.div_ceil(HEAD_BLOCK);
+const BLOCK_ROWS: usize = GRID_HEIGHT.div_ceil(HEAD_BLOCK);
+// Block of a snake that is not indexed (dead, despawned or never seen)
+const UNINDEXED: u32 = u32::MAX;
+
+#[derive(Debug, Clone)]
+pub struct HeadIndex {
+    // Snake ids per block, row-major
+    blocks: Vec<Vec<u32>>,
+    // Block each snake id is listed in
+    block_of: Vec<u32>,
+}
+
+impl HeadIndex {
+    pub fn new() -> Self {
+        Self {
+            blocks: vec![Vec::new(); BLOCK_COLUMNS * BLOCK_ROWS],
+            block_of: Vec::new(),
+        }
+    }
+
+    /// Index of the live heads of `snakes`
+    pub fn from_snakes(snakes: &[GridAwareSnake]) -> Self {
+        let mut index = Self::new();
+        index.rebuild(snakes);
+        index
+    }
+
+    /// Forget everything and list the live heads of `snakes`
+    pub fn rebuild(&mut self, snakes: &[GridAwareSnake]) {
+        self.blocks.iter_mut().for_each(Vec::clear);
+        self.block_of.clear();
+        for snake in snakes {
+            self.update(snake.id(), live_head(snake));
+        }
+    }
+
+    /// Snake `id` now has its head at `head`, or none for a dead or removed snake
+    #[inline]
+    pub fn update(&mut self, id: u32, head: Option<Point>) {
+        let block = head.map_or(UNINDEXED, |head| block_index(head.x as usize / HEAD_BLOCK, head.y as usize / HEAD_BLOCK) as u32);
+        if id as usize >= self.block_of.len() {
+            self.block_of.resize(id as usize + 1, UNINDEXED);
+        }
+        let old = self.block_of[id as usize];
+        if old == block {
+            return;
+        }
+        if old != UNINDEXED {
+            let ids = &mut self.blocks[old as usize];
+            if let Some(position) = ids.iter().position(|&listed| listed == id) {
+                ids.swap_remove(position);
+            }
+        }
+        if block != UNINDEXED {
+            self.blocks[block as usize].push(id);
+        }
+        self.block_of[id as usize] = block;
+    }
+
+    /// Ids listed in the blocks `rect` overlaps, each once: a superset of the snakes whose
+    /// head is inside `rect`
+    pub fn candidates(&self, rect: Rect) -> impl Iterator<Item = u32> + '_ {
+        let columns = block_span(rect.x as usize, rect.width as usize, GRID_WIDTH, BLOCK_COLUMNS);
+        let rows = block_span(rect.y as usize, rect.height as usize, GRID_HEIGHT, BLOCK_ROWS);
+        rows.flat_map(move |row| columns.clone().map(move |column| block_index(column, row)))
+            .flat_map(move |block| self.blocks[block].iter().copied())
+    }
+}
+
+impl Default for HeadIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn live_head(snake: &GridAwareSnake) -> Option<Point> {
+    snake.head().copied().filter(|_| snake.is_alive())
+}
+
+fn block_index(column: usize, row: usize) -> usize {
+    row * BLOCK_COLUMNS + column
+}
+
+/// Blocks along one axis covering `len` cells from `start`, wrapping at `size`, each once
+fn block_span(start: usize, len: usize, size: usize, blocks: usize) -> impl Iterator<Item = usize> + Clone {
+    let first = start / HEAD_BLOCK;
+    let end = start + len.max(1) - 1;
+    let (head, tail) = if len >= size {
+        (0..blocks, 0..0)
+    } else if end < size {
+        (first..end / HEAD_BLOCK + 1, 0..0)
+    } else {
+        let wrapped = (end - size) / HEAD_BLOCK;
+        if wrapped >= first { (0..blocks, 0..0) } else { (first..blocks, 0..wrapped + 1) }
+    };
+    head.chain(tail)
+}
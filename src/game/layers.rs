@@ -0,0 +1,121 @@
+//! Stacked grids joined by portals.
+//!
+//! A [`LayeredGrid`] holds several full-size [`Grid`]s, one per z-level (the surface,
+//! tunnels below it, overpasses above it). Portal cells link a cell of one layer to the
+//! same cell of another: a head stepping onto a portal comes out on the far layer, so
+//! two snakes can cross the same (x, y) at different levels. Every layer is a complete
+//! grid, which multiplies the working set by the layer count in a controlled way.
+//!
+//! This is the data structure only. `GameState` still runs on a single [`Grid`] and no
+//! runner builds or advances a `LayeredGrid`, so portals do not move snakes in a game yet.
+//! One layer of a `LayeredGrid` is a drop-in stand-in for a `Grid` (see
+//! [`LayeredGrid::into_layers`]).
+
+use crate::game::grid::{Cell, Grid};
+use crate::game::types::{Direction, Point};
+use std::collections::HashMap;
+
+/// A cell on a given layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LayeredPoint {
+    pub layer: u8,
+    pub point: Point,
+}
+
+impl LayeredPoint {
+    pub fn new(layer: u8, point: Point) -> Self {
+        Self { layer, point }
+    }
+}
+
+#[derive(Clone)]
+pub struct LayeredGrid {
+    layers: Vec<Grid>,
+    // Portal entrances and the layer each leads to
+    portals: HashMap<LayeredPoint, u8>,
+}
+
+impl LayeredGrid {
+    /// `layers` empty grids, no portals. Panics on 0 or more than 256 layers.
+    pub fn new(layers: usize) -> Self {
+        assert!((1..=u8::MAX as usize + 1).contains(&layers), "1 to 256 layers");
+        Self {
+            layers: (0..layers).map(|_| Grid::new()).collect(),
+            portals: HashMap::new(),
+        }
+    }
+
+    /// Wrap an existing grid as layer 0 of a one-layer stack
+    pub fn from_grid(grid: Grid) -> Self {
+        Self { layers: vec![grid], portals: HashMap::new() }
+    }
+
+    pub fn num_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn layer(&self, layer: u8) -> &Grid {
+        &self.layers[layer as usize]
+    }
+
+    pub fn layer_mut(&mut self, layer: u8) -> &mut Grid {
+        &mut self.layers[layer as usize]
+    }
+
+    pub fn into_layers(self) -> Vec<Grid> {
+        self.layers
+    }
+
+    #[inline(always)]
+    pub fn get_cell(&self, at: &LayeredPoint) -> Cell {
+        self.layers[at.layer as usize].get_cell(&at.point)
+    }
+
+    #[inline(always)]
+    pub fn set_cell(&mut self, at: LayeredPoint, cell: Cell) {
+        self.layers[at.layer as usize].set_cell(at.point, cell);
+    }
+
+    /// Join `point` on layers `a` and `b` both ways. Replaces any portal already there.
+    pub fn add_portal(&mut self, point: Point, a: u8, b: u8) {
+        assert!(a != b, "a portal joins two different layers");
+        assert!((a.max(b) as usize) < self.layers.len(), "portal to a missing layer");
+        self.portals.insert(LayeredPoint::new(a, point), b);
+        self.portals.insert(LayeredPoint::new(b, point), a);
+    }
+
+    /// Remove the portal at `point` on `layer` and its far end. Returns whether there was one.
+    pub fn remove_portal(&mut self, point: Point, layer: u8) -> bool {
+        match self.portals.remove(&LayeredPoint::new(layer, point)) {
+            Some(far) => {
+                self.portals.remove(&LayeredPoint::new(far, point));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Layer the portal at `at` leads to, if there is one
+    #[inline]
+    pub fn portal(&self, at: &LayeredPoint) -> Option<u8> {
+        if self.portals.is_empty() {
+            return None;
+        }
+        self.portals.get(at).copied()
+    }
+
+    pub fn num_portals(&self) -> usize {
+        self.portals.len() / 2
+    }
+
+    /// Where a head at `from` moving `direction` lands: the next cell on the same layer,
+    /// taken through to the far layer when that cell is a portal
+    #[inline]
+    pub fn step(&self, from: LayeredPoint, direction: Direction) -> LayeredPoint {
+        let next = LayeredPoint::new(from.layer, from.point.step(direction));
+        match self.portal(&next) {
+            Some(layer) => LayeredPoint::new(layer, next.point),
+            None => next,
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Incremental top-K standings.
+//!
+//! A snake scores [`APPLE_POINTS`] per apple and [`KILL_POINTS`] per kill (kills only
+//! count when the config tracks them) and loses its score when it dies. Scores only move
+//! on those events, so the engine feeds the [`Leaderboard`] each tick's eats, kills and
+//! deaths instead of anyone sorting every snake. The board keeps all non-zero scores
+//! ordered, and at the end of a tick that changed anything it compares its top K with
+//! the one it last reported. The differences are the tick's [`StandingChange`]s: enough
+//! for a spectator to keep its own copy of the standings current.
+
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+pub const APPLE_POINTS: u64 = 1;
+pub const KILL_POINTS: u64 = 10;
+
+/// One place of the top K
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Standing {
+    pub snake_id: u32,
+    pub score: u64,
+}
+
+/// Change to the top K since the previous report. Removing every `Left` snake from the
+/// previous standings and then setting every `Rank` in order (a rank one past the end
+/// appends) gives the current ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StandingChange {
+    /// `snake_id` left the top K (it died or was overtaken)
+    Left { snake_id: u32 },
+    /// Place `rank` (0 is first) is now `snake_id` with `score`
+    Rank { rank: usize, snake_id: u32, score: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct Leaderboard {
+    size: usize,
+    // Score by snake id
+    scores: Vec<u64>,
+    // Every non-zero score, best first; equal scores by id
+    ranked: BTreeSet<(Reverse<u64>, u32)>,
+    // Top K as last reported
+    top: Vec<Standing>,
+    changes: Vec<StandingChange>,
+    dirty: bool,
+}
+
+impl Leaderboard {
+    /// Standings of the best `size` snakes
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            scores: Vec::new(),
+            ranked: BTreeSet::new(),
+            top: Vec::with_capacity(size),
+            changes: Vec::new(),
+            dirty: false,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn score(&self, snake_id: u32) -> u64 {
+        self.scores.get(snake_id as usize).copied().unwrap_or(0)
+    }
+
+    /// The top K as of the last `end_tick`
+    pub fn top(&self) -> &[Standing] {
+        &self.top
+    }
+
+    /// Changes reported by the last `end_tick`
+    pub fn changes(&self) -> &[StandingChange] {
+        &self.changes
+    }
+
+    /// Every non-zero score, best first
+    pub fn scores(&self) -> impl Iterator<Item = Standing> + '_ {
+        self.ranked.iter().map(|&(Reverse(score), snake_id)| Standing { snake_id, score })
+    }
+
+    /// Board of the best `size` holding `scores`, as read back from a snapshot. Its top K
+    /// counts as already reported, so the next `end_tick` lists only later changes.
+    pub fn restore(size: usize, scores: impl IntoIterator<Item = Standing>) -> Self {
+        let mut board = Self::new(size);
+        for standing in scores {
+            board.set_score(standing.snake_id, standing.score);
+        }
+        board.end_tick();
+        board.changes.clear();
+        board
+    }
+
+    pub fn on_eat(&mut self, snake_id: u32) {
+        self.add(snake_id, APPLE_POINTS);
+    }
+
+    pub fn on_kill(&mut self, killer: u32) {
+        self.add(killer, KILL_POINTS);
+    }
+
+    /// `snake_id` died or was removed; its id starts from zero when reused
+    pub fn on_death(&mut self, snake_id: u32) {
+        self.set_score(snake_id, 0);
+    }
+
+    /// Overwrite the score of `snake_id`, as a snapshot delta does; 0 clears it
+    pub fn set_score(&mut self, snake_id: u32, score: u64) {
+        let old = self.score(snake_id);
+        if old == score {
+            return;
+        }
+        let id = snake_id as usize;
+        if id >= self.scores.len() {
+            self.scores.resize(id + 1, 0);
+        }
+        if old > 0 {
+            self.ranked.remove(&(Reverse(old), snake_id));
+        }
+        if score > 0 {
+            self.ranked.insert((Reverse(score), snake_id));
+        }
+        self.scores[id] = score;
+        self.dirty = true;
+    }
+
+    fn add(&mut self, snake_id: u32, points: u64) {
+        self.set_score(snake_id, self.score(snake_id) + points);
+    }
+
+    /// Report how the top K changed since the last call, in `changes`. Clears the
+    /// changes when nothing scored or died.
+    pub fn end_tick(&mut self) {
+        self.changes.clear();
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+        let top: Vec<Standing> = self
+            .ranked
+            .iter()
+            .take(self.size)
+            .map(|&(Reverse(score), snake_id)| Standing { snake_id, score })
+            .collect();
+        for old in &self.top {
+            if !top.iter().any(|standing| standing.snake_id == old.snake_id) {
+                self.changes.push(StandingChange::Left { snake_id: old.snake_id });
+            }
+        }
+        // Everything below a removed or changed place may have shifted
+        let first_change = top.iter().zip(&self.top).take_while(|(new, old)| new == old).count();
+        for (rank, standing) in top.iter().enumerate().skip(first_change) {
+            self.changes.push(StandingChange::Rank { rank, snake_id: standing.snake_id, score: standing.score });
+        }
+        self.top = top;
+    }
+}
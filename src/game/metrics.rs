@@ -0,0 +1,252 @@
+use crate::game::snake::GridAwareSnake;
+use crate::runtime::platform::timestamp;
+
+// Power-of-two length buckets: [1], [2,3], [4,7], ... , [128, inf)
+pub const LENGTH_HISTOGRAM_BUCKETS: usize = 8;
+
+/// Population statistics maintained incrementally by the engine.
+///
+/// Only births, deaths and growth touch these counters, so reading them never
+/// requires a scan over the snakes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PopulationMetrics {
+    /// Snakes currently alive
+    pub alive: u32,
+    /// Body cells across all alive snakes
+    pub total_body_cells: u64,
+    /// Alive snakes per length bucket (see [`length_bucket`])
+    pub length_histogram: [u32; LENGTH_HISTOGRAM_BUCKETS],
+    /// Snakes added during the current tick
+    pub births_this_tick: u32,
+    /// Snakes that died during the current tick
+    pub deaths_this_tick: u32,
+    pub total_births: u64,
+    pub total_deaths: u64,
+}
+
+impl PopulationMetrics {
+    /// Build the counters from scratch. Only used at construction or to resync.
+    pub fn from_snakes(snakes: &[GridAwareSnake]) -> Self {
+        let mut metrics = Self::default();
+        for snake in snakes.iter().filter(|snake| snake.is_alive()) {
+            metrics.add_alive(snake.body().len());
+        }
+        metrics
+    }
+
+    /// Reset the per-tick counters; called at the start of every tick
+    #[inline(always)]
+    pub fn begin_tick(&mut self) {
+        self.births_this_tick = 0;
+        self.deaths_this_tick = 0;
+    }
+
+    #[inline(always)]
+    pub fn on_birth(&mut self, length: usize) {
+        self.births_this_tick += 1;
+        self.total_births += 1;
+        self.add_alive(length);
+    }
+
+    #[inline(always)]
+    pub fn on_death(&mut self, length: usize) {
+        self.deaths_this_tick += 1;
+        self.total_deaths += 1;
+        // Saturating: snakes pushed without registration are invisible to the counters
+        self.alive = self.alive.saturating_sub(1);
+        self.total_body_cells = self.total_body_cells.saturating_sub(length as u64);
+        let bucket = &mut self.length_histogram[length_bucket(length)];
+        *bucket = bucket.saturating_sub(1);
+    }
+
+    /// A snake of `old_length` grew by one cell
+    #[inline(always)]
+    pub fn on_growth(&mut self, old_length: usize) {
+        self.total_body_cells += 1;
+        let old_bucket = length_bucket(old_length);
+        let new_bucket = length_bucket(old_length + 1);
+        if old_bucket != new_bucket && self.length_histogram[old_bucket] > 0 {
+            self.length_histogram[old_bucket] -= 1;
+            self.length_histogram[new_bucket] += 1;
+        }
+    }
+
+    /// A snake of `old_length` handed its rear `fragment_length` cells to a new snake.
+    /// Counts as a birth; the total body cells stay the same.
+    pub fn on_split(&mut self, old_length: usize, fragment_length: usize) {
+        let old_bucket = length_bucket(old_length);
+        if self.length_histogram[old_bucket] > 0 {
+            self.length_histogram[old_bucket] -= 1;
+            self.length_histogram[length_bucket(old_length - fragment_length)] += 1;
+        }
+        self.births_this_tick += 1;
+        self.total_births += 1;
+        self.alive += 1;
+        self.length_histogram[length_bucket(fragment_length)] += 1;
+    }
+
+    /// Average body length of alive snakes
+    pub fn mean_length(&self) -> f64 {
+        if self.alive == 0 {
+            0.0
+        } else {
+            self.total_body_cells as f64 / self.alive as f64
+        }
+    }
+
+    fn add_alive(&mut self, length: usize) {
+        self.alive += 1;
+        self.total_body_cells += length as u64;
+        self.length_histogram[length_bucket(length)] += 1;
+    }
+}
+
+/// Histogram bucket for a body length: floor(log2(length)), clamped to the last bucket
+#[inline(always)]
+pub fn length_bucket(length: usize) -> usize {
+    (length.max(1).ilog2() as usize).min(LENGTH_HISTOGRAM_BUCKETS - 1)
+}
+
+/// Fullest buckets listed by [`BucketStats`]
+pub const HOT_BUCKETS: usize = 8;
+// Occupancy classes of the bucket histogram: 0, 1, [2,3], [4,7], ... , [1024, inf)
+pub const OCCUPANCY_CLASSES: usize = 12;
+// Width of the longest histogram bar
+const BAR_WIDTH: usize = 40;
+
+/// How the movement records of one tick spread over the spatial buckets (see
+/// `GameState::set_bucket_stats`), for tuning `BUCKET_BITS` against the real distribution
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BucketStats {
+    pub buckets: usize,
+    pub records: usize,
+    /// Records in the fullest bucket
+    pub max: usize,
+    /// Buckets without a record
+    pub empty: usize,
+    /// Up to [`HOT_BUCKETS`] fullest non-empty buckets as `(bucket, records)`, fullest
+    /// first, lower index first among equals
+    pub hot: Vec<(usize, usize)>,
+    /// Buckets per occupancy class (see [`occupancy_class`])
+    pub histogram: [usize; OCCUPANCY_CLASSES],
+}
+
+impl BucketStats {
+    /// Replace the stats with those of buckets holding `counts` records each, in bucket order
+    pub fn observe(&mut self, counts: impl IntoIterator<Item = usize>) {
+        self.buckets = 0;
+        self.records = 0;
+        self.max = 0;
+        self.empty = 0;
+        self.hot.clear();
+        self.histogram = [0; OCCUPANCY_CLASSES];
+        for (bucket, count) in counts.into_iter().enumerate() {
+            self.buckets += 1;
+            self.records += count;
+            self.max = self.max.max(count);
+            self.empty += (count == 0) as usize;
+            self.histogram[occupancy_class(count)] += 1;
+            // Insertion into the short sorted list; ties keep the earlier bucket first
+            if count > 0 && (self.hot.len() < HOT_BUCKETS || self.hot.last().is_some_and(|&(_, last)| count > last)) {
+                let at = self.hot.partition_point(|&(_, hot)| hot >= count);
+                self.hot.insert(at, (bucket, count));
+                self.hot.truncate(HOT_BUCKETS);
+            }
+        }
+    }
+
+    /// Records per bucket on average
+    pub fn mean(&self) -> f64 {
+        if self.buckets == 0 {
+            0.0
+        } else {
+            self.records as f64 / self.buckets as f64
+        }
+    }
+}
+
+/// Histogram class of a bucket holding `count` records: 0 for none, then
+/// floor(log2(count)) + 1, clamped to the last class
+#[inline(always)]
+pub fn occupancy_class(count: usize) -> usize {
+    match count {
+        0 => 0,
+        count => (count.ilog2() as usize + 1).min(OCCUPANCY_CLASSES - 1),
+    }
+}
+
+impl std::fmt::Display for BucketStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} records over {} buckets: mean {:.2}, max {}, empty {}",
+            self.records,
+            self.buckets,
+            self.mean(),
+            self.max,
+            self.empty
+        )?;
+        if !self.hot.is_empty() {
+            let hot: Vec<String> = self.hot.iter().map(|(bucket, count)| format!("{} ({})", bucket, count)).collect();
+            writeln!(f, "hot buckets: {}", hot.join(", "))?;
+        }
+        let widest = self.histogram.iter().copied().max().unwrap_or(0).max(1);
+        for (class, &buckets) in self.histogram.iter().enumerate().filter(|&(_, &buckets)| buckets > 0) {
+            let range = match class {
+                0 => "0".to_string(),
+                1 => "1".to_string(),
+                class if class == OCCUPANCY_CLASSES - 1 => format!("{}+", 1usize << (class - 1)),
+                class => format!("{}-{}", 1usize << (class - 1), (1usize << class) - 1),
+            };
+            writeln!(f, "{:>10} {:>6} {}", range, buckets, "#".repeat((buckets * BAR_WIDTH).div_ceil(widest)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Names of the tick phases timed by `GameState::set_phase_timing`, in tick order
+pub const TICK_PHASES: [&str; 6] = ["inputs", "collect", "resolve", "tails", "splits", "spawn"];
+
+/// Duration of each of [`TICK_PHASES`] in the last tick, in platform timestamp units
+pub type PhaseTimings = [u64; TICK_PHASES.len()];
+
+/// Stopwatch handing out phase durations; does nothing when disabled
+pub(crate) struct PhaseClock {
+    enabled: bool,
+    // Write laps to the timings; when only enabled for a budget, just keep time
+    record: bool,
+    started: u64,
+    last: u64,
+}
+
+impl PhaseClock {
+    #[inline(always)]
+    pub(crate) fn start(record: bool, budgeted: bool) -> Self {
+        let enabled = record || budgeted;
+        let started = if enabled { timestamp() } else { 0 };
+        Self {
+            enabled,
+            record,
+            started,
+            last: started,
+        }
+    }
+
+    /// Charge the time since the previous lap to `phase`
+    #[inline(always)]
+    pub(crate) fn lap(&mut self, timings: &mut PhaseTimings, phase: usize) {
+        if self.enabled {
+            let now = timestamp();
+            if self.record {
+                timings[phase] = now.wrapping_sub(self.last);
+            }
+            self.last = now;
+        }
+    }
+
+    /// Time from the start to the last lap
+    #[inline(always)]
+    pub(crate) fn elapsed(&self) -> u64 {
+        self.last.wrapping_sub(self.started)
+    }
+}
@@ -0,0 +1,98 @@
+//! Dense per-snake movement state for the first two phases of a tick.
+//!
+//! Applying inputs and building movement records need only a snake's direction, whether
+//! it is alive and where its head is. Read from [`GridAwareSnake`] that strides across one
+//! cache-padded struct per snake; [`MotionTable`] keeps the three in parallel arrays
+//! instead, about 6 bytes per snake, so phase 2 over 1024 snakes reads a few KB.
+//!
+//! The bodies stay authoritative. The engine updates the table wherever it turns, moves,
+//! kills or places a snake, and rebuilds it when `snakes` changes length behind its back;
+//! other direct edits of `snakes` need `GameState::reindex_heads`.
+
+use crate::game::snake::GridAwareSnake;
+use crate::game::types::{Direction, Point};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MotionTable {
+    directions: Vec<Direction>,
+    alive: Vec<bool>,
+    // Head cell of every snake with a body; stale for despawned slots, which are not alive
+    heads: Vec<Point>,
+}
+
+impl MotionTable {
+    /// Table of `snakes` as they are now
+    pub fn from_snakes(snakes: &[GridAwareSnake]) -> Self {
+        let mut table = Self::default();
+        table.rebuild(snakes);
+        table
+    }
+
+    /// Forget everything and copy the state of `snakes`
+    pub fn rebuild(&mut self, snakes: &[GridAwareSnake]) {
+        self.directions.clear();
+        self.alive.clear();
+        self.heads.clear();
+        for snake in snakes {
+            self.directions.push(snake.snake().direction);
+            self.alive.push(snake.is_alive());
+            self.heads.push(snake.head().copied().unwrap_or_default());
+        }
+    }
+
+    /// Copy the state of `snake`, growing the table if its id is new
+    pub fn sync(&mut self, snake: &GridAwareSnake) {
+        let id = snake.id() as usize;
+        if id >= self.len() {
+            self.directions.resize(id + 1, Direction::Up);
+            self.alive.resize(id + 1, false);
+            self.heads.resize(id + 1, Point::default());
+        }
+        self.directions[id] = snake.snake().direction;
+        self.alive[id] = snake.is_alive();
+        self.heads[id] = snake.head().copied().unwrap_or_default();
+    }
+
+    pub fn len(&self) -> usize {
+        self.alive.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alive.is_empty()
+    }
+
+    #[inline(always)]
+    pub fn direction(&self, id: u32) -> Direction {
+        self.directions[id as usize]
+    }
+
+    #[inline(always)]
+    pub fn is_alive(&self, id: u32) -> bool {
+        self.alive[id as usize]
+    }
+
+    #[inline(always)]
+    pub fn head(&self, id: u32) -> Point {
+        self.heads[id as usize]
+    }
+
+    #[inline(always)]
+    pub fn set_direction(&mut self, id: u32, direction: Direction) {
+        self.directions[id as usize] = direction;
+    }
+
+    #[inline(always)]
+    pub fn set_head(&mut self, id: u32, head: Point) {
+        self.heads[id as usize] = head;
+    }
+
+    #[inline(always)]
+    pub fn kill(&mut self, id: u32) {
+        self.alive[id as usize] = false;
+    }
+
+    /// Whether the table matches `snakes`, for debug checks and tests
+    pub fn matches(&self, snakes: &[GridAwareSnake]) -> bool {
+        *self == Self::from_snakes(snakes)
+    }
+}
@@ -0,0 +1,33 @@
+use crate::game::types::{Direction, Point};
+
+/// A split power-up eaten during the last tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Split {
+    /// Snake that ate the power-up; it keeps the front half of its body
+    pub parent: u32,
+    /// New snake made of the rear half, on a dead snake's id when one is free
+    pub fragment: u32,
+    pub fragment_length: usize,
+}
+
+/// Cells a snake of `length` hands to its fragment: the rear half, rounded down
+#[inline(always)]
+pub fn fragment_length(length: usize) -> usize {
+    length / 2
+}
+
+/// Body (tail first) and direction of the fragment made from `rear`, the parent's rear
+/// cells tail first, where `joint` is the parent cell that followed them.
+///
+/// The fragment runs the other way: the parent's old tail becomes its head and it heads
+/// away from the joint, so neither snake starts out facing the other.
+pub fn fragment_body(rear: &[Point], joint: Point) -> (Vec<Point>, Direction) {
+    let body: Vec<Point> = rear.iter().rev().copied().collect();
+    let head = rear[0];
+    let behind = rear.get(1).copied().unwrap_or(joint);
+    let direction = Direction::ALL
+        .into_iter()
+        .find(|direction| behind.step(*direction) == head)
+        .unwrap_or(Direction::Up);
+    (body, direction)
+}
@@ -0,0 +1,153 @@
+//! Grid consistency checks and repair.
+//!
+//! Snakes are the truth for snake cells: every segment of a snake still holding a body
+//! (alive, or a corpse not yet cleared) must be a `Snake` cell, and no other cell may be.
+//! Apples and split power-ups are only recorded on the grid, so for them the grid is the
+//! truth and `num_apples`/`num_powerups` are recounted from it. A segment found on an
+//! apple or power-up keeps the cell; the item is lost.
+//!
+//! Meant for snapshots written by older versions, after migrations that change how
+//! bodies are stored, and as a safety net in soak tests. Both entry points scan the
+//! whole grid, so neither belongs in the tick loop.
+
+use crate::game::engine::GameState;
+use crate::game::grid::{Cell, GRID_HEIGHT, Grid};
+use crate::game::types::Point;
+use std::collections::HashSet;
+use std::fmt;
+
+// Discrepancies of each kind listed by Display before summarising the rest
+const LISTED: usize = 16;
+
+/// What `GameState::verify_and_repair` found and fixed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GridRepair {
+    /// Snake cells no snake owns, now empty
+    pub orphan_cells: Vec<Point>,
+    /// Segments the grid showed as another cell, now snake cells
+    pub missing_segments: Vec<(Point, Cell)>,
+    /// `num_apples` before and after recounting
+    pub apples: (u64, u64),
+    /// `num_powerups` before and after recounting
+    pub powerups: (u64, u64),
+}
+
+impl GridRepair {
+    /// Whether the grid already agreed with the snakes and the counters
+    pub fn is_clean(&self) -> bool {
+        self.orphan_cells.is_empty()
+            && self.missing_segments.is_empty()
+            && self.apples.0 == self.apples.1
+            && self.powerups.0 == self.powerups.1
+    }
+}
+
+impl fmt::Display for GridRepair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return writeln!(f, "grid consistent");
+        }
+        for point in self.orphan_cells.iter().take(LISTED) {
+            writeln!(f, "orphan snake cell at ({}, {}) emptied", point.x, point.y)?;
+        }
+        if self.orphan_cells.len() > LISTED {
+            writeln!(f, "... and {} more orphan snake cells", self.orphan_cells.len() - LISTED)?;
+        }
+        for (point, cell) in self.missing_segments.iter().take(LISTED) {
+            writeln!(f, "segment at ({}, {}) was {:?} on the grid", point.x, point.y, cell)?;
+        }
+        if self.missing_segments.len() > LISTED {
+            writeln!(f, "... and {} more missing segments", self.missing_segments.len() - LISTED)?;
+        }
+        if self.apples.0 != self.apples.1 {
+            writeln!(f, "apple count {} recounted as {}", self.apples.0, self.apples.1)?;
+        }
+        if self.powerups.0 != self.powerups.1 {
+            writeln!(f, "power-up count {} recounted as {}", self.powerups.0, self.powerups.1)?;
+        }
+        Ok(())
+    }
+}
+
+impl GameState {
+    /// Compare the grid with the snake bodies and the apple and power-up counters, fix
+    /// every discrepancy in place and report them
+    pub fn verify_and_repair(&mut self) -> GridRepair {
+        let mut repair = GridRepair::default();
+        let mut segments = HashSet::new();
+        for snake in &self.snakes {
+            for segment in snake.segments() {
+                segments.insert(segment);
+                let cell = self.grid.get_cell(&segment);
+                if cell != Cell::Snake {
+                    repair.missing_segments.push((segment, cell));
+                    if cell == Cell::Apple {
+                        self.apple_expiry.forget(&segment);
+                    }
+                    self.grid.set_cell(segment, Cell::Snake);
+                }
+            }
+        }
+
+        let (mut apples, mut powerups) = (0, 0);
+        for y in 0..GRID_HEIGHT {
+            for (x, cell) in self.grid.row(y).iter().enumerate() {
+                match cell {
+                    Cell::Apple => apples += 1,
+                    Cell::Split => powerups += 1,
+                    Cell::Snake => {
+                        let point = Point { x: x as u16, y: y as u16 };
+                        if !segments.contains(&point) {
+                            repair.orphan_cells.push(point);
+                        }
+                    }
+                    Cell::Empty => {}
+                }
+            }
+        }
+        for point in &repair.orphan_cells {
+            self.grid.set_cell(*point, Cell::Empty);
+        }
+
+        repair.apples = (self.num_apples, apples);
+        repair.powerups = (self.num_powerups, powerups);
+        self.num_apples = apples;
+        self.num_powerups = powerups;
+        repair
+    }
+
+    /// Build the grid afresh: the apples and power-ups of the current grid, then every
+    /// snake segment, with the counters recounted. Gives the same grid as
+    /// `verify_and_repair` without comparing anything.
+    pub fn rebuild_grid_from_entities(&mut self) {
+        let mut grid = Grid::new();
+        let (mut apples, mut powerups) = (0, 0);
+        for y in 0..GRID_HEIGHT {
+            for (x, &cell) in self.grid.row(y).iter().enumerate() {
+                if matches!(cell, Cell::Apple | Cell::Split) {
+                    grid.set_cell(Point { x: x as u16, y: y as u16 }, cell);
+                }
+            }
+        }
+        for snake in &self.snakes {
+            for segment in snake.segments() {
+                if grid.get_cell(&segment) == Cell::Apple {
+                    self.apple_expiry.forget(&segment);
+                }
+                grid.set_cell(segment, Cell::Snake);
+            }
+        }
+        for y in 0..GRID_HEIGHT {
+            for cell in grid.row(y) {
+                match cell {
+                    Cell::Apple => apples += 1,
+                    Cell::Split => powerups += 1,
+                    _ => {}
+                }
+            }
+        }
+        self.grid = grid;
+        self.num_apples = apples;
+        self.num_powerups = powerups;
+    }
+}
@@ -0,0 +1,36 @@
+use crate::game::types::Point;
+
+/// What one tick did, built up by the core phases and the systems run after them (see
+/// [`crate::game::systems`]). `GameState::tick` returns it and `GameState::report` holds
+/// the last one, so renderers, scorers and telemetry need not diff states.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TickReport {
+    /// Number of the tick, `GameState::now` before it ran
+    pub tick: u64,
+    /// Snakes that died during the tick, in the order they died
+    pub deaths: Vec<u32>,
+    /// Apples eaten as (position, eater), in the order they were eaten
+    pub apples_eaten: Vec<(Point, u32)>,
+    /// Inputs that reached their snakes, whether or not they changed its direction
+    pub inputs_applied: u32,
+    /// Inputs left out: past the degraded input cap, within the turn interval, for a dead
+    /// snake or reversing a snake the reversal rule keeps from reversing
+    pub inputs_dropped: u32,
+    /// Split power-ups placed on the grid
+    pub powerups_spawned: u32,
+    /// Apples moved elsewhere because their TTL ran out
+    pub apples_expired: u32,
+}
+
+impl TickReport {
+    /// Empty the report for tick `tick`, keeping its allocations
+    pub fn begin(&mut self, tick: u64) {
+        self.tick = tick;
+        self.deaths.clear();
+        self.apples_eaten.clear();
+        self.inputs_applied = 0;
+        self.inputs_dropped = 0;
+        self.powerups_spawned = 0;
+        self.apples_expired = 0;
+    }
+}
@@ -0,0 +1,159 @@
+use rand::RngCore;
+
+/// SplitMix64: a tiny, fast generator whose whole state is one `u64`.
+///
+/// Seeds placement in `GameState::seeded`, and is the step function behind the engine's
+/// [`RngStreams`], whose whole position a snapshot can capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Raw generator state, for snapshots
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// Restore a generator from [`SplitMix64::state`]
+    pub const fn from_state(state: u64) -> Self {
+        Self { state }
+    }
+}
+
+impl RngCore for SplitMix64 {
+    #[inline(always)]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    #[inline(always)]
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        mix(self.state)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+/// Engine subsystems that draw randomness, each from a stream of its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    /// Apple spawns, including apples moved when their TTL runs out
+    Apples = 0,
+    /// Split power-up spawns
+    PowerUps = 1,
+    /// `GameState::respawn_dead` placements and directions
+    Respawns = 2,
+    /// Scheduled world events (see `game::events`)
+    Events = 3,
+    /// Random tie-breaks between heads reaching the same apple
+    Contests = 4,
+}
+
+impl Stream {
+    /// New subsystems append a variant; renumbering one changes its sequence
+    pub const ALL: [Stream; 5] = [Stream::Apples, Stream::PowerUps, Stream::Respawns, Stream::Events, Stream::Contests];
+    pub const COUNT: usize = Self::ALL.len();
+}
+
+/// Counter-based engine randomness, split into one stream per [`Stream`].
+///
+/// The n-th draw of a stream during a tick is a pure function of (seed, tick, stream, n),
+/// so a subsystem drawing more, or a new subsystem drawing at all, never shifts the
+/// sequence another subsystem sees. Replays recorded before a feature was added keep
+/// their apples where they were.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RngStreams {
+    seed: u64,
+    tick: u64,
+    draws: [u64; Stream::COUNT],
+}
+
+impl RngStreams {
+    pub const fn new(seed: u64) -> Self {
+        Self::from_parts(seed, 0, [0; Stream::COUNT])
+    }
+
+    /// Restore streams from [`RngStreams::seed`], [`RngStreams::tick`] and [`RngStreams::draws`]
+    pub const fn from_parts(seed: u64, tick: u64, draws: [u64; Stream::COUNT]) -> Self {
+        Self { seed, tick, draws }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Ticks the streams have been advanced
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Values drawn from each stream this tick, indexed by `Stream as usize`
+    pub fn draws(&self) -> [u64; Stream::COUNT] {
+        self.draws
+    }
+
+    /// Move every stream on to the next tick
+    pub fn advance(&mut self) {
+        self.tick += 1;
+        self.draws = [0; Stream::COUNT];
+    }
+
+    /// Generator for `stream`'s remaining draws this tick
+    #[inline(always)]
+    pub fn stream(&mut self, stream: Stream) -> StreamRng<'_> {
+        let key = mix(mix(self.seed.wrapping_add(stream as u64)).wrapping_add(self.tick));
+        StreamRng {
+            key,
+            draws: &mut self.draws[stream as usize],
+        }
+    }
+}
+
+/// One stream's draws within one tick; see [`RngStreams::stream`]
+pub struct StreamRng<'a> {
+    key: u64,
+    draws: &'a mut u64,
+}
+
+impl RngCore for StreamRng<'_> {
+    #[inline(always)]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    #[inline(always)]
+    fn next_u64(&mut self) -> u64 {
+        // Draw n is SplitMix64 started at the key, n steps in
+        let value = SplitMix64::from_state(self.key.wrapping_add(self.draws.wrapping_mul(GOLDEN_GAMMA))).next_u64();
+        *self.draws += 1;
+        value
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// SplitMix64's output function, a bijection with good avalanche
+#[inline(always)]
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,114 @@
+//! Lock-free collection of movement records on several threads.
+//!
+//! Phase 2 pushes each snake's record into its bucket's `Vec`. With several threads
+//! collecting at once those pushes would contend on every bucket. [`RecordScatter`]
+//! instead splits the snakes into contiguous id ranges, one per worker, and works in
+//! two passes:
+//!
+//! 1. each worker counts its records per bucket;
+//! 2. a prefix sum over (bucket, worker) gives every worker a disjoint run of slots in
+//!    each bucket, and the workers write their records straight into them.
+//!
+//! The result is one contiguous array sorted by bucket and, within a bucket, by snake
+//! id: the order `collect_records` builds, whatever the number of workers. The
+//! `record_scatter` bench group races it against a mutex per bucket.
+
+use crate::game::engine::{MovementRecord, NUM_BUCKETS, bucket_index};
+use crate::game::grid::Cell;
+use crate::game::snake::GridAwareSnake;
+use crate::game::types::Point;
+
+const UNFILLED: MovementRecord = MovementRecord::new(0, Point { x: 0, y: 0 }, Cell::Empty);
+
+/// Records of one tick, bucket after bucket. Reuses its storage across ticks.
+#[derive(Debug, Clone, Default)]
+pub struct RecordScatter {
+    records: Vec<MovementRecord>,
+    // Bucket b holds records[starts[b]..starts[b + 1]]
+    starts: Vec<usize>,
+    // [worker][bucket] record counts of the first pass
+    counts: Vec<[usize; NUM_BUCKETS]>,
+}
+
+impl RecordScatter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collect one record per alive snake of `snakes` on up to `workers` threads (the
+    /// calling thread alone for one)
+    pub fn collect(&mut self, snakes: &[GridAwareSnake], workers: usize) {
+        let chunk = snakes.len().div_ceil(workers.max(1)).max(1);
+        let chunks: Vec<&[GridAwareSnake]> = snakes.chunks(chunk).collect();
+
+        // Pass 1: count
+        self.counts.clear();
+        self.counts.resize(chunks.len(), [0; NUM_BUCKETS]);
+        run(chunks.iter().zip(self.counts.iter_mut()), |(chunk, counts)| {
+            for snake in chunk.iter().filter(|snake| snake.is_alive()) {
+                counts[bucket_index(&snake.calculate_new_head())] += 1;
+            }
+        });
+
+        // Prefix sum: bucket-major, so each bucket lists its workers in id order
+        self.starts.clear();
+        let mut total = 0;
+        for bucket in 0..NUM_BUCKETS {
+            self.starts.push(total);
+            total += self.counts.iter().map(|counts| counts[bucket]).sum::<usize>();
+        }
+        self.starts.push(total);
+        self.records.clear();
+        self.records.resize(total, UNFILLED);
+
+        // Hand each worker its run of every bucket
+        let mut runs: Vec<Vec<&mut [MovementRecord]>> = chunks.iter().map(|_| Vec::with_capacity(NUM_BUCKETS)).collect();
+        let mut rest = self.records.as_mut_slice();
+        for bucket in 0..NUM_BUCKETS {
+            for (worker, counts) in self.counts.iter().enumerate() {
+                let (run, after) = std::mem::take(&mut rest).split_at_mut(counts[bucket]);
+                runs[worker].push(run);
+                rest = after;
+            }
+        }
+
+        // Pass 2: scatter
+        run(chunks.iter().zip(runs), |(chunk, mut runs)| {
+            let mut filled = [0usize; NUM_BUCKETS];
+            for snake in chunk.iter().filter(|snake| snake.is_alive()) {
+                let new_head = snake.calculate_new_head();
+                let bucket = bucket_index(&new_head);
+                runs[bucket][filled[bucket]] = MovementRecord::new(snake.id(), new_head, Cell::Empty);
+                filled[bucket] += 1;
+            }
+        });
+    }
+
+    /// Every record, sorted by bucket then snake id
+    pub fn records(&self) -> &[MovementRecord] {
+        &self.records
+    }
+
+    pub fn bucket(&self, bucket: usize) -> &[MovementRecord] {
+        &self.records[self.starts[bucket]..self.starts[bucket + 1]]
+    }
+}
+
+// Run `job` on every item, one scoped thread per item after the first, which runs on
+// the calling thread
+fn run<I, F>(items: I, job: F)
+where
+    I: Iterator,
+    I::Item: Send,
+    F: Fn(I::Item) + Sync,
+{
+    let mut items = items;
+    let Some(first) = items.next() else { return };
+    std::thread::scope(|scope| {
+        for item in items {
+            let job = &job;
+            scope.spawn(move || job(item));
+        }
+        job(first);
+    });
+}
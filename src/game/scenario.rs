@@ -0,0 +1,530 @@
+use crate::game::apple::{Apple, AppleHotspots, AppleRespawn};
+use crate::game::body::Body;
+use crate::game::collision::AppleTieBreak;
+use crate::game::config::GameConfig;
+use crate::game::engine::GameState;
+use crate::game::events::{EventSchedule, ScheduledEvent, WorldEvent};
+use crate::game::fixedpoint::Fixed;
+use crate::game::grid::{Cell, GRID_HEIGHT, GRID_WIDTH};
+use crate::game::rng::{RngStreams, Stream};
+use crate::game::snake::{ReversalRule, Snake};
+use crate::game::types::{Direction, Input, Point, Rect};
+use crate::input::bot::{DifficultyMix, TieredBots};
+use crate::input::replay::{Replay, ReplayFrame};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::iter::Peekable;
+use std::path::Path;
+use std::slice;
+
+pub const SCENARIO_HEADER: &str = "# high-frequency-snake scenario v1";
+
+/// A snake as a scenario stores it; its id is its index in [`Scenario::snakes`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioSnake {
+    /// Tail first, head last
+    pub body: Vec<Point>,
+    pub direction: Direction,
+    pub alive: bool,
+    pub pending_growth: u32,
+}
+
+/// Bots driving every snake of a scenario (split fragments included) next to its inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScenarioBots {
+    pub mix: DifficultyMix,
+    /// Chance per tick that a random-tier bot turns, in percent
+    pub turn_percent: u8,
+    pub seed: u64,
+}
+
+/// A self-contained, entity-level game setup plus the inputs to play on it.
+///
+/// Unlike a snapshot it lists entities instead of the grid, so small scenarios stay small
+/// and can be read and edited by hand. Population totals start from zero when built, and so
+/// do the clocks of expiring apples; replacements of eaten apples still waiting are dropped.
+///
+/// Text format, one item per line (`#` starts a comment):
+///
+/// ```text
+/// # high-frequency-snake scenario v1
+/// config apples <n> kills <0|1> growth <percent> seed <n>
+/// splits <power-ups> <min length>                           (optional, default off)
+/// apple_ttl <ticks>                                           (optional, default off)
+/// apple_clearance <cells>                                     (optional, default off)
+/// apple_tie_break <bucket|random|longest|first_input>         (optional, default bucket)
+/// reversal <never|single|always>                              (optional, default never)
+/// turn_interval <ticks>                                       (optional, default off)
+/// apple_hotspots <count> <radius> <drift ticks>               (optional, default off)
+/// max_growth <cells per tick>                                 (optional, default off)
+/// max_length <cells> <drop|apples>                            (optional, default off)
+/// apple_respawn <delay> <max per tick> [<x>,<y> <w>x<h>]      (optional, default immediate)
+/// bots random <w> greedy <w> lookahead <w> turn <percent> seed <n>   (optional)
+/// rng <seed> <tick> <draws per stream ...>                (engine RNG streams)
+/// ticks <n>
+/// snake <direction> <alive|dead> <pending growth> x,y x,y ...   (tail first; none if despawned)
+/// apple x,y
+/// powerup x,y
+/// input <tick> <snake> <direction>
+/// event <tick> apples <count> <x>,<y> <width>x<height>     (scheduled world events)
+/// event <tick> respawn <count>
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    /// `snake_capacity` always equals `snakes.len()` in scenarios read from a file
+    pub config: GameConfig,
+    pub rng: RngStreams,
+    pub snakes: Vec<ScenarioSnake>,
+    pub apples: Vec<Point>,
+    /// Split power-ups
+    pub powerups: Vec<Point>,
+    /// Inputs by tick, counting from 0 at the start of the scenario
+    pub replay: Replay,
+    /// World events by tick, fired before the tick's inputs are applied
+    pub events: EventSchedule,
+    /// Ticks to simulate
+    pub ticks: u64,
+    /// Recorded inputs are applied after the bots', so they win when both steer a snake
+    pub bots: Option<ScenarioBots>,
+}
+
+impl Scenario {
+    /// Capture `state` (taken at `start_tick`) and the inputs of `replay` for the next `ticks` ticks
+    pub fn from_state(state: &GameState, start_tick: u64, replay: &Replay, ticks: u64) -> Self {
+        let snakes = state
+            .snakes
+            .iter()
+            .map(|snake| ScenarioSnake {
+                body: snake.segments().collect(),
+                direction: snake.snake().direction,
+                alive: snake.is_alive(),
+                pending_growth: snake.snake().pending_growth,
+            })
+            .collect();
+
+        let mut apples = Vec::with_capacity(state.num_apples as usize);
+        let mut powerups = Vec::with_capacity(state.num_powerups as usize);
+        for y in 0..GRID_HEIGHT {
+            for (x, cell) in state.grid.row(y).iter().enumerate() {
+                let point = Point { x: x as u16, y: y as u16 };
+                match cell {
+                    Cell::Apple => apples.push(point),
+                    Cell::Split => powerups.push(point),
+                    _ => {}
+                }
+            }
+        }
+
+        Self {
+            config: state.config,
+            rng: state.rng,
+            snakes,
+            apples,
+            powerups,
+            replay: replay.window(start_tick, ticks),
+            events: EventSchedule::default(),
+            ticks,
+            bots: None,
+        }
+    }
+
+    /// A fresh game state holding exactly this scenario's entities
+    pub fn build(&self) -> GameState {
+        let config = GameConfig {
+            snake_capacity: self.snakes.len(),
+            apple_capacity: self.config.apple_capacity.max(self.apples.len()),
+            split_powerups: self.config.split_powerups.max(self.powerups.len()),
+            ..self.config
+        };
+        let mut state = GameState::with_config(config);
+        for (id, snake) in self.snakes.iter().enumerate() {
+            let body: Body = snake.body.iter().copied().collect();
+            state.add_snake(Snake {
+                id: id as u32,
+                body,
+                direction: snake.direction,
+                is_alive: snake.alive,
+                pending_growth: snake.pending_growth,
+            });
+        }
+        for position in &self.apples {
+            state.add_apple(Apple { position: *position });
+        }
+        for position in &self.powerups {
+            state.add_split_powerup(*position);
+        }
+        state.rng = self.rng;
+        state
+    }
+
+    /// Build and simulate the first `ticks` ticks
+    pub fn state_after(&self, ticks: u64) -> GameState {
+        let mut state = self.build();
+        self.play(&mut state, ticks, |_, _| true);
+        state
+    }
+
+    /// Build and simulate, calling `check` after every tick with the state and the tick just
+    /// simulated. Returns the first tick `check` rejected.
+    pub fn first_failure<F>(&self, check: F) -> Option<u64>
+    where
+        F: FnMut(&GameState, u64) -> bool,
+    {
+        self.play(&mut self.build(), self.ticks, check)
+    }
+
+    /// Drive a run of this scenario tick by tick on `state`, built with [`Scenario::build`]
+    pub fn playback(&self, state: &GameState) -> Playback<'_> {
+        let bots = self.bots.map(|bots| {
+            let turn_probability = Fixed::from_ratio(bots.turn_percent as i64, 100);
+            TieredBots::new(0..state.snakes.len() as u32, bots.mix, turn_probability, bots.seed)
+        });
+        Playback {
+            frames: self.replay.frames.iter().peekable(),
+            events: self.events.clone(),
+            bots,
+            inputs: Vec::new(),
+        }
+    }
+
+    // Stops early, returning the tick, when `check` rejects the state
+    fn play<F>(&self, state: &mut GameState, ticks: u64, mut check: F) -> Option<u64>
+    where
+        F: FnMut(&GameState, u64) -> bool,
+    {
+        let mut playback = self.playback(state);
+        for tick in 0..ticks {
+            playback.step(state, tick, None);
+            if !check(state, tick) {
+                return Some(tick);
+            }
+        }
+        None
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{}", SCENARIO_HEADER)?;
+        writeln!(
+            writer,
+            "config apples {} kills {} growth {} seed {}",
+            self.config.apple_capacity, self.config.attribute_kills as u8, self.config.kill_growth_percent, self.config.rng_seed
+        )?;
+        if self.config.split_powerups > 0 {
+            writeln!(writer, "splits {} {}", self.config.split_powerups, self.config.split_min_length)?;
+        }
+        if self.config.apple_ttl > 0 {
+            writeln!(writer, "apple_ttl {}", self.config.apple_ttl)?;
+        }
+        if self.config.apple_head_clearance > 0 {
+            writeln!(writer, "apple_clearance {}", self.config.apple_head_clearance)?;
+        }
+        if self.config.apple_tie_break != AppleTieBreak::BucketOrder {
+            writeln!(writer, "apple_tie_break {}", self.config.apple_tie_break.name())?;
+        }
+        if self.config.reversal_rule != ReversalRule::Never {
+            writeln!(writer, "reversal {}", self.config.reversal_rule.name())?;
+        }
+        if self.config.turn_interval > 0 {
+            writeln!(writer, "turn_interval {}", self.config.turn_interval)?;
+        }
+        let hotspots = self.config.apple_hotspots;
+        if hotspots.is_enabled() {
+            writeln!(writer, "apple_hotspots {} {} {}", hotspots.count, hotspots.radius, hotspots.drift_interval)?;
+        }
+        if self.config.max_growth_per_tick > 0 {
+            writeln!(writer, "max_growth {}", self.config.max_growth_per_tick)?;
+        }
+        if self.config.max_snake_length > 0 {
+            let tails = if self.config.length_cap_apples { "apples" } else { "drop" };
+            writeln!(writer, "max_length {} {}", self.config.max_snake_length, tails)?;
+        }
+        let respawn = self.config.apple_respawn;
+        if respawn != AppleRespawn::IMMEDIATE {
+            write!(writer, "apple_respawn {} {}", respawn.delay, respawn.max_per_tick)?;
+            if let Some(area) = respawn.region {
+                write!(writer, " {},{} {}x{}", area.x, area.y, area.width, area.height)?;
+            }
+            writeln!(writer)?;
+        }
+        if let Some(bots) = &self.bots {
+            writeln!(
+                writer,
+                "bots random {} greedy {} lookahead {} turn {} seed {}",
+                bots.mix.random, bots.mix.greedy, bots.mix.lookahead, bots.turn_percent, bots.seed
+            )?;
+        }
+        write!(writer, "rng {} {}", self.rng.seed(), self.rng.tick())?;
+        for draws in self.rng.draws() {
+            write!(writer, " {}", draws)?;
+        }
+        writeln!(writer)?;
+        writeln!(writer, "ticks {}", self.ticks)?;
+        for snake in &self.snakes {
+            write!(
+                writer,
+                "snake {} {} {}",
+                snake.direction.name(),
+                if snake.alive { "alive" } else { "dead" },
+                snake.pending_growth
+            )?;
+            for point in &snake.body {
+                write!(writer, " {},{}", point.x, point.y)?;
+            }
+            writeln!(writer)?;
+        }
+        for apple in &self.apples {
+            writeln!(writer, "apple {},{}", apple.x, apple.y)?;
+        }
+        for powerup in &self.powerups {
+            writeln!(writer, "powerup {},{}", powerup.x, powerup.y)?;
+        }
+        for (tick, input) in self.replay.steps() {
+            writeln!(writer, "input {} {} {}", tick, input.snake_id, input.direction.name())?;
+        }
+        for scheduled in self.events.events() {
+            match scheduled.event {
+                WorldEvent::SpawnApples { count, area } => writeln!(
+                    writer,
+                    "event {} apples {} {},{} {}x{}",
+                    scheduled.tick, count, area.x, area.y, area.width, area.height
+                )?,
+                WorldEvent::RespawnWave { count } => writeln!(writer, "event {} respawn {}", scheduled.tick, count)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        if text.lines().next() != Some(SCENARIO_HEADER) {
+            return Err(invalid_data("not a scenario file".to_string()));
+        }
+
+        let mut scenario = Self {
+            config: GameConfig::default(),
+            rng: RngStreams::new(0),
+            snakes: Vec::new(),
+            apples: Vec::new(),
+            powerups: Vec::new(),
+            replay: Replay::new(),
+            events: EventSchedule::default(),
+            ticks: 0,
+            bots: None,
+        };
+        let mut inputs: Vec<(u64, Input)> = Vec::new();
+        let mut events: Vec<ScheduledEvent> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let words: Vec<&str> = words.collect();
+            let error = |message: &str| invalid_data(format!("line {}: {}", number + 1, message));
+            match (keyword, words.as_slice()) {
+                ("config", ["apples", apples, "kills", kills, "growth", growth, "seed", seed]) => {
+                    scenario.config.apple_capacity = parse(apples).ok_or_else(|| error("bad apples"))?;
+                    scenario.config.attribute_kills = parse::<u8>(kills).ok_or_else(|| error("bad kills"))? != 0;
+                    scenario.config.kill_growth_percent = parse(growth).ok_or_else(|| error("bad growth"))?;
+                    scenario.config.rng_seed = parse(seed).ok_or_else(|| error("bad seed"))?;
+                }
+                ("splits", [powerups, min_length]) => {
+                    scenario.config.split_powerups = parse(powerups).ok_or_else(|| error("bad power-up count"))?;
+                    scenario.config.split_min_length = parse(min_length).ok_or_else(|| error("bad min length"))?;
+                }
+                ("apple_ttl", [ttl]) => scenario.config.apple_ttl = parse(ttl).ok_or_else(|| error("bad apple ttl"))?,
+                ("apple_clearance", [cells]) => {
+                    scenario.config.apple_head_clearance = parse(cells).ok_or_else(|| error("bad apple clearance"))?
+                }
+                ("apple_tie_break", [name]) => {
+                    scenario.config.apple_tie_break = AppleTieBreak::from_name(name).ok_or_else(|| error("bad apple tie-break"))?
+                }
+                ("reversal", [name]) => {
+                    scenario.config.reversal_rule = ReversalRule::from_name(name).ok_or_else(|| error("bad reversal rule"))?
+                }
+                ("turn_interval", [ticks]) => {
+                    scenario.config.turn_interval = parse(ticks).ok_or_else(|| error("bad turn interval"))?
+                }
+                ("apple_hotspots", [count, radius, drift]) => {
+                    scenario.config.apple_hotspots = AppleHotspots {
+                        count: parse(count).ok_or_else(|| error("bad hotspot count"))?,
+                        radius: parse(radius).ok_or_else(|| error("bad hotspot radius"))?,
+                        drift_interval: parse(drift).ok_or_else(|| error("bad hotspot drift"))?,
+                    }
+                }
+                ("max_growth", [cells]) => {
+                    scenario.config.max_growth_per_tick = parse(cells).ok_or_else(|| error("bad max growth"))?
+                }
+                ("max_length", [cells, tails]) => {
+                    scenario.config.max_snake_length = parse(cells).ok_or_else(|| error("bad max length"))?;
+                    scenario.config.length_cap_apples = match *tails {
+                        "drop" => false,
+                        "apples" => true,
+                        _ => return Err(error("bad max length tails")),
+                    };
+                }
+                ("apple_respawn", [delay, max_per_tick, region @ ..]) => {
+                    let region = match region {
+                        [] => None,
+                        [corner, size] => Some(parse_area(corner, size).ok_or_else(|| error("bad respawn region"))?),
+                        _ => return Err(error("bad respawn region")),
+                    };
+                    scenario.config.apple_respawn = AppleRespawn {
+                        delay: parse(delay).ok_or_else(|| error("bad respawn delay"))?,
+                        max_per_tick: parse(max_per_tick).ok_or_else(|| error("bad respawn limit"))?,
+                        region,
+                    };
+                }
+                ("bots", ["random", random, "greedy", greedy, "lookahead", lookahead, "turn", turn, "seed", seed]) => {
+                    scenario.bots = Some(ScenarioBots {
+                        mix: DifficultyMix {
+                            random: parse(random).ok_or_else(|| error("bad random weight"))?,
+                            greedy: parse(greedy).ok_or_else(|| error("bad greedy weight"))?,
+                            lookahead: parse(lookahead).ok_or_else(|| error("bad lookahead weight"))?,
+                        },
+                        turn_percent: parse(turn).filter(|turn| *turn <= 100).ok_or_else(|| error("bad turn percent"))?,
+                        seed: parse(seed).ok_or_else(|| error("bad bot seed"))?,
+                    })
+                }
+                // A lone number is the single generator state of older scenarios
+                ("rng", [seed]) => scenario.rng = RngStreams::new(parse(seed).ok_or_else(|| error("bad rng seed"))?),
+                ("rng", [seed, tick, streams @ ..]) if streams.len() <= Stream::COUNT => {
+                    let mut draws = [0; Stream::COUNT];
+                    for (slot, value) in draws.iter_mut().zip(streams) {
+                        *slot = parse(value).ok_or_else(|| error("bad rng draws"))?;
+                    }
+                    scenario.rng = RngStreams::from_parts(
+                        parse(seed).ok_or_else(|| error("bad rng seed"))?,
+                        parse(tick).ok_or_else(|| error("bad rng tick"))?,
+                        draws,
+                    );
+                }
+                ("ticks", [ticks]) => scenario.ticks = parse(ticks).ok_or_else(|| error("bad ticks"))?,
+                ("snake", [direction, alive, growth, body @ ..]) => {
+                    let body = body
+                        .iter()
+                        .map(|point| parse_point(point))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| error("bad snake body"))?;
+                    let alive = match *alive {
+                        "alive" => true,
+                        "dead" => false,
+                        _ => return Err(error("expected alive or dead")),
+                    };
+                    // Only despawned slots, always dead, have no cells
+                    if alive && body.is_empty() {
+                        return Err(error("bad snake body"));
+                    }
+                    scenario.snakes.push(ScenarioSnake {
+                        body,
+                        direction: Direction::from_name(direction).ok_or_else(|| error("bad direction"))?,
+                        alive,
+                        pending_growth: parse(growth).ok_or_else(|| error("bad pending growth"))?,
+                    });
+                }
+                ("apple", [point]) => scenario.apples.push(parse_point(point).ok_or_else(|| error("bad apple"))?),
+                ("powerup", [point]) => {
+                    scenario.powerups.push(parse_point(point).ok_or_else(|| error("bad power-up"))?)
+                }
+                ("input", [tick, snake_id, direction]) => inputs.push((
+                    parse(tick).ok_or_else(|| error("bad input tick"))?,
+                    Input {
+                        snake_id: parse(snake_id).ok_or_else(|| error("bad snake id"))?,
+                        direction: Direction::from_name(direction).ok_or_else(|| error("bad direction"))?,
+                    },
+                )),
+                ("event", [tick, kind, rest @ ..]) => {
+                    let tick = parse(tick).ok_or_else(|| error("bad event tick"))?;
+                    let event = match (*kind, rest) {
+                        ("apples", [count, corner, size]) => WorldEvent::SpawnApples {
+                            count: parse(count).ok_or_else(|| error("bad apple count"))?,
+                            area: parse_area(corner, size).ok_or_else(|| error("bad event area"))?,
+                        },
+                        ("respawn", [count]) => WorldEvent::RespawnWave {
+                            count: parse(count).ok_or_else(|| error("bad respawn count"))?,
+                        },
+                        _ => return Err(error("unknown event")),
+                    };
+                    events.push(ScheduledEvent { tick, event });
+                }
+                _ => return Err(error("unrecognised line")),
+            }
+        }
+
+        if let Some((_, input)) = inputs.iter().find(|(_, input)| input.snake_id as usize >= scenario.snakes.len()) {
+            return Err(invalid_data(format!("input for unknown snake {}", input.snake_id)));
+        }
+        scenario.config.snake_capacity = scenario.snakes.len();
+        scenario.config.validate().map_err(|error| invalid_data(error.to_string()))?;
+        // Same-tick inputs keep their file order
+        inputs.sort_by_key(|(tick, _)| *tick);
+        scenario.replay = Replay::from_steps(inputs);
+        scenario.events = EventSchedule::new(events);
+        Ok(scenario)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        fs::write(path, bytes)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(&mut fs::File::open(path)?)
+    }
+}
+
+/// A scenario's events, bots and recorded inputs applied to a run one tick at a time
+pub struct Playback<'a> {
+    frames: Peekable<slice::Iter<'a, ReplayFrame>>,
+    events: EventSchedule,
+    bots: Option<TieredBots>,
+    inputs: Vec<Input>,
+}
+
+impl Playback<'_> {
+    /// Simulate `tick`: fire due events, then apply the bots' inputs followed by the recorded
+    /// ones, or by `recorded` in their place. Returns every input the tick was given.
+    pub fn step(&mut self, state: &mut GameState, tick: u64, recorded: Option<&[Input]>) -> &[Input] {
+        self.events.fire_due(state);
+        self.inputs.clear();
+        if let Some(bots) = &mut self.bots {
+            bots.decide(state.view(), &mut self.inputs);
+        }
+        let frame = self.frames.next_if(|frame| frame.tick == tick);
+        match (recorded, frame) {
+            (Some(inputs), _) => self.inputs.extend_from_slice(inputs),
+            (None, Some(frame)) => self.inputs.extend_from_slice(&frame.inputs),
+            (None, None) => {}
+        }
+        state.tick(&self.inputs);
+        if let Some(bots) = &mut self.bots {
+            for split in &state.splits {
+                bots.adopt(split.fragment);
+            }
+        }
+        &self.inputs
+    }
+}
+
+pub(crate) fn parse<T: std::str::FromStr>(word: &str) -> Option<T> {
+    word.parse().ok()
+}
+
+// `<x>,<y>` and `<width>x<height>`
+fn parse_area(corner: &str, size: &str) -> Option<Rect> {
+    let corner = parse_point(corner)?;
+    let (width, height) = size.split_once('x')?;
+    Some(Rect::new(corner.x, corner.y, parse(width)?, parse(height)?))
+}
+
+pub(crate) fn parse_point(word: &str) -> Option<Point> {
+    let (x, y) = word.split_once(',')?;
+    let point = Point { x: parse(x)?, y: parse(y)? };
+    ((point.x as usize) < GRID_WIDTH && (point.y as usize) < GRID_HEIGHT).then_some(point)
+}
+
+pub(crate) fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -1,29 +1,100 @@
+use crate::game::body::Body;
 use crate::game::grid::{GRID_HEIGHT, GRID_WIDTH, Cell, Grid};
 use crate::game::types::{Direction, Point};
 use crossbeam_utils::CachePadded;
-use tinydeque::TinyDeque;
 
 pub const SNAKE_CAPACITY: usize = 1024;
 
+/// When a snake may turn straight back the way it came
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReversalRule {
+    /// Reversing inputs are ignored
+    #[default]
+    Never,
+    /// Single-cell snakes, which have no neck to run into, may reverse (classic snake)
+    SingleCell,
+    /// Any snake may reverse; a longer one then runs into its own neck
+    Always,
+}
+
+impl ReversalRule {
+    pub const ALL: [ReversalRule; 3] = [ReversalRule::Never, ReversalRule::SingleCell, ReversalRule::Always];
+
+    /// Compact encoding used by snapshots
+    pub fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Inverse of [`ReversalRule::to_u8`]
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    /// Name used by scenario files
+    pub fn name(self) -> &'static str {
+        match self {
+            ReversalRule::Never => "never",
+            ReversalRule::SingleCell => "single",
+            ReversalRule::Always => "always",
+        }
+    }
+
+    /// Inverse of [`ReversalRule::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+
+    /// Whether a snake of `length` cells may reverse
+    #[inline(always)]
+    pub fn allows(self, length: usize) -> bool {
+        match self {
+            ReversalRule::Never => false,
+            ReversalRule::SingleCell => length <= 1,
+            ReversalRule::Always => true,
+        }
+    }
+}
+
 pub struct Snake {
     pub id: u32,
-    pub body: TinyDeque<[Point; 16]>,  // Stack-allocated for small snakes, heap for large
+    /// Tail first, head last. Never empty outside despawned slots: every snake starts with
+    /// one cell and moving pushes the new head before popping the tail.
+    pub body: Body,  // Stack-allocated for small snakes, heap for large
     pub direction: Direction,
     pub is_alive: bool,
+    /// Cells still to be grown, one per tick (e.g. from kills)
+    pub pending_growth: u32,
 }
 
 impl Snake {
     pub fn new(id: u32, start_pos: Point, initial_direction: Direction) -> Self {
-        let mut body = TinyDeque::new();
+        let mut body = Body::new();
         body.push_back(start_pos);
         Self {
             id,
             body,
             direction: initial_direction,
             is_alive: true,
+            pending_growth: 0,
         }
     }
 
+    #[inline(always)]
+    pub fn head(&self) -> Point {
+        *self.body.back().expect("snake bodies are never empty")
+    }
+
+    #[inline(always)]
+    pub fn tail(&self) -> Point {
+        *self.body.front().expect("snake bodies are never empty")
+    }
+
+    /// Body cells from tail to head
+    #[inline]
+    pub fn segments(&self) -> impl DoubleEndedIterator<Item = Point> + ExactSizeIterator + '_ {
+        self.body.iter().copied()
+    }
+
     pub fn move_forward(&mut self, will_grow: bool) {
         let new_head = self.calculate_new_head();
         self.body.push_back(new_head);
@@ -35,59 +106,63 @@ impl Snake {
     /// Calculate where the snake's head will be after moving forward
     #[inline(always)]
     pub fn calculate_new_head(&self) -> Point {
-        let current_head = self.body.get(self.body.len() - 1).unwrap();
-        match self.direction {
-            Direction::Up => Point {
-                x: current_head.x,
-                y: if current_head.y == 0 {
-                    (GRID_HEIGHT - 1) as u16
-                } else {
-                    current_head.y - 1
-                },
-            },
-            Direction::Down => Point {
-                x: current_head.x,
-                y: if current_head.y == (GRID_HEIGHT - 1) as u16 {
-                    0
-                } else {
-                    current_head.y + 1
-                },
-            },
-            Direction::Left => Point {
-                x: if current_head.x == 0 {
-                    (GRID_WIDTH - 1) as u16
-                } else {
-                    current_head.x - 1
-                },
-                y: current_head.y,
-            },
-            Direction::Right => Point {
-                x: if current_head.x == (GRID_WIDTH - 1) as u16 {
-                    0
-                } else {
-                    current_head.x + 1
-                },
-                y: current_head.y,
-            },
-        }
+        next_head(self.head(), self.direction)
     }
 
 
     pub fn change_direction(&mut self, new_direction: Direction) {
         // Prevent snake from reversing on itself
-        let can_change = !matches!(
-            (self.direction, new_direction),
-            (Direction::Up, Direction::Down)
-                | (Direction::Down, Direction::Up)
-                | (Direction::Left, Direction::Right)
-                | (Direction::Right, Direction::Left)
-        );
-        if can_change {
+        self.turn(new_direction, ReversalRule::Never);
+    }
+
+    /// Face `new_direction` unless that reverses the snake and `rule` forbids it
+    #[inline(always)]
+    pub fn turn(&mut self, new_direction: Direction, rule: ReversalRule) {
+        if new_direction != self.direction.opposite() || rule.allows(self.body.len()) {
             self.direction = new_direction;
         }
     }
 }
 
+/// Cell one step from `current_head` in `direction`, wrapping around the grid edges
+#[inline(always)]
+pub fn next_head(current_head: Point, direction: Direction) -> Point {
+    match direction {
+        Direction::Up => Point {
+            x: current_head.x,
+            y: if current_head.y == 0 {
+                (GRID_HEIGHT - 1) as u16
+            } else {
+                current_head.y - 1
+            },
+        },
+        Direction::Down => Point {
+            x: current_head.x,
+            y: if current_head.y == (GRID_HEIGHT - 1) as u16 {
+                0
+            } else {
+                current_head.y + 1
+            },
+        },
+        Direction::Left => Point {
+            x: if current_head.x == 0 {
+                (GRID_WIDTH - 1) as u16
+            } else {
+                current_head.x - 1
+            },
+            y: current_head.y,
+        },
+        Direction::Right => Point {
+            x: if current_head.x == (GRID_WIDTH - 1) as u16 {
+                0
+            } else {
+                current_head.x + 1
+            },
+            y: current_head.y,
+        },
+    }
+}
+
 /// Smart wrapper around Snake that automatically manages grid updates
 pub struct GridAwareSnake {
     snake: CachePadded<Snake>,
@@ -104,6 +179,11 @@ impl GridAwareSnake {
         wrapper
     }
     
+    /// Wrap a snake whose cells are already on the grid (e.g. restored from a snapshot)
+    pub fn from_placed(snake: Snake) -> Self {
+        Self { snake: CachePadded::new(snake) }
+    }
+
     /// Calculate new head position (no grid access)
     #[inline(always)]
     pub fn calculate_new_head(&self) -> Point {
@@ -113,11 +193,32 @@ impl GridAwareSnake {
     /// Get current tail position (no grid access)
     #[inline(always)]
     pub fn tail_position(&self) -> Option<Point> {
-        if self.snake.body.len() > 0 {
-            self.snake.body.get(0).copied()
-        } else {
-            None
-        }
+        self.snake.body.front().copied()
+    }
+
+    /// Head cell; dead snakes keep their bodies, only despawned slots are empty (see
+    /// `GameState::despawn`)
+    #[inline(always)]
+    pub fn head_point(&self) -> Point {
+        self.snake.head()
+    }
+
+    /// Tail cell, see [`GridAwareSnake::head_point`]
+    #[inline(always)]
+    pub fn tail_point(&self) -> Point {
+        self.snake.tail()
+    }
+
+    /// Body cells from tail to head
+    #[inline]
+    pub fn segments(&self) -> impl DoubleEndedIterator<Item = Point> + ExactSizeIterator + '_ {
+        self.snake.segments()
+    }
+
+    /// The body as at most two contiguous runs, tail first
+    #[inline]
+    pub fn as_slices(&self) -> (&[Point], &[Point]) {
+        self.snake.body.as_slices()
     }
     
     /// Update snake body after successful movement (no grid access)
@@ -126,6 +227,39 @@ impl GridAwareSnake {
         self.snake.move_forward(will_grow);
     }
     
+    /// Queue `cells` of growth to be applied one per tick (no grid access)
+    #[inline(always)]
+    pub fn queue_growth(&mut self, cells: u32) {
+        self.snake.pending_growth += cells;
+    }
+
+    /// Take one cell of queued growth if any is pending (no grid access)
+    #[inline(always)]
+    pub fn take_queued_growth(&mut self) -> bool {
+        if self.snake.pending_growth > 0 {
+            self.snake.pending_growth -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// True if any body segment is at `point` (linear scan of the body)
+    pub fn occupies(&self, point: &Point) -> bool {
+        self.snake.body.contains(point)
+    }
+
+    /// Remove the `count` rearmost segments and return them tail first (no grid access)
+    pub fn take_rear(&mut self, count: usize) -> Vec<Point> {
+        (0..count).filter_map(|_| self.snake.body.pop_front()).collect()
+    }
+
+    /// Drop every segment and any queued growth, releasing spilled storage (no grid access)
+    pub fn release_body(&mut self) {
+        self.snake.body = Body::new();
+        self.snake.pending_growth = 0;
+    }
+
     /// Mark snake as dead (no grid access)
     #[inline(always)]
     pub fn mark_dead(&mut self) {
@@ -149,10 +283,10 @@ impl GridAwareSnake {
         
         // No collision - proceed with movement
         // Only clear the tail position from grid if not growing
-        if !will_grow {
-            if let Some(tail) = self.snake.body.get(0) {
-                grid.set_cell(*tail, Cell::Empty);
-            }
+        if !will_grow
+            && let Some(tail) = self.snake.body.get(0)
+        {
+            grid.set_cell(*tail, Cell::Empty);
         }
         
         // Move the snake with growth flag
@@ -163,7 +297,7 @@ impl GridAwareSnake {
             grid.set_cell(*head, Cell::Snake);
         }
         
-        return true;
+        true
     }
     
     
@@ -179,6 +313,12 @@ impl GridAwareSnake {
     pub fn change_direction(&mut self, new_direction: Direction) {
         self.snake.change_direction(new_direction);
     }
+
+    /// Change direction under `rule` (no grid update needed)
+    #[inline(always)]
+    pub fn turn(&mut self, new_direction: Direction, rule: ReversalRule) {
+        self.snake.turn(new_direction, rule);
+    }
     
     /// Get a reference to the underlying snake
     pub fn snake(&self) -> &Snake {
@@ -203,35 +343,27 @@ impl GridAwareSnake {
     /// Get snake head position
     #[inline(always)]
     pub fn head(&self) -> Option<&Point> {
-        if self.snake.body.len() > 0 {
-            self.snake.body.get(self.snake.body.len() - 1)
-        } else {
-            None
-        }
+        self.snake.body.back()
     }
     
 
     
     /// Get snake body
-    pub fn body(&self) -> &TinyDeque<[Point; 16]> {
+    pub fn body(&self) -> &Body {
         &self.snake.body
     }
     
     // Private helper methods
 
     fn update_grid_with_body(&self, grid: &mut Grid) {
-        for i in 0..self.snake.body.len() {
-            if let Some(part) = self.snake.body.get(i) {
-                grid.set_cell(*part, Cell::Snake);
-            }
+        for part in self.segments() {
+            grid.set_cell(part, Cell::Snake);
         }
     }
     
     fn clear_from_grid(&self, grid: &mut Grid) {
-        for i in 0..self.snake.body.len() {
-            if let Some(part) = self.snake.body.get(i) {
-                grid.set_cell(*part, Cell::Empty);
-            }
+        for part in self.segments() {
+            grid.set_cell(part, Cell::Empty);
         }
     }
 }
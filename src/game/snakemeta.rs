@@ -0,0 +1,140 @@
+//! Cosmetic identity of snakes for viewers.
+//!
+//! A [`SnakeMeta`] gives a snake id a palette color, a name and a team, so viewers can
+//! tell snakes apart the same way from tick to tick and across reconnects. The table is
+//! written when a snake joins (`GameState::spawn_snake_with_meta`, `set_snake_meta`) and
+//! cleared when it is despawned or its id goes to a new joiner; `tick()` never reads or
+//! writes it. It travels in snapshots and snapshot deltas. Ids without an entry show [`SnakeMeta::fallback`].
+
+use crate::game::snapshot::{invalid_data, read_array};
+use std::io::{self, Read, Write};
+
+/// Colors viewers must be able to draw; `color` indexes their palette
+pub const PALETTE_SIZE: u8 = 16;
+/// Longest name kept, in bytes; longer names are cut at a character boundary
+pub const MAX_NAME_BYTES: usize = 24;
+/// `team` of a snake in no team
+pub const NO_TEAM: u16 = 0;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnakeMeta {
+    /// Palette index, below [`PALETTE_SIZE`]
+    pub color: u8,
+    pub name: String,
+    pub team: u16,
+}
+
+impl SnakeMeta {
+    /// Metadata with `color` wrapped into the palette and `name` cut to [`MAX_NAME_BYTES`]
+    pub fn new(color: u8, name: &str, team: u16) -> Self {
+        let mut end = name.len().min(MAX_NAME_BYTES);
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        Self {
+            color: color % PALETTE_SIZE,
+            name: name[..end].to_string(),
+            team,
+        }
+    }
+
+    /// What snake `id` shows without an entry: a color from its id, no name, no team
+    pub fn fallback(id: u32) -> Self {
+        Self {
+            color: (id % PALETTE_SIZE as u32) as u8,
+            name: String::new(),
+            team: NO_TEAM,
+        }
+    }
+}
+
+/// Metadata by snake id, see the module docs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnakeMetaTable {
+    entries: Vec<Option<SnakeMeta>>,
+}
+
+impl SnakeMetaTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give snake `id` its metadata, replacing any it had
+    pub fn set(&mut self, id: u32, meta: SnakeMeta) {
+        let index = id as usize;
+        if index >= self.entries.len() {
+            self.entries.resize(index + 1, None);
+        }
+        self.entries[index] = Some(SnakeMeta::new(meta.color, &meta.name, meta.team));
+    }
+
+    pub fn get(&self, id: u32) -> Option<&SnakeMeta> {
+        self.entries.get(id as usize)?.as_ref()
+    }
+
+    /// Snake `id`'s metadata, or its [`SnakeMeta::fallback`]
+    pub fn get_or_fallback(&self, id: u32) -> SnakeMeta {
+        self.get(id).cloned().unwrap_or_else(|| SnakeMeta::fallback(id))
+    }
+
+    pub fn remove(&mut self, id: u32) -> Option<SnakeMeta> {
+        self.entries.get_mut(id as usize)?.take()
+    }
+
+    /// Drop the entries of ids from `len` on, e.g. when the snake list shrinks
+    pub fn truncate(&mut self, len: usize) {
+        self.entries.truncate(len);
+    }
+
+    /// Entries by id
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &SnakeMeta)> {
+        self.entries.iter().enumerate().filter_map(|(id, meta)| Some((id as u32, meta.as_ref()?)))
+    }
+
+    /// Number of ids with metadata
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|meta| meta.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.iter().all(Option::is_none)
+    }
+
+    /// `count:u32` then per entry `id:u32` and the metadata as by [`write_meta`]
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&(self.len() as u32).to_le_bytes())?;
+        for (id, meta) in self.iter() {
+            writer.write_all(&id.to_le_bytes())?;
+            write_meta(writer, meta)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut table = Self::new();
+        let count = u32::from_le_bytes(read_array(reader)?);
+        for _ in 0..count {
+            let id = u32::from_le_bytes(read_array(reader)?);
+            table.set(id, read_meta(reader)?);
+        }
+        Ok(table)
+    }
+}
+
+/// `color:u8 team:u16 name_len:u8 name[name_len]`, the name in UTF-8
+pub(crate) fn write_meta<W: Write>(writer: &mut W, meta: &SnakeMeta) -> io::Result<()> {
+    writer.write_all(&[meta.color])?;
+    writer.write_all(&meta.team.to_le_bytes())?;
+    writer.write_all(&[meta.name.len() as u8])?;
+    writer.write_all(meta.name.as_bytes())
+}
+
+pub(crate) fn read_meta<R: Read>(reader: &mut R) -> io::Result<SnakeMeta> {
+    let [color] = read_array(reader)?;
+    let team = u16::from_le_bytes(read_array(reader)?);
+    let [len] = read_array(reader)?;
+    let mut name = vec![0; len as usize];
+    reader.read_exact(&mut name)?;
+    let name = String::from_utf8(name).map_err(|_| invalid_data("snake name is not UTF-8"))?;
+    Ok(SnakeMeta::new(color, &name, team))
+}
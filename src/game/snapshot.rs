@@ -0,0 +1,418 @@
+use crate::game::apple::{AppleExpiry, AppleHotspots, AppleRespawn, AppleScaling, RespawnWheel};
+use crate::game::body::Body;
+use crate::game::config::GameConfig;
+use crate::game::engine::{GameState, MAX_RECORD_SNAKES};
+use crate::game::codec::{GridCodec, decode_grid, encode_grid};
+use crate::game::collision::AppleTieBreak;
+use crate::game::leaderboard::{Leaderboard, Standing};
+use crate::game::metrics::{LENGTH_HISTOGRAM_BUCKETS, PopulationMetrics};
+use crate::game::rng::{RngStreams, Stream};
+use crate::game::snake::{GridAwareSnake, ReversalRule, Snake};
+use crate::game::snakemeta::SnakeMetaTable;
+use crate::game::types::{Direction, Point, Rect};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"HFSS";
+pub const SNAPSHOT_VERSION: u16 = 15;
+
+/// Write the full simulation state as of `tick`, usually `state.now()`; the restored
+/// state's clock starts there.
+///
+/// On-disk layout (little endian):
+/// `magic[4] version:u16 tick:u64`, the config
+/// `snake_capacity:u64 apple_capacity:u64 attribute_kills:u8 kill_growth_percent:u8 rng_seed:u64`
+/// `split_powerups:u64 split_min_length:u64 apple_ttl:u32 apple_head_clearance:u8`
+/// `apple_tie_break:u8 reversal_rule:u8 turn_interval:u32`
+/// `hotspot_count:u8 hotspot_radius:u16 hotspot_drift_interval:u32`
+/// `max_growth_per_tick:u32 max_snake_length:u32 length_cap_apples:u8`
+/// `respawn_delay:u32 respawn_max_per_tick:u32 respawn_region:u8 x:u16 y:u16 width:u16 height:u16`
+/// (the rect zeroed without a region), the apple scaling `scaling:u8 snakes_per_apple:u32`
+/// `cells_per_apple:u32 min_apples:u64 max_apples:u64 every_ticks:u64` (zeroed without
+/// one), the engine RNG `rng_seed:u64 rng_tick:u64 stream_count:u8` then `stream_count` x
+/// `draws:u64`, `num_apples:u64 num_powerups:u64`, the apple expiry clock
+/// `expiry_now:u64 deadline_count:u32` followed by that many `(x:u16 y:u16 deadline:u64)`,
+/// the apple respawn wheel `respawn_backlog:u64 respawn_count:u32` followed by that many
+/// `(due:u64 count:u64)`,
+/// the population counters
+/// `alive:u32 total_body_cells:u64 histogram:[u32; 8] births_this_tick:u32 deaths_this_tick:u32`
+/// `total_births:u64 total_deaths:u64`,
+/// `snake_count:u32` then per snake
+/// `id:u32 direction:u8 alive:u8 pending_growth:u32 turn_ready:u64 len:u32` followed by
+/// `len` x `(x:u16 y:u16)`, the viewer metadata as by [`SnakeMetaTable::write_to`],
+/// the leaderboard `board:u8 board_size:u64 score_count:u32` followed by that many
+/// `(snake_id:u32 score:u64)`, best first (zeroed and empty without a board), and finally `codec:u8` and the grid compressed with that [`GridCodec`] (row-major runs
+/// by default).
+///
+/// Version 1 files lack the split fields and `num_powerups`, versions before 3 the apple
+/// TTL and expiry clock; they read back with those mechanics off. Versions before 4 have
+/// no codec byte and are always run-length encoded. Versions before 5 hold a single
+/// `rng_state:u64`, which restores as the seed of fresh RNG streams. Versions before 6
+/// lack the apple head clearance and spawn apples anywhere; versions before 7 break
+/// apple ties in bucket order, versions before 8 never let snakes reverse, versions
+/// before 9 leave turns unlimited, versions before 10 spawn apples uniformly, versions
+/// before 11 leave growth and length unlimited, versions before 12 replace eaten
+/// apples at once and anywhere, with nothing waiting, versions before 13 carry no
+/// snake colors, names or teams, versions before 14 keep a fixed apple capacity, and
+/// versions before 15 have no leaderboard.
+///
+/// The grid is stored rather than rebuilt from bodies because corpses and overlapping
+/// moves can leave it differing from the union of snake bodies.
+/// The collision policy is not part of the snapshot; restored states use `ClassicPolicy`.
+pub fn write_snapshot<W: Write>(state: &GameState, tick: u64, writer: &mut W) -> io::Result<()> {
+    write_snapshot_with(state, tick, GridCodec::default(), writer)
+}
+
+/// [`write_snapshot`] with the grid compressed by `codec`
+pub fn write_snapshot_with<W: Write>(state: &GameState, tick: u64, codec: GridCodec, writer: &mut W) -> io::Result<()> {
+    writer.write_all(&SNAPSHOT_MAGIC)?;
+    writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+    writer.write_all(&tick.to_le_bytes())?;
+
+    let config = &state.config;
+    writer.write_all(&(config.snake_capacity as u64).to_le_bytes())?;
+    writer.write_all(&(config.apple_capacity as u64).to_le_bytes())?;
+    writer.write_all(&[config.attribute_kills as u8, config.kill_growth_percent])?;
+    writer.write_all(&config.rng_seed.to_le_bytes())?;
+    writer.write_all(&(config.split_powerups as u64).to_le_bytes())?;
+    writer.write_all(&(config.split_min_length as u64).to_le_bytes())?;
+    writer.write_all(&config.apple_ttl.to_le_bytes())?;
+    writer.write_all(&[config.apple_head_clearance])?;
+    writer.write_all(&[config.apple_tie_break.to_u8()])?;
+    writer.write_all(&[config.reversal_rule.to_u8()])?;
+    writer.write_all(&config.turn_interval.to_le_bytes())?;
+    writer.write_all(&[config.apple_hotspots.count])?;
+    writer.write_all(&config.apple_hotspots.radius.to_le_bytes())?;
+    writer.write_all(&config.apple_hotspots.drift_interval.to_le_bytes())?;
+    writer.write_all(&config.max_growth_per_tick.to_le_bytes())?;
+    writer.write_all(&config.max_snake_length.to_le_bytes())?;
+    writer.write_all(&[config.length_cap_apples as u8])?;
+    let respawn = config.apple_respawn;
+    writer.write_all(&respawn.delay.to_le_bytes())?;
+    writer.write_all(&respawn.max_per_tick.to_le_bytes())?;
+    let region = respawn.region.unwrap_or_default();
+    writer.write_all(&[respawn.region.is_some() as u8])?;
+    for value in [region.x, region.y, region.width, region.height] {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    let scaling = state.apple_scaling();
+    let AppleScaling { snakes_per_apple, cells_per_apple, min_apples, max_apples, every_ticks } = scaling.unwrap_or_default();
+    writer.write_all(&[scaling.is_some() as u8])?;
+    writer.write_all(&snakes_per_apple.to_le_bytes())?;
+    writer.write_all(&cells_per_apple.to_le_bytes())?;
+    for value in [min_apples as u64, max_apples as u64, every_ticks] {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+
+    write_rng(&state.rng, writer)?;
+    writer.write_all(&state.num_apples.to_le_bytes())?;
+    writer.write_all(&state.num_powerups.to_le_bytes())?;
+
+    let deadlines = state.apple_expiry.entries();
+    writer.write_all(&state.apple_expiry.now().to_le_bytes())?;
+    writer.write_all(&(deadlines.len() as u32).to_le_bytes())?;
+    for (position, deadline) in deadlines {
+        writer.write_all(&position.x.to_le_bytes())?;
+        writer.write_all(&position.y.to_le_bytes())?;
+        writer.write_all(&deadline.to_le_bytes())?;
+    }
+    let respawns = state.apple_respawns.entries();
+    writer.write_all(&state.apple_respawns.backlog().to_le_bytes())?;
+    writer.write_all(&(respawns.len() as u32).to_le_bytes())?;
+    for (due, count) in respawns {
+        writer.write_all(&due.to_le_bytes())?;
+        writer.write_all(&count.to_le_bytes())?;
+    }
+
+    write_population(&state.population, writer)?;
+
+    writer.write_all(&(state.snakes.len() as u32).to_le_bytes())?;
+    for snake in &state.snakes {
+        let snake = snake.snake();
+        writer.write_all(&snake.id.to_le_bytes())?;
+        writer.write_all(&[snake.direction.to_u8(), snake.is_alive as u8])?;
+        writer.write_all(&snake.pending_growth.to_le_bytes())?;
+        writer.write_all(&state.turn_ready(snake.id).to_le_bytes())?;
+        writer.write_all(&(snake.body.len() as u32).to_le_bytes())?;
+        for point in snake.segments() {
+            writer.write_all(&point.x.to_le_bytes())?;
+            writer.write_all(&point.y.to_le_bytes())?;
+        }
+    }
+    state.snake_meta.write_to(writer)?;
+    let board = state.leaderboard();
+    writer.write_all(&[board.is_some() as u8])?;
+    writer.write_all(&(board.map_or(0, Leaderboard::size) as u64).to_le_bytes())?;
+    let scores: Vec<Standing> = board.map(|board| board.scores().collect()).unwrap_or_default();
+    writer.write_all(&(scores.len() as u32).to_le_bytes())?;
+    for standing in scores {
+        writer.write_all(&standing.snake_id.to_le_bytes())?;
+        writer.write_all(&standing.score.to_le_bytes())?;
+    }
+
+    writer.write_all(&[codec.to_u8()])?;
+    encode_grid(&state.grid, codec, writer)
+}
+
+/// Read a snapshot written by [`write_snapshot`], returning its tick and the restored state
+pub fn read_snapshot<R: Read>(reader: &mut R) -> io::Result<(u64, GameState)> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != SNAPSHOT_MAGIC {
+        return Err(invalid_data("not a snapshot file"));
+    }
+    let version = u16::from_le_bytes(read_array(reader)?);
+    if !(1..=SNAPSHOT_VERSION).contains(&version) {
+        return Err(invalid_data("unsupported snapshot version"));
+    }
+    let tick = u64::from_le_bytes(read_array(reader)?);
+
+    let snake_capacity = u64::from_le_bytes(read_array(reader)?) as usize;
+    let apple_capacity = u64::from_le_bytes(read_array(reader)?) as usize;
+    let [attribute_kills, kill_growth_percent] = read_array(reader)?;
+    let rng_seed = u64::from_le_bytes(read_array(reader)?);
+    let mut config = GameConfig {
+        snake_capacity,
+        apple_capacity,
+        attribute_kills: attribute_kills != 0,
+        kill_growth_percent,
+        rng_seed,
+        ..GameConfig::default()
+    };
+    if version >= 2 {
+        config.split_powerups = u64::from_le_bytes(read_array(reader)?) as usize;
+        config.split_min_length = u64::from_le_bytes(read_array(reader)?) as usize;
+    }
+    if version >= 3 {
+        config.apple_ttl = u32::from_le_bytes(read_array(reader)?);
+    }
+    if version >= 6 {
+        config.apple_head_clearance = read_array::<_, 1>(reader)?[0];
+    }
+    if version >= 7 {
+        let [tie_break] = read_array(reader)?;
+        config.apple_tie_break = AppleTieBreak::from_u8(tie_break).ok_or_else(|| invalid_data("invalid apple tie-break"))?;
+    }
+    if version >= 8 {
+        let [rule] = read_array(reader)?;
+        config.reversal_rule = ReversalRule::from_u8(rule).ok_or_else(|| invalid_data("invalid reversal rule"))?;
+    }
+    if version >= 9 {
+        config.turn_interval = u32::from_le_bytes(read_array(reader)?);
+    }
+    if version >= 10 {
+        config.apple_hotspots = AppleHotspots {
+            count: read_array::<_, 1>(reader)?[0],
+            radius: u16::from_le_bytes(read_array(reader)?),
+            drift_interval: u32::from_le_bytes(read_array(reader)?),
+        };
+    }
+    if version >= 11 {
+        config.max_growth_per_tick = u32::from_le_bytes(read_array(reader)?);
+        config.max_snake_length = u32::from_le_bytes(read_array(reader)?);
+        config.length_cap_apples = read_array::<_, 1>(reader)?[0] != 0;
+    }
+    if version >= 12 {
+        let delay = u32::from_le_bytes(read_array(reader)?);
+        let max_per_tick = u32::from_le_bytes(read_array(reader)?);
+        let [has_region] = read_array(reader)?;
+        let mut rect = [0u16; 4];
+        for value in &mut rect {
+            *value = u16::from_le_bytes(read_array(reader)?);
+        }
+        let [x, y, width, height] = rect;
+        config.apple_respawn = AppleRespawn {
+            delay,
+            max_per_tick,
+            region: (has_region != 0).then_some(Rect::new(x, y, width, height)),
+        };
+    }
+    let mut apple_scaling = None;
+    if version >= 14 {
+        let [has_scaling] = read_array(reader)?;
+        let scaling = AppleScaling {
+            snakes_per_apple: u32::from_le_bytes(read_array(reader)?),
+            cells_per_apple: u32::from_le_bytes(read_array(reader)?),
+            min_apples: u64::from_le_bytes(read_array(reader)?) as usize,
+            max_apples: u64::from_le_bytes(read_array(reader)?) as usize,
+            every_ticks: u64::from_le_bytes(read_array(reader)?),
+        };
+        apple_scaling = (has_scaling != 0).then_some(scaling);
+    }
+
+    let rng = if version >= 5 {
+        read_rng(reader)?
+    } else {
+        RngStreams::new(u64::from_le_bytes(read_array(reader)?))
+    };
+    let num_apples = u64::from_le_bytes(read_array(reader)?);
+    let num_powerups = if version >= 2 { u64::from_le_bytes(read_array(reader)?) } else { 0 };
+
+    let mut expiry_now = 0;
+    let mut deadlines = Vec::new();
+    if version >= 3 {
+        expiry_now = u64::from_le_bytes(read_array(reader)?);
+        let count = u32::from_le_bytes(read_array(reader)?);
+        for _ in 0..count {
+            let x = u16::from_le_bytes(read_array(reader)?);
+            let y = u16::from_le_bytes(read_array(reader)?);
+            let deadline = u64::from_le_bytes(read_array(reader)?);
+            deadlines.push((Point { x, y }, deadline));
+        }
+    }
+    let mut respawn_backlog = 0;
+    let mut respawns = Vec::new();
+    if version >= 12 {
+        respawn_backlog = u64::from_le_bytes(read_array(reader)?);
+        let count = u32::from_le_bytes(read_array(reader)?);
+        for _ in 0..count {
+            let due = u64::from_le_bytes(read_array(reader)?);
+            respawns.push((due, u64::from_le_bytes(read_array(reader)?)));
+        }
+    }
+
+    let population = read_population(reader)?;
+
+    let snake_count = u32::from_le_bytes(read_array(reader)?);
+    config.validate().map_err(|error| invalid_data(&error.to_string()))?;
+    if snake_count as usize > MAX_RECORD_SNAKES {
+        return Err(invalid_data("more snakes than the engine can tick"));
+    }
+    let mut snakes = Vec::with_capacity((snake_count as usize).max(snake_capacity));
+    let mut turn_ready = Vec::new();
+    for _ in 0..snake_count {
+        let id = u32::from_le_bytes(read_array(reader)?);
+        let [direction, alive] = read_array(reader)?;
+        let direction = Direction::from_u8(direction).ok_or_else(|| invalid_data("invalid direction"))?;
+        let pending_growth = u32::from_le_bytes(read_array(reader)?);
+        if version >= 9 {
+            turn_ready.push((id, u64::from_le_bytes(read_array(reader)?)));
+        }
+        let len = u32::from_le_bytes(read_array(reader)?);
+        let mut body = Body::new();
+        for _ in 0..len {
+            let x = u16::from_le_bytes(read_array(reader)?);
+            let y = u16::from_le_bytes(read_array(reader)?);
+            body.push_back(Point { x, y });
+        }
+        snakes.push(GridAwareSnake::from_placed(Snake {
+            id,
+            body,
+            direction,
+            is_alive: alive != 0,
+            pending_growth,
+        }));
+    }
+    let snake_meta = if version >= 13 { SnakeMetaTable::read_from(reader)? } else { SnakeMetaTable::new() };
+    let mut leaderboard = None;
+    if version >= 15 {
+        let [has_board] = read_array(reader)?;
+        let size = u64::from_le_bytes(read_array(reader)?) as usize;
+        let count = u32::from_le_bytes(read_array(reader)?);
+        let mut scores = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let snake_id = u32::from_le_bytes(read_array(reader)?);
+            scores.push(Standing { snake_id, score: u64::from_le_bytes(read_array(reader)?) });
+        }
+        leaderboard = (has_board != 0).then(|| Leaderboard::restore(size, scores));
+    }
+
+    let codec = if version >= 4 { GridCodec::from_u8(read_array::<_, 1>(reader)?[0])? } else { GridCodec::Rle };
+    let grid = decode_grid(codec, reader)?;
+
+    let mut state = GameState::from_parts(config, snakes, num_apples, grid);
+    state.population = population;
+    state.num_powerups = num_powerups;
+    state.apple_expiry = AppleExpiry::restore(config.apple_ttl, expiry_now, deadlines);
+    state.apple_respawns = RespawnWheel::restore(tick, respawn_backlog, respawns);
+    state.rng = rng;
+    state.snake_meta = snake_meta;
+    state.set_apple_scaling(apple_scaling);
+    state.leaderboard = leaderboard;
+    for (id, ready) in turn_ready.into_iter().filter(|&(_, ready)| ready > 0) {
+        state.set_turn_ready(id, ready);
+    }
+    state.set_now(tick);
+    Ok((tick, state))
+}
+
+pub fn save_snapshot<P: AsRef<Path>>(state: &GameState, tick: u64, path: P) -> io::Result<()> {
+    save_snapshot_with(state, tick, GridCodec::default(), path)
+}
+
+pub fn save_snapshot_with<P: AsRef<Path>>(state: &GameState, tick: u64, codec: GridCodec, path: P) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_snapshot_with(state, tick, codec, &mut writer)?;
+    writer.flush()
+}
+
+pub fn load_snapshot<P: AsRef<Path>>(path: P) -> io::Result<(u64, GameState)> {
+    read_snapshot(&mut BufReader::new(File::open(path)?))
+}
+
+/// `seed:u64 tick:u64 stream_count:u8` then `stream_count` x `draws:u64`
+pub(crate) fn write_rng<W: Write>(rng: &RngStreams, writer: &mut W) -> io::Result<()> {
+    writer.write_all(&rng.seed().to_le_bytes())?;
+    writer.write_all(&rng.tick().to_le_bytes())?;
+    writer.write_all(&[Stream::COUNT as u8])?;
+    for draws in rng.draws() {
+        writer.write_all(&draws.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_rng<R: Read>(reader: &mut R) -> io::Result<RngStreams> {
+    let seed = u64::from_le_bytes(read_array(reader)?);
+    let rng_tick = u64::from_le_bytes(read_array(reader)?);
+    let [stream_count] = read_array(reader)?;
+    if stream_count as usize > Stream::COUNT {
+        return Err(invalid_data("snapshot has RNG streams this build does not know"));
+    }
+    // Streams added since the snapshot was written have drawn nothing yet
+    let mut draws = [0; Stream::COUNT];
+    for slot in &mut draws[..stream_count as usize] {
+        *slot = u64::from_le_bytes(read_array(reader)?);
+    }
+    Ok(RngStreams::from_parts(seed, rng_tick, draws))
+}
+
+/// `alive:u32 total_body_cells:u64 histogram:[u32; 8] births_this_tick:u32 deaths_this_tick:u32`
+/// `total_births:u64 total_deaths:u64`
+pub(crate) fn write_population<W: Write>(population: &PopulationMetrics, writer: &mut W) -> io::Result<()> {
+    writer.write_all(&population.alive.to_le_bytes())?;
+    writer.write_all(&population.total_body_cells.to_le_bytes())?;
+    for count in &population.length_histogram {
+        writer.write_all(&count.to_le_bytes())?;
+    }
+    writer.write_all(&population.births_this_tick.to_le_bytes())?;
+    writer.write_all(&population.deaths_this_tick.to_le_bytes())?;
+    writer.write_all(&population.total_births.to_le_bytes())?;
+    writer.write_all(&population.total_deaths.to_le_bytes())
+}
+
+pub(crate) fn read_population<R: Read>(reader: &mut R) -> io::Result<PopulationMetrics> {
+    let mut population = PopulationMetrics {
+        alive: u32::from_le_bytes(read_array(reader)?),
+        total_body_cells: u64::from_le_bytes(read_array(reader)?),
+        ..PopulationMetrics::default()
+    };
+    for bucket in 0..LENGTH_HISTOGRAM_BUCKETS {
+        population.length_histogram[bucket] = u32::from_le_bytes(read_array(reader)?);
+    }
+    population.births_this_tick = u32::from_le_bytes(read_array(reader)?);
+    population.deaths_this_tick = u32::from_le_bytes(read_array(reader)?);
+    population.total_births = u64::from_le_bytes(read_array(reader)?);
+    population.total_deaths = u64::from_le_bytes(read_array(reader)?);
+    Ok(population)
+}
+
+pub(crate) fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
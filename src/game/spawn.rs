@@ -0,0 +1,144 @@
+use crate::game::grid::{Cell, Grid};
+use crate::game::snake::Snake;
+use crate::game::types::{Direction, Point};
+use std::fmt;
+
+/// How a freshly spawned body is laid out, walking from the tail to the head
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyShape {
+    /// One straight line ending in the head's direction
+    Straight,
+    /// The tail half runs perpendicular, then the body turns into the head's direction
+    LShaped,
+    /// A square spiral wound outwards, the head on the outside. Occupies about
+    /// `sqrt(length)` x `sqrt(length)` cells instead of a long line.
+    Coiled,
+}
+
+/// Initial body of spawned snakes, shared by the random and deterministic generators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpawnConfig {
+    /// Body cells including the head (at least 1)
+    pub initial_length: usize,
+    pub shape: BodyShape,
+}
+
+impl Default for SpawnConfig {
+    /// Straight 4-cell bodies, as the random spawners always produced
+    fn default() -> Self {
+        Self {
+            initial_length: 4,
+            shape: BodyShape::Straight,
+        }
+    }
+}
+
+/// How `GameState::spawn_snake` places a snake joining a running game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JoinConfig {
+    pub body: SpawnConfig,
+    /// Steps (wrapped Manhattan) kept between every new body cell and any other snake's cell
+    pub clearance: u16,
+    /// Random spots tried before giving up with [`SpawnError::NoRoom`]
+    pub attempts: u32,
+}
+
+impl Default for JoinConfig {
+    fn default() -> Self {
+        Self {
+            body: SpawnConfig::default(),
+            clearance: 8,
+            attempts: 100,
+        }
+    }
+}
+
+/// Why `GameState::spawn_snake` placed nothing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnError {
+    /// No free id and `config.snake_capacity` snakes already registered
+    Full,
+    /// No spot tried had room for the body and its clearance
+    NoRoom,
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpawnError::Full => f.write_str("every snake slot is in use"),
+            SpawnError::NoRoom => f.write_str("no room for the snake"),
+        }
+    }
+}
+
+impl std::error::Error for SpawnError {}
+
+impl SpawnConfig {
+    /// Body cells starting at `tail`, tail first, for a snake that will move in `direction`.
+    /// Wraps around the grid edges like movement does.
+    pub fn body_cells(&self, tail: Point, direction: Direction) -> Vec<Point> {
+        let mut cells = Vec::with_capacity(self.initial_length.max(1));
+        cells.push(tail);
+        for step in self.steps(direction) {
+            let next = cells[cells.len() - 1].step(step);
+            cells.push(next);
+        }
+        cells
+    }
+
+    /// A snake with this body if every cell is empty on `grid`. Nothing is written to the grid.
+    pub fn try_place(&self, grid: &Grid, id: u32, tail: Point, direction: Direction) -> Option<Snake> {
+        let cells = self.body_cells(tail, direction);
+        if cells.iter().any(|cell| grid.get_cell(cell) != Cell::Empty) {
+            return None;
+        }
+        let mut snake = Snake::new(id, tail, direction);
+        snake.body = cells.into_iter().collect();
+        Some(snake)
+    }
+
+    // Directions of the `initial_length - 1` moves from tail to head; the last one is `direction`
+    fn steps(&self, direction: Direction) -> Vec<Direction> {
+        let moves = self.initial_length.saturating_sub(1);
+        match self.shape {
+            BodyShape::Straight => vec![direction; moves],
+            BodyShape::LShaped => {
+                // Approach from the left of `direction` so the turn into it is clockwise
+                let side = direction.turn_right().turn_right().turn_right();
+                let leg = moves / 2;
+                let mut steps = vec![side; leg];
+                steps.resize(moves, direction);
+                steps
+            }
+            BodyShape::Coiled => {
+                // Runs of 1, 1, 2, 2, 3, 3, ... turning clockwise each run
+                let mut steps = Vec::with_capacity(moves);
+                let mut heading = Direction::Up;
+                let mut run = 1;
+                while steps.len() < moves {
+                    for _ in 0..2 {
+                        let take = run.min(moves - steps.len());
+                        steps.extend(std::iter::repeat_n(heading, take));
+                        heading = heading.turn_right();
+                    }
+                    run += 1;
+                }
+                // Rotate the spiral so the head leaves along `direction`
+                if let Some(&last) = steps.last() {
+                    let mut turns = 0;
+                    let mut rotated = last;
+                    while rotated != direction {
+                        rotated = rotated.turn_right();
+                        turns += 1;
+                    }
+                    for step in &mut steps {
+                        for _ in 0..turns {
+                            *step = step.turn_right();
+                        }
+                    }
+                }
+                steps
+            }
+        }
+    }
+}
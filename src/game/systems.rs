@@ -0,0 +1,167 @@
+//! Custom per-tick mechanics without touching `tick()`.
+//!
+//! A [`System`] runs once per tick after the core phases (inputs, movement, collisions,
+//! tail clearing, splits and apple replacement) and before the clock advances, with the
+//! whole state and the tick's [`TickReport`] in hand. The state's [`SystemRegistry`]
+//! runs its systems in order; it starts out with the built-ins that top up power-ups and
+//! expire apples, and [`ShrinkingZone`] is there to add.
+//!
+//! Systems draw randomness from the engine's streams (`state.rng`) to stay reproducible.
+//! They are not part of snapshots: a restored state starts with the built-ins.
+
+use crate::game::engine::GameState;
+use crate::game::grid::{GRID_HEIGHT, GRID_WIDTH};
+use crate::game::report::TickReport;
+use crate::game::types::Rect;
+
+/// A mechanic run every tick, see the module docs
+pub trait System: Send + Sync {
+    /// Name to find the system by in its registry
+    fn name(&self) -> &'static str;
+
+    fn run(&mut self, state: &mut GameState, report: &mut TickReport);
+}
+
+/// Systems in the order they run
+#[derive(Default)]
+pub struct SystemRegistry {
+    systems: Vec<Box<dyn System>>,
+}
+
+impl SystemRegistry {
+    /// What every state starts with: [`PowerUps`], then [`AppleDecay`]
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        registry.push(PowerUps);
+        registry.push(AppleDecay);
+        registry
+    }
+
+    /// Run `system` after the others
+    pub fn push<S: System + 'static>(&mut self, system: S) {
+        self.systems.push(Box::new(system));
+    }
+
+    /// Run `system` right before the one named `before`, or last if there is none
+    pub fn insert_before<S: System + 'static>(&mut self, before: &str, system: S) {
+        let index = self.position(before).unwrap_or(self.systems.len());
+        self.systems.insert(index, Box::new(system));
+    }
+
+    /// Take out the system named `name`
+    pub fn remove(&mut self, name: &str) -> Option<Box<dyn System>> {
+        self.position(name).map(|index| self.systems.remove(index))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// Names in running order
+    pub fn names(&self) -> Vec<&'static str> {
+        self.systems.iter().map(|system| system.name()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.systems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+
+    /// Run every system in order
+    pub fn run(&mut self, state: &mut GameState, report: &mut TickReport) {
+        for system in &mut self.systems {
+            system.run(state, report);
+        }
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.systems.iter().position(|system| system.name() == name)
+    }
+}
+
+/// Tops split power-ups up to `config.split_powerups` at random empty cells
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PowerUps;
+
+impl System for PowerUps {
+    fn name(&self) -> &'static str {
+        "powerups"
+    }
+
+    fn run(&mut self, state: &mut GameState, report: &mut TickReport) {
+        report.powerups_spawned += state.spawn_powerups();
+    }
+}
+
+/// Moves apples whose `config.apple_ttl` ran out to random empty cells
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AppleDecay;
+
+impl System for AppleDecay {
+    fn name(&self) -> &'static str {
+        "apple_decay"
+    }
+
+    fn run(&mut self, state: &mut GameState, report: &mut TickReport) {
+        report.apples_expired += state.expire_apples();
+    }
+}
+
+/// A safe area closing in: every `every_ticks` ticks it loses a cell on each side, down
+/// to `min_size` cells across, and any snake whose head is outside it dies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShrinkingZone {
+    area: Rect,
+    every_ticks: u64,
+    min_size: u16,
+}
+
+impl ShrinkingZone {
+    pub fn new(area: Rect, every_ticks: u64, min_size: u16) -> Self {
+        Self {
+            area,
+            every_ticks: every_ticks.max(1),
+            min_size,
+        }
+    }
+
+    /// The safe area now
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn shrink(&mut self) {
+        if self.area.width >= self.min_size.saturating_add(2) {
+            self.area.x = ((self.area.x as usize + 1) % GRID_WIDTH) as u16;
+            self.area.width -= 2;
+        }
+        if self.area.height >= self.min_size.saturating_add(2) {
+            self.area.y = ((self.area.y as usize + 1) % GRID_HEIGHT) as u16;
+            self.area.height -= 2;
+        }
+    }
+}
+
+impl System for ShrinkingZone {
+    fn name(&self) -> &'static str {
+        "zone"
+    }
+
+    fn run(&mut self, state: &mut GameState, report: &mut TickReport) {
+        if report.tick > 0 && report.tick.is_multiple_of(self.every_ticks) {
+            self.shrink();
+        }
+        for id in 0..state.snakes.len() as u32 {
+            let snake = &state.snakes[id as usize];
+            if snake.is_alive()
+                && snake.head().is_some_and(|head| !self.area.contains(head))
+                && state.kill_snake(id)
+            {
+                report.deaths.push(id);
+            }
+        }
+    }
+}
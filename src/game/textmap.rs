@@ -0,0 +1,211 @@
+//! Hand-drawn maps: a text picture of part of the grid, read into a [`Scenario`].
+//!
+//! Meant for collision setups in tests and bug reports, where placing every segment
+//! with Rust code or `snake` lines is tedious. The picture uses the characters of
+//! [`GameState::debug_dump`](crate::game::engine::GameState::debug_dump) where it can:
+//!
+//! ```text
+//! # comments and settings come before the map
+//! origin 500,500            (optional: grid cell of the picture's top-left, default 0,0)
+//! ticks 5                   (optional, default 0)
+//! input 2 A left            (optional: tick, snake id or head letter, direction)
+//! map
+//! ..........
+//! .aaA..<...
+//! ....*..+..
+//! .XXXXXXXX.
+//! ```
+//!
+//! - `.` or space: empty; `*`: apple; `+`: split power-up
+//! - `^` `v` `<` `>`: a one-cell snake heading that way
+//! - `A`-`Z`: the head of snake 0-25, `a`-`z` its body, which must run as a single chain
+//!   of neighbouring cells from the head. It heads away from the segment behind its head.
+//! - `X`: wall. The grid has no wall cells, so walls are the corpse of one dead snake:
+//!   they block like any body until `respawn_dead` or a split fragment takes its id.
+//!
+//! Lettered snakes keep their letter's id, with despawned snakes filling any gaps.
+//! Arrow snakes follow in reading order, then the walls.
+
+use crate::game::config::GameConfig;
+use crate::game::events::EventSchedule;
+use crate::game::grid::{GRID_HEIGHT, GRID_WIDTH};
+use crate::game::rng::RngStreams;
+use crate::game::scenario::{Scenario, ScenarioSnake, invalid_data, parse, parse_point};
+use crate::game::types::{Direction, Input, Point};
+use crate::input::replay::Replay;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+impl Scenario {
+    /// Read a hand-drawn map (see [`crate::game::textmap`])
+    pub fn from_map(text: &str) -> io::Result<Self> {
+        let mut origin = Point { x: 0, y: 0 };
+        let mut ticks = 0;
+        let mut inputs: Vec<(u64, Input)> = Vec::new();
+        let mut lines = text.lines().enumerate();
+        for (number, line) in lines.by_ref() {
+            let error = |message: &str| invalid_data(format!("line {}: {}", number + 1, message));
+            let words: Vec<&str> = line.split('#').next().unwrap_or_default().split_whitespace().collect();
+            match words.as_slice() {
+                [] => continue,
+                ["map"] => break,
+                ["origin", point] => origin = parse_point(point).ok_or_else(|| error("bad origin"))?,
+                ["ticks", count] => ticks = parse(count).ok_or_else(|| error("bad ticks"))?,
+                ["input", tick, snake, direction] => inputs.push((
+                    parse(tick).ok_or_else(|| error("bad input tick"))?,
+                    Input {
+                        snake_id: parse(snake).or_else(|| letter_id(snake)).ok_or_else(|| error("bad snake"))?,
+                        direction: Direction::from_name(direction).ok_or_else(|| error("bad direction"))?,
+                    },
+                )),
+                _ => return Err(error("unrecognised line")),
+            }
+        }
+
+        let first_row = lines.clone().next().map_or(0, |(number, _)| number);
+        let rows: Vec<Vec<char>> = lines.map(|(_, line)| line.chars().collect()).collect();
+        if rows.len() > GRID_HEIGHT || rows.iter().any(|row| row.len() > GRID_WIDTH) {
+            return Err(invalid_data("map larger than the grid".to_string()));
+        }
+        let at = |x: usize, y: usize| rows.get(y).and_then(|row| row.get(x)).copied().unwrap_or('.');
+        let place = |x: usize, y: usize| Point {
+            x: ((origin.x as usize + x) % GRID_WIDTH) as u16,
+            y: ((origin.y as usize + y) % GRID_HEIGHT) as u16,
+        };
+
+        let mut lettered: Vec<Option<ScenarioSnake>> = Vec::new();
+        let mut arrows = Vec::new();
+        let mut walls = Vec::new();
+        let mut apples = Vec::new();
+        let mut powerups = Vec::new();
+        let mut body_cells = [0usize; 26];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &symbol) in row.iter().enumerate() {
+                let error = |message: String| invalid_data(format!("line {}: {}", first_row + y + 1, message));
+                match symbol {
+                    '.' | ' ' => {}
+                    '*' => apples.push(place(x, y)),
+                    '+' => powerups.push(place(x, y)),
+                    'X' => walls.push(place(x, y)),
+                    '^' | 'v' | '<' | '>' => arrows.push(ScenarioSnake {
+                        body: vec![place(x, y)],
+                        direction: arrow_direction(symbol),
+                        alive: true,
+                        pending_growth: 0,
+                    }),
+                    'a'..='z' => body_cells[(symbol as u8 - b'a') as usize] += 1,
+                    'A'..='Z' => {
+                        let id = (symbol as u8 - b'A') as usize;
+                        if lettered.len() <= id {
+                            lettered.resize(id + 1, None);
+                        }
+                        if lettered[id].is_some() {
+                            return Err(error(format!("second head {}", symbol)));
+                        }
+                        let (cells, direction) = trace_body(&at, x, y).map_err(error)?;
+                        lettered[id] = Some(ScenarioSnake {
+                            body: cells.into_iter().rev().map(|(x, y)| place(x, y)).collect(),
+                            direction,
+                            alive: true,
+                            pending_growth: 0,
+                        });
+                    }
+                    _ => return Err(error(format!("unknown map character {:?}", symbol))),
+                }
+            }
+        }
+        for (letter, &count) in body_cells.iter().enumerate() {
+            let traced = lettered.get(letter).and_then(Option::as_ref).map_or(0, |snake| snake.body.len() - 1);
+            if count != traced {
+                let name = (b'a' + letter as u8) as char;
+                return Err(invalid_data(format!("{} body cells of {} not connected to its head", count - traced, name)));
+            }
+        }
+
+        let despawned = ScenarioSnake { body: Vec::new(), direction: Direction::Up, alive: false, pending_growth: 0 };
+        let mut snakes: Vec<ScenarioSnake> = lettered.into_iter().map(|snake| snake.unwrap_or_else(|| despawned.clone())).collect();
+        snakes.extend(arrows);
+        if !walls.is_empty() {
+            snakes.push(ScenarioSnake { body: walls, alive: false, ..despawned });
+        }
+        if let Some((_, input)) = inputs.iter().find(|(_, input)| input.snake_id as usize >= snakes.len()) {
+            return Err(invalid_data(format!("input for unknown snake {}", input.snake_id)));
+        }
+        inputs.sort_by_key(|(tick, _)| *tick);
+
+        let config = GameConfig {
+            snake_capacity: snakes.len(),
+            apple_capacity: apples.len(),
+            ..GameConfig::default()
+        };
+        config.validate().map_err(|error| invalid_data(error.to_string()))?;
+        Ok(Self {
+            config,
+            rng: RngStreams::new(config.rng_seed),
+            snakes,
+            apples,
+            powerups,
+            replay: Replay::from_steps(inputs),
+            events: EventSchedule::default(),
+            ticks,
+            bots: None,
+        })
+    }
+
+    pub fn load_map<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_map(&fs::read_to_string(path)?)
+    }
+}
+
+// `A` is snake 0, up to `Z`
+fn letter_id(word: &str) -> Option<u32> {
+    match word.as_bytes() {
+        [letter @ b'A'..=b'Z'] => Some((letter - b'A') as u32),
+        _ => None,
+    }
+}
+
+fn arrow_direction(symbol: char) -> Direction {
+    match symbol {
+        '^' => Direction::Up,
+        'v' => Direction::Down,
+        '<' => Direction::Left,
+        _ => Direction::Right,
+    }
+}
+
+/// Cells of the snake whose head is at `(x, y)`, head first, and its direction. Each
+/// step goes to the one unvisited neighbour holding the head's lowercase letter.
+fn trace_body<F: Fn(usize, usize) -> char>(at: &F, x: usize, y: usize) -> Result<(Vec<(usize, usize)>, Direction), String> {
+    let head = at(x, y);
+    let body = head.to_ascii_lowercase();
+    let mut cells = vec![(x, y)];
+    loop {
+        let (x, y) = cells[cells.len() - 1];
+        let around = [Some((x + 1, y)), Some((x, y + 1)), x.checked_sub(1).map(|x| (x, y)), y.checked_sub(1).map(|y| (x, y))];
+        let neighbours: Vec<(usize, usize)> = around
+            .into_iter()
+            .flatten()
+            .filter(|&(x, y)| at(x, y) == body && !cells.contains(&(x, y)))
+            .collect();
+        match neighbours.as_slice() {
+            [] => break,
+            [cell] => cells.push(*cell),
+            _ => return Err(format!("body of {} branches at column {}", head, x + 1)),
+        }
+    }
+    let Some(&(neck_x, neck_y)) = cells.get(1) else {
+        return Err(format!("{} has no body to tell its direction; draw a one-cell snake as an arrow", head));
+    };
+    let direction = if neck_x < x {
+        Direction::Right
+    } else if neck_x > x {
+        Direction::Left
+    } else if neck_y < y {
+        Direction::Down
+    } else {
+        Direction::Up
+    };
+    Ok((cells, direction))
+}
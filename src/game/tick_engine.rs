@@ -0,0 +1,57 @@
+//! Interchangeable ways of running a tick.
+//!
+//! [`DirectReads`] and [`GatheredReads`] produce the same states from the same inputs
+//! and differ only in how phase 3 reads the grid; [`Legacy`] is the pre cache-aware loop,
+//! kept as a baseline. Benches run them head to head on one state and input stream (see
+//! `cell_reads` in game_bench).
+
+use crate::game::engine::GameState;
+use crate::game::types::Input;
+
+/// One way of advancing a [`GameState`] by a tick
+pub trait TickEngine {
+    /// Label used in bench ids
+    const NAME: &'static str;
+
+    /// Switch `state` into this engine's mode. Call once before ticking.
+    fn prepare(state: &mut GameState);
+
+    fn tick(state: &mut GameState, inputs: &[Input]) {
+        state.tick(inputs);
+    }
+}
+
+/// `tick` reading each record's cell right before processing it (the default)
+pub struct DirectReads;
+
+impl TickEngine for DirectReads {
+    const NAME: &'static str = "direct";
+
+    fn prepare(state: &mut GameState) {
+        state.set_gather_reads(false);
+    }
+}
+
+/// `tick` gathering a bucket's cells into a scratch array before processing its records
+pub struct GatheredReads;
+
+impl TickEngine for GatheredReads {
+    const NAME: &'static str = "gathered";
+
+    fn prepare(state: &mut GameState) {
+        state.set_gather_reads(true);
+    }
+}
+
+/// The pre cache-aware loop, `tick_legacy`
+pub struct Legacy;
+
+impl TickEngine for Legacy {
+    const NAME: &'static str = "legacy";
+
+    fn prepare(_state: &mut GameState) {}
+
+    fn tick(state: &mut GameState, inputs: &[Input]) {
+        state.tick_legacy(inputs);
+    }
+}
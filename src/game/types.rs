@@ -8,6 +8,55 @@ pub struct Point {
     pub y: u16,
 }
 
+impl Point {
+    /// The neighbouring cell in `direction`, wrapping around the grid edges
+    pub fn step(self, direction: Direction) -> Point {
+        match direction {
+            Direction::Up => Point { x: self.x, y: ((self.y as usize + GRID_HEIGHT - 1) % GRID_HEIGHT) as u16 },
+            Direction::Down => Point { x: self.x, y: ((self.y as usize + 1) % GRID_HEIGHT) as u16 },
+            Direction::Left => Point { x: ((self.x as usize + GRID_WIDTH - 1) % GRID_WIDTH) as u16, y: self.y },
+            Direction::Right => Point { x: ((self.x as usize + 1) % GRID_WIDTH) as u16, y: self.y },
+        }
+    }
+}
+
+/// Rectangular region of the grid. Like snake movement it wraps around the grid edges,
+/// so a rect starting near the right edge continues at column 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub const fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// The square reaching `radius` cells from `center` in every direction
+    pub fn around(center: Point, radius: u16) -> Self {
+        let x = (center.x as usize + GRID_WIDTH - radius as usize % GRID_WIDTH) % GRID_WIDTH;
+        let y = (center.y as usize + GRID_HEIGHT - radius as usize % GRID_HEIGHT) % GRID_HEIGHT;
+        Self::new(x as u16, y as u16, radius * 2 + 1, radius * 2 + 1)
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        let dx = (point.x as usize + GRID_WIDTH - self.x as usize) % GRID_WIDTH;
+        let dy = (point.y as usize + GRID_HEIGHT - self.y as usize) % GRID_HEIGHT;
+        dx < self.width as usize && dy < self.height as usize
+    }
+
+    /// The point at offset `(dx, dy)` from the top-left corner, wrapped onto the grid
+    pub fn at(&self, dx: u16, dy: u16) -> Point {
+        Point {
+            x: ((self.x as usize + dx as usize) % GRID_WIDTH) as u16,
+            y: ((self.y as usize + dy as usize) % GRID_HEIGHT) as u16,
+        }
+    }
+}
+
 impl Distribution<Point> for StandardUniform {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Point {
         Point {
@@ -26,6 +75,66 @@ pub enum Direction {
     Right,
 }
 
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    /// Compact encoding used by replays and other on-disk formats
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+        }
+    }
+
+    /// The direction a quarter turn clockwise
+    pub fn turn_right(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// The direction a snake may never turn to directly
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// Lowercase name used by text formats ("up", "down", "left", "right")
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
+    }
+
+    /// Inverse of [`Direction::name`]
+    pub fn from_name(name: &str) -> Option<Self> {
+        (0..4).filter_map(Direction::from_u8).find(|direction| direction.name() == name)
+    }
+
+    /// Inverse of [`Direction::to_u8`]
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Direction::Up),
+            1 => Some(Direction::Down),
+            2 => Some(Direction::Left),
+            3 => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
 impl Distribution<Direction> for StandardUniform {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Direction {
         match rng.random_range(0..4) {
@@ -37,8 +146,31 @@ impl Distribution<Direction> for StandardUniform {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Input {
     pub snake_id: u32,
     pub direction: Direction,
 }
+
+/// An input with the stamp its producer gave it, for ticks fed by several sources at once
+/// (bots, network, ...) whose arrival order says nothing about when inputs were made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StampedInput {
+    pub input: Input,
+    /// Producer clock or sequence number; lower applies first
+    pub seq: u64,
+    /// Id of the producing source, breaking `seq` ties: lower applies first
+    pub source: u16,
+}
+
+impl StampedInput {
+    /// Put `batch` in application order: by `seq`, then `source`, then direction encoding
+    /// (only reached for duplicate stamps), per snake. Inputs of different snakes never
+    /// interact, so they are just grouped by snake id. The order never depends on
+    /// arrival, so replaying the sorted batch reproduces the tick.
+    pub fn sort_batch(batch: &mut [StampedInput]) {
+        batch.sort_unstable_by_key(|stamped| {
+            (stamped.input.snake_id, stamped.seq, stamped.source, stamped.input.direction.to_u8())
+        });
+    }
+}
@@ -0,0 +1,203 @@
+use crate::game::config::GameConfig;
+use crate::game::engine::GameState;
+use crate::game::grid::{Cell, GRID_HEIGHT, GRID_WIDTH, Grid};
+use crate::game::leaderboard::Leaderboard;
+use crate::game::metrics::PopulationMetrics;
+use crate::game::snake::GridAwareSnake;
+use crate::game::types::{Point, Rect};
+
+/// Read-only borrow of a [`GameState`] for bots, metrics and renderers.
+///
+/// Only reads are reachable through it, so code handed a view cannot change the world it
+/// observes, and it is `Copy + Send + Sync`: several threads can read one state at once
+/// (between ticks, or from a published copy).
+#[derive(Clone, Copy)]
+pub struct GameStateView<'a> {
+    state: &'a GameState,
+}
+
+impl<'a> GameStateView<'a> {
+    pub fn new(state: &'a GameState) -> Self {
+        Self { state }
+    }
+
+    #[inline(always)]
+    pub fn cell(&self, point: &Point) -> Cell {
+        self.state.grid.get_cell(point)
+    }
+
+    pub fn grid(&self) -> &'a Grid {
+        &self.state.grid
+    }
+
+    pub fn config(&self) -> &'a GameConfig {
+        &self.state.config
+    }
+
+    /// Every snake slot, dead ones included, indexed by id
+    pub fn snakes(&self) -> &'a [GridAwareSnake] {
+        &self.state.snakes
+    }
+
+    pub fn snake(&self, snake_id: u32) -> Option<&'a GridAwareSnake> {
+        self.state.snakes.get(snake_id as usize)
+    }
+
+    /// Head of `snake_id` if it is alive
+    pub fn head(&self, snake_id: u32) -> Option<Point> {
+        self.snake(snake_id).filter(|snake| snake.is_alive()).and_then(|snake| snake.head().copied())
+    }
+
+    pub fn alive_snakes(&self) -> impl Iterator<Item = &'a GridAwareSnake> + 'a {
+        self.state.snakes.iter().filter(|snake| snake.is_alive())
+    }
+
+    pub fn num_apples(&self) -> u64 {
+        self.state.num_apples
+    }
+
+    pub fn num_powerups(&self) -> u64 {
+        self.state.num_powerups
+    }
+
+    pub fn population(&self) -> &'a PopulationMetrics {
+        &self.state.population
+    }
+
+    /// See [`GameState::set_leaderboard`]
+    pub fn leaderboard(&self) -> Option<&'a Leaderboard> {
+        self.state.leaderboard()
+    }
+
+    /// See [`GameState::now`]
+    pub fn now(&self) -> u64 {
+        self.state.now()
+    }
+
+    /// See [`GameState::nearest_apple`]
+    pub fn nearest_apple(&self, from: Point, max_radius: u16) -> Option<Point> {
+        self.state.nearest_apple(from, max_radius)
+    }
+
+    /// See [`GameState::nearest_apples`]
+    pub fn nearest_apples(&self, from: &[Point], max_radius: u16, out: &mut Vec<Option<Point>>) {
+        self.state.nearest_apples(from, max_radius, out);
+    }
+
+    /// See [`GameState::extract_view_tensor`]
+    pub fn extract_view_tensor(&self, center: Point, radius: u16, viewer: u32, out: &mut [u8]) {
+        self.state.extract_view_tensor(center, radius, viewer, out);
+    }
+
+    /// See [`GameState::heads_in_rect`]
+    pub fn heads_in_rect(&self, rect: Rect) -> impl Iterator<Item = (u32, Point)> + 'a {
+        self.state.heads_in_rect(rect)
+    }
+}
+
+impl<'a> From<&'a GameState> for GameStateView<'a> {
+    fn from(state: &'a GameState) -> Self {
+        Self::new(state)
+    }
+}
+
+/// Channels of a view tensor, in order
+pub const VIEW_CHANNELS: usize = 4;
+/// Segments of the viewing snake
+pub const CHANNEL_SELF: usize = 0;
+/// Segments (and corpses) of every other snake
+pub const CHANNEL_OTHER: usize = 1;
+pub const CHANNEL_APPLE: usize = 2;
+/// Impassable cells. The grid wraps so there are none yet; kept so the layout is stable.
+pub const CHANNEL_WALL: usize = 3;
+
+/// Bytes [`GameState::extract_view_tensor`] writes for `radius`
+pub const fn view_tensor_len(radius: u16) -> usize {
+    let side = radius as usize * 2 + 1;
+    VIEW_CHANNELS * side * side
+}
+
+impl GameState {
+    /// Read-only view of this state (see [`GameStateView`])
+    pub fn view(&self) -> GameStateView<'_> {
+        GameStateView::new(self)
+    }
+
+    /// Closest apple to `from` within `max_radius` steps (wrapped Manhattan distance), by
+    /// an outward ring search of the grid (see [`Grid::nearest`])
+    pub fn nearest_apple(&self, from: Point, max_radius: u16) -> Option<Point> {
+        self.grid.nearest(from, max_radius, Cell::Apple)
+    }
+
+    /// [`GameState::nearest_apple`] for every point of `from`, written to `out` in the same
+    /// order. Queries run in row order so neighbouring heads share the rows they read.
+    pub fn nearest_apples(&self, from: &[Point], max_radius: u16, out: &mut Vec<Option<Point>>) {
+        out.clear();
+        out.resize(from.len(), None);
+        let mut order: Vec<usize> = (0..from.len()).collect();
+        order.sort_unstable_by_key(|&i| (from[i].y, from[i].x));
+        for i in order {
+            out[i] = self.nearest_apple(from[i], max_radius);
+        }
+    }
+
+    /// Live snakes with their head inside `rect` (which may wrap), as `(id, head)` in no
+    /// particular order. Only visits the head index blocks `rect` overlaps (see
+    /// [`crate::game::heads`]), not every snake.
+    pub fn heads_in_rect(&self, rect: Rect) -> impl Iterator<Item = (u32, Point)> + '_ {
+        self.heads.candidates(rect).filter_map(move |id| {
+            let snake = self.snakes.get(id as usize)?;
+            let head = *snake.head()?;
+            (snake.is_alive() && rect.contains(&head)).then_some((id, head))
+        })
+    }
+
+    /// Write the `2 * radius + 1` square around `center` into `out` as channel-major planes
+    /// (`[channel][dy][dx]`, see the `CHANNEL_*` constants) of 0/1 bytes. Segments of
+    /// snake `viewer` go to the self channel; pass an id past the end for none.
+    ///
+    /// Reads each grid row as at most two contiguous runs (the view may wrap) and never
+    /// allocates. Split power-ups have no channel. Panics if `out` is shorter than
+    /// [`view_tensor_len`] or the view is wider than the grid.
+    pub fn extract_view_tensor(&self, center: Point, radius: u16, viewer: u32, out: &mut [u8]) {
+        let side = radius as usize * 2 + 1;
+        let plane = side * side;
+        let out = &mut out[..VIEW_CHANNELS * plane];
+        out.fill(0);
+
+        let rect = Rect::around(center, radius);
+        // Columns up to the right edge, then the wrapped remainder from column 0
+        let first = side.min(GRID_WIDTH - rect.x as usize);
+        for dy in 0..side {
+            let row = self.grid.row((rect.y as usize + dy) % GRID_HEIGHT);
+            let start = rect.x as usize;
+            let runs = [&row[start..start + first], &row[..side - first]];
+            for (dx, cell) in runs.into_iter().flatten().enumerate() {
+                let channel = match cell {
+                    Cell::Snake => CHANNEL_OTHER,
+                    Cell::Apple => CHANNEL_APPLE,
+                    Cell::Empty | Cell::Split => continue,
+                };
+                out[channel * plane + dy * side + dx] = 1;
+            }
+        }
+
+        // Move the viewer's own segments from the other channel to the self channel
+        let Some(snake) = self.snakes.get(viewer as usize) else {
+            return;
+        };
+        for point in snake.segments() {
+            if !rect.contains(&point) {
+                continue;
+            }
+            let dx = (point.x as usize + GRID_WIDTH - rect.x as usize) % GRID_WIDTH;
+            let dy = (point.y as usize + GRID_HEIGHT - rect.y as usize) % GRID_HEIGHT;
+            let offset = dy * side + dx;
+            // Only where the grid agrees: a dead snake's cells may have been reused
+            if out[CHANNEL_OTHER * plane + offset] == 1 {
+                out[CHANNEL_OTHER * plane + offset] = 0;
+                out[CHANNEL_SELF * plane + offset] = 1;
+            }
+        }
+    }
+}
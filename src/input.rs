@@ -0,0 +1,8 @@
+#[deny(clippy::float_arithmetic)]
+pub mod bot;
+pub mod load;
+pub mod replay;
+pub mod scheduler;
+pub mod script;
+pub mod source;
+pub mod tap;
@@ -0,0 +1,251 @@
+use crate::game::view::GameStateView;
+use crate::game::fixedpoint::{FRAC_BITS, Fixed};
+use crate::game::grid::{Cell, GRID_HEIGHT, GRID_WIDTH};
+use crate::game::snake::GridAwareSnake;
+use crate::game::types::{Direction, Input, Point, Rect};
+use crate::input::source::InputSource;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+/// Steps (Manhattan distance) a greedy bot looks for apples
+pub const BOT_SIGHT: u16 = 16;
+
+// Added to a move's score when an enemy head may enter the same cell
+const DANGER_PENALTY: u32 = 1 << 20;
+
+/// Random-walker bots: every tick, each controlled snake turns with probability
+/// `turn_probability` (fixed point, `Fixed::ONE` = always) to a uniformly random direction.
+pub struct RandomBot {
+    snake_ids: std::ops::Range<u32>,
+    // Snakes taken over after construction, e.g. split fragments
+    adopted: Vec<u32>,
+    turn_probability: Fixed,
+    rng: StdRng,
+}
+
+impl RandomBot {
+    /// Drive the snakes in `snake_ids` with a seeded RNG, so runs are reproducible.
+    pub fn new(snake_ids: std::ops::Range<u32>, turn_probability: Fixed, seed: u64) -> Self {
+        Self {
+            snake_ids,
+            adopted: Vec::new(),
+            turn_probability,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Also drive `snake_id` from now on; feed it the fragments of `GameState::splits`
+    /// to keep them moving. Ids already driven are ignored.
+    pub fn adopt(&mut self, snake_id: u32) {
+        if !self.snake_ids.contains(&snake_id) && !self.adopted.contains(&snake_id) {
+            self.adopted.push(snake_id);
+        }
+    }
+}
+
+impl InputSource for RandomBot {
+    fn next_batch(&mut self, _tick: u64, buf: &mut Vec<Input>) {
+        for snake_id in self.snake_ids.clone().chain(self.adopted.iter().copied()) {
+            if let Some(direction) = random_turn(&mut self.rng, self.turn_probability) {
+                buf.push(Input { snake_id, direction });
+            }
+        }
+    }
+}
+
+/// Bot behaviours, weakest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    /// Turns at random like [`RandomBot`]
+    Random,
+    /// Steers towards the nearest apple within [`BOT_SIGHT`] and never into an occupied cell
+    Greedy,
+    /// Greedy, but also avoids cells an enemy head could enter on the same tick
+    Lookahead,
+}
+
+impl Difficulty {
+    /// Lowercase name used by text formats
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Random => "random",
+            Difficulty::Greedy => "greedy",
+            Difficulty::Lookahead => "lookahead",
+        }
+    }
+}
+
+/// Relative weights of the difficulty tiers. Snake ids are dealt out round-robin, so with
+/// weights 2/1/1 ids 0 and 1 are random, 2 greedy, 3 lookahead, 4 random again, ...
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DifficultyMix {
+    pub random: u32,
+    pub greedy: u32,
+    pub lookahead: u32,
+}
+
+impl DifficultyMix {
+    /// Every snake on the same tier
+    pub const fn only(difficulty: Difficulty) -> Self {
+        match difficulty {
+            Difficulty::Random => Self { random: 1, greedy: 0, lookahead: 0 },
+            Difficulty::Greedy => Self { random: 0, greedy: 1, lookahead: 0 },
+            Difficulty::Lookahead => Self { random: 0, greedy: 0, lookahead: 1 },
+        }
+    }
+
+    /// Tier of `snake_id`; an all-zero mix is all random
+    pub fn tier(&self, snake_id: u32) -> Difficulty {
+        let total = self.random + self.greedy + self.lookahead;
+        if total == 0 {
+            return Difficulty::Random;
+        }
+        let slot = snake_id % total;
+        if slot < self.random {
+            Difficulty::Random
+        } else if slot < self.random + self.greedy {
+            Difficulty::Greedy
+        } else {
+            Difficulty::Lookahead
+        }
+    }
+}
+
+impl Default for DifficultyMix {
+    fn default() -> Self {
+        Self::only(Difficulty::Random)
+    }
+}
+
+/// Bots of mixed difficulty. Unlike [`RandomBot`] they look at the game, so instead of
+/// being an `InputSource` they are asked for inputs with the state about to be ticked.
+///
+/// Decisions depend only on that state and the seeded RNG (random tier only), so runs
+/// stay reproducible.
+pub struct TieredBots {
+    snakes: Vec<(u32, Difficulty)>,
+    mix: DifficultyMix,
+    turn_probability: Fixed,
+    sight: u16,
+    rng: StdRng,
+    // Cells enemy heads may enter this tick and who could enter them (u32::MAX for several)
+    danger: HashMap<Point, u32>,
+    // Heads of the steering snakes and their nearest apples, queried as one batch
+    heads: Vec<Point>,
+    targets: Vec<Option<Point>>,
+}
+
+impl TieredBots {
+    /// Drive `snake_ids` with tiers from `mix`; random-tier snakes turn with `turn_probability`
+    pub fn new(snake_ids: std::ops::Range<u32>, mix: DifficultyMix, turn_probability: Fixed, seed: u64) -> Self {
+        Self {
+            snakes: snake_ids.map(|id| (id, mix.tier(id))).collect(),
+            mix,
+            turn_probability,
+            sight: BOT_SIGHT,
+            rng: StdRng::seed_from_u64(seed),
+            danger: HashMap::new(),
+            heads: Vec::new(),
+            targets: Vec::new(),
+        }
+    }
+
+    /// Look `sight` cells for apples instead of [`BOT_SIGHT`]
+    pub fn with_sight(mut self, sight: u16) -> Self {
+        self.sight = sight;
+        self
+    }
+
+    /// Also drive `snake_id`, on the tier the mix deals it. Ids already driven are ignored.
+    pub fn adopt(&mut self, snake_id: u32) {
+        if !self.snakes.iter().any(|(id, _)| *id == snake_id) {
+            self.snakes.push((snake_id, self.mix.tier(snake_id)));
+        }
+    }
+
+    /// Tier of each driven snake
+    pub fn tiers(&self) -> &[(u32, Difficulty)] {
+        &self.snakes
+    }
+
+    /// Append the inputs for the coming tick of `state` to `buf`
+    pub fn decide(&mut self, state: GameStateView<'_>, buf: &mut Vec<Input>) {
+        // Only heads within two cells of a lookahead head can reach a cell it may enter
+        self.danger.clear();
+        for &(snake_id, _) in self.snakes.iter().filter(|(_, tier)| *tier == Difficulty::Lookahead) {
+            let Some(own_head) = state.head(snake_id) else { continue };
+            for (id, head) in state.heads_in_rect(Rect::around(own_head, 2)) {
+                let back = state.snakes()[id as usize].snake().direction.opposite();
+                for direction in Direction::ALL.into_iter().filter(|direction| *direction != back) {
+                    self.danger
+                        .entry(head.step(direction))
+                        .and_modify(|owner| if *owner != id { *owner = u32::MAX })
+                        .or_insert(id);
+                }
+            }
+        }
+
+        self.heads.clear();
+        self.heads.extend(
+            self.snakes
+                .iter()
+                .filter(|(_, tier)| *tier != Difficulty::Random)
+                .filter_map(|&(snake_id, _)| state.head(snake_id)),
+        );
+        state.nearest_apples(&self.heads, self.sight, &mut self.targets);
+
+        let mut targets = self.targets.iter().copied();
+        for &(snake_id, tier) in &self.snakes {
+            let Some(snake) = state.snake(snake_id).filter(|snake| snake.is_alive()) else {
+                continue;
+            };
+            let direction = match tier {
+                Difficulty::Random => random_turn(&mut self.rng, self.turn_probability),
+                Difficulty::Greedy => steer(state, snake, targets.next().flatten(), None),
+                Difficulty::Lookahead => steer(state, snake, targets.next().flatten(), Some(&self.danger)),
+            };
+            if let Some(direction) = direction {
+                buf.push(Input { snake_id, direction });
+            }
+        }
+    }
+}
+
+// Uniform in [0, 1) at Fixed resolution; no floats so every platform rolls the same
+fn random_turn(rng: &mut StdRng, turn_probability: Fixed) -> Option<Direction> {
+    let roll = Fixed::from_raw((rng.random::<u32>() >> (32 - FRAC_BITS)) as i32);
+    (roll < turn_probability).then(|| rng.random())
+}
+
+/// Best of straight on, right and left for `snake`: never an occupied cell, then away from
+/// `danger` cells of other snakes, then closest to `target` (its nearest apple). Ties keep
+/// the current direction. `None` when that is already the choice or every move is blocked.
+fn steer(state: GameStateView<'_>, snake: &GridAwareSnake, target: Option<Point>, danger: Option<&HashMap<Point, u32>>) -> Option<Direction> {
+    let head = *snake.head()?;
+    let current = snake.snake().direction;
+
+    let mut best: Option<(u32, Direction)> = None;
+    for direction in [current, current.turn_right(), current.turn_right().opposite()] {
+        let next = head.step(direction);
+        if state.cell(&next) == Cell::Snake {
+            continue;
+        }
+        let mut score = target.map_or(0, |target| distance(next, target));
+        if let Some(&owner) = danger.and_then(|danger| danger.get(&next))
+            && owner != snake.id()
+        {
+            score += DANGER_PENALTY;
+        }
+        if best.is_none_or(|(best_score, _)| score < best_score) {
+            best = Some((score, direction));
+        }
+    }
+    best.map(|(_, direction)| direction).filter(|direction| *direction != current)
+}
+
+fn distance(a: Point, b: Point) -> u32 {
+    let dx = (a.x as usize).abs_diff(b.x as usize);
+    let dy = (a.y as usize).abs_diff(b.y as usize);
+    (dx.min(GRID_WIDTH - dx) + dy.min(GRID_HEIGHT - dy)) as u32
+}
@@ -0,0 +1,296 @@
+use crate::game::types::{Direction, Input};
+use crate::runtime::idle::{IdleConfig, Idler};
+use rand::distr::weighted::WeightedIndex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Time before a scheduled arrival the generator spins rather than yields or sleeps
+pub const SPIN_BEFORE_ARRIVAL: Duration = Duration::from_micros(50);
+
+/// Longest idle stretch (rate 0) one `next_arrival` call skips before giving up
+pub const MAX_IDLE_SKIP: Duration = Duration::from_secs(1);
+
+/// How input arrivals are spaced in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrivalProcess {
+    /// Evenly spaced, exactly `1 / rate` apart
+    Uniform,
+    /// Exponentially distributed inter-arrival times (memoryless)
+    Poisson,
+    /// Poisson-spaced bursts of `burst_size` back-to-back inputs, same mean rate
+    Bursty { burst_size: u32 },
+}
+
+/// How the offered rate evolves over the run
+#[derive(Debug, Clone, PartialEq)]
+pub enum RampProfile {
+    /// Always `target_rate`
+    Constant,
+    /// Linear ramp from `start_rate` to `target_rate` over `duration`, then constant
+    Linear { start_rate: f64, duration: Duration },
+    /// Piecewise constant: each `(start, rate)` applies from `start` until the next step.
+    /// Before the first step the target rate applies.
+    Steps(Vec<(Duration, f64)>),
+}
+
+/// Markov steering of a snake's inputs: each input follows from the last one sent to the
+/// same snake, going straight on most of the time like a real player rather than picking
+/// a direction uniformly (a quarter of which reverse and get rejected).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionBias {
+    /// Chance an input turns away from the snake's last direction instead of repeating it
+    pub turn_probability: f64,
+    /// Share of those turns that go straight back; the rest go left or right evenly
+    pub reverse_share: f64,
+}
+
+impl DirectionBias {
+    /// Mostly straight on, never reversing
+    pub const PLAYER: DirectionBias = DirectionBias {
+        turn_probability: 0.1,
+        reverse_share: 0.0,
+    };
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadConfig {
+    /// Offered load in inputs per second
+    pub target_rate: f64,
+    pub arrival: ArrivalProcess,
+    pub ramp: RampProfile,
+    /// Snake ids are drawn from `0..num_snakes`
+    pub num_snakes: u32,
+    /// Relative activity weight per snake id; empty means every snake is equally active
+    pub snake_activity: Vec<f64>,
+    /// Steering of every snake; `None` draws each direction uniformly
+    pub direction_bias: Option<DirectionBias>,
+    /// Steering per snake id, overriding `direction_bias` for the ids it covers
+    pub snake_bias: Vec<DirectionBias>,
+    pub seed: u64,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        Self {
+            target_rate: 1_000_000.0,
+            arrival: ArrivalProcess::Poisson,
+            ramp: RampProfile::Constant,
+            num_snakes: 1000,
+            snake_activity: Vec::new(),
+            direction_bias: None,
+            snake_bias: Vec::new(),
+            seed: 42,
+        }
+    }
+}
+
+/// Outcome of a load generator run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadStats {
+    /// Inputs accepted by the sink
+    pub sent: u64,
+    /// Inputs the sink refused (queue full). Open loop: these are not retried.
+    pub dropped: u64,
+}
+
+/// Open-loop load generator.
+///
+/// Arrival times are computed from the configured process independently of how fast
+/// the consumer drains, so a slow consumer shows up as queueing delay and drops rather
+/// than silently lowering the offered load (no coordinated omission).
+pub struct LoadGenerator {
+    config: LoadConfig,
+    rng: StdRng,
+    activity: Option<WeightedIndex<f64>>,
+    // Scheduled time of the next arrival, relative to the start of the run
+    next_arrival: Duration,
+    burst_remaining: u32,
+    // Last direction sent to each snake id, for biased steering
+    headings: Vec<Option<Direction>>,
+    // Backs off the wait between far-apart arrivals; None yields throughout
+    idler: Option<Idler>,
+}
+
+impl LoadGenerator {
+    /// Panics if `snake_activity` is non-empty but not a valid weight list.
+    pub fn new(config: LoadConfig) -> Self {
+        let activity = if config.snake_activity.is_empty() {
+            None
+        } else {
+            Some(WeightedIndex::new(&config.snake_activity).expect("invalid snake activity weights"))
+        };
+        // The first burst starts at time zero
+        let burst_remaining = match config.arrival {
+            ArrivalProcess::Bursty { burst_size } => burst_size.max(1),
+            _ => 0,
+        };
+        Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            activity,
+            next_arrival: Duration::ZERO,
+            burst_remaining,
+            headings: Vec::new(),
+            idler: None,
+        }
+    }
+
+    pub fn config(&self) -> &LoadConfig {
+        &self.config
+    }
+
+    /// Wait for far-off arrivals with an [`Idler`] backing off to sleeps, rather than
+    /// yielding in a loop; the last [`SPIN_BEFORE_ARRIVAL`] before each arrival still spin
+    pub fn set_idle(&mut self, config: IdleConfig) {
+        self.idler = Some(Idler::new(config));
+    }
+
+    /// Offered rate (inputs/sec) at `elapsed` into the run
+    pub fn rate_at(&self, elapsed: Duration) -> f64 {
+        let target = self.config.target_rate;
+        match &self.config.ramp {
+            RampProfile::Constant => target,
+            RampProfile::Linear { start_rate, duration } => {
+                if elapsed >= *duration || duration.is_zero() {
+                    target
+                } else {
+                    let progress = elapsed.as_secs_f64() / duration.as_secs_f64();
+                    start_rate + (target - start_rate) * progress
+                }
+            }
+            RampProfile::Steps(steps) => steps
+                .iter()
+                .take_while(|(start, _)| *start <= elapsed)
+                .last()
+                .map_or(target, |(_, rate)| *rate),
+        }
+    }
+
+    /// Produce the next input together with its scheduled send time (relative to the run start).
+    /// None while the rate stays at 0 for [`MAX_IDLE_SKIP`]; the schedule has then moved on
+    /// by that much and the next call carries on from there.
+    pub fn next_arrival(&mut self) -> Option<(Duration, Input)> {
+        // Idle periods (rate 0) are skipped in 1ms steps until load resumes
+        let idle_until = self.next_arrival + MAX_IDLE_SKIP;
+        let mut rate = self.rate_at(self.next_arrival);
+        while rate <= 0.0 {
+            if self.next_arrival >= idle_until {
+                return None;
+            }
+            self.next_arrival += Duration::from_millis(1);
+            rate = self.rate_at(self.next_arrival);
+        }
+
+        let at = self.next_arrival;
+        let gap = match self.config.arrival {
+            ArrivalProcess::Uniform => 1.0 / rate,
+            ArrivalProcess::Poisson => self.exponential(rate),
+            ArrivalProcess::Bursty { burst_size } => {
+                if self.burst_remaining > 1 {
+                    self.burst_remaining -= 1;
+                    0.0
+                } else {
+                    let burst_size = burst_size.max(1);
+                    self.burst_remaining = burst_size;
+                    self.exponential(rate / burst_size as f64)
+                }
+            }
+        };
+        self.next_arrival += Duration::from_secs_f64(gap);
+
+        Some((at, self.random_input()))
+    }
+
+    /// Drive `sink` in real time until `stop` is set (or `max_duration` elapses).
+    /// `sink` receives the scheduled send time with each input and returns false when
+    /// it cannot accept it.
+    pub fn run<F: FnMut(Instant, Input) -> bool>(
+        &mut self,
+        stop: &AtomicBool,
+        max_duration: Option<Duration>,
+        mut sink: F,
+    ) -> LoadStats {
+        let mut stats = LoadStats::default();
+        let start = Instant::now();
+
+        'outer: loop {
+            // While idle there is nothing to send, but waiting out the skipped stretch
+            // still watches `stop`
+            let arrival = self.next_arrival();
+            let at = arrival.as_ref().map_or(self.next_arrival, |(at, _)| *at);
+            if max_duration.is_some_and(|max| at >= max) {
+                break;
+            }
+
+            // Wait for the scheduled send time
+            loop {
+                if stop.load(Ordering::Relaxed) {
+                    break 'outer;
+                }
+                let now = start.elapsed();
+                if now >= at {
+                    break;
+                }
+                match &mut self.idler {
+                    Some(idler) if at - now > SPIN_BEFORE_ARRIVAL => {
+                        idler.idle_within(at - now - SPIN_BEFORE_ARRIVAL, || false);
+                    }
+                    None if at - now > SPIN_BEFORE_ARRIVAL => std::thread::yield_now(),
+                    _ => std::hint::spin_loop(),
+                }
+            }
+            if let Some(idler) = &mut self.idler {
+                idler.reset();
+            }
+
+            let Some((_, input)) = arrival else {
+                continue;
+            };
+            if sink(start + at, input) {
+                stats.sent += 1;
+            } else {
+                stats.dropped += 1;
+            }
+        }
+
+        stats
+    }
+
+    fn exponential(&mut self, rate: f64) -> f64 {
+        // 1 - U lies in (0, 1], so ln never sees zero
+        let u: f64 = self.rng.random();
+        -(1.0 - u).ln() / rate
+    }
+
+    fn random_input(&mut self) -> Input {
+        let snake_id = match &self.activity {
+            Some(weights) => self.rng.sample(weights) as u32,
+            None => self.rng.random_range(0..self.config.num_snakes.max(1)),
+        };
+        let bias = self.config.snake_bias.get(snake_id as usize).or(self.config.direction_bias.as_ref()).copied();
+        let direction = match bias {
+            Some(bias) => self.steer(snake_id, bias),
+            None => self.rng.random(),
+        };
+        Input { snake_id, direction }
+    }
+
+    // Next direction of `snake_id` from its last one; uniform for a snake not steered yet
+    fn steer(&mut self, snake_id: u32, bias: DirectionBias) -> Direction {
+        let index = snake_id as usize;
+        if index >= self.headings.len() {
+            self.headings.resize(index + 1, None);
+        }
+        let direction = match self.headings[index] {
+            None => self.rng.random(),
+            Some(heading) if !self.rng.random_bool(bias.turn_probability.clamp(0.0, 1.0)) => heading,
+            Some(heading) if self.rng.random_bool(bias.reverse_share.clamp(0.0, 1.0)) => heading.opposite(),
+            Some(heading) if self.rng.random() => heading.turn_right(),
+            Some(heading) => heading.turn_right().opposite(),
+        };
+        self.headings[index] = Some(direction);
+        direction
+    }
+}
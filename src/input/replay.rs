@@ -0,0 +1,205 @@
+use crate::game::types::{Direction, Input};
+use crate::input::source::InputSource;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+pub const REPLAY_MAGIC: [u8; 4] = *b"HFSR";
+pub const REPLAY_VERSION: u16 = 1;
+
+/// All inputs applied on a single tick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayFrame {
+    pub tick: u64,
+    pub inputs: Vec<Input>,
+}
+
+/// A recorded input stream.
+///
+/// On-disk layout (little endian):
+/// `magic[4] version:u16 frame_count:u64` then per frame
+/// `tick:u64 count:u32` followed by `count` x `(snake_id:u32 direction:u8)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new() -> Self {
+        Self { frames: Vec::new() }
+    }
+
+    /// Append the batch applied on `tick`. Empty batches are not stored.
+    pub fn record(&mut self, tick: u64, inputs: &[Input]) {
+        if inputs.is_empty() {
+            return;
+        }
+        self.frames.push(ReplayFrame {
+            tick,
+            inputs: inputs.to_vec(),
+        });
+    }
+
+    /// Group `(tick, input)` steps, already sorted by tick, into frames
+    pub fn from_steps<I: IntoIterator<Item = (u64, Input)>>(steps: I) -> Self {
+        let mut replay = Replay::new();
+        for (tick, input) in steps {
+            match replay.frames.last_mut() {
+                Some(frame) if frame.tick == tick => frame.inputs.push(input),
+                _ => replay.frames.push(ReplayFrame { tick, inputs: vec![input] }),
+            }
+        }
+        replay
+    }
+
+    /// Every input with its tick, in playback order
+    pub fn steps(&self) -> impl Iterator<Item = (u64, Input)> + '_ {
+        self.frames
+            .iter()
+            .flat_map(|frame| frame.inputs.iter().map(move |input| (frame.tick, *input)))
+    }
+
+    /// Frames with `start <= tick < start + len`, renumbered so `start` becomes tick 0
+    pub fn window(&self, start: u64, len: u64) -> Replay {
+        let frames = self
+            .frames
+            .iter()
+            .filter(|frame| frame.tick >= start && frame.tick - start < len)
+            .map(|frame| ReplayFrame {
+                tick: frame.tick - start,
+                inputs: frame.inputs.clone(),
+            })
+            .collect();
+        Replay { frames }
+    }
+
+    /// Total number of inputs across all frames
+    pub fn input_count(&self) -> usize {
+        self.frames.iter().map(|frame| frame.inputs.len()).sum()
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&REPLAY_MAGIC)?;
+        writer.write_all(&REPLAY_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.frames.len() as u64).to_le_bytes())?;
+        for frame in &self.frames {
+            write_frame(writer, frame.tick, &frame.inputs)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != REPLAY_MAGIC {
+            return Err(invalid_data("not a replay file"));
+        }
+        let version = u16::from_le_bytes(read_array(reader)?);
+        if version != REPLAY_VERSION {
+            return Err(invalid_data("unsupported replay version"));
+        }
+
+        let frame_count = u64::from_le_bytes(read_array(reader)?);
+        let mut frames = Vec::new();
+        for _ in 0..frame_count {
+            let tick = u64::from_le_bytes(read_array(reader)?);
+            frames.push(read_frame_inputs(reader, tick)?);
+        }
+        Ok(Self { frames })
+    }
+
+    /// Read a frame log: replay frames back to back with no header, up to end of input,
+    /// as written by [`RecordingTap`](crate::input::tap::RecordingTap)
+    pub fn read_frame_log<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut frames = Vec::new();
+        loop {
+            let mut tick = [0u8; 8];
+            let mut filled = 0;
+            while filled < tick.len() {
+                match reader.read(&mut tick[filled..]) {
+                    Ok(0) if filled == 0 => return Ok(Self { frames }),
+                    Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                    Ok(read) => filled += read,
+                    Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                    Err(error) => return Err(error),
+                }
+            }
+            frames.push(read_frame_inputs(reader, u64::from_le_bytes(tick))?);
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+/// Plays a [`Replay`] back frame by frame.
+pub struct ReplaySource {
+    replay: Replay,
+    cursor: usize,
+}
+
+impl ReplaySource {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, cursor: 0 }
+    }
+
+    /// True once every recorded frame has been emitted
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.replay.frames.len()
+    }
+}
+
+impl InputSource for ReplaySource {
+    fn next_batch(&mut self, tick: u64, buf: &mut Vec<Input>) {
+        let frames = &self.replay.frames;
+        while self.cursor < frames.len() && frames[self.cursor].tick < tick {
+            self.cursor += 1;
+        }
+        if self.cursor < frames.len() && frames[self.cursor].tick == tick {
+            buf.extend_from_slice(&frames[self.cursor].inputs);
+            self.cursor += 1;
+        }
+    }
+}
+
+/// One frame in the on-disk layout: `tick:u64 count:u32` then the inputs
+pub(crate) fn write_frame<W: Write>(writer: &mut W, tick: u64, inputs: &[Input]) -> io::Result<()> {
+    writer.write_all(&tick.to_le_bytes())?;
+    writer.write_all(&(inputs.len() as u32).to_le_bytes())?;
+    for input in inputs {
+        writer.write_all(&input.snake_id.to_le_bytes())?;
+        writer.write_all(&[input.direction.to_u8()])?;
+    }
+    Ok(())
+}
+
+// The rest of a frame once its tick has been read
+fn read_frame_inputs<R: Read>(reader: &mut R, tick: u64) -> io::Result<ReplayFrame> {
+    let count = u32::from_le_bytes(read_array(reader)?);
+    let mut inputs = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let snake_id = u32::from_le_bytes(read_array(reader)?);
+        let [direction] = read_array::<_, 1>(reader)?;
+        let direction = Direction::from_u8(direction)
+            .ok_or_else(|| invalid_data("invalid direction"))?;
+        inputs.push(Input { snake_id, direction });
+    }
+    Ok(ReplayFrame { tick, inputs })
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
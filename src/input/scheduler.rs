@@ -0,0 +1,176 @@
+//! Draining several input queues on the game thread.
+//!
+//! A [`QueueScheduler`] owns the consumer end of every lane (the load generator's queue,
+//! a local tool's queue, a priority or control lane) and is itself the game loop's
+//! [`InputSource`]. Each batch it visits the lanes in the order they were added, taking
+//! up to a lane's weight per round and repeating rounds until the lanes are empty or the
+//! tick's budget is spent. Inputs land in the batch in the order taken, so when two lanes
+//! steer the same snake the lane taken from later wins.
+//!
+//! A budget can leave a busy lane unserved tick after tick. A lane left waiting with
+//! inputs queued for `starvation_ticks` is served first on the next tick, budget or not.
+
+use crate::game::types::Input;
+use crate::input::source::InputSource;
+use crate::ipc::spsc::Consumer;
+
+/// Index of a lane in its [`QueueScheduler`], in the order added
+pub type LaneId = usize;
+
+/// Counters of one lane
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LaneStats {
+    /// Inputs taken from the lane so far
+    pub drained: u64,
+    /// Ticks in a row the lane has had inputs queued but got none
+    pub waiting_ticks: u32,
+    /// Ticks starvation protection served the lane ahead of the others
+    pub rescues: u64,
+}
+
+struct Lane<const N: usize> {
+    name: &'static str,
+    queue: Consumer<Input, N>,
+    weight: u32,
+    stats: LaneStats,
+    // Inputs taken during the current batch
+    taken: usize,
+}
+
+impl<const N: usize> Lane<N> {
+    // Move up to `limit` inputs into `buf`
+    fn take(&mut self, limit: usize, buf: &mut Vec<Input>) -> usize {
+        let mut taken = 0;
+        while taken < limit
+            && let Some(input) = self.queue.consume()
+        {
+            buf.push(input);
+            taken += 1;
+        }
+        self.taken += taken;
+        self.stats.drained += taken as u64;
+        taken
+    }
+
+    fn round_limit(&self) -> usize {
+        match self.weight {
+            0 => usize::MAX,
+            weight => weight as usize,
+        }
+    }
+}
+
+/// Weighted round-robin over input queues with starvation protection, see the module docs
+pub struct QueueScheduler<const N: usize> {
+    lanes: Vec<Lane<N>>,
+    budget: usize,
+    starvation_ticks: u32,
+}
+
+impl<const N: usize> Default for QueueScheduler<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> QueueScheduler<N> {
+    /// A scheduler draining every lane completely each tick
+    pub fn new() -> Self {
+        Self {
+            lanes: Vec::new(),
+            budget: 0,
+            starvation_ticks: 0,
+        }
+    }
+
+    /// Take at most `budget` inputs per tick (0: no limit), serving a lane first once it
+    /// has waited `starvation_ticks` ticks with inputs queued (0: never)
+    pub fn with_budget(budget: usize, starvation_ticks: u32) -> Self {
+        Self {
+            lanes: Vec::new(),
+            budget,
+            starvation_ticks,
+        }
+    }
+
+    /// Add a lane drained after those added before it. `weight` inputs are taken per
+    /// round; 0 drains the lane in one go.
+    pub fn add_lane(&mut self, name: &'static str, queue: Consumer<Input, N>, weight: u32) -> LaneId {
+        self.lanes.push(Lane {
+            name,
+            queue,
+            weight,
+            stats: LaneStats::default(),
+            taken: 0,
+        });
+        self.lanes.len() - 1
+    }
+
+    pub fn lane_count(&self) -> usize {
+        self.lanes.len()
+    }
+
+    pub fn lane_name(&self, lane: LaneId) -> &'static str {
+        self.lanes[lane].name
+    }
+
+    pub fn stats(&self, lane: LaneId) -> LaneStats {
+        self.lanes[lane].stats
+    }
+
+    /// The queue a lane drains, to inspect; only the scheduler consumes from it
+    pub fn lane_queue(&self, lane: LaneId) -> &Consumer<Input, N> {
+        &self.lanes[lane].queue
+    }
+
+    /// Inputs queued across every lane
+    pub fn depth(&self) -> usize {
+        self.lanes.iter().map(|lane| lane.queue.len()).sum()
+    }
+}
+
+impl<const N: usize> InputSource for QueueScheduler<N> {
+    fn next_batch(&mut self, _tick: u64, buf: &mut Vec<Input>) {
+        let mut left = match self.budget {
+            0 => usize::MAX,
+            budget => budget,
+        };
+        for lane in &mut self.lanes {
+            lane.taken = 0;
+        }
+
+        // Starved lanes get a round before anyone else, outside the budget
+        if self.starvation_ticks > 0 {
+            for lane in &mut self.lanes {
+                if lane.stats.waiting_ticks >= self.starvation_ticks {
+                    let taken = lane.take(lane.round_limit(), buf);
+                    left = left.saturating_sub(taken);
+                    lane.stats.rescues += 1;
+                }
+            }
+        }
+
+        while left > 0 {
+            let mut progressed = false;
+            for lane in &mut self.lanes {
+                let taken = lane.take(lane.round_limit().min(left), buf);
+                left -= taken;
+                progressed |= taken > 0;
+                if left == 0 {
+                    break;
+                }
+            }
+            if !progressed {
+                break;
+            }
+        }
+
+        for lane in &mut self.lanes {
+            if lane.taken == 0 && !lane.queue.is_empty() {
+                lane.stats.waiting_ticks += 1;
+            } else {
+                lane.stats.waiting_ticks = 0;
+            }
+        }
+    }
+}
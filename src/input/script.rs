@@ -0,0 +1,36 @@
+use crate::game::types::Input;
+use crate::input::source::InputSource;
+
+/// A fixed list of `(tick, input)` pairs played back at the right ticks.
+///
+/// Used for hand-written scenarios in tests and benches.
+pub struct ScriptedSource {
+    steps: Vec<(u64, Input)>,
+    cursor: usize,
+}
+
+impl ScriptedSource {
+    /// Build a script. Steps are sorted by tick; steps for the same tick keep their order.
+    pub fn new(mut steps: Vec<(u64, Input)>) -> Self {
+        steps.sort_by_key(|(tick, _)| *tick);
+        Self { steps, cursor: 0 }
+    }
+
+    /// True once every step has been emitted
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.steps.len()
+    }
+}
+
+impl InputSource for ScriptedSource {
+    fn next_batch(&mut self, tick: u64, buf: &mut Vec<Input>) {
+        // Steps scheduled for ticks we never asked for are dropped
+        while self.cursor < self.steps.len() && self.steps[self.cursor].0 < tick {
+            self.cursor += 1;
+        }
+        while self.cursor < self.steps.len() && self.steps[self.cursor].0 == tick {
+            buf.push(self.steps[self.cursor].1);
+            self.cursor += 1;
+        }
+    }
+}
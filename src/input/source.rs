@@ -0,0 +1,91 @@
+use crate::game::types::Input;
+use crate::ipc::spsc::Consumer;
+
+/// Anything that can feed inputs to the game loop.
+///
+/// The runner, the benches and tests all pull inputs through this trait, so swapping
+/// the live queue for a replay, a script or bots does not touch the loop itself.
+pub trait InputSource {
+    /// Append the inputs to apply on `tick` to `buf`.
+    ///
+    /// Implementations must only push, never clear: the caller owns `buf` and reuses
+    /// its capacity across ticks.
+    fn next_batch(&mut self, tick: u64, buf: &mut Vec<Input>);
+}
+
+impl<S: InputSource + ?Sized> InputSource for Box<S> {
+    #[inline(always)]
+    fn next_batch(&mut self, tick: u64, buf: &mut Vec<Input>) {
+        (**self).next_batch(tick, buf);
+    }
+}
+
+impl<S: InputSource + ?Sized> InputSource for &mut S {
+    #[inline(always)]
+    fn next_batch(&mut self, tick: u64, buf: &mut Vec<Input>) {
+        (**self).next_batch(tick, buf);
+    }
+}
+
+/// Both sources: the first one's inputs, then the second one's, which therefore win
+/// when both steer the same snake
+impl<A: InputSource, B: InputSource> InputSource for (A, B) {
+    #[inline(always)]
+    fn next_batch(&mut self, tick: u64, buf: &mut Vec<Input>) {
+        self.0.next_batch(tick, buf);
+        self.1.next_batch(tick, buf);
+    }
+}
+
+/// A source that may be absent, e.g. an optional second queue
+impl<S: InputSource> InputSource for Option<S> {
+    #[inline(always)]
+    fn next_batch(&mut self, tick: u64, buf: &mut Vec<Input>) {
+        if let Some(source) = self {
+            source.next_batch(tick, buf);
+        }
+    }
+}
+
+/// An `InputSource` calling `f(tick, buf)` for every batch; see [`from_fn`]
+pub struct FromFn<F> {
+    f: F,
+}
+
+/// Wrap a closure as an input source, e.g. for `GameState::tick_n`
+pub fn from_fn<F: FnMut(u64, &mut Vec<Input>)>(f: F) -> FromFn<F> {
+    FromFn { f }
+}
+
+impl<F: FnMut(u64, &mut Vec<Input>)> InputSource for FromFn<F> {
+    #[inline(always)]
+    fn next_batch(&mut self, tick: u64, buf: &mut Vec<Input>) {
+        (self.f)(tick, buf);
+    }
+}
+
+/// Consumer side of the SPSC queue: drains whatever the producer has published so far.
+pub struct QueueSource<const N: usize> {
+    queue: Consumer<Input, N>,
+}
+
+impl<const N: usize> QueueSource<N> {
+    /// Wrap the consumer end of a [`Spsc::split`](crate::ipc::spsc::Spsc::split) queue
+    pub fn new(queue: Consumer<Input, N>) -> Self {
+        Self { queue }
+    }
+
+    /// Inputs published since the last batch was drained
+    pub fn depth(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+impl<const N: usize> InputSource for QueueSource<N> {
+    #[inline(always)]
+    fn next_batch(&mut self, _tick: u64, buf: &mut Vec<Input>) {
+        while let Some(input) = self.queue.consume() {
+            buf.push(input);
+        }
+    }
+}
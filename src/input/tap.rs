@@ -0,0 +1,135 @@
+//! Recording tap: copies every batch an [`InputSource`] hands the engine into a frame log.
+//!
+//! [`RecordingTap`] wraps any source and is itself a source, so recording is a matter of
+//! wrapping, not of changing the producer. On the tick thread it only encodes the new
+//! inputs and pushes the bytes into an SPSC byte ring; a background thread drains the
+//! ring into a buffered writer. When the ring has no room for a frame the frame is
+//! dropped and counted rather than stalling the tick.
+//!
+//! The log holds replay frames back to back without a header (see
+//! [`Replay::read_frame_log`](crate::input::replay::Replay::read_frame_log)).
+
+use crate::game::types::Input;
+use crate::input::replay::write_frame;
+use crate::input::source::InputSource;
+use crate::ipc::spsc::{Consumer, Producer, Spsc};
+use std::io::{self, BufWriter, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Byte ring between the tick thread and the writer thread
+pub const TAP_RING_BYTES: usize = 1 << 16;
+/// How long the writer sleeps when the ring is empty
+const WRITER_IDLE: Duration = Duration::from_millis(1);
+
+pub struct RecordingTap<S, W: Write + Send + 'static> {
+    inner: S,
+    ring: Producer<u8, TAP_RING_BYTES>,
+    done: Arc<AtomicBool>,
+    writer: Option<JoinHandle<io::Result<W>>>,
+    // Encoded frame, reused across ticks
+    frame: Vec<u8>,
+    recorded_frames: u64,
+    dropped_frames: u64,
+}
+
+impl<S: InputSource, W: Write + Send + 'static> RecordingTap<S, W> {
+    /// Wrap `inner`, logging its batches to `sink` from a background thread
+    pub fn new(inner: S, sink: W) -> Self {
+        let (ring, writer_ring) = Spsc::new().split();
+        let done = Arc::new(AtomicBool::new(false));
+        let writer_done = Arc::clone(&done);
+        let writer = thread::spawn(move || drain(writer_ring, &writer_done, sink));
+        Self {
+            inner,
+            ring,
+            done,
+            writer: Some(writer),
+            frame: Vec::new(),
+            recorded_frames: 0,
+            dropped_frames: 0,
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Frames handed to the writer
+    pub fn recorded_frames(&self) -> u64 {
+        self.recorded_frames
+    }
+
+    /// Frames left out of the log because the ring was full
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    /// Stop the writer once it has drained the ring, flush and hand back the sink
+    pub fn finish(mut self) -> io::Result<W> {
+        self.done.store(true, Ordering::Release);
+        let writer = self.writer.take().expect("writer joined once");
+        writer.join().map_err(|_| io::Error::other("tap writer panicked"))?
+    }
+}
+
+impl<S: InputSource, W: Write + Send + 'static> InputSource for RecordingTap<S, W> {
+    #[inline]
+    fn next_batch(&mut self, tick: u64, buf: &mut Vec<Input>) {
+        let start = buf.len();
+        self.inner.next_batch(tick, buf);
+        let batch = &buf[start..];
+        if batch.is_empty() {
+            return;
+        }
+
+        self.frame.clear();
+        write_frame(&mut self.frame, tick, batch).expect("writes to a Vec cannot fail");
+        // The ring only drains behind our back, so this room is a lower bound
+        let room = TAP_RING_BYTES - 1 - self.ring.len();
+        if self.frame.len() > room {
+            self.dropped_frames += 1;
+            return;
+        }
+        for byte in &self.frame {
+            self.ring.produce(*byte);
+        }
+        self.recorded_frames += 1;
+    }
+}
+
+impl<S, W: Write + Send + 'static> Drop for RecordingTap<S, W> {
+    // A tap dropped without `finish` still lets its writer drain and exit
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Release);
+    }
+}
+
+// Writer thread: ring to sink until `done` and empty
+fn drain<W: Write>(mut ring: Consumer<u8, TAP_RING_BYTES>, done: &AtomicBool, sink: W) -> io::Result<W> {
+    let mut sink = BufWriter::new(sink);
+    let mut chunk = Vec::with_capacity(TAP_RING_BYTES);
+    loop {
+        // Check before draining so nothing pushed before `done` is missed
+        let finished = done.load(Ordering::Acquire);
+        while let Some(byte) = ring.consume() {
+            chunk.push(byte);
+        }
+        if chunk.is_empty() {
+            if finished {
+                break;
+            }
+            thread::sleep(WRITER_IDLE);
+            continue;
+        }
+        sink.write_all(&chunk)?;
+        chunk.clear();
+    }
+    sink.into_inner().map_err(|error| error.into_error())
+}
@@ -1 +1,9 @@
+pub mod async_adapter;
+pub mod channel;
+#[cfg(unix)]
+pub mod handoff;
+#[cfg(unix)]
+pub mod socket;
+pub mod segment;
+pub mod sharing;
 pub mod spsc;
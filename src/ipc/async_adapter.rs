@@ -0,0 +1,97 @@
+use crate::ipc::spsc::{Consumer, Producer};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Async producer half of an [`Spsc`](crate::ipc::spsc::Spsc) queue.
+///
+/// `send` waits for space instead of dropping. The queue has no waker registration, so a
+/// full queue turns into a cooperative yield loop: the future wakes itself and lets the
+/// executor run other tasks before retrying. That suits the pinned consumer, which drains
+/// continuously. Works with any executor (tokio, async-std, ...).
+///
+/// The [`Producer`] it owns is the queue's only one, so a multi-threaded executor may
+/// resume the task on any worker.
+pub struct AsyncProducer<T, const N: usize> {
+    queue: Producer<T, N>,
+}
+
+impl<T: Copy, const N: usize> AsyncProducer<T, N> {
+    pub fn new(queue: Producer<T, N>) -> Self {
+        Self { queue }
+    }
+
+    /// Enqueue without waiting. Returns false if the queue is full.
+    pub fn try_send(&mut self, value: T) -> bool {
+        self.queue.produce(value)
+    }
+
+    /// Enqueue, waiting for space. `&mut self` keeps at most one send in flight.
+    pub fn send(&mut self, value: T) -> SendFuture<'_, T, N> {
+        SendFuture {
+            queue: &mut self.queue,
+            value,
+        }
+    }
+}
+
+pub struct SendFuture<'a, T, const N: usize> {
+    queue: &'a mut Producer<T, N>,
+    value: T,
+}
+
+// The value is only ever copied out, never pinned
+impl<T, const N: usize> Unpin for SendFuture<'_, T, N> {}
+
+impl<T: Copy, const N: usize> Future for SendFuture<'_, T, N> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let value = self.value;
+        if self.queue.produce(value) {
+            Poll::Ready(())
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// Async consumer half of an [`Spsc`](crate::ipc::spsc::Spsc) queue, polling the same way
+/// as [`AsyncProducer`].
+pub struct AsyncConsumer<T, const N: usize> {
+    queue: Consumer<T, N>,
+}
+
+impl<T, const N: usize> AsyncConsumer<T, N> {
+    pub fn new(queue: Consumer<T, N>) -> Self {
+        Self { queue }
+    }
+
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.queue.consume()
+    }
+
+    /// Dequeue, waiting for a value
+    pub fn recv(&mut self) -> RecvFuture<'_, T, N> {
+        RecvFuture { queue: &mut self.queue }
+    }
+}
+
+pub struct RecvFuture<'a, T, const N: usize> {
+    queue: &'a mut Consumer<T, N>,
+}
+
+impl<T, const N: usize> Future for RecvFuture<'_, T, N> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        match self.queue.consume() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
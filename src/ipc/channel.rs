@@ -0,0 +1,236 @@
+//! Channel endpoints over the SPSC queue, and bridges to `std::sync::mpsc` and crossbeam
+//! channels (feature `crossbeam`).
+//!
+//! [`SpscSender`] and [`SpscReceiver`] follow the std endpoint API (`send`, `try_send`,
+//! `recv`, `try_recv`, `recv_timeout`, `try_iter`) with std's error types, including
+//! disconnection once the other endpoint is dropped, so code written against channels
+//! can take the queue as is. Blocking calls spin briefly, then yield.
+//!
+//! [`bridge_in`] and [`bridge_out`] connect a queue to an application's existing channel
+//! on a thread of their own. Each value is copied once more than through the queue alone,
+//! which costs nothing on the tick path: the engine still only sees the queue.
+
+use crate::ipc::spsc::{Consumer, Producer, Spsc};
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// Busy polls of a blocking call before it starts yielding its time slice
+const SPINS_BEFORE_YIELD: u32 = 64;
+
+/// Sending endpoint of an [`Spsc`] queue
+pub struct SpscSender<T, const N: usize> {
+    queue: Producer<T, N>,
+    // Set when either endpoint is dropped
+    disconnected: Arc<AtomicBool>,
+}
+
+/// Receiving endpoint of an [`Spsc`] queue
+pub struct SpscReceiver<T, const N: usize> {
+    queue: Consumer<T, N>,
+    disconnected: Arc<AtomicBool>,
+}
+
+/// Endpoints of a new queue
+pub fn channel<T, const N: usize>() -> (SpscSender<T, N>, SpscReceiver<T, N>) {
+    endpoints(Spsc::new())
+}
+
+/// Endpoints of an existing queue, which they own from now on
+pub fn endpoints<T, const N: usize>(queue: Spsc<T, N>) -> (SpscSender<T, N>, SpscReceiver<T, N>) {
+    let (producer, consumer) = queue.split();
+    let disconnected = Arc::new(AtomicBool::new(false));
+    (
+        SpscSender { queue: producer, disconnected: Arc::clone(&disconnected) },
+        SpscReceiver { queue: consumer, disconnected },
+    )
+}
+
+impl<T: Copy, const N: usize> SpscSender<T, N> {
+    /// Enqueue without waiting
+    pub fn try_send(&mut self, value: T) -> Result<(), TrySendError<T>> {
+        if self.disconnected.load(Ordering::Acquire) {
+            Err(TrySendError::Disconnected(value))
+        } else if self.queue.produce(value) {
+            Ok(())
+        } else {
+            Err(TrySendError::Full(value))
+        }
+    }
+
+    /// Enqueue, waiting for space. Fails once the receiver is gone.
+    pub fn send(&mut self, value: T) -> Result<(), SendError<T>> {
+        let mut spins = 0;
+        loop {
+            match self.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(value)) => return Err(SendError(value)),
+                Err(TrySendError::Full(_)) => back_off(&mut spins),
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> SpscSender<T, N> {
+    pub fn queue(&self) -> &Producer<T, N> {
+        &self.queue
+    }
+}
+
+impl<T, const N: usize> Drop for SpscSender<T, N> {
+    fn drop(&mut self) {
+        self.disconnected.store(true, Ordering::Release);
+    }
+}
+
+impl<T, const N: usize> SpscReceiver<T, N> {
+    /// Dequeue without waiting. Values sent before the sender was dropped are still
+    /// received; `Disconnected` comes only once they are gone.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        if let Some(value) = self.queue.consume() {
+            return Ok(value);
+        }
+        if !self.disconnected.load(Ordering::Acquire) {
+            return Err(TryRecvError::Empty);
+        }
+        // The sender may have sent a last value between the two checks
+        self.queue.consume().ok_or(TryRecvError::Disconnected)
+    }
+
+    /// Dequeue, waiting for a value. Fails once the sender is gone and the queue empty.
+    pub fn recv(&mut self) -> Result<T, RecvError> {
+        let mut spins = 0;
+        loop {
+            match self.try_recv() {
+                Ok(value) => return Ok(value),
+                Err(TryRecvError::Disconnected) => return Err(RecvError),
+                Err(TryRecvError::Empty) => back_off(&mut spins),
+            }
+        }
+    }
+
+    /// [`SpscReceiver::recv`], giving up after `timeout`
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut spins = 0;
+        loop {
+            match self.try_recv() {
+                Ok(value) => return Ok(value),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) if Instant::now() >= deadline => return Err(RecvTimeoutError::Timeout),
+                Err(TryRecvError::Empty) => back_off(&mut spins),
+            }
+        }
+    }
+
+    /// Every value available now, without waiting
+    pub fn try_iter(&mut self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(move || self.try_recv().ok())
+    }
+
+    pub fn queue(&self) -> &Consumer<T, N> {
+        &self.queue
+    }
+}
+
+impl<T, const N: usize> Drop for SpscReceiver<T, N> {
+    fn drop(&mut self) {
+        self.disconnected.store(true, Ordering::Release);
+    }
+}
+
+fn back_off(spins: &mut u32) {
+    if *spins < SPINS_BEFORE_YIELD {
+        *spins += 1;
+        std::hint::spin_loop();
+    } else {
+        thread::yield_now();
+    }
+}
+
+/// A channel receiver [`bridge_in`] can drain
+pub trait BridgeSource<T>: Send {
+    /// Next value, waiting for one; None once every sender is gone
+    fn recv_value(&self) -> Option<T>;
+}
+
+/// A channel sender [`bridge_out`] can feed
+pub trait BridgeSink<T>: Send {
+    /// Send `value`, waiting for space if bounded; false once the receiver is gone
+    fn send_value(&self, value: T) -> bool;
+}
+
+impl<T: Send> BridgeSource<T> for mpsc::Receiver<T> {
+    fn recv_value(&self) -> Option<T> {
+        self.recv().ok()
+    }
+}
+
+impl<T: Send> BridgeSink<T> for mpsc::Sender<T> {
+    fn send_value(&self, value: T) -> bool {
+        self.send(value).is_ok()
+    }
+}
+
+impl<T: Send> BridgeSink<T> for mpsc::SyncSender<T> {
+    fn send_value(&self, value: T) -> bool {
+        self.send(value).is_ok()
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl<T: Send> BridgeSource<T> for crossbeam_channel::Receiver<T> {
+    fn recv_value(&self) -> Option<T> {
+        self.recv().ok()
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl<T: Send> BridgeSink<T> for crossbeam_channel::Sender<T> {
+    fn send_value(&self, value: T) -> bool {
+        self.send(value).is_ok()
+    }
+}
+
+/// Forward everything `source` receives into the queue behind `sender`, on a new thread.
+/// The thread ends, dropping `sender`, when every sender of `source` is gone or the
+/// queue's receiver is; it returns how many values it forwarded.
+pub fn bridge_in<T, S, const N: usize>(source: S, mut sender: SpscSender<T, N>) -> io::Result<JoinHandle<u64>>
+where
+    T: Copy + Send + 'static,
+    S: BridgeSource<T> + 'static,
+{
+    thread::Builder::new().name("channel-bridge-in".to_string()).spawn(move || {
+        let mut forwarded = 0;
+        while let Some(value) = source.recv_value() {
+            if sender.send(value).is_err() {
+                break;
+            }
+            forwarded += 1;
+        }
+        forwarded
+    })
+}
+
+/// Forward everything the queue behind `receiver` yields into `sink`, on a new thread.
+/// The thread ends when the queue's sender is gone and the queue drained, or when
+/// `sink`'s receiver is gone; it returns how many values it forwarded.
+pub fn bridge_out<T, S, const N: usize>(mut receiver: SpscReceiver<T, N>, sink: S) -> io::Result<JoinHandle<u64>>
+where
+    T: Send + 'static,
+    S: BridgeSink<T> + 'static,
+{
+    thread::Builder::new().name("channel-bridge-out".to_string()).spawn(move || {
+        let mut forwarded = 0;
+        while let Ok(value) = receiver.recv() {
+            if !sink.send_value(value) {
+                break;
+            }
+            forwarded += 1;
+        }
+        forwarded
+    })
+}
@@ -0,0 +1,386 @@
+//! Live migration of a running simulation to a new process.
+//!
+//! The running process (the incumbent) maps a file, usually under `/dev/shm`, as a
+//! [`SegmentKind::Snapshot`] segment holding a small control block and room for one
+//! snapshot, and holds the segment's producer role. A new process (the successor, e.g.
+//! an upgraded build) maps the same file and calls [`HandoffSegment::take_over`]:
+//!
+//! 1. the successor claims the consumer role and asks for the state (`Requested`);
+//! 2. the incumbent notices between ticks ([`HandoffSegment::poll`]), stops taking
+//!    inputs, applies what is queued and writes a snapshot into the segment (`Ready`);
+//! 3. the successor checks the snapshot's checksum, restores it and confirms
+//!    (`Resumed`), the incumbent gives up the producer role and exits, and the successor
+//!    takes the role over, ready to hand off in turn.
+//!
+//! Either side can give up: a successor that times out or cannot read the snapshot, or
+//! an incumbent that hears nothing back, marks the handoff `Aborted` and the incumbent
+//! carries on. Every phase change is a compare-and-swap on the control block, so the
+//! two never disagree on the outcome.
+//!
+//! The segment's layout hash covers the control block only, not the crate version, so
+//! builds of different versions can hand off to each other; the snapshot carries its own
+//! version (see [`crate::game::snapshot`]).
+
+use crate::game::codec::GridCodec;
+use crate::game::engine::GameState;
+use crate::game::snapshot::{read_snapshot, write_snapshot_with};
+use crate::ipc::segment::{
+    self, FNV_OFFSET, SEGMENT_HEADER_BYTES, SegmentHeader, SegmentKind, SegmentLayout, SegmentRole, fnv1a,
+};
+use std::cell::Cell;
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind};
+use std::mem::size_of;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Version of the control block; bump when it changes
+pub const HANDOFF_VERSION: u32 = 1;
+/// Snapshot bytes a segment holds unless told otherwise. The file is sparse, so only
+/// the pages a snapshot fills take memory.
+pub const DEFAULT_HANDOFF_CAPACITY: usize = 256 << 20;
+/// A side that has not beaten for this long is presumed dead
+pub const HANDOFF_STALE_AFTER: Duration = Duration::from_secs(5);
+
+const CONTROL_BYTES: usize = size_of::<HandoffControl>();
+const POLL_INTERVAL: Duration = Duration::from_micros(100);
+
+/// Where a handoff stands
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandoffPhase {
+    /// The incumbent is running; nobody asked for the state
+    Idle = 0,
+    /// A successor is waiting for the state
+    Requested = 1,
+    /// The snapshot is in the segment; the incumbent is paused
+    Ready = 2,
+    /// The successor runs the simulation now
+    Resumed = 3,
+    /// Given up; the incumbent carries on
+    Aborted = 4,
+}
+
+impl HandoffPhase {
+    fn from_u32(value: u32) -> Self {
+        match value {
+            1 => HandoffPhase::Requested,
+            2 => HandoffPhase::Ready,
+            3 => HandoffPhase::Resumed,
+            4 => HandoffPhase::Aborted,
+            _ => HandoffPhase::Idle,
+        }
+    }
+}
+
+// Right after the segment header; the snapshot follows
+#[repr(C, align(64))]
+struct HandoffControl {
+    phase: AtomicU32,
+    // Snapshot tick, length and FNV-1a hash, written before the phase turns Ready
+    tick: AtomicU64,
+    len: AtomicU64,
+    checksum: AtomicU64,
+}
+
+fn layout(capacity: usize) -> SegmentLayout {
+    let mut hash = FNV_OFFSET;
+    for bytes in [
+        b"handoff".as_slice(),
+        &HANDOFF_VERSION.to_le_bytes(),
+        &(CONTROL_BYTES as u64).to_le_bytes(),
+    ] {
+        hash = fnv1a(hash, bytes);
+    }
+    SegmentLayout {
+        hash,
+        capacity: capacity as u64,
+    }
+}
+
+/// One side's mapping of a handoff file, see the module docs
+pub struct HandoffSegment {
+    path: PathBuf,
+    memory: *mut u8,
+    len: usize,
+    role: SegmentRole,
+    // Whether this process holds `role`: from its claim until it hands the simulation off
+    holds_role: Cell<bool>,
+    // Keeps the mapping's file open for as long as the mapping
+    _file: File,
+}
+
+impl HandoffSegment {
+    /// Set up `path` for handing this process's simulation off later, with room for a
+    /// snapshot of `capacity` bytes, and take the producer role. Whatever `path` held is
+    /// replaced, so check with [`HandoffSegment::attach`] that no live process uses it.
+    pub fn create<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path)?;
+        let len = SEGMENT_HEADER_BYTES + CONTROL_BYTES + capacity;
+        file.set_len(len as u64)?;
+        let memory = map(&file, len)?;
+        let segment = Self {
+            path,
+            memory,
+            len,
+            role: SegmentRole::Producer,
+            holds_role: Cell::new(false),
+            _file: file,
+        };
+        // SAFETY: the mapping is `len` bytes, page aligned, and nobody attaches before the
+        // header's magic is written
+        let header = unsafe { segment::create(memory, len, SegmentKind::Snapshot, layout(capacity)) }.map_err(segment_error)?;
+        // SAFETY: the control block lies in the mapping, aligned, right after the header
+        unsafe {
+            memory.add(SEGMENT_HEADER_BYTES).cast::<HandoffControl>().write(HandoffControl {
+                phase: AtomicU32::new(HandoffPhase::Idle as u32),
+                tick: AtomicU64::new(0),
+                len: AtomicU64::new(0),
+                checksum: AtomicU64::new(0),
+            });
+        }
+        header.claim(SegmentRole::Producer, HANDOFF_STALE_AFTER).map_err(segment_error)?;
+        segment.holds_role.set(true);
+        Ok(segment)
+    }
+
+    /// Map the handoff file at `path` of a running incumbent, to take over from it.
+    /// `NotFound` when there is no file or the incumbent is no longer alive.
+    pub fn attach<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let len = file.metadata()?.len() as usize;
+        if len < SEGMENT_HEADER_BYTES + CONTROL_BYTES {
+            return Err(io::Error::new(ErrorKind::InvalidData, "handoff file too short"));
+        }
+        let memory = map(&file, len)?;
+        let segment = Self {
+            path,
+            memory,
+            len,
+            role: SegmentRole::Consumer,
+            holds_role: Cell::new(false),
+            _file: file,
+        };
+        let capacity = len - SEGMENT_HEADER_BYTES - CONTROL_BYTES;
+        // SAFETY: the mapping is `len` bytes and page aligned
+        let header = unsafe { segment::attach(memory, len, SegmentKind::Snapshot, layout(capacity)) }.map_err(segment_error)?;
+        if !header.is_alive(SegmentRole::Producer, HANDOFF_STALE_AFTER) {
+            return Err(io::Error::new(ErrorKind::NotFound, "no live process to take over from"));
+        }
+        header.claim(SegmentRole::Consumer, HANDOFF_STALE_AFTER).map_err(segment_error)?;
+        segment.holds_role.set(true);
+        Ok(segment)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Snapshot bytes the segment holds
+    pub fn capacity(&self) -> usize {
+        self.len - SEGMENT_HEADER_BYTES - CONTROL_BYTES
+    }
+
+    pub fn phase(&self) -> HandoffPhase {
+        HandoffPhase::from_u32(self.control().phase.load(Ordering::Acquire))
+    }
+
+    /// Record that this side is still running; the incumbent should beat well within
+    /// [`HANDOFF_STALE_AFTER`]
+    pub fn beat(&self) {
+        self.header().beat(self.role);
+    }
+
+    /// Incumbent: whether a successor is waiting for the state. One atomic load unless a
+    /// successor gave up, in which case the segment is made ready for the next one.
+    #[inline]
+    pub fn poll(&self) -> bool {
+        match self.phase() {
+            HandoffPhase::Requested => true,
+            HandoffPhase::Aborted => {
+                self.transition(HandoffPhase::Aborted, HandoffPhase::Idle);
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Incumbent: write `state` as of `tick` into the segment for the waiting successor.
+    /// Inputs must be drained first: anything not applied by now is lost. Fails, aborting
+    /// the handoff, when the snapshot does not fit or the successor gave up.
+    pub fn publish(&mut self, state: &GameState, tick: u64) -> io::Result<()> {
+        let written = {
+            let mut payload = self.payload_mut();
+            let capacity = payload.len();
+            write_snapshot_with(state, tick, GridCodec::default(), &mut payload).map(|()| capacity - payload.len())
+        };
+        let len = match written {
+            Ok(len) => len,
+            Err(error) => {
+                self.transition(HandoffPhase::Requested, HandoffPhase::Aborted);
+                return Err(error);
+            }
+        };
+        let control = self.control();
+        control.tick.store(tick, Ordering::Relaxed);
+        control.len.store(len as u64, Ordering::Relaxed);
+        control.checksum.store(fnv1a(FNV_OFFSET, &self.payload()[..len]), Ordering::Relaxed);
+        if self.transition(HandoffPhase::Requested, HandoffPhase::Ready) {
+            Ok(())
+        } else {
+            self.transition(HandoffPhase::Aborted, HandoffPhase::Idle);
+            Err(io::Error::new(ErrorKind::Interrupted, "successor gave up before the snapshot was ready"))
+        }
+    }
+
+    /// Incumbent: turn down the waiting successor, e.g. when the state could not be
+    /// drained
+    pub fn abort(&self) {
+        self.transition(HandoffPhase::Requested, HandoffPhase::Aborted);
+    }
+
+    /// Incumbent: wait up to `timeout` for the successor to confirm after
+    /// [`HandoffSegment::publish`]. True once it runs the simulation, in which case the
+    /// producer role is given up and this process should exit; false when the handoff was
+    /// aborted and this process should carry on.
+    pub fn await_resumed(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while self.phase() == HandoffPhase::Ready {
+            self.beat();
+            // Give up at the deadline, unless the successor confirms meanwhile
+            if Instant::now() >= deadline {
+                self.transition(HandoffPhase::Ready, HandoffPhase::Aborted);
+            } else {
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+        if self.phase() == HandoffPhase::Resumed {
+            self.holds_role.set(false);
+            self.header().release(SegmentRole::Producer);
+            return true;
+        }
+        self.transition(HandoffPhase::Aborted, HandoffPhase::Idle);
+        false
+    }
+
+    /// Successor: ask the incumbent for its state, wait up to `timeout` for it and restore
+    /// it, returning its tick and the state. On success this process holds the producer
+    /// role, ready to hand off to the next successor.
+    pub fn take_over(&mut self, timeout: Duration) -> io::Result<(u64, GameState)> {
+        if !self.transition(HandoffPhase::Idle, HandoffPhase::Requested) {
+            return Err(io::Error::new(ErrorKind::WouldBlock, format!("handoff already {:?}", self.phase())));
+        }
+        let deadline = Instant::now() + timeout;
+        while self.phase() == HandoffPhase::Requested {
+            if Instant::now() >= deadline && self.transition(HandoffPhase::Requested, HandoffPhase::Aborted) {
+                return Err(io::Error::new(ErrorKind::TimedOut, "incumbent did not publish its state"));
+            }
+            self.beat();
+            thread::sleep(POLL_INTERVAL);
+        }
+        if self.phase() != HandoffPhase::Ready {
+            return Err(io::Error::new(ErrorKind::Interrupted, "incumbent aborted the handoff"));
+        }
+
+        let restored = self.read_published();
+        if restored.is_err() || !self.transition(HandoffPhase::Ready, HandoffPhase::Resumed) {
+            self.transition(HandoffPhase::Ready, HandoffPhase::Aborted);
+            return restored.and(Err(io::Error::new(ErrorKind::TimedOut, "incumbent stopped waiting for us")));
+        }
+
+        // The incumbent lets go of the producer role once it sees Resumed
+        let header = self.header();
+        while header.pid(SegmentRole::Producer).is_some() && header.is_alive(SegmentRole::Producer, HANDOFF_STALE_AFTER) {
+            thread::sleep(POLL_INTERVAL);
+        }
+        header.claim(SegmentRole::Producer, HANDOFF_STALE_AFTER).map_err(segment_error)?;
+        self.control().phase.store(HandoffPhase::Idle as u32, Ordering::Release);
+        header.release(SegmentRole::Consumer);
+        self.role = SegmentRole::Producer;
+        restored
+    }
+
+    fn read_published(&self) -> io::Result<(u64, GameState)> {
+        let control = self.control();
+        let len = control.len.load(Ordering::Relaxed) as usize;
+        let payload = self.payload().get(..len).ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "snapshot longer than the segment"))?;
+        if fnv1a(FNV_OFFSET, payload) != control.checksum.load(Ordering::Relaxed) {
+            return Err(io::Error::new(ErrorKind::InvalidData, "snapshot checksum mismatch"));
+        }
+        let (tick, state) = read_snapshot(&mut &payload[..])?;
+        if tick != control.tick.load(Ordering::Relaxed) {
+            return Err(io::Error::new(ErrorKind::InvalidData, "snapshot tick mismatch"));
+        }
+        Ok((tick, state))
+    }
+
+    fn transition(&self, from: HandoffPhase, to: HandoffPhase) -> bool {
+        self.control()
+            .phase
+            .compare_exchange(from as u32, to as u32, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    fn header(&self) -> &SegmentHeader {
+        // SAFETY: validated by create or attach, and mapped for as long as self
+        unsafe { &*self.memory.cast::<SegmentHeader>() }
+    }
+
+    fn control(&self) -> &HandoffControl {
+        // SAFETY: written by create before the magic, mapped for as long as self
+        unsafe { &*self.memory.add(SEGMENT_HEADER_BYTES).cast::<HandoffControl>() }
+    }
+
+    // The phase decides which side touches the payload: the incumbent while Requested,
+    // the successor once Ready
+    fn payload(&self) -> &[u8] {
+        // SAFETY: the payload fills the rest of the mapping
+        unsafe { std::slice::from_raw_parts(self.memory.add(SEGMENT_HEADER_BYTES + CONTROL_BYTES), self.capacity()) }
+    }
+
+    fn payload_mut(&mut self) -> &mut [u8] {
+        // SAFETY: as for payload, and the borrow of self keeps this the only slice of it in
+        // this process; only the incumbent writes, and only while Requested
+        unsafe { std::slice::from_raw_parts_mut(self.memory.add(SEGMENT_HEADER_BYTES + CONTROL_BYTES), self.capacity()) }
+    }
+}
+
+impl Drop for HandoffSegment {
+    fn drop(&mut self) {
+        if self.holds_role.get() {
+            self.header().release(self.role);
+        }
+        // SAFETY: mapped by map with this length; nothing borrows it past self
+        unsafe {
+            libc::munmap(self.memory.cast(), self.len);
+        }
+    }
+}
+
+fn map(file: &File, len: usize) -> io::Result<*mut u8> {
+    // SAFETY: a fresh shared mapping of the file; the kernel picks the address
+    let memory = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if memory == libc::MAP_FAILED {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(memory.cast())
+    }
+}
+
+fn segment_error(error: segment::SegmentError) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, error)
+}
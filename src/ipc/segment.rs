@@ -0,0 +1,253 @@
+//! Versioned header for memory segments shared between processes.
+//!
+//! The SPSC queue and snapshot buffers are plain in-process memory; mapping one into
+//! several processes (a file under `/dev/shm`, say) means every process must agree on
+//! what lies there. The process creating a segment writes a [`SegmentHeader`] at its
+//! start, and every other process goes through [`attach`], which checks the magic, the
+//! header version, what the segment holds and a hash of the payload's layout and
+//! capacity before anything else is read. A build with a different element type, size
+//! or crate version fails with a [`SegmentError`] instead of reading garbage.
+//!
+//! The header also records which process produces and which consumes, with a heartbeat
+//! each, so a restarted process can tell a live peer from a dead one.
+
+use std::any::type_name;
+use std::fmt;
+use std::mem::{align_of, size_of};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// "HFSSEG" and two zero bytes, little endian; written last, so it also marks the header done
+pub const SEGMENT_MAGIC: u64 = u64::from_le_bytes(*b"HFSSEG\0\0");
+pub const SEGMENT_VERSION: u32 = 1;
+/// Bytes before the payload
+pub const SEGMENT_HEADER_BYTES: usize = size_of::<SegmentHeader>();
+
+/// What a segment's payload is
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    Queue = 1,
+    Snapshot = 2,
+}
+
+/// Side of a segment a process takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentRole {
+    Producer,
+    Consumer,
+}
+
+/// Hash of a payload's element type and capacity, compared on attach
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentLayout {
+    pub hash: u64,
+    pub capacity: u64,
+}
+
+impl SegmentLayout {
+    /// Layout of `capacity` elements of `T`, as built by this crate version. The type
+    /// name, size and alignment all go into the hash.
+    pub fn of<T>(capacity: usize) -> Self {
+        let mut hash = FNV_OFFSET;
+        for bytes in [
+            type_name::<T>().as_bytes(),
+            &(size_of::<T>() as u64).to_le_bytes(),
+            &(align_of::<T>() as u64).to_le_bytes(),
+            env!("CARGO_PKG_VERSION").as_bytes(),
+        ] {
+            hash = fnv1a(hash, bytes);
+        }
+        Self {
+            hash,
+            capacity: capacity as u64,
+        }
+    }
+}
+
+pub(crate) const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+pub(crate) fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Why a segment could not be created or attached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentError {
+    /// Shorter than the header
+    TooSmall { len: usize },
+    /// Not aligned for the header
+    Misaligned,
+    /// No header: never created, or not one of ours
+    BadMagic,
+    Version { found: u32, expected: u32 },
+    Kind { found: u32, expected: SegmentKind },
+    /// Created by a build with another element type, size or crate version
+    Layout { found: u64, expected: u64 },
+    Capacity { found: u64, expected: u64 },
+    /// The role is held by a live process
+    Claimed { role: SegmentRole, pid: u32 },
+}
+
+impl fmt::Display for SegmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SegmentError::TooSmall { len } => write!(f, "segment of {} bytes cannot hold its {} byte header", len, SEGMENT_HEADER_BYTES),
+            SegmentError::Misaligned => f.write_str("segment is not aligned for its header"),
+            SegmentError::BadMagic => f.write_str("segment has no header"),
+            SegmentError::Version { found, expected } => write!(f, "segment header version {}, expected {}", found, expected),
+            SegmentError::Kind { found, expected } => write!(f, "segment kind {}, expected {:?}", found, expected),
+            SegmentError::Layout { found, expected } => {
+                write!(f, "segment layout {:016x}, expected {:016x}: built from a different version", found, expected)
+            }
+            SegmentError::Capacity { found, expected } => write!(f, "segment capacity {}, expected {}", found, expected),
+            SegmentError::Claimed { role, pid } => write!(f, "segment {:?} role held by live process {}", role, pid),
+        }
+    }
+}
+
+impl std::error::Error for SegmentError {}
+
+/// Start of a shared segment, see the module docs. Every field another process may
+/// write is atomic.
+#[repr(C, align(64))]
+pub struct SegmentHeader {
+    magic: AtomicU64,
+    version: u32,
+    kind: u32,
+    layout_hash: u64,
+    capacity: u64,
+    // 0 while nobody holds the role
+    pids: [AtomicU32; 2],
+    // Milliseconds since the Unix epoch of each role's last beat
+    heartbeats: [AtomicU64; 2],
+}
+
+/// Write a header at the start of `memory` and return it. The payload starts
+/// [`SEGMENT_HEADER_BYTES`] in.
+///
+/// # Safety
+/// `memory` must be valid for reads and writes of `len` bytes for `'a`, and nothing may
+/// attach to it before this returns.
+pub unsafe fn create<'a>(memory: *mut u8, len: usize, kind: SegmentKind, layout: SegmentLayout) -> Result<&'a SegmentHeader, SegmentError> {
+    check_bounds(memory, len)?;
+    let header = memory.cast::<SegmentHeader>();
+    // SAFETY: in bounds and aligned (checked above), and nobody else reads it yet
+    unsafe {
+        header.write(SegmentHeader {
+            magic: AtomicU64::new(0),
+            version: SEGMENT_VERSION,
+            kind: kind as u32,
+            layout_hash: layout.hash,
+            capacity: layout.capacity,
+            pids: [AtomicU32::new(0), AtomicU32::new(0)],
+            heartbeats: [AtomicU64::new(0), AtomicU64::new(0)],
+        });
+        (*header).magic.store(SEGMENT_MAGIC, Ordering::Release);
+        Ok(&*header)
+    }
+}
+
+/// Validate the header at the start of `memory` against what this process expects
+///
+/// # Safety
+/// `memory` must be valid for reads and atomic writes of `len` bytes for `'a`.
+pub unsafe fn attach<'a>(memory: *const u8, len: usize, kind: SegmentKind, layout: SegmentLayout) -> Result<&'a SegmentHeader, SegmentError> {
+    check_bounds(memory, len)?;
+    // SAFETY: in bounds and aligned; only the magic is read before it shows the rest written
+    let header = unsafe { &*memory.cast::<SegmentHeader>() };
+    if header.magic.load(Ordering::Acquire) != SEGMENT_MAGIC {
+        return Err(SegmentError::BadMagic);
+    }
+    if header.version != SEGMENT_VERSION {
+        return Err(SegmentError::Version { found: header.version, expected: SEGMENT_VERSION });
+    }
+    if header.kind != kind as u32 {
+        return Err(SegmentError::Kind { found: header.kind, expected: kind });
+    }
+    if header.layout_hash != layout.hash {
+        return Err(SegmentError::Layout { found: header.layout_hash, expected: layout.hash });
+    }
+    if header.capacity != layout.capacity {
+        return Err(SegmentError::Capacity { found: header.capacity, expected: layout.capacity });
+    }
+    Ok(header)
+}
+
+fn check_bounds(memory: *const u8, len: usize) -> Result<(), SegmentError> {
+    if len < SEGMENT_HEADER_BYTES {
+        Err(SegmentError::TooSmall { len })
+    } else if !memory.cast::<SegmentHeader>().is_aligned() {
+        Err(SegmentError::Misaligned)
+    } else {
+        Ok(())
+    }
+}
+
+impl SegmentHeader {
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Process holding `role`, if any
+    pub fn pid(&self, role: SegmentRole) -> Option<u32> {
+        match self.pids[role as usize].load(Ordering::Acquire) {
+            0 => None,
+            pid => Some(pid),
+        }
+    }
+
+    /// Take `role` for this process
+    pub fn claim(&self, role: SegmentRole, stale_after: Duration) -> Result<(), SegmentError> {
+        self.claim_as(role, std::process::id(), stale_after)
+    }
+
+    /// Take `role` for process `pid`: free, already its own, or held by a process that has
+    /// not beaten for `stale_after` and is presumed dead. Beats on success.
+    pub fn claim_as(&self, role: SegmentRole, pid: u32, stale_after: Duration) -> Result<(), SegmentError> {
+        let slot = &self.pids[role as usize];
+        let mut holder = slot.load(Ordering::Acquire);
+        loop {
+            if holder != 0 && holder != pid && self.is_alive(role, stale_after) {
+                return Err(SegmentError::Claimed { role, pid: holder });
+            }
+            match slot.compare_exchange(holder, pid, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => break,
+                Err(current) => holder = current,
+            }
+        }
+        self.beat(role);
+        Ok(())
+    }
+
+    /// Give `role` up
+    pub fn release(&self, role: SegmentRole) {
+        self.pids[role as usize].store(0, Ordering::Release);
+    }
+
+    /// Record that `role`'s holder is still running
+    pub fn beat(&self, role: SegmentRole) {
+        self.heartbeats[role as usize].store(now_millis(), Ordering::Release);
+    }
+
+    /// Time of `role`'s last beat
+    pub fn last_beat(&self, role: SegmentRole) -> Option<SystemTime> {
+        match self.heartbeats[role as usize].load(Ordering::Acquire) {
+            0 => None,
+            millis => Some(UNIX_EPOCH + Duration::from_millis(millis)),
+        }
+    }
+
+    /// Whether `role`'s holder beat within `stale_after`
+    pub fn is_alive(&self, role: SegmentRole, stale_after: Duration) -> bool {
+        self.pid(role).is_some()
+            && self.last_beat(role).is_some_and(|beat| beat.elapsed().map_or(true, |age| age <= stale_after))
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_millis() as u64)
+}
@@ -0,0 +1,206 @@
+//! Access counting for fields shared between threads (the `sharing-audit` build).
+//!
+//! A [`Probe`] counts loads and stores per field and per thread. Each thread's counters
+//! sit on cache lines of their own, so counting adds no sharing of its own. A
+//! [`SharingReport`] then places the fields on cache lines and lists false sharing: a
+//! thread storing to one field while another thread touches a different field of the
+//! same line. Two threads on the same field (the SPSC indices, by design) is true sharing
+//! and is not flagged.
+//!
+//! `Spsc` carries a probe on its indices when the feature is on; without it the queue
+//! has no probe and counts nothing.
+
+use crossbeam_utils::CachePadded;
+use std::cell::Cell;
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Line size the report groups fields by
+pub const CACHE_LINE: usize = 64;
+
+/// Threads counted apart per probe; any further threads share the last slot
+pub const MAX_THREADS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Load,
+    Store,
+}
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    // Nonzero id of this thread, assigned on first use
+    static THREAD_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Nonzero id of the calling thread, stable for its lifetime
+pub(crate) fn current_thread_id() -> u64 {
+    THREAD_ID.with(|id| {
+        if id.get() == 0 {
+            id.set(NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed));
+        }
+        id.get()
+    })
+}
+
+/// Load and store counters of `F` named fields
+pub struct Probe<const F: usize> {
+    names: [&'static str; F],
+    // Thread id owning each slot, 0 while free
+    owners: [AtomicU64; MAX_THREADS],
+    labels: Mutex<[Option<String>; MAX_THREADS]>,
+    // [slot][field][load, store]
+    counters: [CachePadded<[[AtomicU64; 2]; F]>; MAX_THREADS],
+}
+
+impl<const F: usize> Probe<F> {
+    pub fn new(names: [&'static str; F]) -> Self {
+        Self {
+            names,
+            owners: std::array::from_fn(|_| AtomicU64::new(0)),
+            labels: Mutex::new(std::array::from_fn(|_| None)),
+            counters: std::array::from_fn(|_| CachePadded::new(std::array::from_fn(|_| [AtomicU64::new(0), AtomicU64::new(0)]))),
+        }
+    }
+
+    /// Count one access to field `field` from the calling thread
+    #[inline]
+    pub fn record(&self, field: usize, access: Access) {
+        let slot = self.slot();
+        self.counters[slot][field][access as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn slot(&self) -> usize {
+        let id = current_thread_id();
+        for (slot, owner) in self.owners.iter().enumerate() {
+            let current = owner.load(Ordering::Relaxed);
+            if current == id {
+                return slot;
+            }
+            if current == 0 && owner.compare_exchange(0, id, Ordering::Relaxed, Ordering::Relaxed).is_ok() {
+                let name = std::thread::current().name().unwrap_or("unnamed").to_string();
+                self.labels.lock().unwrap()[slot] = Some(name);
+                return slot;
+            }
+        }
+        MAX_THREADS - 1
+    }
+
+    /// Counts so far, with `addresses[i]` the address of field `i`
+    pub fn report(&self, addresses: [usize; F]) -> SharingReport {
+        let claimed = self.owners.iter().take_while(|owner| owner.load(Ordering::Relaxed) != 0).count();
+        let labels = self.labels.lock().unwrap();
+        let mut threads: Vec<String> = labels[..claimed].iter().map(|label| label.clone().unwrap_or_else(|| "unnamed".to_string())).collect();
+        drop(labels);
+        if threads.len() == MAX_THREADS {
+            threads[MAX_THREADS - 1].push_str(" (and later threads)");
+        }
+        let fields = (0..F)
+            .map(|field| {
+                let count = |access: Access| {
+                    (0..threads.len()).map(|slot| self.counters[slot][field][access as usize].load(Ordering::Relaxed)).collect()
+                };
+                FieldReport {
+                    name: self.names[field],
+                    address: addresses[field],
+                    loads: count(Access::Load),
+                    stores: count(Access::Store),
+                }
+            })
+            .collect();
+        SharingReport { threads, fields }
+    }
+}
+
+/// Accesses to one field, indexed by `SharingReport::threads`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldReport {
+    pub name: &'static str,
+    pub address: usize,
+    pub loads: Vec<u64>,
+    pub stores: Vec<u64>,
+}
+
+impl FieldReport {
+    pub fn line(&self) -> usize {
+        self.address / CACHE_LINE
+    }
+
+    fn touched_by(&self, thread: usize) -> bool {
+        self.loads[thread] + self.stores[thread] > 0
+    }
+}
+
+/// One thread storing to a field while another touches a different field on its line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FalseSharing {
+    pub line: usize,
+    pub writer: String,
+    pub written: &'static str,
+    pub other: String,
+    pub touched: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharingReport {
+    /// Names of the counted threads, in order of their first access
+    pub threads: Vec<String>,
+    pub fields: Vec<FieldReport>,
+}
+
+impl SharingReport {
+    pub fn false_sharing(&self) -> Vec<FalseSharing> {
+        let mut found = Vec::new();
+        for written in &self.fields {
+            for touched in self.fields.iter().filter(|field| field.name != written.name && field.line() == written.line()) {
+                for writer in (0..self.threads.len()).filter(|&thread| written.stores[thread] > 0) {
+                    for other in (0..self.threads.len()).filter(|&thread| thread != writer && touched.touched_by(thread)) {
+                        found.push(FalseSharing {
+                            line: written.line(),
+                            writer: self.threads[writer].clone(),
+                            written: written.name,
+                            other: self.threads[other].clone(),
+                            touched: touched.name,
+                        });
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+impl fmt::Display for SharingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<12} {:>18}", "field", "cache line")?;
+        for thread in &self.threads {
+            write!(f, "  {:>24}", format!("{} loads/stores", thread))?;
+        }
+        writeln!(f)?;
+        for field in &self.fields {
+            write!(f, "{:<12} {:>#18x}", field.name, field.line() * CACHE_LINE)?;
+            for thread in 0..self.threads.len() {
+                write!(f, "  {:>24}", format!("{}/{}", field.loads[thread], field.stores[thread]))?;
+            }
+            writeln!(f)?;
+        }
+        let found = self.false_sharing();
+        if found.is_empty() {
+            return writeln!(f, "no false sharing");
+        }
+        for hazard in found {
+            writeln!(
+                f,
+                "false sharing on line {:#x}: {} stores {} while {} touches {}",
+                hazard.line * CACHE_LINE,
+                hazard.writer,
+                hazard.written,
+                hazard.other,
+                hazard.touched
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+//! Local input bridge over a Unix domain socket.
+//!
+//! Tools on the same machine (scripts, a keyboard driver, a TUI controller) connect and
+//! write one input per line, `<snake id> <up|down|left|right>`, e.g. with
+//! `socat - UNIX-CONNECT:snake.sock`. Blank lines are skipped and `#` starts a comment.
+//! A malformed line is answered with `error: <reason>` on the same connection and
+//! otherwise ignored. Inputs from every client leave through the one thread polling the
+//! bridge, so it can be the single producer of an SPSC queue.
+
+use crate::game::types::{Direction, Input};
+use std::io::{self, ErrorKind, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+// A client sending this much without a newline is not speaking the protocol
+const MAX_LINE: usize = 256;
+const IDLE_SLEEP: Duration = Duration::from_millis(1);
+
+/// Counters of a bridge since it was bound
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BridgeStats {
+    pub connections: u64,
+    /// Inputs the sink accepted
+    pub forwarded: u64,
+    /// Inputs the sink refused, e.g. because the queue was full
+    pub dropped: u64,
+    /// Malformed lines
+    pub rejected: u64,
+}
+
+struct Client {
+    stream: UnixStream,
+    // Bytes after the last complete line
+    pending: Vec<u8>,
+}
+
+pub struct SocketBridge {
+    listener: UnixListener,
+    path: PathBuf,
+    num_snakes: u32,
+    clients: Vec<Client>,
+    stats: BridgeStats,
+}
+
+impl SocketBridge {
+    /// Listen at `path` for inputs to snakes `0..num_snakes`. A socket file left behind
+    /// by an earlier run is replaced; any other file at `path` is an error.
+    pub fn bind<P: AsRef<Path>>(path: P, num_snakes: u32) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if std::fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+            std::fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            path,
+            num_snakes,
+            clients: Vec::new(),
+            stats: BridgeStats::default(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn stats(&self) -> BridgeStats {
+        self.stats
+    }
+
+    /// Accept waiting clients and hand every complete line received so far to `sink`,
+    /// which returns false when it cannot take the input. Never blocks. Returns the
+    /// number of lines handled.
+    pub fn poll<F: FnMut(Input) -> bool>(&mut self, sink: &mut F) -> io::Result<usize> {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(true)?;
+                    self.clients.push(Client { stream, pending: Vec::new() });
+                    self.stats.connections += 1;
+                    log::debug!("input bridge client connected on {}", self.path.display());
+                }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        let mut lines = 0;
+        let (num_snakes, stats) = (self.num_snakes, &mut self.stats);
+        self.clients.retain_mut(|client| {
+            let open = read_available(client);
+            while let Some(end) = client.pending.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = client.pending.drain(..=end).collect();
+                lines += 1;
+                match std::str::from_utf8(&line).map_err(|_| "not UTF-8".to_string()).and_then(|line| parse_line(line, num_snakes)) {
+                    Ok(Some(input)) if sink(input) => stats.forwarded += 1,
+                    Ok(Some(_)) => stats.dropped += 1,
+                    Ok(None) => {}
+                    Err(reason) => {
+                        stats.rejected += 1;
+                        log::debug!("input bridge rejected a line: {}", reason);
+                        // A client that stopped reading loses its error replies, not its inputs
+                        let _ = writeln!(client.stream, "error: {}", reason);
+                    }
+                }
+            }
+            if client.pending.len() > MAX_LINE {
+                stats.rejected += 1;
+                log::warn!("input bridge dropped a client sending lines over {} bytes", MAX_LINE);
+                let _ = writeln!(client.stream, "error: line longer than {} bytes", MAX_LINE);
+                return false;
+            }
+            open
+        });
+        Ok(lines)
+    }
+
+    /// Poll until `stop` is set, sleeping briefly whenever no client sent anything
+    pub fn serve<F: FnMut(Input) -> bool>(&mut self, stop: &AtomicBool, mut sink: F) -> io::Result<BridgeStats> {
+        while !stop.load(Ordering::Relaxed) {
+            if self.poll(&mut sink)? == 0 {
+                std::thread::sleep(IDLE_SLEEP);
+            }
+        }
+        Ok(self.stats)
+    }
+}
+
+impl Drop for SocketBridge {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Parse one line of the bridge protocol. `Ok(None)` for blank and comment-only lines.
+pub fn parse_line(line: &str, num_snakes: u32) -> Result<Option<Input>, String> {
+    let line = line.split('#').next().unwrap_or_default();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words[..] {
+        [] => Ok(None),
+        [snake_id, direction] => {
+            let snake_id: u32 = snake_id.parse().map_err(|_| format!("bad snake id: {}", snake_id))?;
+            if snake_id >= num_snakes {
+                return Err(format!("no snake {} (ids go up to {})", snake_id, num_snakes.saturating_sub(1)));
+            }
+            let direction = Direction::from_name(direction).ok_or_else(|| format!("bad direction: {}", direction))?;
+            Ok(Some(Input { snake_id, direction }))
+        }
+        _ => Err("expected <snake id> <up|down|left|right>".to_string()),
+    }
+}
+
+// Append whatever the client has sent to its pending bytes. Returns false once it hung up.
+fn read_available(client: &mut Client) -> bool {
+    let mut buf = [0u8; 1024];
+    loop {
+        match client.stream.read(&mut buf) {
+            Ok(0) => return false,
+            Ok(read) => client.pending.extend_from_slice(&buf[..read]),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => return true,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(_) => return false,
+        }
+    }
+}
@@ -1,27 +1,136 @@
 use core::hint::unlikely;
 use crossbeam_utils::CachePadded;
+#[cfg(feature = "sharing-audit")]
+use crate::ipc::sharing::{Probe, SharingReport};
+use crate::ipc::sharing::Access;
+#[cfg(not(feature = "safe-fallbacks"))]
 use std::cell::UnsafeCell;
+#[cfg(not(feature = "safe-fallbacks"))]
 use std::mem::MaybeUninit;
+#[cfg(debug_assertions)]
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// A lock-free, single-producer, single-consumer queue.
+///
+/// Debug builds check the contract: the first thread to produce becomes the producer
+/// and the first to consume the consumer, and a call to either from any other thread
+/// panics. Hand a side to a new thread with [`Spsc::release_producer`] or
+/// [`Spsc::release_consumer`]. Release builds check nothing. [`Spsc::split`] instead
+/// hands out one [`Producer`] and one [`Consumer`], which enforce the contract by type.
+///
+/// Each side keeps the last index it saw of the other next to its own and only loads the
+/// other side's line again once that copy says the queue is full or empty.
 #[allow(dead_code)]
 pub struct Spsc<T, const N: usize> {
-    head: CachePadded<AtomicUsize>,
-    tail: CachePadded<AtomicUsize>,
-    buffer: [UnsafeCell<MaybeUninit<T>>; N],
+    head: CachePadded<Side>,
+    tail: CachePadded<Side>,
+    buffer: Buffer<T, N>,
+    // Loads and stores of head and tail per thread (see sharing_report)
+    #[cfg(feature = "sharing-audit")]
+    probe: Probe<2>,
+    // Thread id of the producer and the consumer, 0 until the first call
+    #[cfg(debug_assertions)]
+    owners: [AtomicU64; 2],
 }
 
+// Written and read with no synchronisation but head and tail
+#[cfg(not(feature = "safe-fallbacks"))]
+type Buffer<T, const N: usize> = [UnsafeCell<MaybeUninit<T>>; N];
+// Safe stand-in for Miri and sanitizer runs; never contended, as head and tail keep the
+// producer and the consumer on different slots. Boxed, as large rings of mutexes would
+// not fit on the stack while being built.
+#[cfg(feature = "safe-fallbacks")]
+type Buffer<T, const N: usize> = Box<[std::sync::Mutex<Option<T>>]>;
+
+// One side's index and its copy of the other side's, only touched by that side (so
+// Relaxed), on one line. A stale copy is behind the real index, which only makes the
+// queue look fuller or emptier than it is.
+#[derive(Default)]
+struct Side {
+    index: AtomicUsize,
+    seen: AtomicUsize,
+}
+
+const HEAD: usize = 0;
+const TAIL: usize = 1;
+
+const PRODUCER: usize = 0;
+const CONSUMER: usize = 1;
+
 #[allow(dead_code)]
 impl<T, const N: usize> Spsc<T, N> {
     pub fn new() -> Self {
         Self {
-            head: CachePadded::new(AtomicUsize::new(0)),
-            tail: CachePadded::new(AtomicUsize::new(0)),
+            head: CachePadded::new(Side::default()),
+            tail: CachePadded::new(Side::default()),
+            #[cfg(not(feature = "safe-fallbacks"))]
             buffer: std::array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit())),
+            #[cfg(feature = "safe-fallbacks")]
+            buffer: (0..N).map(|_| std::sync::Mutex::new(None)).collect(),
+            #[cfg(feature = "sharing-audit")]
+            probe: Probe::new(["head", "tail"]),
+            #[cfg(debug_assertions)]
+            owners: [AtomicU64::new(0), AtomicU64::new(0)],
+        }
+    }
+
+    /// Split the queue into its two sides. Neither handle can be cloned, so there is one
+    /// producer and one consumer, each free to move to another thread.
+    pub fn split(self) -> (Producer<T, N>, Consumer<T, N>) {
+        let queue = Arc::new(self);
+        (Producer { queue: Arc::clone(&queue) }, Consumer { queue })
+    }
+
+    /// Panic unless the calling thread is, or now becomes, the owner of `role`
+    #[inline(always)]
+    fn claim(&self, _role: usize) {
+        #[cfg(debug_assertions)]
+        {
+            let id = crate::ipc::sharing::current_thread_id();
+            if let Err(owner) = self.owners[_role].compare_exchange(0, id, Ordering::Relaxed, Ordering::Relaxed)
+                && owner != id
+            {
+                let (call, side) = if _role == PRODUCER { ("produce", "producer") } else { ("consume", "consumer") };
+                panic!(
+                    "Spsc::{} called from thread {:?}, but another thread is the queue's single {}",
+                    call,
+                    std::thread::current().name().unwrap_or("unnamed"),
+                    side
+                );
+            }
         }
     }
 
+    /// Let the next `produce`, from any thread, become the producer. The caller must make
+    /// sure the old producer is done: joined, or its last `produce` otherwise ordered
+    /// before the new producer's first.
+    pub fn release_producer(&self) {
+        #[cfg(debug_assertions)]
+        self.owners[PRODUCER].store(0, Ordering::Relaxed);
+    }
+
+    /// Let the next `consume`, from any thread, become the consumer (see
+    /// [`Spsc::release_producer`])
+    pub fn release_consumer(&self) {
+        #[cfg(debug_assertions)]
+        self.owners[CONSUMER].store(0, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    fn track(&self, _index: usize, _access: Access) {
+        #[cfg(feature = "sharing-audit")]
+        self.probe.record(_index, _access);
+    }
+
+    /// Which threads loaded and stored head and tail so far, and whether any of them
+    /// share a cache line written by another thread
+    #[cfg(feature = "sharing-audit")]
+    pub fn sharing_report(&self) -> SharingReport {
+        self.probe.report([std::ptr::from_ref(&*self.head).addr(), std::ptr::from_ref(&*self.tail).addr()])
+    }
+
     #[inline]
     fn next_index(&self, index: usize) -> usize {
         // TODO: Check if unlikely work in the elf
@@ -36,23 +145,43 @@ impl<T, const N: usize> Spsc<T, N> {
     ///
     /// This operation is lock-free and only safe to be called from the single producer.
     pub fn produce(&self, val: T) -> bool {
-        let tail = self.tail.load(Ordering::Relaxed);
+        self.claim(PRODUCER);
+        self.push(val)
+    }
+
+    // `produce` minus the ownership check
+    #[inline(always)]
+    fn push(&self, val: T) -> bool {
+        let tail = self.tail.index.load(Ordering::Relaxed);
+        self.track(TAIL, Access::Load);
         let next_tail = self.next_index(tail);
 
-        if next_tail == self.head.load(Ordering::Acquire) {
-            return false;
+        if next_tail == self.tail.seen.load(Ordering::Relaxed) {
+            // Full as far as we know: see how far the consumer got
+            self.track(HEAD, Access::Load);
+            let head = self.head.index.load(Ordering::Acquire);
+            self.tail.seen.store(head, Ordering::Relaxed);
+            if next_tail == head {
+                return false;
+            }
         }
 
+        #[cfg(feature = "safe-fallbacks")]
+        {
+            *self.buffer[tail].lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(val);
+        }
         // Safety
         // This is safe because:
         // 1. We've checked that the queue is not full, so `tail` is a valid slot.
         // 2. We are the single producer, so no other thread is writing to this slot.
+        #[cfg(not(feature = "safe-fallbacks"))]
         unsafe {
             let slot = self.buffer.get_unchecked(tail);
             (*slot.get()).write(val);
         }
 
-        self.tail.store(next_tail, Ordering::Release);
+        self.tail.index.store(next_tail, Ordering::Release);
+        self.track(TAIL, Access::Store);
 
         true
     }
@@ -61,30 +190,128 @@ impl<T, const N: usize> Spsc<T, N> {
     ///
     /// This operation is lock-free and only safe to be called from the single consumer.
     pub fn consume(&self) -> Option<T> {
-        let head = self.head.load(Ordering::Relaxed);
+        self.claim(CONSUMER);
+        self.pop()
+    }
+
+    // `consume` minus the ownership check
+    #[inline(always)]
+    fn pop(&self) -> Option<T> {
+        let head = self.head.index.load(Ordering::Relaxed);
+        self.track(HEAD, Access::Load);
         let next_head = self.next_index(head);
 
-        if head == self.tail.load(Ordering::Acquire) {
-            return None;
+        if head == self.head.seen.load(Ordering::Relaxed) {
+            // Empty as far as we know: see how far the producer got
+            self.track(TAIL, Access::Load);
+            let tail = self.tail.index.load(Ordering::Acquire);
+            self.head.seen.store(tail, Ordering::Relaxed);
+            if head == tail {
+                return None;
+            }
         }
 
+        #[cfg(feature = "safe-fallbacks")]
+        let value = self.buffer[head]
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+            .expect("a slot below tail holds a value");
         // Safety
         // This is safe because:
         // 1. We've checked that the queue is not empty.
         // 2. We are the single consumer, so no other thread is reading from this slot.
+        #[cfg(not(feature = "safe-fallbacks"))]
         let value = unsafe {
             let slot = self.buffer.get_unchecked(head);
             (*slot.get()).assume_init_read()
         };
 
-        self.head.store(next_head, Ordering::Release);
+        self.head.index.store(next_head, Ordering::Release);
+        self.track(HEAD, Access::Store);
 
         Some(value)
     }
+
+    /// Number of queued values. Exact from the consumer thread when the producer is idle;
+    /// otherwise a snapshot that may already be stale.
+    pub fn len(&self) -> usize {
+        let head = self.head.index.load(Ordering::Acquire);
+        let tail = self.tail.index.load(Ordering::Acquire);
+        self.track(HEAD, Access::Load);
+        self.track(TAIL, Access::Load);
+        (tail + N - head) % N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Producing side of a [`Spsc::split`] queue
+pub struct Producer<T, const N: usize> {
+    queue: Arc<Spsc<T, N>>,
+}
+
+impl<T, const N: usize> Producer<T, N> {
+    /// [`Spsc::produce`]; `&mut` so a shared handle cannot produce from two threads
+    #[inline]
+    pub fn produce(&mut self, val: T) -> bool {
+        self.queue.push(val)
+    }
+
+    /// Number of queued values; only ever too high, as the consumer may have taken some since
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// Consuming side of a [`Spsc::split`] queue
+pub struct Consumer<T, const N: usize> {
+    queue: Arc<Spsc<T, N>>,
+}
+
+impl<T, const N: usize> Consumer<T, N> {
+    /// [`Spsc::consume`]; `&mut` so a shared handle cannot consume from two threads
+    #[inline]
+    pub fn consume(&mut self) -> Option<T> {
+        self.queue.pop()
+    }
+
+    /// Number of queued values; only ever too low, as the producer may have added some since
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// The queue both handles share, e.g. to lock it in memory
+    pub fn as_ptr(&self) -> *const Spsc<T, N> {
+        Arc::as_ptr(&self.queue)
+    }
+
+    /// [`Spsc::sharing_report`] of the queue
+    #[cfg(feature = "sharing-audit")]
+    pub fn sharing_report(&self) -> SharingReport {
+        self.queue.sharing_report()
+    }
+}
+
+impl<T, const N: usize> Default for Spsc<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 // Safety
 // This is safe because the SPSC queue is designed to be used by a single producer and a single consumer.
 // The producer only ever writes to the `tail` and the consumer only ever reads from the `head`.
 // The atomic operations on `head` and `tail` ensure that the producer and consumer never access the same slot at the same time.
+#[cfg(not(feature = "safe-fallbacks"))]
 unsafe impl<T: Send, const N: usize> Sync for Spsc<T, N> {}
@@ -1,6 +1,15 @@
 #![feature(likely_unlikely)]
+pub mod env;
+pub mod ffi;
 pub mod game;
+pub mod input;
 pub mod ipc;
+pub mod net;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod runtime;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
 
 #[cfg(test)]
 mod tests;
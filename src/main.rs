@@ -1,148 +1,1040 @@
+use high_frequency_snake::game::apple::AppleScaling;
+use high_frequency_snake::game::codec::GridCodec;
+use high_frequency_snake::game::config::GameConfig;
 use high_frequency_snake::game::engine::GameState;
-use high_frequency_snake::game::types::{Direction, Input};
-use high_frequency_snake::ipc::spsc::Spsc;
-use rand::Rng;
+use high_frequency_snake::game::types::Input;
+use high_frequency_snake::input::load::{ArrivalProcess, DirectionBias, LoadConfig, LoadGenerator, RampProfile};
+use high_frequency_snake::input::scheduler::QueueScheduler;
+use high_frequency_snake::input::source::{InputSource, QueueSource};
+#[cfg(unix)]
+use high_frequency_snake::ipc::handoff::{DEFAULT_HANDOFF_CAPACITY, HandoffSegment};
+use high_frequency_snake::ipc::spsc::{Producer, Spsc};
+use high_frequency_snake::game::scenario::Scenario;
+use high_frequency_snake::game::snapshot::load_snapshot;
+use high_frequency_snake::input::replay::Replay;
+use high_frequency_snake::runtime::balance::BalanceConfig;
+use high_frequency_snake::runtime::compare::{self, DEFAULT_THRESHOLD_PERCENT, Verdict};
+use high_frequency_snake::runtime::determinism::{self, Workload};
+use high_frequency_snake::runtime::doctor::{self, Status};
+use high_frequency_snake::runtime::export::{ExportOptions, write_replay_events, write_run_events};
+use high_frequency_snake::runtime::idle::{Doorbell, IdleConfig, Idler};
+use high_frequency_snake::runtime::logging::{self, LOG_ENV, LogLevels};
+use high_frequency_snake::runtime::platform;
+use high_frequency_snake::runtime::outliers::{OutlierCapture, OutlierConfig};
+use high_frequency_snake::runtime::platform::{TIMESTAMP_UNIT, timestamp};
+use high_frequency_snake::runtime::records::{RecordStore, render_career};
+#[cfg(feature = "phase-sampler")]
+use high_frequency_snake::runtime::sampler::{PhaseBeacon, PhaseSampler};
+use high_frequency_snake::runtime::supervisor::{
+    EXIT_WORKER_PANIC, InputHistory, Shutdown, Supervisor, write_crash_dump,
+};
+use high_frequency_snake::runtime::sweep::{SweepConfig, run_sweep, write_csv};
+use high_frequency_snake::runtime::threads::{self, ThreadRole};
+use high_frequency_snake::runtime::warmup::{WarmupConfig, warmup};
+use high_frequency_snake::runtime::world::{PersistentWorld, WorldConfig};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
+use std::time::Duration;
+#[cfg(feature = "profile")]
 use std::time::Instant;
 
 const QUEUE_CAPACITY: usize = 1024;
+// Input batches kept for the crash dump
+const CRASH_HISTORY_TICKS: usize = 1024;
+const CRASH_DIR: &str = "crash";
+// How long either side of a world handoff waits for the other
+#[cfg(unix)]
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(2);
+// Ticks between heartbeats of the process offering its world for handoff
+#[cfg(unix)]
+const HANDOFF_BEAT_TICKS: u64 = 1024;
+// Phase sampling of the game loop in the phase-sampler build
+#[cfg(feature = "phase-sampler")]
+const PHASE_SAMPLE_INTERVAL: Duration = Duration::from_micros(100);
 
-#[cfg(feature = "profile")]
-fn get_cpu_cycles() -> u64 {
-    unsafe { std::arch::x86_64::_rdtsc() }
+const USAGE: &str = "usage: high-frequency-snake [--log <levels>] [--rate <inputs/sec>] [--arrival uniform|poisson|bursty:<n>] [--ramp <secs>] [--seed <n>] [--turn-probability <p>] [--reverse-share <p>] [--speculate] [--adaptive-idle] [--warmup] [--prefault] [--mlock] [--bucket-stats] [--socket <path>] [--apple-scaling <snakes>,<cells>,<min>,<max>,<every>] [--leaderboard <size>] [--outliers <threshold> [--outlier-snapshots <dir>]]
+       high-frequency-snake sweep [--min-rate <inputs/sec>] [--max-rate <inputs/sec>] [--steps <n>] [--step-secs <secs>] [--arrival <process>] [--out <file.csv>]
+       high-frequency-snake world [--dir <path>] [--checkpoint-every <ticks>] [--keep <n>] [--codec rle|bitmask|lz4] [--target-alive <n>] [--max-occupancy <percent>] [--apple-scaling <spec>] [--leaderboard <size>] [--handoff <file>] [load flags]
+       high-frequency-snake export <file.replay> [--from <file.snap>] [--until <tick>] [--respawns <n>] [--target-alive <n>] [--max-occupancy <percent>] [--ticks] [--audit-inputs] [--out <file.jsonl>]
+       high-frequency-snake doctor
+       high-frequency-snake records <file> [--top <n>] [--snake <id>]
+       high-frequency-snake verify-determinism [<file.scenario>] [--ticks <n>] [--seed <n>] [--snakes <n>] [--turn <percent>] [--threads <a>,<b>]
+       high-frequency-snake compare <baseline> <candidate> [--threshold <percent>]   (perf_summary.json or target/criterion[@<baseline>])";
+
+fn parse_arrival(value: &str) -> Result<ArrivalProcess, String> {
+    match value {
+        "uniform" => Ok(ArrivalProcess::Uniform),
+        "poisson" => Ok(ArrivalProcess::Poisson),
+        bursty => bursty
+            .strip_prefix("bursty:")
+            .and_then(|n| n.parse().ok())
+            .map(|burst_size| ArrivalProcess::Bursty { burst_size })
+            .ok_or_else(|| format!("invalid arrival process: {}", value)),
+    }
 }
 
-fn main() {
-    println!("Snake Battle Royale: Low Level Optimization Playground");
+fn parse_apple_scaling(value: &str) -> Result<AppleScaling, String> {
+    AppleScaling::from_spec(value).ok_or_else(|| format!("invalid apple scaling: {}", value))
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
 
-    // Get the available CPU cores
-    let core_ids = core_affinity::get_core_ids().unwrap();
-    if core_ids.len() < 2 {
-        panic!("This application requires at least 2 CPU cores.");
+/// Parse an offered rate in inputs/sec, which must be finite and above zero
+fn parse_rate(flag: &str, value: &str) -> Result<f64, String> {
+    let rate: f64 = parse_value(flag, value)?;
+    if rate.is_finite() && rate > 0.0 {
+        Ok(rate)
+    } else {
+        Err(format!("{} must be a positive rate: {}", flag, value))
     }
+}
 
-    // Create a shared SPSC queue
-    let queue = Arc::new(Spsc::<Input, QUEUE_CAPACITY>::new());
-    let producer_queue = Arc::clone(&queue);
-    let consumer_queue = Arc::clone(&queue);
+/// Parse a duration in (possibly fractional) seconds; negative or non-finite values are errors
+fn parse_secs(flag: &str, value: &str) -> Result<Duration, String> {
+    Duration::try_from_secs_f64(parse_value(flag, value)?).map_err(|_| format!("invalid duration for {}: {}", flag, value))
+}
 
-    // --- Input Generator Thread ---
-    let input_thread_core = core_ids[0];
-    let input_generator = thread::spawn(move || {
-        // Pin this thread to the first core
-        core_affinity::set_for_current(input_thread_core);
-
-        let mut rng = rand::rng();
-        println!(
-            "Input generator thread started on core {:?}",
-            input_thread_core.id
-        );
-
-        loop {
-            let input = Input {
-                snake_id: rng.random_range(1..=1000), // Simulate for 1000 snakes
-                direction: rng.random(),
-            };
+/// Parse the load generator flags. Unknown flags or bad values return an error message.
+fn parse_load_config(args: &[String]) -> Result<LoadConfig, String> {
+    let mut config = LoadConfig::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--rate" => config.target_rate = parse_rate(flag, value)?,
+            "--arrival" => config.arrival = parse_arrival(value)?,
+            "--ramp" => {
+                config.ramp = RampProfile::Linear {
+                    start_rate: 0.0,
+                    duration: parse_secs(flag, value)?,
+                };
+            }
+            "--seed" => config.seed = parse_value(flag, value)?,
+            "--turn-probability" => {
+                config.direction_bias.get_or_insert(DirectionBias::PLAYER).turn_probability = parse_value(flag, value)?
+            }
+            "--reverse-share" => {
+                config.direction_bias.get_or_insert(DirectionBias::PLAYER).reverse_share = parse_value(flag, value)?
+            }
+            _ => return Err(format!("unknown flag: {}", flag)),
+        }
+    }
+    Ok(config)
+}
+
+/// Parse the `sweep` subcommand flags into a sweep config and optional CSV output path
+fn parse_sweep_config(args: &[String]) -> Result<(SweepConfig, Option<String>), String> {
+    let mut config = SweepConfig::default();
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--min-rate" => config.min_rate = parse_rate(flag, value)?,
+            "--max-rate" => config.max_rate = parse_rate(flag, value)?,
+            "--steps" => config.steps = parse_value(flag, value)?,
+            "--step-secs" => {
+                config.step_duration = parse_secs(flag, value)?;
+                if config.step_duration.is_zero() {
+                    return Err(format!("{} must be above zero", flag));
+                }
+            }
+            "--arrival" => config.arrival = parse_arrival(value)?,
+            "--out" => out = Some(value.clone()),
+            _ => return Err(format!("unknown flag: {}", flag)),
+        }
+    }
+    Ok((config, out))
+}
+
+/// Latency-under-load sweep: offered rate from low to saturation, one CSV row per step
+fn run_sweep_command(args: &[String]) {
+    let (mut config, out) = parse_sweep_config(args).unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        exit(2);
+    });
+
+    config.cores = platform::core_pair();
+
+    log::info!(
+        "Sweeping {:.0} -> {:.0} inputs/sec in {} steps of {:?}",
+        config.min_rate, config.max_rate, config.steps, config.step_duration
+    );
+    let points = run_sweep(&config);
+
+    let result = match out {
+        Some(path) => File::create(&path).and_then(|mut file| write_csv(&points, &mut file)),
+        None => write_csv(&points, &mut std::io::stdout()),
+    };
+    if let Err(error) = result {
+        log::error!("failed to write sweep results: {}", error);
+        exit(1);
+    }
+}
+
+/// Parse the `world` subcommand flags into the world config, the handoff file if any and
+/// the load config; anything not world-specific goes to the load generator
+fn parse_world_config(args: &[String]) -> Result<(WorldConfig, Option<PathBuf>, LoadConfig), String> {
+    let mut config = WorldConfig::default();
+    let mut handoff = None;
+    let mut load_args = Vec::new();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--dir" => config.dir = PathBuf::from(value),
+            "--checkpoint-every" => config.checkpoint_interval = parse_value(flag, value)?,
+            "--keep" => config.keep_checkpoints = parse_value(flag, value)?,
+            "--codec" => {
+                config.snapshot_codec = GridCodec::from_name(value).ok_or_else(|| format!("unknown codec: {}", value))?
+            }
+            "--target-alive" => config.balance.get_or_insert_with(BalanceConfig::default).target_alive = parse_value(flag, value)?,
+            "--max-occupancy" => {
+                config.balance.get_or_insert_with(BalanceConfig::default).max_occupancy_percent = parse_value(flag, value)?
+            }
+            "--apple-scaling" => config.apple_scaling = Some(parse_apple_scaling(value)?),
+            "--leaderboard" => config.leaderboard = Some(parse_value(flag, value)?),
+            "--handoff" => handoff = Some(PathBuf::from(value)),
+            _ => load_args.extend([flag.clone(), value.clone()]),
+        }
+    }
+    Ok((config, handoff, parse_load_config(&load_args)?))
+}
+
+/// Take the world over from the process serving `path`, or open it from its checkpoints
+/// when none is, and offer it for handoff at `path` either way
+#[cfg(unix)]
+fn open_world_for_handoff(config: WorldConfig, path: &Path) -> std::io::Result<(PersistentWorld, HandoffSegment)> {
+    match HandoffSegment::attach(path) {
+        Ok(mut segment) => {
+            let (tick, state) = segment.take_over(HANDOFF_TIMEOUT)?;
+            log::info!("took the world over at tick {} through {:?}", tick, path);
+            Ok((PersistentWorld::resume(config, state)?, segment))
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            let world = PersistentWorld::open(config)?;
+            Ok((world, HandoffSegment::create(path, DEFAULT_HANDOFF_CAPACITY)?))
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Hand the world off to the successor waiting on `segment`, the load stopped: apply what
+/// is queued, checkpoint and publish the state. True once the successor runs the world.
+#[cfg(unix)]
+fn hand_off_world(
+    world: &mut PersistentWorld,
+    segment: &mut HandoffSegment,
+    input_source: &mut QueueSource<QUEUE_CAPACITY>,
+) -> std::io::Result<bool> {
+    let mut inputs = Vec::with_capacity(QUEUE_CAPACITY);
+    input_source.next_batch(world.tick(), &mut inputs);
+    let drained = if inputs.is_empty() { Ok(()) } else { world.step(&inputs) };
+    if let Err(error) = drained.and_then(|()| world.checkpoint()) {
+        segment.abort();
+        return Err(error);
+    }
+    segment.publish(world.state(), world.tick())?;
+    Ok(segment.await_resumed(HANDOFF_TIMEOUT))
+}
+
+/// Load generator thread feeding the world's queue
+struct LoadThread {
+    shutdown: Arc<AtomicBool>,
+    handle: thread::JoinHandle<Producer<Input, QUEUE_CAPACITY>>,
+}
+
+impl LoadThread {
+    fn spawn(load_config: LoadConfig, mut queue: Producer<Input, QUEUE_CAPACITY>) -> Self {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let generator_shutdown = Arc::clone(&shutdown);
+        let handle = thread::spawn(move || {
+            LoadGenerator::new(load_config).run(&generator_shutdown, None, |_, input| queue.produce(input));
+            queue
+        });
+        Self { shutdown, handle }
+    }
+
+    /// Stop the thread and take its end of the queue back
+    fn stop(self) -> Producer<Input, QUEUE_CAPACITY> {
+        self.shutdown.store(true, Ordering::Relaxed);
+        self.handle.join().unwrap_or_else(|payload| panic::resume_unwind(payload))
+    }
+}
+
+/// Persistent world: runs until killed, checkpointing as it goes and resuming on restart.
+/// With `--handoff` a newer process started on the same file takes the world over live.
+fn run_world_command(args: &[String]) {
+    let (config, handoff, mut load_config) = parse_world_config(args).unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        exit(2);
+    });
+
+    #[cfg(unix)]
+    let (mut world, mut handoff) = match handoff {
+        Some(path) => open_world_for_handoff(config, &path).map(|(world, segment)| (world, Some(segment))),
+        None => PersistentWorld::open(config).map(|world| (world, None)),
+    }
+    .unwrap_or_else(|error| {
+        log::error!("failed to open world: {}", error);
+        exit(1);
+    });
+    #[cfg(not(unix))]
+    let mut world = {
+        if handoff.is_some() {
+            eprintln!("--handoff needs shared memory, which this platform lacks\n{}", USAGE);
+            exit(2);
+        }
+        PersistentWorld::open(config).unwrap_or_else(|error| {
+            log::error!("failed to open world: {}", error);
+            exit(1);
+        })
+    };
+    log::info!(
+        "World {:?} at tick {} ({} snakes alive), checkpoint every {} ticks",
+        world.config().dir,
+        world.tick(),
+        world.state().population.alive,
+        world.config().checkpoint_interval
+    );
+    load_config.num_snakes = world.state().snakes.len() as u32;
+
+    let (producer, consumer) = Spsc::<Input, QUEUE_CAPACITY>::new().split();
+    let mut input_source = QueueSource::new(consumer);
+    let mut load = LoadThread::spawn(load_config.clone(), producer);
+
+    let mut inputs = Vec::with_capacity(QUEUE_CAPACITY);
+    loop {
+        input_source.next_batch(world.tick(), &mut inputs);
+        if let Err(error) = world.step(&inputs) {
+            log::error!("checkpoint failed at tick {}: {}", world.tick(), error);
+            break;
+        }
+        inputs.clear();
+
+        #[cfg(unix)]
+        if let Some(segment) = &mut handoff {
+            if world.tick().is_multiple_of(HANDOFF_BEAT_TICKS) {
+                segment.beat();
+            }
+            if segment.poll() {
+                log::info!("handing the world off at tick {}", world.tick());
+                let producer = load.stop();
+                match hand_off_world(&mut world, segment, &mut input_source) {
+                    Ok(true) => {
+                        log::info!("successor took the world over at tick {}", world.tick());
+                        exit(0);
+                    }
+                    Ok(false) => log::warn!("handoff aborted, carrying on at tick {}", world.tick()),
+                    Err(error) => log::warn!("handoff failed, carrying on at tick {}: {}", world.tick(), error),
+                }
+                load = LoadThread::spawn(load_config.clone(), producer);
+            }
+        }
+    }
+
+    load.stop();
+    exit(1);
+}
+
+struct ExportArgs {
+    replay: PathBuf,
+    snapshot: Option<PathBuf>,
+    options: ExportOptions,
+    out: Option<PathBuf>,
+}
+
+fn parse_export_args(args: &[String]) -> Result<ExportArgs, String> {
+    let (replay, args) = args.split_first().ok_or("missing replay file")?;
+    let mut export = ExportArgs {
+        replay: PathBuf::from(replay),
+        snapshot: None,
+        options: ExportOptions::default(),
+        out: None,
+    };
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        if flag == "--ticks" {
+            export.options.tick_summaries = true;
+            continue;
+        }
+        if flag == "--audit-inputs" {
+            export.options.audit_inputs = true;
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--from" => export.snapshot = Some(PathBuf::from(value)),
+            "--until" => export.options.end_tick = Some(parse_value(flag, value)?),
+            "--respawns" => export.options.respawns_per_tick = parse_value(flag, value)?,
+            "--target-alive" => {
+                export.options.balance.get_or_insert_with(BalanceConfig::default).target_alive = parse_value(flag, value)?
+            }
+            "--max-occupancy" => {
+                export.options.balance.get_or_insert_with(BalanceConfig::default).max_occupancy_percent = parse_value(flag, value)?
+            }
+            "--out" => export.out = Some(PathBuf::from(value)),
+            _ => return Err(format!("unknown flag: {}", flag)),
+        }
+    }
+    Ok(export)
+}
+
+/// Compare two bench result artifacts; exits with 1 on a regression past the threshold
+fn run_compare_command(args: &[String]) {
+    let parsed = match args {
+        [baseline, candidate] => Ok((baseline, candidate, DEFAULT_THRESHOLD_PERCENT)),
+        [baseline, candidate, flag, value] if flag == "--threshold" => {
+            parse_value(flag, value).map(|threshold| (baseline, candidate, threshold))
+        }
+        _ => Err("expected <baseline> <candidate> [--threshold <percent>]".to_string()),
+    };
+    let (baseline, candidate, threshold) = parsed.unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        exit(2);
+    });
+    let load = |path: &String| {
+        compare::load_results(path).unwrap_or_else(|error| {
+            log::error!("cannot read {}: {}", path, error);
+            exit(2);
+        })
+    };
+    let comparison = compare::compare(&load(baseline), &load(candidate), threshold);
+    let _ = comparison.write_to(&mut std::io::stdout());
+    if comparison.count(Verdict::Regressed) > 0 {
+        exit(1);
+    }
+}
+
+/// Replay (and optionally its starting snapshot) to JSONL, see `runtime::export` for the schema
+fn run_export_command(args: &[String]) {
+    let export = parse_export_args(args).unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        exit(2);
+    });
+
+    let result = Replay::load(&export.replay).and_then(|replay| {
+        let mut writer: BufWriter<Box<dyn Write>> = BufWriter::new(match &export.out {
+            Some(path) => Box::new(File::create(path)?),
+            None => Box::new(std::io::stdout()),
+        });
+        match &export.snapshot {
+            Some(path) => {
+                let (start_tick, mut state) = load_snapshot(path)?;
+                let repair = state.verify_and_repair();
+                if !repair.is_clean() {
+                    log::warn!("repaired the snapshot's grid:\n{}", repair.to_string().trim_end());
+                }
+                write_run_events(&mut state, start_tick, &replay, export.options, &mut writer)?;
+            }
+            None => write_replay_events(&replay, &mut writer)?,
+        }
+        writer.flush()
+    });
+    if let Err(error) = result {
+        log::error!("export failed: {}", error);
+        exit(1);
+    }
+}
+
+// Ticks and bot turn chance of a seeded run when not given
+const VERIFY_TICKS: u64 = 1_000;
+const VERIFY_TURN_PERCENT: u8 = 10;
+
+fn parse_verify_args(args: &[String]) -> Result<(Workload, [usize; 2]), String> {
+    let (scenario, args) = match args.split_first() {
+        Some((path, rest)) if !path.starts_with("--") => {
+            let scenario = Scenario::load(path).map_err(|error| format!("cannot read {}: {}", path, error))?;
+            (Some(scenario), rest)
+        }
+        _ => (None, args),
+    };
+    let mut config = GameConfig::default();
+    let mut ticks = None;
+    let mut turn_percent = VERIFY_TURN_PERCENT;
+    let mut threads = [1, std::thread::available_parallelism().map_or(1, |cores| cores.get())];
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--ticks" => ticks = Some(parse_value(flag, value)?),
+            "--seed" => config.rng_seed = parse_value(flag, value)?,
+            "--snakes" => config.snake_capacity = parse_value(flag, value)?,
+            "--turn" => turn_percent = parse_value(flag, value)?,
+            "--threads" => {
+                let (first, second) = value.split_once(',').ok_or_else(|| format!("expected <a>,<b> for {}: {}", flag, value))?;
+                threads = [parse_value(flag, first)?, parse_value(flag, second)?];
+            }
+            _ => return Err(format!("unknown flag: {}", flag)),
+        }
+    }
+    let workload = match scenario {
+        Some(mut scenario) => {
+            scenario.ticks = ticks.unwrap_or(scenario.ticks);
+            Workload::Scenario(Box::new(scenario))
+        }
+        None => {
+            config.validate().map_err(|error| error.to_string())?;
+            Workload::Seeded { config, ticks: ticks.unwrap_or(VERIFY_TICKS), turn_percent }
+        }
+    };
+    Ok((workload, threads))
+}
+
+/// Play a scenario or seeded world twice and compare per-tick checksums; exits with 1 on
+/// the first divergence
+fn run_verify_command(args: &[String]) {
+    let (workload, threads) = parse_verify_args(args).unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        exit(2);
+    });
+    log::info!("Verifying {} ticks on {} and {} generator threads", workload.ticks(), threads[0], threads[1]);
+    let report = determinism::verify(&workload, threads);
+    let _ = write!(std::io::stdout(), "{}", report);
+    if !report.is_deterministic() {
+        exit(1);
+    }
+}
+
+// High scores and careers listed by `records` when not given
+const RECORDS_TOP: usize = 10;
 
-            // Push to the queue
-            while !producer_queue.produce(input) {
-                // Queue is full, spin for a moment
-                thread::yield_now();
+/// Print the match history summary of a record store, or one snake's career
+fn run_records_command(args: &[String]) {
+    let parsed = args.split_first().ok_or_else(|| "expected <file>".to_string()).and_then(|(path, rest)| {
+        let (mut top, mut snake) = (RECORDS_TOP, None);
+        let mut rest = rest.iter();
+        while let Some(flag) = rest.next() {
+            let value = rest.next().ok_or_else(|| format!("missing value for {}", flag))?;
+            match flag.as_str() {
+                "--top" => top = parse_value(flag, value)?,
+                "--snake" => snake = Some(parse_value::<u32>(flag, value)?),
+                _ => return Err(format!("unknown flag: {}", flag)),
             }
         }
+        Ok((path, top, snake))
+    });
+    let (path, top, snake) = parsed.unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        exit(2);
+    });
+    let store = RecordStore::open(path).unwrap_or_else(|error| {
+        log::error!("cannot open {}: {}", path, error);
+        exit(2);
+    });
+    if store.skipped() > 0 {
+        log::warn!("skipped {} unreadable lines of {}", store.skipped(), path);
+    }
+    match snake {
+        Some(id) => match store.career(id) {
+            Some(career) => println!("snake matches wins best apples kills deaths longest\n{}", render_career(career)),
+            None => {
+                eprintln!("snake {} has no recorded matches", id);
+                exit(1);
+            }
+        },
+        None => print!("{}", store.render(top)),
+    }
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let levels = match take_value(&mut args, "--log") {
+        Ok(spec) => spec.or_else(|| std::env::var(LOG_ENV).ok()).map_or(Ok(LogLevels::default()), |spec| LogLevels::parse(&spec)),
+        Err(message) => Err(message),
+    };
+    let levels = levels.unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        std::process::exit(2);
     });
+    logging::init(levels).expect("logger already installed");
+
+    if args.first().is_some_and(|command| command == "export") {
+        run_export_command(&args[1..]);
+        return;
+    }
+
+    log::info!("Snake Battle Royale: Low Level Optimization Playground");
+
+    if args.first().is_some_and(|command| command == "sweep") {
+        run_sweep_command(&args[1..]);
+        return;
+    }
+    if args.first().is_some_and(|command| command == "world") {
+        run_world_command(&args[1..]);
+        return;
+    }
+    if args.first().is_some_and(|command| command == "compare") {
+        run_compare_command(&args[1..]);
+        return;
+    }
+    if args.first().is_some_and(|command| command == "verify-determinism") {
+        run_verify_command(&args[1..]);
+        return;
+    }
+    if args.first().is_some_and(|command| command == "records") {
+        run_records_command(&args[1..]);
+        return;
+    }
+    if args.first().is_some_and(|command| command == "doctor") {
+        let report = doctor::diagnose(std::mem::size_of::<Spsc<Input, QUEUE_CAPACITY>>());
+        // A closed stdout (e.g. `| head`) only loses the rest of the report
+        let _ = report.write_to(&mut std::io::stdout());
+        if report.status() == Status::Fail {
+            exit(1);
+        }
+        return;
+    }
+
+    // Boolean flags, so they are taken out before the flag/value pairs are parsed
+    let speculate = args.iter().any(|arg| arg == "--speculate");
+    let adaptive_idle = args.iter().any(|arg| arg == "--adaptive-idle");
+    let warm_up = args.iter().any(|arg| arg == "--warmup");
+    let prefault = args.iter().any(|arg| arg == "--prefault");
+    let lock_memory = args.iter().any(|arg| arg == "--mlock");
+    let bucket_stats = args.iter().any(|arg| arg == "--bucket-stats");
+    let mut args: Vec<String> = args
+        .into_iter()
+        .filter(|arg| !["--speculate", "--adaptive-idle", "--warmup", "--prefault", "--mlock", "--bucket-stats"].contains(&arg.as_str()))
+        .collect();
+    let socket_path = match take_value(&mut args, "--socket") {
+        Ok(path) => path,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            exit(2);
+        }
+    };
+    let apple_scaling = match take_value(&mut args, "--apple-scaling").and_then(|spec| spec.map(|spec| parse_apple_scaling(&spec)).transpose()) {
+        Ok(scaling) => scaling,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            exit(2);
+        }
+    };
+    let leaderboard = match take_value(&mut args, "--leaderboard").and_then(|size| size.map(|size| parse_value("--leaderboard", &size)).transpose()) {
+        Ok(size) => size,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            exit(2);
+        }
+    };
+    let outliers = match parse_outlier_config(&mut args) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            exit(2);
+        }
+    };
+
+    let load_config = match parse_load_config(&args) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            exit(2);
+        }
+    };
+
+    // Refuse to start on a machine the run cannot survive; the full report is `doctor`
+    let report = doctor::diagnose(std::mem::size_of::<Spsc<Input, QUEUE_CAPACITY>>());
+    if report.status() == Status::Fail {
+        let mut text = Vec::new();
+        let _ = report.write_to(&mut text);
+        log::error!("startup checks failed:\n{}", String::from_utf8_lossy(&text).trim_end());
+        exit(1);
+    }
+    if report.count(Status::Warn) > 0 {
+        log::warn!("{} startup checks warned; run `high-frequency-snake doctor` for details", report.count(Status::Warn));
+    }
+
+    // Pin input and game threads to the first two cores where the OS allows it
+    let cores = platform::core_pair();
+    if cores.is_none() {
+        log::warn!("fewer than 2 usable CPU cores; running unpinned, latencies will be noisy");
+    }
+
+    // Create the SPSC queue, its producer for the input thread and its consumer for the game
+    let (mut producer_queue, queue) = Spsc::<Input, QUEUE_CAPACITY>::new().split();
+    if lock_memory {
+        let locked = platform::lock_memory(queue.as_ptr().cast(), std::mem::size_of::<Spsc<Input, QUEUE_CAPACITY>>());
+        if let Err(error) = locked {
+            log::warn!("could not lock the input queue in memory: {}", error);
+        }
+    }
+    // Local tools get a queue of their own: each SPSC queue has exactly one producer
+    let (socket_queue, socket_lane) = match socket_path {
+        Some(path) => {
+            let (producer, consumer) = Spsc::<Input, QUEUE_CAPACITY>::new().split();
+            (Some((path, producer)), Some(consumer))
+        }
+        None => (None, None),
+    };
+    // Both lanes drain completely every tick; local tools go last, so their inputs win
+    let mut input_source = QueueScheduler::new();
+    input_source.add_lane("load", queue, 0);
+    if let Some(socket_lane) = socket_lane {
+        input_source.add_lane("socket", socket_lane, 0);
+    }
+    let mut loop_options = LoopOptions {
+        speculate,
+        outliers: outliers.map(OutlierCapture::new),
+        idler: adaptive_idle.then(|| Idler::new(IdleConfig::default())),
+    };
+    // Producers wake the game loop when it has backed off to sleeping
+    let doorbell = loop_options.idler.as_ref().map(Idler::doorbell);
+    let mut supervisor = Supervisor::new();
+
+    // --- Input Generator Thread ---
+    let input_thread_core = cores.map(|(input, _)| input);
+    let input_doorbell = doorbell.clone();
+    supervisor
+        .spawn("inputgen", move |shutdown| {
+            let registration = threads::register(ThreadRole::InputGen, input_thread_core);
+            log::info!(
+                "Input generator thread started on {} ({:.0} inputs/sec, {:?})",
+                registration.placement(), load_config.target_rate, load_config.arrival
+            );
+
+            // Open loop: inputs that find the queue full are dropped, not retried
+            let mut load_generator = LoadGenerator::new(load_config);
+            if input_doorbell.is_some() {
+                load_generator.set_idle(IdleConfig::default());
+            }
+            load_generator.run(shutdown.flag(), None, |_, input| produce(&mut producer_queue, input, input_doorbell.as_deref()));
+        })
+        .expect("failed to spawn input thread");
+
+    // --- Local Input Bridge Thread ---
+    if let Some((path, socket_queue)) = socket_queue {
+        spawn_socket_bridge(&mut supervisor, &path, socket_queue, doorbell);
+    }
 
     // --- Game Logic Thread ---
-    let game_thread_core = core_ids[1];
-    let game_logic = thread::spawn(move || {
-        // Pin this thread to the second core
-        core_affinity::set_for_current(game_thread_core);
-
-        let mut game_state = GameState::random();
-        let mut inputs = Vec::with_capacity(QUEUE_CAPACITY);
-        println!(
-            "Game logic thread started on core {:?}",
-            game_thread_core.id
-        );
-
-        #[cfg(feature = "profile")]
-        {
-            let mut tick_count = 0u64;
-            let mut total_consume_cycles = 0u64;
-            let mut total_tick_cycles = 0u64;
-            let mut min_consume_cycles = u64::MAX;
-            let mut max_consume_cycles = 0u64;
-            let mut min_tick_cycles = u64::MAX;
-            let mut max_tick_cycles = 0u64;
-            let start_time = Instant::now();
-
-            loop {
-                // Measure the consume part
-                let consume_start_cycles = get_cpu_cycles();
-                while let Some(input) = consumer_queue.consume() {
-                    inputs.push(input);
+    let game_thread_core = cores.map(|(_, game)| game);
+    supervisor
+        .spawn("game", move |shutdown| {
+            let registration = threads::register(ThreadRole::Game, game_thread_core);
+
+            let mut game_state = GameState::random();
+            game_state.set_apple_scaling(apple_scaling);
+            game_state.set_leaderboard(leaderboard);
+            if prefault || lock_memory {
+                game_state.prefault();
+            }
+            if lock_memory {
+                match game_state.lock_memory() {
+                    Ok(bytes) => log::info!("Locked {} MiB of game state in memory", bytes >> 20),
+                    Err(error) => log::warn!("could not lock the game state in memory: {} (see ulimit -l)", error),
                 }
-                let consume_end_cycles = get_cpu_cycles();
-                let consume_cycles = consume_end_cycles - consume_start_cycles;
-
-                // Process the collected inputs
-                if !inputs.is_empty() {
-                    // Measure the tick part
-                    let tick_start_cycles = get_cpu_cycles();
-                    game_state.tick(&inputs);
-                    let tick_end_cycles = get_cpu_cycles();
-                    let tick_cycles = tick_end_cycles - tick_start_cycles;
-                    
-                    // Update consume statistics
-                    total_consume_cycles += consume_cycles;
-                    min_consume_cycles = min_consume_cycles.min(consume_cycles);
-                    max_consume_cycles = max_consume_cycles.max(consume_cycles);
-                    
-                    // Update tick statistics
-                    total_tick_cycles += tick_cycles;
-                    min_tick_cycles = min_tick_cycles.min(tick_cycles);
-                    max_tick_cycles = max_tick_cycles.max(tick_cycles);
-                    
-                    inputs.clear();
+            }
+            if warm_up {
+                // Inputs arriving meanwhile find the queue full and are dropped
+                let report = warmup(&mut game_state, &WarmupConfig::default(), |state, _| {
+                    state.tick(&[]);
+                });
+                log::info!(
+                    "Warm-up: {} ticks, {} at {} {} per tick",
+                    report.ticks,
+                    if report.steady { "steady" } else { "not steady" },
+                    report.mean,
+                    TIMESTAMP_UNIT
+                );
+            }
+            game_state.set_phase_timing(loop_options.outliers.is_some());
+            game_state.set_bucket_stats(bucket_stats);
+            #[cfg(feature = "phase-sampler")]
+            let sampler = {
+                let beacon = Arc::new(PhaseBeacon::new());
+                game_state.set_phase_beacon(Some(Arc::clone(&beacon)), 1);
+                PhaseSampler::start(beacon, PHASE_SAMPLE_INTERVAL).expect("failed to spawn phase sampler")
+            };
+            let mut history = InputHistory::new(CRASH_HISTORY_TICKS);
+            let mut tick_count = 0u64;
+            log::info!("Game logic thread started on {}", registration.placement());
+            log::info!("Threads:\n{}", threads::render(&threads::list()).trim_end());
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                run_game_loop(&mut game_state, &mut input_source, &mut history, &mut tick_count, &mut loop_options, &shutdown)
+            }));
+
+            // Reports go to stderr in one piece, after the log lines before them
+            log::logger().flush();
+            if let Some(capture) = &mut loop_options.outliers {
+                eprintln!("{} slow ticks over {} {}", capture.captured(), capture.config().threshold, TIMESTAMP_UNIT);
+                let _ = capture.flush(&mut std::io::stderr());
+            }
+            if let Some(stats) = game_state.bucket_stats() {
+                eprint!("Bucket occupancy of the last tick:\n{}", stats);
+            }
+            if let Some(board) = game_state.leaderboard() {
+                eprintln!("Leaderboard:");
+                for (rank, standing) in board.top().iter().enumerate() {
+                    eprintln!("{:>4}. snake {} with {} points", rank + 1, standing.snake_id, standing.score);
                 }
+            }
+            #[cfg(feature = "sharing-audit")]
+            for lane in 0..input_source.lane_count() {
+                eprint!("Input queue sharing ({}):\n{}", input_source.lane_name(lane), input_source.lane_queue(lane).sharing_report());
+            }
+            #[cfg(feature = "phase-sampler")]
+            eprint!("Game loop phase samples:\n{}", sampler.stop());
+            if let Some(idler) = &loop_options.idler {
+                let stats = idler.stats();
+                eprintln!(
+                    "Idle polls: {} spinning, {} pausing, {} yielding, {} sleeping",
+                    stats.spins, stats.pauses, stats.yields, stats.sleeps
+                );
+            }
 
-                tick_count += 1;
-
-                // Report performance every 1000 ticks
-                if tick_count % 1000 == 0 {
-                    let elapsed = start_time.elapsed();
-                    let ticks_per_second = tick_count as f64 / elapsed.as_secs_f64();
-                    let avg_consume_cycles = total_consume_cycles / tick_count;
-                    let avg_tick_cycles = total_tick_cycles / tick_count;
-                    
-                    println!(
-                        "Tick {}: {:.2} ticks/sec | Consume: avg={} cycles, min={} cycles, max={} cycles | Tick: avg={} cycles, min={} cycles, max={} cycles",
-                        tick_count, ticks_per_second, 
-                        avg_consume_cycles, min_consume_cycles, max_consume_cycles,
-                        avg_tick_cycles, min_tick_cycles, max_tick_cycles
-                    );
+            // Either we panicked or a peer did: keep the evidence before exiting
+            if result.is_err() || shutdown.is_failure() {
+                match write_crash_dump(Path::new(CRASH_DIR), &game_state, tick_count, &history) {
+                    Ok(path) => log::error!("Crash snapshot written to {}", path.display()),
+                    Err(error) => log::error!("failed to write crash snapshot: {}", error),
                 }
             }
+            if let Err(payload) = result {
+                panic::resume_unwind(payload);
+            }
+        })
+        .expect("failed to spawn game thread");
+
+    // Workers run until one of them fails
+    if let Err(failure) = supervisor.join() {
+        log::error!("{} thread panicked: {}", failure.worker, failure.message);
+        exit(EXIT_WORKER_PANIC);
+    }
+}
+
+/// Serve inputs from local tools at `path` into `queue`; exits the process if it can't bind
+#[cfg(unix)]
+fn spawn_socket_bridge(
+    supervisor: &mut Supervisor,
+    path: &str,
+    mut queue: Producer<Input, QUEUE_CAPACITY>,
+    doorbell: Option<Arc<Doorbell>>,
+) {
+    use high_frequency_snake::game::snake::SNAKE_CAPACITY;
+    use high_frequency_snake::ipc::socket::SocketBridge;
+
+    let mut bridge = SocketBridge::bind(path, SNAKE_CAPACITY as u32).unwrap_or_else(|error| {
+        log::error!("failed to listen on {}: {}", path, error);
+        exit(1);
+    });
+    log::info!("Accepting inputs on {} (one '<snake id> <direction>' per line)", path);
+    supervisor
+        .spawn("net-rx", move |shutdown| {
+            let _registration = threads::register(ThreadRole::NetRx, None);
+            match bridge.serve(shutdown.flag(), |input| produce(&mut queue, input, doorbell.as_deref())) {
+                Ok(stats) => log::info!(
+                    "Socket bridge: {} connections, {} inputs forwarded, {} dropped, {} lines rejected",
+                    stats.connections, stats.forwarded, stats.dropped, stats.rejected
+                ),
+                Err(error) => log::error!("socket bridge stopped: {}", error),
+            }
+        })
+        .expect("failed to spawn socket bridge thread");
+}
+
+#[cfg(not(unix))]
+fn spawn_socket_bridge(
+    _supervisor: &mut Supervisor,
+    _path: &str,
+    _queue: Producer<Input, QUEUE_CAPACITY>,
+    _doorbell: Option<Arc<Doorbell>>,
+) {
+    log::error!("--socket needs Unix domain sockets");
+    exit(2);
+}
+
+/// Enqueue `input` for the game loop, waking it if it sleeps; false when the queue is full
+fn produce(queue: &mut Producer<Input, QUEUE_CAPACITY>, input: Input, doorbell: Option<&Doorbell>) -> bool {
+    let produced = queue.produce(input);
+    if produced && let Some(doorbell) = doorbell {
+        doorbell.ring();
+    }
+    produced
+}
+
+/// Exit with `code` once the log writer has caught up
+fn exit(code: i32) -> ! {
+    log::logger().flush();
+    std::process::exit(code)
+}
+
+/// Take `flag <value>` out of `args`, if present
+fn take_value(args: &mut Vec<String>, flag: &str) -> Result<Option<String>, String> {
+    let Some(index) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        return Err(format!("missing value for {}", flag));
+    }
+    Ok(args.drain(index..index + 2).nth(1))
+}
+
+/// Optional behaviour of the game loop
+struct LoopOptions {
+    /// Precompute the next tick while the queue is empty
+    speculate: bool,
+    /// Keep reports of ticks over a duration threshold
+    outliers: Option<OutlierCapture>,
+    /// Back off while no inputs arrive instead of spinning hot
+    idler: Option<Idler>,
+}
+
+/// Take `--outliers <threshold>` and `--outlier-snapshots <dir>` out of `args`
+fn parse_outlier_config(args: &mut Vec<String>) -> Result<Option<OutlierConfig>, String> {
+    let mut config = None;
+    let mut snapshot_dir = None;
+    let mut index = 0;
+    while index < args.len() {
+        let flag = args[index].as_str();
+        if flag != "--outliers" && flag != "--outlier-snapshots" {
+            index += 2;
+            continue;
         }
+        let value = args.get(index + 1).ok_or_else(|| format!("missing value for {}", flag))?;
+        if flag == "--outliers" {
+            config = Some(OutlierConfig { threshold: parse_value(flag, value)?, ..OutlierConfig::default() });
+        } else {
+            snapshot_dir = Some(PathBuf::from(value));
+        }
+        args.drain(index..index + 2);
+    }
+    if snapshot_dir.is_some() && config.is_none() {
+        return Err("--outlier-snapshots needs --outliers".to_string());
+    }
+    Ok(config.map(|config| OutlierConfig { snapshot_dir, ..config }))
+}
+
+fn run_game_loop(
+    game_state: &mut GameState,
+    input_source: &mut QueueScheduler<QUEUE_CAPACITY>,
+    history: &mut InputHistory,
+    tick_count: &mut u64,
+    options: &mut LoopOptions,
+    shutdown: &Shutdown,
+) {
+    let speculate = options.speculate;
+    let mut inputs = Vec::with_capacity(QUEUE_CAPACITY);
 
-        #[cfg(not(feature = "profile"))]
-        {
-            loop {
-                // Drain the queue
-                while let Some(input) = consumer_queue.consume() {
-                    inputs.push(input);
+    #[cfg(feature = "profile")]
+    {
+        let mut total_consume_cycles = 0u64;
+        let mut total_tick_cycles = 0u64;
+        let mut min_consume_cycles = u64::MAX;
+        let mut max_consume_cycles = 0u64;
+        let mut min_tick_cycles = u64::MAX;
+        let mut max_tick_cycles = 0u64;
+        let start_time = Instant::now();
+
+        while !shutdown.is_requested() {
+            // Measure the consume part
+            let consume_start_cycles = timestamp();
+            input_source.next_batch(*tick_count, &mut inputs);
+            let consume_end_cycles = timestamp();
+            let consume_cycles = consume_end_cycles - consume_start_cycles;
+
+            // Process the collected inputs
+            if !inputs.is_empty() {
+                history.record(*tick_count, &inputs);
+
+                // Measure the tick part
+                let tick_start_cycles = timestamp();
+                game_state.tick(&inputs);
+                let tick_end_cycles = timestamp();
+                let tick_cycles = tick_end_cycles - tick_start_cycles;
+                
+                // Update consume statistics
+                total_consume_cycles += consume_cycles;
+                min_consume_cycles = min_consume_cycles.min(consume_cycles);
+                max_consume_cycles = max_consume_cycles.max(consume_cycles);
+                
+                // Update tick statistics
+                total_tick_cycles += tick_cycles;
+                min_tick_cycles = min_tick_cycles.min(tick_cycles);
+                max_tick_cycles = max_tick_cycles.max(tick_cycles);
+
+                if let Some(capture) = &mut options.outliers {
+                    capture.observe(*tick_count, tick_cycles, game_state, inputs.len(), input_source.depth());
+                }
+                
+                inputs.clear();
+                if let Some(idler) = &mut options.idler {
+                    idler.reset();
                 }
+            } else {
+                idle(game_state, input_source, speculate, &mut options.idler);
+            }
 
-                // Process the collected inputs
-                if !inputs.is_empty() {
-                    game_state.tick(&inputs);
-                    inputs.clear();
+            *tick_count += 1;
+
+            // Report performance every 1000 ticks
+            if tick_count.is_multiple_of(1000) {
+                let elapsed = start_time.elapsed();
+                let ticks_per_second = *tick_count as f64 / elapsed.as_secs_f64();
+                let avg_consume_cycles = total_consume_cycles / *tick_count;
+                let avg_tick_cycles = total_tick_cycles / *tick_count;
+                
+                log::info!(
+                    "Tick {}: {:.2} ticks/sec | Consume: avg={} {unit}, min={} {unit}, max={} {unit} | Tick: avg={} {unit}, min={} {unit}, max={} {unit}",
+                    tick_count, ticks_per_second, 
+                    avg_consume_cycles, min_consume_cycles, max_consume_cycles,
+                    avg_tick_cycles, min_tick_cycles, max_tick_cycles,
+                    unit = TIMESTAMP_UNIT
+                );
+            }
+        }
+    }
+
+    #[cfg(not(feature = "profile"))]
+    {
+        while !shutdown.is_requested() {
+            // Drain the queue
+            input_source.next_batch(*tick_count, &mut inputs);
+
+            // Process the collected inputs
+            if !inputs.is_empty() {
+                history.record(*tick_count, &inputs);
+                match &mut options.outliers {
+                    Some(capture) => {
+                        let start = timestamp();
+                        game_state.tick(&inputs);
+                        let duration = timestamp() - start;
+                        capture.observe(*tick_count, duration, game_state, inputs.len(), input_source.depth());
+                    }
+                    None => {
+                        game_state.tick(&inputs);
+                    }
+                }
+                inputs.clear();
+                if let Some(idler) = &mut options.idler {
+                    idler.reset();
                 }
+            } else {
+                idle(game_state, input_source, speculate, &mut options.idler);
             }
+
+            *tick_count += 1;
         }
-    });
+    }
+}
 
-    input_generator.join().unwrap();
-    game_logic.join().unwrap();
+/// One game loop iteration without inputs: speculate if asked, then back off if asked
+#[inline(always)]
+fn idle(game_state: &mut GameState, input_source: &QueueScheduler<QUEUE_CAPACITY>, speculate: bool, idler: &mut Option<Idler>) {
+    if speculate {
+        // Nothing to do until inputs arrive: get the next tick's records ready
+        game_state.speculate();
+    }
+    if let Some(idler) = idler {
+        idler.idle(|| input_source.depth() > 0);
+    }
 }
@@ -0,0 +1,343 @@
+//! Authoritative time for clients.
+//!
+//! The server stamps a [`TickBeacon`] (its tick number and a monotonic timestamp) from one
+//! [`BeaconClock`] and sends it along with whatever else it sends a client. The client
+//! also sends a [`Probe`] now and then, stamped with its own clock; the server answers
+//! with a [`ProbeReply`] echoing that stamp next to a beacon, and the echo gives the round
+//! trip, which an [`RttEstimator`] smooths. A [`ClockSync`] takes both and gives back the
+//! server time and the fractional server tick for any local instant: a stable time base
+//! to predict and interpolate against.
+//!
+//! Each beacon gives one offset sample, taken to be the server timestamp plus half the
+//! round trip, minus the local arrival time. A reply knows its own round trip; a plain
+//! beacon uses the smoothed one. A least-squares line through the samples of a sliding
+//! window gives the offset now and the drift between the two clocks; samples whose round
+//! trip was much slower than the fastest seen are left out, since their one-way delay is
+//! the least known.
+//!
+//! Timestamps are nanoseconds from an arbitrary origin on each side.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::time::Instant;
+
+/// Offset samples a [`ClockSync`] fits by default
+pub const SYNC_WINDOW: usize = 32;
+// Samples whose round trip exceeds the fastest one seen by more than this are skipped
+const RTT_SLACK_PERCENT: u64 = 50;
+
+/// A server tick and when it ran
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickBeacon {
+    pub tick: u64,
+    /// Server monotonic clock, nanoseconds since its [`BeaconClock`] started
+    pub server_nanos: u64,
+}
+
+impl TickBeacon {
+    /// Encoded size: `tick:u64 server_nanos:u64`, little endian
+    pub const BYTES: usize = 16;
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.tick.to_le_bytes())?;
+        writer.write_all(&self.server_nanos.to_le_bytes())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0u8; Self::BYTES];
+        reader.read_exact(&mut bytes)?;
+        let (tick, nanos) = bytes.split_at(8);
+        Ok(Self {
+            tick: u64::from_le_bytes(tick.try_into().expect("8 bytes")),
+            server_nanos: u64::from_le_bytes(nanos.try_into().expect("8 bytes")),
+        })
+    }
+}
+
+/// A client's request for a [`ProbeReply`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Probe {
+    /// Client clock when sent
+    pub client_nanos: u64,
+}
+
+impl Probe {
+    /// Encoded size: `client_nanos:u64`, little endian
+    pub const BYTES: usize = 8;
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.client_nanos.to_le_bytes())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0u8; Self::BYTES];
+        reader.read_exact(&mut bytes)?;
+        Ok(Self {
+            client_nanos: u64::from_le_bytes(bytes),
+        })
+    }
+}
+
+/// The server's answer to a [`Probe`]: its stamp echoed, and the server's tick and clock
+/// when it answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeReply {
+    pub probe: Probe,
+    pub beacon: TickBeacon,
+}
+
+impl ProbeReply {
+    /// Encoded size: the probe, then the beacon
+    pub const BYTES: usize = Probe::BYTES + TickBeacon::BYTES;
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.probe.write_to(writer)?;
+        self.beacon.write_to(writer)
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Ok(Self {
+            probe: Probe::read_from(reader)?,
+            beacon: TickBeacon::read_from(reader)?,
+        })
+    }
+}
+
+/// The server's monotonic clock for beacons
+#[derive(Debug, Clone, Copy)]
+pub struct BeaconClock {
+    origin: Instant,
+}
+
+impl Default for BeaconClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BeaconClock {
+    pub fn new() -> Self {
+        Self { origin: Instant::now() }
+    }
+
+    /// Nanoseconds since the clock started
+    pub fn nanos(&self) -> u64 {
+        self.origin.elapsed().as_nanos() as u64
+    }
+
+    /// Beacon for `tick`, stamped now
+    pub fn beacon(&self, tick: u64) -> TickBeacon {
+        TickBeacon {
+            tick,
+            server_nanos: self.nanos(),
+        }
+    }
+
+    /// Answer `probe` during `tick`
+    pub fn reply(&self, probe: Probe, tick: u64) -> ProbeReply {
+        ProbeReply {
+            probe,
+            beacon: self.beacon(tick),
+        }
+    }
+}
+
+/// Round-trip time from echoed probes, smoothed the way TCP does (RFC 6298): a moving
+/// average weighted 1/8 per sample and a mean deviation weighted 1/4
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RttEstimator {
+    smoothed: u64,
+    variation: u64,
+    min: u64,
+    samples: u64,
+}
+
+impl RttEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `reply` arrived at `local_nanos`; returns its round trip
+    pub fn observe_reply(&mut self, reply: &ProbeReply, local_nanos: u64) -> u64 {
+        let rtt = local_nanos.saturating_sub(reply.probe.client_nanos);
+        self.observe(rtt);
+        rtt
+    }
+
+    /// One round trip of `rtt` nanoseconds
+    pub fn observe(&mut self, rtt: u64) {
+        if self.samples == 0 {
+            self.smoothed = rtt;
+            self.variation = rtt / 2;
+            self.min = rtt;
+        } else {
+            self.variation = (3 * self.variation + self.smoothed.abs_diff(rtt)) / 4;
+            self.smoothed = (7 * self.smoothed + rtt) / 8;
+            self.min = self.min.min(rtt);
+        }
+        self.samples += 1;
+    }
+
+    /// Smoothed round trip in nanoseconds; 0 before the first sample
+    pub fn smoothed(&self) -> u64 {
+        self.smoothed
+    }
+
+    /// Mean deviation of the round trip
+    pub fn variation(&self) -> u64 {
+        self.variation
+    }
+
+    /// Fastest round trip seen
+    pub fn min(&self) -> u64 {
+        self.min
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+}
+
+// One beacon as the client saw it
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    beacon: TickBeacon,
+    local_nanos: u64,
+    rtt: u64,
+}
+
+impl Sample {
+    // (local time, server minus local), the server time advanced by half the round trip
+    fn point(&self) -> (f64, f64) {
+        let server = self.beacon.server_nanos as f64 + self.rtt as f64 / 2.0;
+        (self.local_nanos as f64, server - self.local_nanos as f64)
+    }
+}
+
+/// Client-side estimate of the server clock from [`TickBeacon`]s, see the module docs
+#[derive(Debug, Clone)]
+pub struct ClockSync {
+    window: usize,
+    samples: VecDeque<Sample>,
+    // Fit of offset (server - local) against local time, around the mean local time
+    mean_local: f64,
+    mean_offset: f64,
+    drift: f64,
+    // Server nanoseconds per tick, from the beacons in the window
+    tick_nanos: f64,
+}
+
+impl Default for ClockSync {
+    fn default() -> Self {
+        Self::new(SYNC_WINDOW)
+    }
+}
+
+impl ClockSync {
+    /// Fit the last `window` samples (at least 2)
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(2),
+            samples: VecDeque::new(),
+            mean_local: 0.0,
+            mean_offset: 0.0,
+            drift: 0.0,
+            tick_nanos: 0.0,
+        }
+    }
+
+    /// Whether a beacon has been observed
+    pub fn is_synced(&self) -> bool {
+        !self.samples.is_empty()
+    }
+
+    /// `beacon` arrived at `local_nanos` on the client clock, with round trips as
+    /// `rtt` currently estimates them
+    pub fn observe(&mut self, beacon: TickBeacon, local_nanos: u64, rtt: &RttEstimator) {
+        self.push(beacon, local_nanos, rtt.smoothed(), rtt.min());
+    }
+
+    /// `reply` arrived at `local_nanos`: feeds its round trip to `rtt` and its beacon,
+    /// corrected by that round trip, to the fit
+    pub fn observe_reply(&mut self, reply: &ProbeReply, local_nanos: u64, rtt: &mut RttEstimator) {
+        let round_trip = rtt.observe_reply(reply, local_nanos);
+        self.push(reply.beacon, local_nanos, round_trip, rtt.min());
+    }
+
+    fn push(&mut self, beacon: TickBeacon, local_nanos: u64, rtt: u64, min_rtt: u64) {
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample { beacon, local_nanos, rtt });
+        self.refit(min_rtt);
+    }
+
+    /// Server clock minus local clock at `local_nanos`, in nanoseconds
+    pub fn offset_at(&self, local_nanos: u64) -> f64 {
+        self.mean_offset + self.drift * (local_nanos as f64 - self.mean_local)
+    }
+
+    /// How much faster the server clock runs, in parts per million
+    pub fn drift_ppm(&self) -> f64 {
+        self.drift * 1e6
+    }
+
+    /// Estimated server clock at `local_nanos`
+    pub fn server_nanos(&self, local_nanos: u64) -> u64 {
+        (local_nanos as f64 + self.offset_at(local_nanos)).max(0.0) as u64
+    }
+
+    /// Server nanoseconds per tick, 0 until two beacons of different ticks arrived
+    pub fn tick_nanos(&self) -> f64 {
+        self.tick_nanos
+    }
+
+    /// Fractional server tick at `local_nanos`: the latest beacon's tick plus the ticks
+    /// since, for interpolating between ticks or predicting ahead of them
+    pub fn tick_at(&self, local_nanos: u64) -> f64 {
+        let Some(last) = self.samples.back() else {
+            return 0.0;
+        };
+        let beacon = last.beacon;
+        if self.tick_nanos <= 0.0 {
+            return beacon.tick as f64;
+        }
+        let since = self.server_nanos(local_nanos) as f64 - beacon.server_nanos as f64;
+        beacon.tick as f64 + since / self.tick_nanos
+    }
+
+    fn refit(&mut self, min_rtt: u64) {
+        let limit = min_rtt + min_rtt * RTT_SLACK_PERCENT / 100;
+        let mut points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .filter(|sample| sample.rtt <= limit)
+            .map(Sample::point)
+            .collect();
+        // Every sample too slow: fall back to the latest
+        if points.is_empty() {
+            points.push(self.samples.back().expect("just pushed").point());
+        }
+
+        let count = points.len() as f64;
+        self.mean_local = points.iter().map(|(local, _)| local).sum::<f64>() / count;
+        self.mean_offset = points.iter().map(|(_, offset)| offset).sum::<f64>() / count;
+        let spread: f64 = points.iter().map(|(local, _)| (local - self.mean_local).powi(2)).sum();
+        self.drift = if spread > 0.0 {
+            points
+                .iter()
+                .map(|(local, offset)| (local - self.mean_local) * (offset - self.mean_offset))
+                .sum::<f64>()
+                / spread
+        } else {
+            0.0
+        };
+
+        let first = self.samples.front().expect("just pushed").beacon;
+        let last = self.samples.back().expect("just pushed").beacon;
+        if last.tick > first.tick {
+            let elapsed = last.server_nanos.saturating_sub(first.server_nanos);
+            self.tick_nanos = elapsed as f64 / (last.tick - first.tick) as f64;
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Simulated network links for local testing.
+//!
+//! A [`SimLink`] carries messages between two ends that count time in ticks, delaying each
+//! by `delay` plus up to `jitter` ticks, losing some, and holding some back so they arrive
+//! after messages sent later. Every decision comes from a seeded generator, so a test sees
+//! the same losses and the same arrival order on every run.
+//!
+//! [`SimSource`] puts a link behind any [`InputSource`], so the engine gets inputs late, out
+//! of order or not at all, as from remote clients. In the other direction a link of encoded
+//! snapshots or deltas stands in for the connection to a client.
+
+use crate::game::fixedpoint::{FRAC_BITS, Fixed};
+use crate::game::types::Input;
+use crate::input::source::InputSource;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+/// How a [`SimLink`] mistreats its messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkConditions {
+    /// Ticks every message takes at least
+    pub delay: u64,
+    /// Up to this many more ticks, uniformly
+    pub jitter: u64,
+    /// Chance a message is lost (fixed point, `Fixed::ONE` = always)
+    pub loss: Fixed,
+    /// Chance a message is held back `reorder_delay` ticks longer than its delay
+    pub reorder: Fixed,
+    pub reorder_delay: u64,
+}
+
+impl LinkConditions {
+    /// Everything arrives on the tick it was sent, in order
+    pub const PERFECT: LinkConditions = LinkConditions {
+        delay: 0,
+        jitter: 0,
+        loss: Fixed::ZERO,
+        reorder: Fixed::ZERO,
+        reorder_delay: 0,
+    };
+}
+
+impl Default for LinkConditions {
+    fn default() -> Self {
+        Self::PERFECT
+    }
+}
+
+/// Counters of a link since it was made
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinkStats {
+    pub sent: u64,
+    pub delivered: u64,
+    pub lost: u64,
+    /// Messages held back by `reorder`
+    pub held_back: u64,
+}
+
+/// A one-way link delivering messages by tick under [`LinkConditions`]
+pub struct SimLink<T> {
+    conditions: LinkConditions,
+    rng: StdRng,
+    // By (arrival tick, send order)
+    in_flight: BTreeMap<(u64, u64), T>,
+    sent: u64,
+    stats: LinkStats,
+}
+
+impl<T> SimLink<T> {
+    pub fn new(conditions: LinkConditions, seed: u64) -> Self {
+        Self {
+            conditions,
+            rng: StdRng::seed_from_u64(seed),
+            in_flight: BTreeMap::new(),
+            sent: 0,
+            stats: LinkStats::default(),
+        }
+    }
+
+    pub fn conditions(&self) -> LinkConditions {
+        self.conditions
+    }
+
+    /// New conditions for messages sent from now on; those in flight keep their arrival tick
+    pub fn set_conditions(&mut self, conditions: LinkConditions) {
+        self.conditions = conditions;
+    }
+
+    pub fn stats(&self) -> LinkStats {
+        self.stats
+    }
+
+    /// Messages sent and neither delivered nor lost yet
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Send `message` on tick `now`. Returns false when the link loses it.
+    pub fn send(&mut self, now: u64, message: T) -> bool {
+        self.stats.sent += 1;
+        if roll(&mut self.rng, self.conditions.loss) {
+            self.stats.lost += 1;
+            log::trace!("simulated link lost a message sent on tick {}", now);
+            return false;
+        }
+        let mut arrival = now + self.conditions.delay;
+        if self.conditions.jitter > 0 {
+            arrival += self.rng.random_range(0..=self.conditions.jitter);
+        }
+        if roll(&mut self.rng, self.conditions.reorder) {
+            arrival += self.conditions.reorder_delay;
+            self.stats.held_back += 1;
+        }
+        self.in_flight.insert((arrival, self.sent), message);
+        self.sent += 1;
+        true
+    }
+
+    /// Append every message arriving by tick `now` to `out`, in arrival order (send order
+    /// within a tick). Returns how many arrived.
+    pub fn receive(&mut self, now: u64, out: &mut Vec<T>) -> usize {
+        let mut received = 0;
+        while let Some(entry) = self.in_flight.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+            out.push(entry.remove());
+            received += 1;
+        }
+        self.stats.delivered += received as u64;
+        received
+    }
+}
+
+/// An [`InputSource`] whose inputs cross a [`SimLink`] before reaching the engine
+pub struct SimSource<S> {
+    source: S,
+    link: SimLink<Input>,
+    scratch: Vec<Input>,
+}
+
+impl<S: InputSource> SimSource<S> {
+    pub fn new(source: S, conditions: LinkConditions, seed: u64) -> Self {
+        Self {
+            source,
+            link: SimLink::new(conditions, seed),
+            scratch: Vec::new(),
+        }
+    }
+
+    pub fn link(&self) -> &SimLink<Input> {
+        &self.link
+    }
+
+    pub fn link_mut(&mut self) -> &mut SimLink<Input> {
+        &mut self.link
+    }
+}
+
+impl<S: InputSource> InputSource for SimSource<S> {
+    fn next_batch(&mut self, tick: u64, buf: &mut Vec<Input>) {
+        self.scratch.clear();
+        self.source.next_batch(tick, &mut self.scratch);
+        for input in self.scratch.drain(..) {
+            self.link.send(tick, input);
+        }
+        self.link.receive(tick, buf);
+    }
+}
+
+fn roll(rng: &mut StdRng, probability: Fixed) -> bool {
+    probability > Fixed::ZERO && Fixed::from_raw((rng.random::<u32>() >> (32 - FRAC_BITS)) as i32) < probability
+}
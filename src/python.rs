@@ -0,0 +1,300 @@
+//! Python bindings (feature `python`), for parameter sweeps and RL experiments from
+//! notebooks while the tick loop stays in Rust.
+//!
+//! ```text
+//! maturin develop --release --features python
+//! >>> import high_frequency_snake as hfs
+//! >>> game = hfs.Game(snake_capacity=64, seed=1)
+//! >>> game.tick([(0, "up")])
+//! ```
+
+use crate::game::config::GameConfig;
+use crate::game::engine::GameState;
+use crate::game::generator::{DeterministicConfig, DeterministicGenerator, LayoutPattern};
+use crate::game::grid::{GRID_HEIGHT, GRID_WIDTH};
+use crate::game::snapshot::{read_snapshot, write_snapshot};
+use crate::game::spawn::BodyShape;
+use crate::game::types::{Direction, Input, Point};
+use crate::input::replay::{Replay, ReplaySource};
+use crate::input::source::from_fn;
+use pyo3::exceptions::{PyIOError, PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+/// Accepts `"up"`/`"down"`/`"left"`/`"right"` or the replay encoding 0..=3
+fn parse_direction(direction: &Bound<'_, PyAny>) -> PyResult<Direction> {
+    if let Ok(code) = direction.extract::<u8>() {
+        return Direction::from_u8(code).ok_or_else(|| PyValueError::new_err(format!("invalid direction {}", code)));
+    }
+    let name: String = direction.extract()?;
+    match name.to_ascii_lowercase().as_str() {
+        "up" => Ok(Direction::Up),
+        "down" => Ok(Direction::Down),
+        "left" => Ok(Direction::Left),
+        "right" => Ok(Direction::Right),
+        _ => Err(PyValueError::new_err(format!("invalid direction {:?}", name))),
+    }
+}
+
+/// `(alive, direction, length, head)` as returned by `Game.snake`
+type SnakeInfo = (bool, &'static str, usize, Option<(u16, u16)>);
+
+/// A game state plus the inputs queued for its next tick
+#[pyclass(name = "Game", unsendable)]
+pub struct PyGame {
+    state: GameState,
+    inputs: Vec<Input>,
+}
+
+impl PyGame {
+    fn wrap(state: GameState) -> Self {
+        Self {
+            state,
+            inputs: Vec::new(),
+        }
+    }
+
+    fn input(&self, snake_id: u32, direction: &Bound<'_, PyAny>) -> PyResult<Input> {
+        if snake_id as usize >= self.state.snakes.len() {
+            return Err(PyIndexError::new_err(format!("no snake {}", snake_id)));
+        }
+        Ok(Input {
+            snake_id,
+            direction: parse_direction(direction)?,
+        })
+    }
+}
+
+#[pymethods]
+impl PyGame {
+    /// Random placement; reproducible when `seed` is given
+    #[new]
+    #[pyo3(signature = (snake_capacity = 1024, apple_capacity = 128, seed = None))]
+    fn new(snake_capacity: usize, apple_capacity: usize, seed: Option<u64>) -> PyResult<Self> {
+        let config = GameConfig {
+            snake_capacity,
+            apple_capacity,
+            ..GameConfig::default()
+        };
+        config.validate().map_err(|error| PyValueError::new_err(error.to_string()))?;
+        let state = match seed {
+            Some(rng_seed) => GameState::seeded(GameConfig { rng_seed, ..config }),
+            None => GameState::random_with_config(config),
+        };
+        Ok(Self::wrap(state))
+    }
+
+    /// Deterministic layout from `game::generator` (`layout` is "grid" or "concentric",
+    /// `shape` is "straight", "l" or "coiled")
+    #[staticmethod]
+    #[pyo3(signature = (num_snakes, seed = 42, layout = "grid", initial_length = 3, apple_capacity = 128, shape = "straight"))]
+    fn deterministic(
+        num_snakes: usize,
+        seed: u64,
+        layout: &str,
+        initial_length: usize,
+        apple_capacity: usize,
+        shape: &str,
+    ) -> PyResult<Self> {
+        let layout_pattern = match layout {
+            "grid" => LayoutPattern::Grid,
+            "concentric" => LayoutPattern::Concentric,
+            _ => return Err(PyValueError::new_err(format!("unknown layout {:?}", layout))),
+        };
+        let body_shape = match shape {
+            "straight" => BodyShape::Straight,
+            "l" => BodyShape::LShaped,
+            "coiled" => BodyShape::Coiled,
+            _ => return Err(PyValueError::new_err(format!("unknown shape {:?}", shape))),
+        };
+        let config = DeterministicConfig {
+            seed,
+            layout_pattern,
+            initial_snake_length: initial_length.max(1),
+            body_shape,
+            game: GameConfig {
+                snake_capacity: num_snakes,
+                apple_capacity,
+                rng_seed: seed,
+                ..GameConfig::default()
+            },
+        };
+        config.game.validate().map_err(|error| PyValueError::new_err(error.to_string()))?;
+        Ok(Self::wrap(DeterministicGenerator::generate(num_snakes, config)))
+    }
+
+    /// Restore a game from `snapshot()` bytes
+    #[staticmethod]
+    fn from_snapshot(data: &[u8]) -> PyResult<Self> {
+        let (_, state) = read_snapshot(&mut &data[..]).map_err(|error| PyValueError::new_err(error.to_string()))?;
+        Ok(Self::wrap(state))
+    }
+
+    /// Queue an input for the next tick
+    fn push_input(&mut self, snake_id: u32, direction: &Bound<'_, PyAny>) -> PyResult<()> {
+        let input = self.input(snake_id, direction)?;
+        self.inputs.push(input);
+        Ok(())
+    }
+
+    /// Advance one tick with the queued inputs plus `inputs` (a list of `(snake_id, direction)`)
+    #[pyo3(signature = (inputs = None))]
+    fn tick(&mut self, inputs: Option<Vec<(u32, Bound<'_, PyAny>)>>) -> PyResult<()> {
+        for (snake_id, direction) in inputs.unwrap_or_default() {
+            let input = self.input(snake_id, &direction)?;
+            self.inputs.push(input);
+        }
+        self.state.tick(&self.inputs);
+        self.inputs.clear();
+        Ok(())
+    }
+
+    /// Advance `ticks` ticks without inputs, releasing the GIL meanwhile
+    fn run(&mut self, py: Python<'_>, ticks: u64) {
+        let state = &mut self.state;
+        py.detach(|| state.tick_n(ticks, state.now(), &mut from_fn(|_, _| {})));
+    }
+
+    /// Play a replay from its first frame to its last, ticking once per replay tick
+    fn play(&mut self, replay: &PyReplay) {
+        let Some(last) = replay.replay.frames.last().map(|frame| frame.tick) else {
+            return;
+        };
+        let first = replay.replay.frames[0].tick;
+        let mut source = ReplaySource::new(replay.replay.clone());
+        self.state.tick_n(last - first + 1, first, &mut source);
+    }
+
+    #[getter]
+    fn tick_count(&self) -> u64 {
+        self.state.now()
+    }
+
+    #[getter]
+    fn snake_count(&self) -> usize {
+        self.state.snakes.len()
+    }
+
+    /// `(alive, direction, length, (head_x, head_y))`
+    fn snake(&self, snake_id: usize) -> PyResult<SnakeInfo> {
+        let snake = self
+            .state
+            .snakes
+            .get(snake_id)
+            .ok_or_else(|| PyIndexError::new_err(format!("no snake {}", snake_id)))?;
+        Ok((
+            snake.is_alive(),
+            snake.snake().direction.name(),
+            snake.body().len(),
+            snake.head().map(|head| (head.x, head.y)),
+        ))
+    }
+
+    /// Body segments, tail first
+    fn snake_body(&self, snake_id: usize) -> PyResult<Vec<(u16, u16)>> {
+        let snake = self
+            .state
+            .snakes
+            .get(snake_id)
+            .ok_or_else(|| PyIndexError::new_err(format!("no snake {}", snake_id)))?;
+        Ok(snake.segments().map(|point| (point.x, point.y)).collect())
+    }
+
+    /// 0 empty, 1 snake, 2 apple, 3 split power-up
+    fn cell(&self, x: u16, y: u16) -> PyResult<u8> {
+        if x as usize >= GRID_WIDTH || y as usize >= GRID_HEIGHT {
+            return Err(PyIndexError::new_err(format!("({}, {}) is off the grid", x, y)));
+        }
+        Ok(self.state.grid.get_cell(&Point { x, y }).to_u8())
+    }
+
+    /// Row-major cell codes of a `width` x `height` window at `(x, y)`, wrapping at the edges.
+    /// Suitable for `numpy.frombuffer(...).reshape(height, width)`.
+    fn view<'py>(&self, py: Python<'py>, x: u16, y: u16, width: usize, height: usize) -> Bound<'py, PyBytes> {
+        let mut cells = Vec::new();
+        self.state.grid.write_view(x as usize, y as usize, width, height, &mut cells);
+        PyBytes::new(py, &cells)
+    }
+
+    fn population<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let population = &self.state.population;
+        let dict = PyDict::new(py);
+        dict.set_item("alive", population.alive)?;
+        dict.set_item("total_body_cells", population.total_body_cells)?;
+        dict.set_item("length_histogram", population.length_histogram.to_vec())?;
+        dict.set_item("births_this_tick", population.births_this_tick)?;
+        dict.set_item("deaths_this_tick", population.deaths_this_tick)?;
+        dict.set_item("total_births", population.total_births)?;
+        dict.set_item("total_deaths", population.total_deaths)?;
+        dict.set_item("num_apples", self.state.num_apples)?;
+        Ok(dict)
+    }
+
+    /// Full state in the `game::snapshot` format
+    fn snapshot<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let mut bytes = Vec::new();
+        write_snapshot(&self.state, self.state.now(), &mut bytes).map_err(|error| PyIOError::new_err(error.to_string()))?;
+        Ok(PyBytes::new(py, &bytes))
+    }
+}
+
+/// A recorded input stream (`input::replay` format)
+#[pyclass(name = "Replay")]
+pub struct PyReplay {
+    replay: Replay,
+}
+
+#[pymethods]
+impl PyReplay {
+    #[new]
+    fn new() -> Self {
+        Self { replay: Replay::new() }
+    }
+
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        let replay = Replay::load(path).map_err(|error| PyIOError::new_err(error.to_string()))?;
+        Ok(Self { replay })
+    }
+
+    fn save(&self, path: &str) -> PyResult<()> {
+        self.replay.save(path).map_err(|error| PyIOError::new_err(error.to_string()))
+    }
+
+    /// Append a batch of `(snake_id, direction)` applied on `tick`
+    fn record(&mut self, tick: u64, inputs: Vec<(u32, Bound<'_, PyAny>)>) -> PyResult<()> {
+        let inputs = inputs
+            .into_iter()
+            .map(|(snake_id, direction)| Ok(Input { snake_id, direction: parse_direction(&direction)? }))
+            .collect::<PyResult<Vec<_>>>()?;
+        self.replay.record(tick, &inputs);
+        Ok(())
+    }
+
+    /// `[(tick, [(snake_id, direction), ...]), ...]`
+    fn frames(&self) -> Vec<(u64, Vec<(u32, &'static str)>)> {
+        self.replay
+            .frames
+            .iter()
+            .map(|frame| {
+                let inputs = frame
+                    .inputs
+                    .iter()
+                    .map(|input| (input.snake_id, input.direction.name()))
+                    .collect();
+                (frame.tick, inputs)
+            })
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.replay.frames.len()
+    }
+}
+
+#[pymodule]
+fn high_frequency_snake(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGame>()?;
+    module.add_class::<PyReplay>()?;
+    Ok(())
+}
@@ -0,0 +1,23 @@
+#[cfg(feature = "tokio-runtime")]
+pub mod async_runtime;
+pub mod balance;
+pub mod branch;
+pub mod compare;
+pub mod corescan;
+pub mod determinism;
+pub mod doctor;
+pub mod export;
+pub mod idle;
+pub mod latency;
+pub mod logging;
+pub mod outliers;
+pub mod platform;
+pub mod records;
+pub mod sampler;
+pub mod shrink;
+pub mod supervisor;
+pub mod sweep;
+pub mod threads;
+pub mod timeshift;
+pub mod warmup;
+pub mod world;
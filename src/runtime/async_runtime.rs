@@ -0,0 +1,175 @@
+use crate::game::config::GameConfig;
+use crate::game::engine::GameState;
+use crate::game::metrics::PopulationMetrics;
+use crate::game::types::Input;
+use crate::input::source::{InputSource, QueueSource};
+use crate::ipc::async_adapter::AsyncProducer;
+use crate::ipc::spsc::Spsc;
+use crate::runtime::records::RecordStore;
+use crate::runtime::supervisor::{Supervisor, WorkerPanic};
+use crate::runtime::threads::{self, ThreadRole};
+use std::io;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch};
+
+pub const ENGINE_QUEUE_CAPACITY: usize = 1024;
+// High scores and careers listed by the admin endpoint's /records
+const ADMIN_RECORDS_TOP: usize = 10;
+
+/// What the game loop publishes to the async side
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EngineStats {
+    pub tick: u64,
+    pub inputs_applied: u64,
+    pub population: PopulationMetrics,
+}
+
+#[derive(Debug, Clone)]
+pub struct AsyncEngineConfig {
+    pub game: GameConfig,
+    /// Pin the game loop here; async workers stay unpinned
+    pub game_core: Option<core_affinity::CoreId>,
+    /// Bound of the multi-producer channel in front of the SPSC queue
+    pub input_buffer: usize,
+    /// Loop iterations between stats publications
+    pub stats_every: u64,
+}
+
+impl Default for AsyncEngineConfig {
+    fn default() -> Self {
+        Self {
+            game: GameConfig::default(),
+            game_core: None,
+            input_buffer: 4096,
+            stats_every: 10_000,
+        }
+    }
+}
+
+/// Async-side handle to a game loop running on its own pinned thread.
+///
+/// Any number of tasks send inputs through a bounded tokio channel; a single forwarding
+/// task moves them into the lock-free SPSC queue the game loop drains. Stats flow back
+/// through a watch channel, which the game loop updates without blocking.
+pub struct EngineHandle {
+    inputs: mpsc::Sender<Input>,
+    stats: watch::Receiver<EngineStats>,
+    supervisor: Supervisor,
+}
+
+/// Start the game loop thread and the input forwarder. Must be called inside a tokio runtime.
+pub fn spawn_engine(config: AsyncEngineConfig) -> io::Result<EngineHandle> {
+    let (producer, queue) = Spsc::<Input, ENGINE_QUEUE_CAPACITY>::new().split();
+    let mut producer = AsyncProducer::new(producer);
+    let (input_sender, mut input_receiver) = mpsc::channel::<Input>(config.input_buffer.max(1));
+    let (stats_sender, stats) = watch::channel(EngineStats::default());
+
+    tokio::spawn(async move {
+        while let Some(input) = input_receiver.recv().await {
+            producer.send(input).await;
+        }
+    });
+
+    let mut supervisor = Supervisor::new();
+    supervisor.spawn("game", move |shutdown| {
+        let _registration = threads::register(ThreadRole::Game, config.game_core);
+        let mut game_state = GameState::random_with_config(config.game);
+        let mut input_source = QueueSource::new(queue);
+        let mut inputs = Vec::with_capacity(ENGINE_QUEUE_CAPACITY);
+        let mut stats = EngineStats {
+            population: game_state.population,
+            ..EngineStats::default()
+        };
+        let stats_every = config.stats_every.max(1);
+
+        while !shutdown.is_requested() {
+            input_source.next_batch(stats.tick, &mut inputs);
+            if !inputs.is_empty() {
+                game_state.tick(&inputs);
+                stats.inputs_applied += inputs.len() as u64;
+                inputs.clear();
+            }
+            stats.tick += 1;
+
+            if stats.tick.is_multiple_of(stats_every) {
+                stats.population = game_state.population;
+                stats_sender.send_replace(stats);
+            }
+        }
+    })?;
+
+    Ok(EngineHandle {
+        inputs: input_sender,
+        stats,
+        supervisor,
+    })
+}
+
+impl EngineHandle {
+    /// A sender for one more input producer (connection, bot, ...)
+    pub fn inputs(&self) -> mpsc::Sender<Input> {
+        self.inputs.clone()
+    }
+
+    /// A receiver of the latest published stats
+    pub fn stats(&self) -> watch::Receiver<EngineStats> {
+        self.stats.clone()
+    }
+
+    /// Stop the game loop and wait for it. Blocks; call from `spawn_blocking` inside async code.
+    pub fn shutdown(self) -> Result<(), WorkerPanic> {
+        self.supervisor.shutdown().request();
+        self.supervisor.join()
+    }
+}
+
+/// Minimal admin endpoint: answers every HTTP request with the latest stats and the thread
+/// registry (see [`threads::render`]) as plain text
+pub async fn serve_admin(listener: TcpListener, stats: watch::Receiver<EngineStats>) -> io::Result<()> {
+    serve_admin_with_records(listener, stats, None).await
+}
+
+/// [`serve_admin`], also answering `GET /records` with the match history, high scores and
+/// careers in `records` (see [`RecordStore::render`])
+pub async fn serve_admin_with_records(
+    listener: TcpListener,
+    stats: watch::Receiver<EngineStats>,
+    records: Option<Arc<Mutex<RecordStore>>>,
+) -> io::Result<()> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let stats = *stats.borrow();
+        let records = records.clone();
+        tokio::spawn(async move {
+            // Only the path of the request line matters; read once so clients see a clean exchange
+            let mut request = [0u8; 1024];
+            let read = stream.read(&mut request).await.unwrap_or(0);
+            let wants_records = request[..read].starts_with(b"GET /records");
+            let body = match &records {
+                Some(records) if wants_records => records.lock().map_or_else(|_| String::new(), |store| store.render(ADMIN_RECORDS_TOP)),
+                _ => render_stats(&stats) + &threads::render(&threads::list()),
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
+
+/// Stats in `name value` lines, as served by [`serve_admin`]
+pub fn render_stats(stats: &EngineStats) -> String {
+    format!(
+        "tick {}\ninputs_applied {}\nalive {}\ntotal_body_cells {}\ntotal_births {}\ntotal_deaths {}\n",
+        stats.tick,
+        stats.inputs_applied,
+        stats.population.alive,
+        stats.population.total_body_cells,
+        stats.population.total_births,
+        stats.population.total_deaths
+    )
+}
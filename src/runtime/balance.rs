@@ -0,0 +1,95 @@
+//! Population auto-balancing for long runs.
+//!
+//! Left alone, a run loses snakes faster than a fixed respawn rate brings them back (or
+//! the other way round), so the work per tick drifts over a long benchmark. The balancer
+//! respawns just enough dead snakes to hold the alive count near a target, and stops
+//! respawning while the grid is crowded.
+//!
+//! Its decisions depend only on the game state, so a run re-simulated with the same
+//! [`BalanceConfig`] (e.g. by `runtime::export`) respawns exactly the same snakes.
+
+use crate::game::engine::GameState;
+use crate::game::grid::{GRID_HEIGHT, GRID_WIDTH};
+use crate::game::snake::SNAKE_CAPACITY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceConfig {
+    /// Alive snakes to hold. Only dead snakes are respawned, so at most the number of
+    /// snakes in the game is reached.
+    pub target_alive: u32,
+    /// Respawns per tick at most, so a mass death is refilled over several ticks
+    pub max_spawns_per_tick: u32,
+    /// Percentage of grid cells covered by snake bodies from which respawns stop
+    pub max_occupancy_percent: u8,
+}
+
+impl Default for BalanceConfig {
+    fn default() -> Self {
+        Self {
+            target_alive: SNAKE_CAPACITY as u32,
+            max_spawns_per_tick: 64,
+            max_occupancy_percent: 50,
+        }
+    }
+}
+
+pub struct PopulationBalancer {
+    config: BalanceConfig,
+    spawned: u64,
+    // Ticks below target on which the occupancy cap stopped respawns
+    throttled_ticks: u64,
+}
+
+impl PopulationBalancer {
+    pub fn new(config: BalanceConfig) -> Self {
+        Self {
+            config,
+            spawned: 0,
+            throttled_ticks: 0,
+        }
+    }
+
+    pub fn config(&self) -> &BalanceConfig {
+        &self.config
+    }
+
+    /// Respawns `state` needs to move towards the target, capped per tick; 0 when at or
+    /// above the target or when the grid is too crowded
+    pub fn budget(&self, state: &GameState) -> usize {
+        let deficit = self.config.target_alive.saturating_sub(state.population.alive);
+        if deficit == 0 || self.crowded(state) {
+            return 0;
+        }
+        deficit.min(self.config.max_spawns_per_tick) as usize
+    }
+
+    /// Respawn dead snakes towards the target; call once after every tick. Returns the
+    /// number respawned.
+    pub fn balance(&mut self, state: &mut GameState) -> usize {
+        let budget = self.budget(state);
+        if budget == 0 {
+            if state.population.alive < self.config.target_alive {
+                self.throttled_ticks += 1;
+            }
+            return 0;
+        }
+        let respawned = state.respawn_dead(budget);
+        self.spawned += respawned as u64;
+        respawned
+    }
+
+    /// Snakes respawned so far
+    pub fn spawned(&self) -> u64 {
+        self.spawned
+    }
+
+    /// Ticks the population was below target but respawns were held back by crowding
+    pub fn throttled_ticks(&self) -> u64 {
+        self.throttled_ticks
+    }
+
+    fn crowded(&self, state: &GameState) -> bool {
+        let cells = (GRID_WIDTH * GRID_HEIGHT) as u64;
+        state.population.total_body_cells * 100 >= self.config.max_occupancy_percent as u64 * cells
+    }
+}
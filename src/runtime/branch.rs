@@ -0,0 +1,276 @@
+//! What-if analysis: fork a [`Scenario`] at a tick and compare how the two runs end.
+//!
+//! Both runs play the scenario's first `at` ticks as recorded. From there the base run keeps
+//! to the scenario while the branch gets other inputs, another collision policy, or both.
+//! The branch is recorded as a scenario starting at the fork, with every input it applied
+//! (the bots' included) in its replay, so it can be saved, replayed or branched again.
+//!
+//! The runs are compared tick by tick on their snakes (head, length, alive); the report gives
+//! the first tick they differ and how each snake, and the population, ended up.
+
+use crate::game::collision::CollisionPolicy;
+use crate::game::engine::GameState;
+use crate::game::scenario::Scenario;
+use crate::game::snake::GridAwareSnake;
+use crate::game::types::Point;
+use crate::input::replay::Replay;
+use std::fmt;
+
+// Snakes listed by Display before summarising the rest
+const LISTED: usize = 16;
+
+/// What the branch changes from tick `at` on
+pub struct Fork {
+    /// Tick of the scenario the branch starts at
+    pub at: u64,
+    /// Inputs replacing the recorded ones from `at` on, ticks counted from `at`; bots keep
+    /// playing. `None` keeps the recorded inputs.
+    pub inputs: Option<Replay>,
+    /// Collision policy the branch switches to at `at`; `None` keeps the classic rules
+    pub policy: Option<Box<dyn CollisionPolicy>>,
+}
+
+impl Fork {
+    /// A branch at `at` that changes nothing yet
+    pub fn at(at: u64) -> Self {
+        Self { at, inputs: None, policy: None }
+    }
+
+    pub fn with_inputs(self, inputs: Replay) -> Self {
+        Self { inputs: Some(inputs), ..self }
+    }
+
+    pub fn with_policy<P: CollisionPolicy + 'static>(self, policy: P) -> Self {
+        Self { policy: Some(Box::new(policy)), ..self }
+    }
+}
+
+/// How one snake ended a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnakeOutcome {
+    pub alive: bool,
+    pub length: usize,
+    /// Scenario tick it died on, when that was after the fork
+    pub died_at: Option<u64>,
+}
+
+/// Population totals at the end of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RunSummary {
+    pub alive: u32,
+    pub body_cells: u64,
+    /// Deaths after the fork
+    pub deaths: u64,
+    /// Kills attributed after the fork (only counted when the config tracks kills)
+    pub kills: u64,
+    pub apples: u64,
+}
+
+/// A snake whose outcome differs between the runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnakeDivergence {
+    pub snake_id: u32,
+    /// `None` for a snake the run never had
+    pub base: Option<SnakeOutcome>,
+    pub branch: Option<SnakeOutcome>,
+}
+
+/// How the base run and the branch compare
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchReport {
+    pub at: u64,
+    /// Ticks simulated after the fork
+    pub ticks: u64,
+    /// First scenario tick after which the snakes of the two runs differed
+    pub diverged_at: Option<u64>,
+    pub base: RunSummary,
+    pub branch: RunSummary,
+    /// Snakes that ended differently, by id
+    pub snakes: Vec<SnakeDivergence>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BranchOutcome {
+    pub report: BranchReport,
+    /// The branch from its fork on, for saving or branching again
+    pub scenario: Scenario,
+}
+
+/// Play `scenario` twice, as recorded and as changed by `fork`, and compare the runs.
+/// A fork past the end of the scenario is moved to its end.
+pub fn branch(scenario: &Scenario, fork: Fork) -> BranchOutcome {
+    let at = fork.at.min(scenario.ticks);
+    let ticks = scenario.ticks - at;
+
+    let mut base = scenario.build();
+    let mut other = scenario.build();
+    let mut base_playback = scenario.playback(&base);
+    let mut other_playback = scenario.playback(&other);
+    for tick in 0..at {
+        base_playback.step(&mut base, tick, None);
+        other_playback.step(&mut other, tick, None);
+    }
+
+    // The recorded branch starts from the forked state; the fork itself changes no entity
+    let mut recorded = Scenario {
+        events: scenario.events.window(at, ticks),
+        ..Scenario::from_state(&other, 0, &Replay::new(), ticks)
+    };
+    if let Some(policy) = fork.policy {
+        other.collision_policy = policy;
+    }
+
+    let mut base_run = Run::new(&base);
+    let mut other_run = Run::new(&other);
+    let mut diverged_at = None;
+    let mut frames = fork.inputs.as_ref().map(|inputs| inputs.frames.iter().peekable());
+    let mut steps = Vec::new();
+    for tick in at..ticks + at {
+        base_playback.step(&mut base, tick, None);
+        base_run.observe(&base, tick);
+
+        let replaced = frames.as_mut().map(|frames| {
+            frames
+                .next_if(|frame| frame.tick == tick - at)
+                .map_or(&[][..], |frame| frame.inputs.as_slice())
+        });
+        let applied = other_playback.step(&mut other, tick, replaced);
+        steps.extend(applied.iter().map(|input| (tick - at, *input)));
+        other_run.observe(&other, tick);
+
+        if diverged_at.is_none() && !same_snakes(&base.snakes, &other.snakes) {
+            diverged_at = Some(tick);
+        }
+    }
+    recorded.replay = Replay::from_steps(steps);
+
+    let base_outcomes = base_run.outcomes(&base);
+    let other_outcomes = other_run.outcomes(&other);
+    let snakes = (0..base_outcomes.len().max(other_outcomes.len()))
+        .filter_map(|id| {
+            let (base, branch) = (base_outcomes.get(id).copied(), other_outcomes.get(id).copied());
+            (base != branch).then_some(SnakeDivergence {
+                snake_id: id as u32,
+                base,
+                branch,
+            })
+        })
+        .collect();
+
+    BranchOutcome {
+        report: BranchReport {
+            at,
+            ticks,
+            diverged_at,
+            base: base_run.summary(&base),
+            branch: other_run.summary(&other),
+            snakes,
+        },
+        scenario: recorded,
+    }
+}
+
+impl fmt::Display for BranchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "branched at tick {}, {} ticks compared", self.at, self.ticks)?;
+        match self.diverged_at {
+            Some(tick) => writeln!(f, "runs diverged on tick {}", tick)?,
+            None => return writeln!(f, "runs never diverged"),
+        }
+        writeln!(f, "{:>12} {:>10} {:>10}", "", "base", "branch")?;
+        let rows = [
+            ("alive", self.base.alive as u64, self.branch.alive as u64),
+            ("body cells", self.base.body_cells, self.branch.body_cells),
+            ("deaths", self.base.deaths, self.branch.deaths),
+            ("kills", self.base.kills, self.branch.kills),
+            ("apples", self.base.apples, self.branch.apples),
+        ];
+        for (name, base, branch) in rows {
+            writeln!(f, "{:>12} {:>10} {:>10}", name, base, branch)?;
+        }
+        for divergence in self.snakes.iter().take(LISTED) {
+            writeln!(
+                f,
+                "snake {}: {} -> {}",
+                divergence.snake_id,
+                describe(divergence.base),
+                describe(divergence.branch)
+            )?;
+        }
+        if self.snakes.len() > LISTED {
+            writeln!(f, "... and {} more snakes", self.snakes.len() - LISTED)?;
+        }
+        Ok(())
+    }
+}
+
+fn describe(outcome: Option<SnakeOutcome>) -> String {
+    match outcome {
+        None => "absent".to_string(),
+        Some(SnakeOutcome { alive: true, length, .. }) => format!("alive, length {}", length),
+        Some(SnakeOutcome { died_at: Some(tick), length, .. }) => format!("died on tick {}, length {}", tick, length),
+        Some(SnakeOutcome { length, .. }) => format!("dead, length {}", length),
+    }
+}
+
+// Deaths and kills of one run after the fork
+struct Run {
+    died_at: Vec<Option<u64>>,
+    alive: Vec<bool>,
+    deaths_before: u64,
+    kills: u64,
+}
+
+impl Run {
+    fn new(state: &GameState) -> Self {
+        Self {
+            died_at: vec![None; state.snakes.len()],
+            alive: state.snakes.iter().map(GridAwareSnake::is_alive).collect(),
+            deaths_before: state.population.total_deaths,
+            kills: 0,
+        }
+    }
+
+    fn observe(&mut self, state: &GameState, tick: u64) {
+        self.kills += state.kills.len() as u64;
+        self.died_at.resize(state.snakes.len(), None);
+        self.alive.resize(state.snakes.len(), false);
+        for (id, snake) in state.snakes.iter().enumerate() {
+            let alive = snake.is_alive();
+            if self.alive[id] && !alive {
+                self.died_at[id] = Some(tick);
+            }
+            self.alive[id] = alive;
+        }
+    }
+
+    fn outcomes(&self, state: &GameState) -> Vec<SnakeOutcome> {
+        state
+            .snakes
+            .iter()
+            .zip(&self.died_at)
+            .map(|(snake, died_at)| SnakeOutcome {
+                alive: snake.is_alive(),
+                length: snake.segments().len(),
+                died_at: if snake.is_alive() { None } else { *died_at },
+            })
+            .collect()
+    }
+
+    fn summary(&self, state: &GameState) -> RunSummary {
+        RunSummary {
+            alive: state.population.alive,
+            body_cells: state.population.total_body_cells,
+            deaths: state.population.total_deaths - self.deaths_before,
+            kills: self.kills,
+            apples: state.num_apples,
+        }
+    }
+}
+
+fn same_snakes(base: &[GridAwareSnake], branch: &[GridAwareSnake]) -> bool {
+    let key = |snake: &GridAwareSnake| -> (bool, usize, Option<Point>) {
+        (snake.is_alive(), snake.segments().len(), snake.segments().next_back())
+    };
+    base.len() == branch.len() && base.iter().zip(branch).all(|(base, branch)| key(base) == key(branch))
+}
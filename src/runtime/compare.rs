@@ -0,0 +1,394 @@
+//! Cross-run benchmark comparison, for gating engine refactors on performance.
+//!
+//! [`compare`] lines up two sets of results of the same benchmarks, a baseline and a
+//! candidate, and reports how every metric present in both moved. [`load_results`] reads
+//! either artifact the bench tooling leaves behind:
+//! - `perf_summary.json` from `benches/perf_summary.py`: cache hit rate, branch prediction
+//!   rate and IPC per snake count (higher is better), with their run-to-run deviations
+//! - a Criterion output directory (`target/criterion`): the mean time of every benchmark
+//!   (lower is better) with its confidence interval. `<dir>@<name>` reads a baseline saved
+//!   with `--save-baseline <name>` instead of the latest run.
+//!
+//! A change is significant when it is larger than both measurements' noise together:
+//! confidence interval half-widths for Criterion, two standard deviations for
+//! perf_summary. A significant change for the worse by more than the threshold is a
+//! regression, and `compare` exits non-zero when there is one.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Regressions smaller than this many percent pass unless a threshold is given
+pub const DEFAULT_THRESHOLD_PERCENT: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub value: f64,
+    /// Half-width of the band around `value` that run-to-run noise alone could explain
+    pub noise: f64,
+    pub higher_is_better: bool,
+    pub unit: &'static str,
+}
+
+/// Measurements by `scenario metric` name
+pub type Results = BTreeMap<String, Measurement>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Improved,
+    Unchanged,
+    Regressed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta {
+    pub name: String,
+    pub baseline: Measurement,
+    pub candidate: Measurement,
+    /// Candidate against baseline; positive when the value went up
+    pub change_percent: f64,
+    /// Larger than the noise of both runs
+    pub significant: bool,
+    pub verdict: Verdict,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub threshold_percent: f64,
+    pub deltas: Vec<Delta>,
+    /// Metrics only one side has; they are listed but never fail the comparison
+    pub only_in_baseline: Vec<String>,
+    pub only_in_candidate: Vec<String>,
+}
+
+impl Comparison {
+    pub fn count(&self, verdict: Verdict) -> usize {
+        self.deltas.iter().filter(|delta| delta.verdict == verdict).count()
+    }
+
+    /// One line per metric, regressions flagged, then a summary line
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{:<56} {:>14} {:>14} {:>9}", "benchmark", "baseline", "candidate", "change")?;
+        for delta in &self.deltas {
+            let verdict = match (delta.verdict, delta.significant) {
+                (Verdict::Regressed, _) => "REGRESSED",
+                (Verdict::Improved, _) => "improved",
+                (Verdict::Unchanged, true) => "within threshold",
+                (Verdict::Unchanged, false) => "noise",
+            };
+            writeln!(
+                writer,
+                "{:<56} {:>14} {:>14} {:>+8.2}% {}",
+                delta.name,
+                format_value(&delta.baseline),
+                format_value(&delta.candidate),
+                delta.change_percent,
+                verdict
+            )?;
+        }
+        for name in &self.only_in_baseline {
+            writeln!(writer, "{:<56} only in baseline", name)?;
+        }
+        for name in &self.only_in_candidate {
+            writeln!(writer, "{:<56} only in candidate", name)?;
+        }
+        writeln!(
+            writer,
+            "{} regressed, {} improved, {} unchanged (threshold {}%)",
+            self.count(Verdict::Regressed),
+            self.count(Verdict::Improved),
+            self.count(Verdict::Unchanged),
+            self.threshold_percent
+        )
+    }
+}
+
+/// Compare every metric both sides measured
+pub fn compare(baseline: &Results, candidate: &Results, threshold_percent: f64) -> Comparison {
+    let deltas = baseline
+        .iter()
+        .filter_map(|(name, before)| candidate.get(name).map(|after| delta(name, *before, *after, threshold_percent)))
+        .collect();
+    let missing = |from: &Results, other: &Results| from.keys().filter(|name| !other.contains_key(*name)).cloned().collect();
+    Comparison {
+        threshold_percent,
+        deltas,
+        only_in_baseline: missing(baseline, candidate),
+        only_in_candidate: missing(candidate, baseline),
+    }
+}
+
+fn delta(name: &str, baseline: Measurement, candidate: Measurement, threshold_percent: f64) -> Delta {
+    let difference = candidate.value - baseline.value;
+    let change_percent = if baseline.value != 0.0 {
+        difference / baseline.value.abs() * 100.0
+    } else if difference == 0.0 {
+        0.0
+    } else {
+        f64::INFINITY.copysign(difference)
+    };
+    let significant = difference.abs() > baseline.noise + candidate.noise;
+    let worse = if baseline.higher_is_better { difference < 0.0 } else { difference > 0.0 };
+    let verdict = match (significant && change_percent.abs() > threshold_percent, worse) {
+        (true, true) => Verdict::Regressed,
+        (true, false) => Verdict::Improved,
+        (false, _) => Verdict::Unchanged,
+    };
+    Delta { name: name.to_string(), baseline, candidate, change_percent, significant, verdict }
+}
+
+fn format_value(measurement: &Measurement) -> String {
+    match measurement.unit {
+        "ns" if measurement.value >= 1e6 => format!("{:.3} ms", measurement.value / 1e6),
+        "ns" if measurement.value >= 1e3 => format!("{:.3} us", measurement.value / 1e3),
+        unit => format!("{:.3}{}", measurement.value, unit),
+    }
+}
+
+/// Results from a `perf_summary.json` file or a Criterion directory (`<dir>[@<baseline>]`)
+pub fn load_results(path: &str) -> io::Result<Results> {
+    let (dir, run) = match path.rsplit_once('@') {
+        Some((dir, run)) if !Path::new(path).exists() => (dir, run),
+        _ => (path, "new"),
+    };
+    if Path::new(dir).is_dir() {
+        let mut results = Results::new();
+        collect_criterion(Path::new(dir), Path::new(dir), run, &mut results)?;
+        if results.is_empty() {
+            return Err(invalid_data(format!("no Criterion '{}' estimates under {}", run, dir)));
+        }
+        return Ok(results);
+    }
+    parse_perf_summary(&fs::read_to_string(path)?)
+}
+
+// Every `<bench>/<run>/estimates.json` below `dir`, named by `<bench>` relative to `root`
+fn collect_criterion(root: &Path, dir: &Path, run: &str, results: &mut Results) -> io::Result<()> {
+    let estimates = dir.join(run).join("estimates.json");
+    if estimates.is_file() {
+        let name = dir.strip_prefix(root).unwrap_or(dir).to_string_lossy().replace('\\', "/");
+        results.insert(format!("{} time", name), parse_criterion_estimates(&fs::read_to_string(estimates)?)?);
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        // Criterion keeps its HTML and the runs of each benchmark next to the benchmarks
+        if entry.file_type()?.is_dir() && name != "report" && name != run {
+            collect_criterion(root, &entry.path(), run, results)?;
+        }
+    }
+    Ok(())
+}
+
+/// The mean time of one Criterion `estimates.json`, noise from its confidence interval
+pub fn parse_criterion_estimates(json: &str) -> io::Result<Measurement> {
+    let json = Json::parse(json)?;
+    let mean = json.get("mean").ok_or_else(|| invalid_data("estimates without a mean"))?;
+    let number = |value: Option<&Json>, what: &str| value.and_then(Json::as_f64).ok_or_else(|| invalid_data(format!("estimates without {}", what)));
+    let value = number(mean.get("point_estimate"), "a point estimate")?;
+    let interval = mean.get("confidence_interval");
+    let lower = number(interval.and_then(|interval| interval.get("lower_bound")), "a lower bound")?;
+    let upper = number(interval.and_then(|interval| interval.get("upper_bound")), "an upper bound")?;
+    Ok(Measurement { value, noise: (upper - lower) / 2.0, higher_is_better: false, unit: "ns" })
+}
+
+/// The per-snake-count metrics of a `perf_summary.json`
+pub fn parse_perf_summary(json: &str) -> io::Result<Results> {
+    const METRICS: [(&str, &str, &str, &str); 3] = [
+        ("cache_hit_rate", "cache_hit_rate_percent", "std_dev_cache_hit_rate", "%"),
+        ("branch_prediction", "branch_prediction_rate_percent", "std_dev_branch_prediction", "%"),
+        ("ipc", "instructions_per_cycle", "std_dev_ipc", ""),
+    ];
+    let json = Json::parse(json)?;
+    let Some(Json::Array(rows)) = json.get("results") else {
+        return Err(invalid_data("perf summary without results"));
+    };
+    let mut results = Results::new();
+    for row in rows {
+        let snakes = row.get("snakes").and_then(Json::as_f64).ok_or_else(|| invalid_data("result without a snake count"))?;
+        // Counts whose runs all failed are written as zeros
+        if row.get("runs_completed").and_then(Json::as_f64) == Some(0.0) {
+            continue;
+        }
+        for (metric, key, deviation, unit) in METRICS {
+            let Some(value) = row.get(key).and_then(Json::as_f64) else { continue };
+            let noise = 2.0 * row.get(deviation).and_then(Json::as_f64).unwrap_or(0.0);
+            let name = format!("perf_counters/{}_snakes {}", snakes, metric);
+            results.insert(name, Measurement { value, noise, higher_is_better: true, unit });
+        }
+    }
+    Ok(results)
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Just enough JSON for the artifacts above
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn parse(text: &str) -> io::Result<Json> {
+        let mut parser = Parser { text, bytes: text.as_bytes(), at: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.at != parser.bytes.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> io::Error {
+        invalid_data(format!("JSON: {} at byte {}", message, self.at))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.bytes.get(self.at).is_some_and(u8::is_ascii_whitespace) {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> io::Result<()> {
+        if self.bytes[self.at..].starts_with(literal.as_bytes()) {
+            self.at += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", literal)))
+        }
+    }
+
+    fn value(&mut self) -> io::Result<Json> {
+        self.skip_whitespace();
+        match self.bytes.get(self.at) {
+            Some(b'n') => self.expect("null").map(|_| Json::Null),
+            Some(b't') => self.expect("true").map(|_| Json::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[') => {
+                self.at += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.at) == Some(&b']') {
+                    self.at += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.bytes.get(self.at) {
+                        Some(b',') => self.at += 1,
+                        Some(b']') => {
+                            self.at += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(self.error("expected , or ]")),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.at += 1;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.at) == Some(&b'}') {
+                    self.at += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(":")?;
+                    fields.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.bytes.get(self.at) {
+                        Some(b',') => self.at += 1,
+                        Some(b'}') => {
+                            self.at += 1;
+                            return Ok(Json::Object(fields));
+                        }
+                        _ => return Err(self.error("expected , or }")),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.at;
+                while self.bytes.get(self.at).is_some_and(|byte| matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+                    self.at += 1;
+                }
+                let number = std::str::from_utf8(&self.bytes[start..self.at]).ok().and_then(|text| text.parse().ok());
+                number.map(Json::Number).ok_or_else(|| self.error("bad number"))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    // Escapes other than \uXXXX surrogate pairs; none of the artifacts need those
+    fn string(&mut self) -> io::Result<String> {
+        self.expect("\"")?;
+        let mut out = String::new();
+        loop {
+            let rest = &self.text[self.at..];
+            let mut chars = rest.chars();
+            match chars.next() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => {
+                    self.at += 1;
+                    return Ok(out);
+                }
+                Some('\\') => {
+                    let escaped = match chars.next() {
+                        Some('u') => {
+                            let code = rest.get(2..6).and_then(|hex| u32::from_str_radix(hex, 16).ok());
+                            self.at += 4;
+                            code.and_then(char::from_u32).ok_or_else(|| self.error("bad \\u escape"))?
+                        }
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some(other @ ('"' | '\\' | '/')) => other,
+                        _ => return Err(self.error("bad escape")),
+                    };
+                    out.push(escaped);
+                    self.at += 2;
+                }
+                Some(other) => {
+                    out.push(other);
+                    self.at += other.len_utf8();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Run-to-run variance of one measurement on every core.
+//!
+//! The benches pin to whichever cores the OS lists first, so their numbers silently
+//! depend on what else those cores do. [`scan`] runs the same measurement pinned to each
+//! core in turn and summarises every core's runs. A core is flagged when its runs spread
+//! widely (interrupts, another busy thread), when its median is well above the best
+//! core's (a slower or shared core), when its later runs are slower than its first ones
+//! (thermal throttling), and when it shares a physical core with another listed core
+//! (SMT siblings, read from `/sys`, so Linux only). [`CoreScan::quietest`] picks cores to
+//! pin to from the result.
+
+use crate::game::config::GameConfig;
+use crate::game::engine::GameState;
+use crate::runtime::doctor::parse_cpulist;
+use crate::runtime::platform;
+use core_affinity::CoreId;
+use std::fmt;
+use std::thread;
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoreScanConfig {
+    /// Measured runs per core
+    pub runs: usize,
+    /// Runs per core thrown away first, to warm caches and clocks
+    pub warmup_runs: usize,
+    /// Flag a core whose runs deviate from their mean by more than this, in percent
+    pub noisy_percent: u32,
+    /// Flag a core whose median exceeds the best core's median by more than this
+    pub slow_percent: u32,
+    /// Flag a core whose last third of runs has a median this much above its first third's
+    pub throttle_percent: u32,
+}
+
+impl Default for CoreScanConfig {
+    fn default() -> Self {
+        Self {
+            runs: 30,
+            warmup_runs: 3,
+            noisy_percent: 5,
+            slow_percent: 10,
+            throttle_percent: 5,
+        }
+    }
+}
+
+/// Why a core is a poor place to measure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoreFlag {
+    /// Its runs spread more than `noisy_percent`
+    Noisy,
+    /// Its median is more than `slow_percent` above the best core's
+    Slow,
+    /// It got slower as it ran
+    Throttling,
+    /// It shares a physical core with another listed core
+    SmtSibling,
+    /// The OS refused to pin to it, so its runs went wherever the scheduler put them
+    Unpinned,
+}
+
+impl fmt::Display for CoreFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CoreFlag::Noisy => "noisy",
+            CoreFlag::Slow => "slow",
+            CoreFlag::Throttling => "throttling",
+            CoreFlag::SmtSibling => "smt-sibling",
+            CoreFlag::Unpinned => "unpinned",
+        })
+    }
+}
+
+/// One core's runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreResult {
+    pub core: usize,
+    /// Other listed cores on the same physical core
+    pub siblings: Vec<usize>,
+    pub pinned: bool,
+    /// Measurements in run order, in the measurement's unit
+    pub samples: Vec<u64>,
+    pub min: u64,
+    pub median: u64,
+    pub mean: f64,
+    /// Standard deviation over the mean, in percent
+    pub spread_percent: f64,
+    /// Median of the last third of runs over that of the first third, minus one, in percent
+    pub drift_percent: f64,
+    pub flags: Vec<CoreFlag>,
+}
+
+impl CoreResult {
+    /// Summarise `samples` of `core`; flags are set by [`CoreScan::from_results`]
+    pub fn from_samples(core: usize, siblings: Vec<usize>, pinned: bool, samples: Vec<u64>) -> Self {
+        let count = samples.len().max(1) as f64;
+        let mean = samples.iter().sum::<u64>() as f64 / count;
+        let variance = samples.iter().map(|&sample| (sample as f64 - mean).powi(2)).sum::<f64>() / count;
+        let third = samples.len() / 3;
+        let (first, last) = (median(&samples[..third]), median(&samples[samples.len() - third..]));
+        Self {
+            core,
+            siblings,
+            pinned,
+            min: samples.iter().copied().min().unwrap_or(0),
+            median: median(&samples),
+            mean,
+            spread_percent: if mean > 0.0 { variance.sqrt() / mean * 100.0 } else { 0.0 },
+            drift_percent: if third > 0 && first > 0 { (last as f64 / first as f64 - 1.0) * 100.0 } else { 0.0 },
+            samples,
+            flags: Vec::new(),
+        }
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.flags.is_empty()
+    }
+}
+
+fn median(samples: &[u64]) -> u64 {
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    sorted.get(sorted.len() / 2).copied().unwrap_or(0)
+}
+
+/// Every core's runs, see the module docs
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreScan {
+    pub config: CoreScanConfig,
+    /// Unit of the samples, for display
+    pub unit: &'static str,
+    pub cores: Vec<CoreResult>,
+}
+
+impl CoreScan {
+    /// Flag `cores` against each other
+    pub fn from_results(config: CoreScanConfig, unit: &'static str, mut cores: Vec<CoreResult>) -> Self {
+        let best = cores.iter().filter(|core| core.pinned).map(|core| core.median).min().unwrap_or(0);
+        for core in &mut cores {
+            if !core.pinned {
+                core.flags.push(CoreFlag::Unpinned);
+            }
+            if core.spread_percent > config.noisy_percent as f64 {
+                core.flags.push(CoreFlag::Noisy);
+            }
+            if best > 0 && core.median > best + best * config.slow_percent as u64 / 100 {
+                core.flags.push(CoreFlag::Slow);
+            }
+            if core.drift_percent > config.throttle_percent as f64 {
+                core.flags.push(CoreFlag::Throttling);
+            }
+            if !core.siblings.is_empty() {
+                core.flags.push(CoreFlag::SmtSibling);
+            }
+        }
+        Self { config, unit, cores }
+    }
+
+    /// Up to `count` cores to pin to, best median first: unflagged ones, where an SMT
+    /// sibling of a core already picked is skipped, and so none share a physical core
+    pub fn quietest(&self, count: usize) -> Vec<CoreId> {
+        let mut ranked: Vec<&CoreResult> = self
+            .cores
+            .iter()
+            .filter(|core| core.flags.iter().all(|&flag| flag == CoreFlag::SmtSibling))
+            .collect();
+        ranked.sort_by_key(|core| (core.median, core.core));
+        let mut picked: Vec<&CoreResult> = Vec::new();
+        for core in ranked {
+            if picked.len() < count && !picked.iter().any(|other| other.siblings.contains(&core.core)) {
+                picked.push(core);
+            }
+        }
+        picked.iter().map(|core| CoreId { id: core.core }).collect()
+    }
+}
+
+impl fmt::Display for CoreScan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>5} {:>12} {:>12} {:>8} {:>8}  flags ({} runs per core, {})",
+            "core", "min", "median", "spread%", "drift%", self.config.runs, self.unit
+        )?;
+        for core in &self.cores {
+            let flags: Vec<String> = core.flags.iter().map(|flag| flag.to_string()).collect();
+            writeln!(
+                f,
+                "{:>5} {:>12} {:>12} {:>8.2} {:>8.2}  {}",
+                core.core,
+                core.min,
+                core.median,
+                core.spread_percent,
+                core.drift_percent,
+                flags.join(",")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Other cores of `cores` sharing a physical core with `core`; empty off Linux
+pub fn smt_siblings(core: usize, cores: &[usize]) -> Vec<usize> {
+    let path = format!("/sys/devices/system/cpu/cpu{}/topology/thread_siblings_list", core);
+    std::fs::read_to_string(path)
+        .map(|list| parse_cpulist(&list))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|&sibling| sibling != core && cores.contains(&sibling))
+        .collect()
+}
+
+/// Run `measure` pinned to every core the OS lists, one core after the other, each time
+/// on a fresh thread: `config.warmup_runs` runs thrown away, then `config.runs` kept.
+/// `measure` returns one measurement in `unit`s, smaller being better.
+pub fn scan<F>(config: CoreScanConfig, unit: &'static str, measure: F) -> CoreScan
+where
+    F: FnMut() -> u64 + Send + Clone,
+{
+    let cores = platform::core_ids();
+    let ids: Vec<usize> = cores.iter().map(|core| core.id).collect();
+    let results = cores
+        .iter()
+        .map(|&core| {
+            let mut measure = measure.clone();
+            let (pinned, samples) = thread::scope(|scope| {
+                scope
+                    .spawn(move || {
+                        let pinned = platform::pin_current(core);
+                        for _ in 0..config.warmup_runs {
+                            measure();
+                        }
+                        (pinned, (0..config.runs).map(|_| measure()).collect())
+                    })
+                    .join()
+                    .expect("measurement panicked")
+            });
+            CoreResult::from_samples(core.id, smt_siblings(core.id, &ids), pinned, samples)
+        })
+        .collect();
+    CoreScan::from_results(config, unit, results)
+}
+
+/// A measurement for [`scan`]: nanoseconds `ticks` input-free ticks take on a state seeded
+/// from `config`, built afresh (and untimed) every run
+pub fn tick_probe(config: GameConfig, ticks: u64) -> impl FnMut() -> u64 + Send + Clone {
+    move || {
+        let mut state = GameState::seeded(config);
+        let start = Instant::now();
+        for _ in 0..ticks {
+            state.tick(&[]);
+        }
+        start.elapsed().as_nanos() as u64
+    }
+}
@@ -0,0 +1,243 @@
+//! Reproducibility check: play the same seeded workload twice and compare every tick.
+//!
+//! Both runs start from the same workload, either a [`Scenario`] or a world generated from
+//! a seed and steered by seeded bots, and are stepped in lockstep. After every tick (and
+//! once before the first) each run's state is folded into a [`checksum`]; the first tick
+//! the checksums differ is reported with the cells, snakes and RNG streams that differ.
+//!
+//! A seeded world is generated band by band on the number of threads given for each run
+//! (see `RandomGenerator::generate_parallel`), so running with 1 and N threads also checks
+//! that the parallel generator builds one state whatever the thread count. A scenario is
+//! built the same way for both runs and the thread counts change nothing.
+
+use crate::game::config::GameConfig;
+use crate::game::engine::GameState;
+use crate::game::fixedpoint::Fixed;
+use crate::game::generator::RandomGenerator;
+use crate::game::grid::{Cell, TILES_X, TILES_Y};
+use crate::game::scenario::{Playback, Scenario};
+use crate::game::snake::GridAwareSnake;
+use crate::game::spawn::SpawnConfig;
+use crate::game::types::{Direction, Input, Point};
+use crate::input::bot::RandomBot;
+use crate::input::source::InputSource;
+use std::fmt;
+
+// Cells and snakes listed by Display before summarising the rest
+const LISTED: usize = 16;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// What both runs play
+#[derive(Debug, Clone)]
+pub enum Workload {
+    /// A recorded scenario, inputs, bots and events included
+    Scenario(Box<Scenario>),
+    /// A world generated from `config.rng_seed`, every snake driven by a random bot seeded
+    /// from it too
+    Seeded {
+        config: GameConfig,
+        ticks: u64,
+        /// Chance per tick that a bot turns, in percent
+        turn_percent: u8,
+    },
+}
+
+impl Workload {
+    /// Ticks each run simulates
+    pub fn ticks(&self) -> u64 {
+        match self {
+            Workload::Scenario(scenario) => scenario.ticks,
+            Workload::Seeded { ticks, .. } => *ticks,
+        }
+    }
+}
+
+/// Where two runs first differed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Ticks simulated when the checksums first differed; 0 for the starting states
+    pub tick: u64,
+    pub checksums: [u64; 2],
+    /// Every differing cell as `(point, first run, second run)`
+    pub cells: Vec<(Point, Cell, Cell)>,
+    /// Ids of snakes whose direction, liveness, head, length or pending growth differ,
+    /// including those only one run has
+    pub snakes: Vec<u32>,
+    pub rng_differs: bool,
+}
+
+/// Outcome of [`verify`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeterminismReport {
+    /// Generator threads of each run
+    pub threads: [usize; 2],
+    /// Ticks both runs simulated identically
+    pub ticks: u64,
+    /// Checksum of the last state both runs agreed on
+    pub checksum: u64,
+    pub divergence: Option<Divergence>,
+}
+
+impl DeterminismReport {
+    pub fn is_deterministic(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+/// Hash of everything a tick's outcome depends on: the grid (through its tile hashes),
+/// every snake's direction, liveness, head, length and pending growth, the apple count
+/// and the RNG streams. Equal states always hash equal.
+pub fn checksum(state: &GameState) -> u64 {
+    let mut hash = FNV_OFFSET;
+    for tile_y in 0..TILES_Y {
+        for tile_x in 0..TILES_X {
+            hash = fold(hash, state.grid.tile_hash(tile_x, tile_y));
+        }
+    }
+    hash = fold(hash, state.snakes.len() as u64);
+    for snake in &state.snakes {
+        let (alive, direction, head, length, growth) = motion(snake);
+        let head = head.unwrap_or_default();
+        hash = fold(hash, snake.id() as u64);
+        hash = fold(hash, alive as u64 | (direction as u64) << 1);
+        hash = fold(hash, head.x as u64 | (head.y as u64) << 16);
+        hash = fold(hash, length as u64 | (growth as u64) << 32);
+    }
+    hash = fold(hash, state.num_apples);
+    hash = fold(hash, state.rng.seed());
+    hash = fold(hash, state.rng.tick());
+    state.rng.draws().into_iter().fold(hash, fold)
+}
+
+fn fold(hash: u64, word: u64) -> u64 {
+    (hash ^ word).wrapping_mul(FNV_PRIME)
+}
+
+// What `checksum` and `compare` look at in a snake
+fn motion(snake: &GridAwareSnake) -> (bool, Direction, Option<Point>, usize, u32) {
+    let inner = snake.snake();
+    (snake.is_alive(), inner.direction, snake.head().copied(), inner.body.len(), inner.pending_growth)
+}
+
+/// Compare two states after `tick` ticks: `None` when their checksums agree
+pub fn compare(first: &GameState, second: &GameState, tick: u64) -> Option<Divergence> {
+    let checksums = [checksum(first), checksum(second)];
+    if checksums[0] == checksums[1] {
+        return None;
+    }
+    let snakes = (0..first.snakes.len().max(second.snakes.len()))
+        .filter(|&id| first.snakes.get(id).map(motion) != second.snakes.get(id).map(motion))
+        .map(|id| id as u32)
+        .collect();
+    Some(Divergence {
+        tick,
+        checksums,
+        cells: first.grid.diff(&second.grid),
+        snakes,
+        rng_differs: first.rng != second.rng,
+    })
+}
+
+// One of the two runs
+enum Run<'a> {
+    Scenario { state: GameState, playback: Playback<'a> },
+    Seeded { state: GameState, bot: RandomBot, inputs: Vec<Input> },
+}
+
+impl<'a> Run<'a> {
+    fn start(workload: &'a Workload, threads: usize) -> Self {
+        match workload {
+            Workload::Scenario(scenario) => {
+                let state = scenario.build();
+                let playback = scenario.playback(&state);
+                Run::Scenario { state, playback }
+            }
+            Workload::Seeded { config, turn_percent, .. } => {
+                let state = RandomGenerator::generate_banded(*config, SpawnConfig::default(), config.rng_seed, threads);
+                let turn_probability = Fixed::from_ratio(*turn_percent as i64, 100);
+                let bot = RandomBot::new(0..state.snakes.len() as u32, turn_probability, config.rng_seed);
+                Run::Seeded { state, bot, inputs: Vec::new() }
+            }
+        }
+    }
+
+    fn step(&mut self, tick: u64) {
+        match self {
+            Run::Scenario { state, playback } => {
+                playback.step(state, tick, None);
+            }
+            Run::Seeded { state, bot, inputs } => {
+                inputs.clear();
+                bot.next_batch(tick, inputs);
+                state.tick(inputs);
+            }
+        }
+    }
+
+    fn state(&self) -> &GameState {
+        match self {
+            Run::Scenario { state, .. } | Run::Seeded { state, .. } => state,
+        }
+    }
+}
+
+/// Play `workload` twice in lockstep, generating the first run's world on `threads[0]`
+/// threads and the second's on `threads[1]`, and stop at the first tick they differ
+pub fn verify(workload: &Workload, threads: [usize; 2]) -> DeterminismReport {
+    let mut runs = threads.map(|threads| Run::start(workload, threads));
+    let mut report = DeterminismReport {
+        threads,
+        ticks: 0,
+        checksum: 0,
+        divergence: None,
+    };
+    for tick in 0..=workload.ticks() {
+        if tick > 0 {
+            for run in &mut runs {
+                run.step(tick - 1);
+            }
+        }
+        let [first, second] = &runs;
+        if let Some(divergence) = compare(first.state(), second.state(), tick) {
+            report.divergence = Some(divergence);
+            break;
+        }
+        report.ticks = tick;
+        report.checksum = checksum(first.state());
+    }
+    report
+}
+
+impl fmt::Display for DeterminismReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "threads {} vs {}", self.threads[0], self.threads[1])?;
+        let Some(divergence) = &self.divergence else {
+            return writeln!(f, "deterministic: {} ticks identical, checksum {:016x}", self.ticks, self.checksum);
+        };
+        match divergence.tick {
+            0 => writeln!(f, "diverged before the first tick")?,
+            tick => writeln!(f, "diverged on tick {} (ticks 1..{} identical)", tick, tick - 1)?,
+        }
+        writeln!(f, "checksums {:016x} vs {:016x}", divergence.checksums[0], divergence.checksums[1])?;
+        if divergence.rng_differs {
+            writeln!(f, "rng streams differ")?;
+        }
+        writeln!(f, "{} cells differ", divergence.cells.len())?;
+        for (point, first, second) in divergence.cells.iter().take(LISTED) {
+            writeln!(f, "  {},{}: {} vs {}", point.x, point.y, first.to_char(), second.to_char())?;
+        }
+        if divergence.cells.len() > LISTED {
+            writeln!(f, "  ... and {} more cells", divergence.cells.len() - LISTED)?;
+        }
+        writeln!(f, "{} snakes differ", divergence.snakes.len())?;
+        for id in divergence.snakes.iter().take(LISTED) {
+            writeln!(f, "  snake {}", id)?;
+        }
+        if divergence.snakes.len() > LISTED {
+            writeln!(f, "  ... and {} more snakes", divergence.snakes.len() - LISTED)?;
+        }
+        Ok(())
+    }
+}
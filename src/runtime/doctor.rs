@@ -0,0 +1,307 @@
+//! Startup validation of the machine a run is about to measure.
+//!
+//! [`diagnose`] checks what decides whether latency numbers mean anything (cores to pin
+//! to and whether they are isolated, an invariant TSC, NUMA placement, huge pages,
+//! access to hardware counters) and what decides whether the run survives at all (free
+//! memory for the grid). Each check comes with guidance for fixing it. `doctor` prints
+//! the full report and the runner refuses to start when a check fails.
+//!
+//! Everything is read from `/proc` and `/sys`, so most checks only report on Linux.
+
+use crate::game::grid::{Cell, GRID_HEIGHT, GRID_WIDTH};
+use crate::runtime::platform;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Ok,
+    /// The run works, but its numbers are noisier or less comparable
+    Warn,
+    /// The run would fail or be meaningless
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    /// What was found
+    pub detail: String,
+    /// How to fix it; empty when the check passed
+    pub guidance: &'static str,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: String) -> Self {
+        Self { name, status: Status::Ok, detail, guidance: "" }
+    }
+
+    fn warn(name: &'static str, detail: String, guidance: &'static str) -> Self {
+        Self { name, status: Status::Warn, detail, guidance }
+    }
+
+    fn fail(name: &'static str, detail: String, guidance: &'static str) -> Self {
+        Self { name, status: Status::Fail, detail, guidance }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// Worst status of any check
+    pub fn status(&self) -> Status {
+        self.checks.iter().map(|check| check.status).max().unwrap_or(Status::Ok)
+    }
+
+    pub fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|check| check.status == status).count()
+    }
+
+    /// One line per check, with guidance indented under the ones that did not pass
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for check in &self.checks {
+            writeln!(writer, "[{:>4}] {:<12} {}", check.status, check.name, check.detail)?;
+            if !check.guidance.is_empty() {
+                writeln!(writer, "       {:<12} -> {}", "", check.guidance)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run every check. `queue_bytes` is the size of the runner's input queue, compared
+/// against the cache sizes together with the grid.
+pub fn diagnose(queue_bytes: usize) -> Report {
+    let cores = platform::core_ids().into_iter().map(|core| core.id).collect::<Vec<_>>();
+    let grid_bytes = GRID_WIDTH * GRID_HEIGHT * std::mem::size_of::<Cell>();
+    Report {
+        checks: vec![
+            check_cores(&cores),
+            check_isolation(&cores),
+            check_tsc(),
+            check_numa(&cores),
+            check_huge_pages(),
+            check_perf_events(),
+            check_memory(grid_bytes),
+            check_footprint(grid_bytes, queue_bytes),
+        ],
+    }
+}
+
+fn check_cores(cores: &[usize]) -> Check {
+    match cores.len() {
+        0 => Check::warn("cores", "the OS does not enumerate cores".into(), "threads run unpinned; numbers will be noisy"),
+        1 => Check::warn(
+            "cores",
+            "1 usable core".into(),
+            "the input and game threads share a core; give the process at least 2 (taskset, cgroup cpuset)",
+        ),
+        n => Check::ok("cores", format!("{} usable, pinning to {} and {}", n, cores[0], cores[1])),
+    }
+}
+
+fn check_isolation(cores: &[usize]) -> Check {
+    let Some(isolated) = read_sys("/sys/devices/system/cpu/isolated") else {
+        return Check::warn("isolation", "unknown on this OS".into(), "");
+    };
+    let isolated = parse_cpulist(&isolated);
+    let pinned = &cores[..cores.len().min(2)];
+    if !pinned.is_empty() && pinned.iter().all(|core| isolated.contains(core)) {
+        Check::ok("isolation", format!("cores {:?} are isolated", pinned))
+    } else {
+        Check::warn(
+            "isolation",
+            format!("pinned cores {:?} are not isolated (isolated: {:?})", pinned, isolated),
+            "boot with isolcpus=/nohz_full= for the pinned cores, or expect scheduler noise",
+        )
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn check_tsc() -> Check {
+    use std::arch::x86_64::__cpuid;
+
+    // CPUID 0x80000007 EDX bit 8: the TSC ticks at a constant rate in every P-, C- and T-state
+    let invariant = __cpuid(0x8000_0000).eax >= 0x8000_0007 && __cpuid(0x8000_0007).edx & (1 << 8) != 0;
+    if invariant {
+        Check::ok("tsc", "invariant; cycle timestamps are comparable across cores".into())
+    } else {
+        Check::warn(
+            "tsc",
+            "not invariant".into(),
+            "cycle counts drift with frequency scaling; pin the CPU frequency or read latencies as rough",
+        )
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn check_tsc() -> Check {
+    Check::ok("tsc", format!("timestamps are {} from the OS clock", platform::TIMESTAMP_UNIT))
+}
+
+fn check_numa(cores: &[usize]) -> Check {
+    let Ok(entries) = std::fs::read_dir("/sys/devices/system/node") else {
+        return Check::ok("numa", "no NUMA information; assuming one node".into());
+    };
+    let mut nodes: Vec<(usize, Vec<usize>)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let id = entry.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+            let cpus = read_sys(entry.path().join("cpulist")).map_or_else(Vec::new, |list| parse_cpulist(&list));
+            Some((id, cpus))
+        })
+        .collect();
+    nodes.sort_unstable();
+    if nodes.len() <= 1 {
+        return Check::ok("numa", "1 node".into());
+    }
+
+    let node_of = |core: usize| nodes.iter().find(|(_, cpus)| cpus.contains(&core)).map(|(id, _)| *id);
+    match cores[..] {
+        [first, second, ..] if node_of(first) != node_of(second) => Check::warn(
+            "numa",
+            format!("{} nodes; pinned cores {} and {} are on different nodes", nodes.len(), first, second),
+            "the input queue crosses the interconnect; restrict the process to one node (numactl --cpunodebind)",
+        ),
+        _ => Check::ok("numa", format!("{} nodes; pinned cores share one", nodes.len())),
+    }
+}
+
+fn check_huge_pages() -> Check {
+    let Some(meminfo) = read_sys("/proc/meminfo") else {
+        return Check::warn("huge pages", "unknown on this OS".into(), "");
+    };
+    let reserved = parse_meminfo_kb(&meminfo, "HugePages_Total").unwrap_or(0);
+    let size_kb = parse_meminfo_kb(&meminfo, "Hugepagesize").unwrap_or(0);
+    let thp = read_sys("/sys/kernel/mm/transparent_hugepage/enabled").and_then(|mode| selected_mode(&mode).map(str::to_owned));
+    let detail = format!(
+        "{} reserved x {} kB, transparent huge pages: {}",
+        reserved,
+        size_kb,
+        thp.as_deref().unwrap_or("unknown")
+    );
+    if reserved > 0 || thp.as_deref() == Some("always") {
+        Check::ok("huge pages", detail)
+    } else {
+        Check::warn(
+            "huge pages",
+            detail,
+            "the grid is spread over 4 KiB pages and pays TLB misses; set transparent_hugepage to always",
+        )
+    }
+}
+
+fn check_perf_events() -> Check {
+    let Some(level) = read_sys("/proc/sys/kernel/perf_event_paranoid") else {
+        return Check::warn("perf events", "perf_event_open needs Linux".into(), "the hardware counter bench will skip");
+    };
+    match level.trim().parse::<i32>() {
+        Ok(level) if level <= 2 => Check::ok("perf events", format!("perf_event_paranoid is {}", level)),
+        _ => Check::warn(
+            "perf events",
+            format!("perf_event_paranoid is {}", level.trim()),
+            "hardware counters are refused; sysctl kernel.perf_event_paranoid=2 (or lower)",
+        ),
+    }
+}
+
+fn check_memory(grid_bytes: usize) -> Check {
+    let Some(available_kb) = read_sys("/proc/meminfo").and_then(|meminfo| parse_meminfo_kb(&meminfo, "MemAvailable")) else {
+        return Check::warn("memory", "available memory unknown on this OS".into(), "");
+    };
+    let available = available_kb as usize * 1024;
+    let detail = format!("{} MiB available, the grid needs {} MiB", available >> 20, grid_bytes >> 20);
+    if available < grid_bytes {
+        Check::fail("memory", detail, "the grid would not fit; free memory or run on a larger machine")
+    } else {
+        Check::ok("memory", detail)
+    }
+}
+
+fn check_footprint(grid_bytes: usize, queue_bytes: usize) -> Check {
+    let caches = cache_sizes();
+    if caches.is_empty() {
+        return Check::ok("footprint", format!("grid {} MiB, queue {} KiB; cache sizes unknown", grid_bytes >> 20, queue_bytes >> 10));
+    }
+    let levels = caches.iter().map(|(level, bytes)| format!("L{} {} KiB", level, bytes >> 10)).collect::<Vec<_>>().join(", ");
+    let detail = format!("grid {} MiB, queue {} KiB; {}", grid_bytes >> 20, queue_bytes >> 10, levels);
+    match caches.iter().find(|(level, _)| *level == 2) {
+        Some(&(_, l2)) if queue_bytes > l2 => {
+            Check::warn("footprint", detail, "the input queue outgrows L2; lower its capacity")
+        }
+        _ => Check::ok("footprint", detail),
+    }
+}
+
+// (level, bytes) of the data and unified caches of cpu0, smallest level first
+fn cache_sizes() -> Vec<(u8, usize)> {
+    let Ok(entries) = std::fs::read_dir("/sys/devices/system/cpu/cpu0/cache") else {
+        return Vec::new();
+    };
+    let mut caches: Vec<(u8, usize)> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| read_sys(entry.path().join("type")).is_some_and(|kind| kind.trim() != "Instruction"))
+        .filter_map(|entry| {
+            let level = read_sys(entry.path().join("level"))?.trim().parse().ok()?;
+            let size = parse_size(&read_sys(entry.path().join("size"))?)?;
+            Some((level, size))
+        })
+        .collect();
+    caches.sort_unstable();
+    caches
+}
+
+fn read_sys<P: AsRef<Path>>(path: P) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+/// Parse a kernel CPU list such as `0-3,8,10-11`. Malformed parts are skipped.
+pub fn parse_cpulist(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter_map(|part| match part.split_once('-') {
+            Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+            None => part.parse().ok().map(|cpu| cpu..=cpu),
+        })
+        .flatten()
+        .collect()
+}
+
+/// Value of `key` in `/proc/meminfo` text, in kB for the sized fields
+pub fn parse_meminfo_kb(meminfo: &str, key: &str) -> Option<u64> {
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .and_then(|value| value.split_whitespace().next()?.parse().ok())
+}
+
+/// The bracketed choice of a sysfs mode file such as `always [madvise] never`
+pub fn selected_mode(modes: &str) -> Option<&str> {
+    modes.split_whitespace().find_map(|mode| mode.strip_prefix('[')?.strip_suffix(']'))
+}
+
+// Cache size as sysfs writes it: `48K`, `2048K`, `32M`
+fn parse_size(size: &str) -> Option<usize> {
+    let size = size.trim();
+    let (digits, scale) = match size.as_bytes().last()? {
+        b'K' => (&size[..size.len() - 1], 1 << 10),
+        b'M' => (&size[..size.len() - 1], 1 << 20),
+        _ => (size, 1),
+    };
+    digits.parse::<usize>().ok().map(|n| n * scale)
+}
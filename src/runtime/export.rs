@@ -0,0 +1,249 @@
+//! JSONL export of recorded runs for tools that don't link against the crate.
+//!
+//! One JSON object per line, each with a `"type"` and (except the header) a `"tick"`.
+//! Events produced by a tick carry that tick's number; the initial population carries
+//! the start tick. Schema version [`EXPORT_SCHEMA_VERSION`]:
+//!
+//! ```text
+//! {"type":"header","schema":1,"source":"replay"|"simulation","grid_width":W,"grid_height":H[,"snakes":N,"start_tick":T]}
+//! {"type":"spawn","tick":T,"snake":ID,"x":X,"y":Y,"length":L,"direction":"up"}
+//! {"type":"input","tick":T,"snake":ID,"direction":"left"}
+//! {"type":"grow","tick":T,"snake":ID,"length":L}
+//! {"type":"death","tick":T,"snake":ID,"x":X,"y":Y,"length":L}
+//! {"type":"kill","tick":T,"killer":ID,"victim":ID,"victim_length":L}
+//! {"type":"split","tick":T,"snake":ID,"fragment":ID,"fragment_length":L}
+//! {"type":"rejected","tick":T,"reversals":N,"dead_snakes":N,"throttled":N,"inputs":[{"snake":ID,"direction":"down","reason":"reversal"|"dead_snake"|"throttled"}]}
+//! {"type":"tick","tick":T,"alive":N,"apples":N,"births":N,"deaths":N}
+//! {"type":"end","tick":T,"alive":N,"total_births":N,"total_deaths":N}
+//! ```
+//!
+//! A replay alone only yields `header` and `input` lines (`"source":"replay"`). Given the
+//! starting snapshot the run is re-simulated and every event type is emitted, `tick` lines
+//! only when [`ExportOptions::tick_summaries`] is set and `rejected` lines (one per tick
+//! that dropped inputs) only when [`ExportOptions::audit_inputs`] is set. `(x, y)` of a death is the head
+//! position the snake died with. A split is followed by a `spawn` of its fragment. Consumers should ignore unknown types and keys.
+
+use crate::game::engine::GameState;
+use crate::game::grid::{GRID_HEIGHT, GRID_WIDTH};
+use crate::game::types::Input;
+use crate::input::replay::{Replay, ReplaySource};
+use crate::runtime::balance::{BalanceConfig, PopulationBalancer};
+use crate::input::source::InputSource;
+use std::io::{self, Write};
+
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExportOptions {
+    /// Emit a `tick` line after every simulated tick
+    pub tick_summaries: bool,
+    /// Simulate up to and including this tick; defaults to the last replay frame
+    pub end_tick: Option<u64>,
+    /// Respawn dead snakes like a persistent world with the same setting did
+    pub respawns_per_tick: usize,
+    /// Respawn like a world balanced with this config did; replaces `respawns_per_tick`
+    pub balance: Option<BalanceConfig>,
+    /// Emit a `rejected` line for every tick with inputs that changed nothing
+    pub audit_inputs: bool,
+}
+
+/// Write the inputs of `replay` as `input` lines
+pub fn write_replay_events<W: Write>(replay: &Replay, writer: &mut W) -> io::Result<()> {
+    write_header(writer, "replay")?;
+    writeln!(writer, "}}")?;
+    for frame in &replay.frames {
+        write_inputs(writer, frame.tick, &frame.inputs)?;
+    }
+    Ok(())
+}
+
+/// Re-simulate `replay` from `state` (a snapshot taken at `start_tick`) and write every
+/// event. Returns the number of ticks simulated; `state` is left at the end of the run.
+pub fn write_run_events<W: Write>(
+    state: &mut GameState,
+    start_tick: u64,
+    replay: &Replay,
+    options: ExportOptions,
+    writer: &mut W,
+) -> io::Result<u64> {
+    write_header(writer, "simulation")?;
+    writeln!(writer, ",\"snakes\":{},\"start_tick\":{}}}", state.snakes.len(), start_tick)?;
+
+    state.set_input_audit(options.audit_inputs);
+    let mut previous = Vec::with_capacity(state.snakes.len());
+    for id in 0..state.snakes.len() {
+        previous.push(SnakeState::of(state, id));
+        if previous[id].alive {
+            write_spawn(writer, state, start_tick, id)?;
+        }
+    }
+
+    let end_tick = options
+        .end_tick
+        .or_else(|| replay.frames.last().map(|frame| frame.tick))
+        .unwrap_or(start_tick);
+    let mut balancer = options.balance.map(PopulationBalancer::new);
+    let mut source = ReplaySource::new(replay.clone());
+    let mut inputs = Vec::new();
+    let mut tick = start_tick;
+
+    while tick <= end_tick {
+        source.next_batch(tick, &mut inputs);
+        write_inputs(writer, tick, &inputs)?;
+        state.tick(&inputs);
+        inputs.clear();
+        write_rejections(writer, state, tick)?;
+
+        for kill in &state.kills {
+            writeln!(
+                writer,
+                "{{\"type\":\"kill\",\"tick\":{},\"killer\":{},\"victim\":{},\"victim_length\":{}}}",
+                tick, kill.killer, kill.victim, kill.victim_length
+            )?;
+        }
+        for split in &state.splits {
+            writeln!(
+                writer,
+                "{{\"type\":\"split\",\"tick\":{},\"snake\":{},\"fragment\":{},\"fragment_length\":{}}}",
+                tick, split.parent, split.fragment, split.fragment_length
+            )?;
+        }
+        // Fragments may take ids past the initial population
+        previous.resize(state.snakes.len(), SnakeState::default());
+        // Deaths and growth before respawning, so a snake dying and respawning on the
+        // same tick reports both
+        for (id, before) in previous.iter_mut().enumerate() {
+            let after = SnakeState::of(state, id);
+            if before.alive && !after.alive {
+                let snake = &state.snakes[id];
+                let head = snake.head().copied().unwrap_or_default();
+                writeln!(
+                    writer,
+                    "{{\"type\":\"death\",\"tick\":{},\"snake\":{},\"x\":{},\"y\":{},\"length\":{}}}",
+                    tick, id, head.x, head.y, after.length
+                )?;
+            } else if !before.alive && after.alive {
+                write_spawn(writer, state, tick, id)?;
+            } else if after.alive && after.length > before.length {
+                writeln!(
+                    writer,
+                    "{{\"type\":\"grow\",\"tick\":{},\"snake\":{},\"length\":{}}}",
+                    tick, id, after.length
+                )?;
+            }
+            *before = after;
+        }
+
+        let respawned = match &mut balancer {
+            Some(balancer) => balancer.balance(state),
+            None if options.respawns_per_tick > 0 => state.respawn_dead(options.respawns_per_tick),
+            None => 0,
+        };
+        if respawned > 0 {
+            for (id, before) in previous.iter_mut().enumerate() {
+                let after = SnakeState::of(state, id);
+                if !before.alive && after.alive {
+                    write_spawn(writer, state, tick, id)?;
+                }
+                *before = after;
+            }
+        }
+
+        if options.tick_summaries {
+            let population = &state.population;
+            writeln!(
+                writer,
+                "{{\"type\":\"tick\",\"tick\":{},\"alive\":{},\"apples\":{},\"births\":{},\"deaths\":{}}}",
+                tick, population.alive, state.num_apples, population.births_this_tick, population.deaths_this_tick
+            )?;
+        }
+        tick += 1;
+    }
+
+    let population = &state.population;
+    writeln!(
+        writer,
+        "{{\"type\":\"end\",\"tick\":{},\"alive\":{},\"total_births\":{},\"total_deaths\":{}}}",
+        tick.saturating_sub(1).max(start_tick),
+        population.alive,
+        population.total_births,
+        population.total_deaths
+    )?;
+    Ok(tick - start_tick)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SnakeState {
+    alive: bool,
+    length: usize,
+}
+
+impl SnakeState {
+    fn of(state: &GameState, id: usize) -> Self {
+        let snake = &state.snakes[id];
+        Self {
+            alive: snake.is_alive(),
+            length: snake.body().len(),
+        }
+    }
+}
+
+// Leaves the object open so callers can append fields
+fn write_header<W: Write>(writer: &mut W, source: &str) -> io::Result<()> {
+    write!(
+        writer,
+        "{{\"type\":\"header\",\"schema\":{},\"source\":\"{}\",\"grid_width\":{},\"grid_height\":{}",
+        EXPORT_SCHEMA_VERSION, source, GRID_WIDTH, GRID_HEIGHT
+    )
+}
+
+fn write_inputs<W: Write>(writer: &mut W, tick: u64, inputs: &[Input]) -> io::Result<()> {
+    for input in inputs {
+        writeln!(
+            writer,
+            "{{\"type\":\"input\",\"tick\":{},\"snake\":{},\"direction\":\"{}\"}}",
+            tick,
+            input.snake_id,
+            input.direction.name()
+        )?;
+    }
+    Ok(())
+}
+
+fn write_rejections<W: Write>(writer: &mut W, state: &GameState, tick: u64) -> io::Result<()> {
+    if state.rejected_inputs.is_empty() {
+        return Ok(());
+    }
+    let audit = state.input_audit();
+    write!(
+        writer,
+        "{{\"type\":\"rejected\",\"tick\":{},\"reversals\":{},\"dead_snakes\":{},\"throttled\":{},\"inputs\":[",
+        tick, audit.reversals, audit.dead_snakes, audit.throttled
+    )?;
+    for (i, rejected) in state.rejected_inputs.iter().enumerate() {
+        write!(
+            writer,
+            "{}{{\"snake\":{},\"direction\":\"{}\",\"reason\":\"{}\"}}",
+            if i == 0 { "" } else { "," },
+            rejected.input.snake_id,
+            rejected.input.direction.name(),
+            rejected.reason.name()
+        )?;
+    }
+    writeln!(writer, "]}}")
+}
+
+fn write_spawn<W: Write>(writer: &mut W, state: &GameState, tick: u64, id: usize) -> io::Result<()> {
+    let snake = &state.snakes[id];
+    let head = snake.head().copied().unwrap_or_default();
+    writeln!(
+        writer,
+        "{{\"type\":\"spawn\",\"tick\":{},\"snake\":{},\"x\":{},\"y\":{},\"length\":{},\"direction\":\"{}\"}}",
+        tick,
+        id,
+        head.x,
+        head.y,
+        snake.body().len(),
+        snake.snake().direction.name()
+    )
+}
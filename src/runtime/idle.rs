@@ -0,0 +1,206 @@
+//! Adaptive waiting for the polling loops.
+//!
+//! The game loop polls its input queues and the load generator polls the clock, both
+//! spinning hot so an input costs no wake-up. That burns a full core while the game is
+//! paused or inputs have stopped. An [`Idler`] counts the polls that found nothing and
+//! backs off in stages: hot spinning, then bursts of pause hints, then yielding the time
+//! slice, then sleeping on a futex for up to [`IdleConfig::max_sleep`]. Any poll that finds
+//! work resets it to hot spinning.
+//!
+//! Producers [`Doorbell::ring`] after enqueueing, which wakes a sleeping consumer at once;
+//! while nobody sleeps a ring costs a fence and one load. Off Linux a sleep cannot be cut
+//! short, so the first input after a quiet spell waits up to `max_sleep`.
+
+use crate::runtime::platform;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering, fence};
+use std::thread;
+use std::time::Duration;
+
+// Pause hints per poll in the pause stage
+const PAUSE_BURST: u32 = 32;
+
+/// When an [`Idler`] moves to the next stage, in consecutive polls that found nothing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdleConfig {
+    /// Polls spinning hot, one pause hint each
+    pub spin_polls: u32,
+    /// Polls after those in bursts of pause hints
+    pub pause_polls: u32,
+    /// Polls after those yielding the time slice
+    pub yield_polls: u32,
+    /// Longest single sleep once yielding is over; a ring ends it early
+    pub max_sleep: Duration,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            spin_polls: 4096,
+            pause_polls: 1024,
+            yield_polls: 256,
+            max_sleep: Duration::from_millis(1),
+        }
+    }
+}
+
+impl IdleConfig {
+    /// Never back off: the loops' behaviour without an idler
+    pub fn always_spin() -> Self {
+        Self {
+            spin_polls: u32::MAX,
+            ..Self::default()
+        }
+    }
+
+    /// Stage of the `polls`-th consecutive poll that found nothing
+    pub fn stage(&self, polls: u32) -> IdleStage {
+        let pause_from = self.spin_polls;
+        let yield_from = pause_from.saturating_add(self.pause_polls);
+        let sleep_from = yield_from.saturating_add(self.yield_polls);
+        if polls < pause_from {
+            IdleStage::Spin
+        } else if polls < yield_from {
+            IdleStage::Pause
+        } else if polls < sleep_from {
+            IdleStage::Yield
+        } else {
+            IdleStage::Sleep
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleStage {
+    Spin,
+    Pause,
+    Yield,
+    Sleep,
+}
+
+/// Polls that found nothing, by the stage they waited in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IdleStats {
+    pub spins: u64,
+    pub pauses: u64,
+    pub yields: u64,
+    pub sleeps: u64,
+}
+
+/// Wakes a consumer sleeping in [`Idler::idle`]; shared with its producers
+#[derive(Debug, Default)]
+pub struct Doorbell {
+    // Bumped by every ring that finds a sleeper; the futex word
+    rings: AtomicU32,
+    sleepers: AtomicU32,
+}
+
+impl Doorbell {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call after publishing work. Orders the publication before the sleeper check, so a
+    /// consumer about to sleep either sees the work or is woken.
+    pub fn ring(&self) {
+        fence(Ordering::SeqCst);
+        if self.sleepers.load(Ordering::Relaxed) > 0 {
+            self.rings.fetch_add(1, Ordering::Release);
+            platform::futex_wake(&self.rings);
+        }
+    }
+
+    // Sleep up to `timeout` unless `ready` says work arrived before we were registered
+    fn sleep(&self, timeout: Duration, ready: impl FnOnce() -> bool) {
+        self.sleepers.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+        let seen = self.rings.load(Ordering::Acquire);
+        if !ready() {
+            platform::futex_wait(&self.rings, seen, timeout);
+        }
+        self.sleepers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Back-off state of one polling loop, see the module docs
+#[derive(Debug)]
+pub struct Idler {
+    config: IdleConfig,
+    doorbell: Arc<Doorbell>,
+    polls: u32,
+    stats: IdleStats,
+}
+
+impl Idler {
+    pub fn new(config: IdleConfig) -> Self {
+        Self::with_doorbell(config, Arc::new(Doorbell::new()))
+    }
+
+    /// An idler woken by rings on `doorbell`
+    pub fn with_doorbell(config: IdleConfig, doorbell: Arc<Doorbell>) -> Self {
+        Self {
+            config,
+            doorbell,
+            polls: 0,
+            stats: IdleStats::default(),
+        }
+    }
+
+    /// The doorbell producers ring to wake this idler
+    pub fn doorbell(&self) -> Arc<Doorbell> {
+        Arc::clone(&self.doorbell)
+    }
+
+    pub fn config(&self) -> &IdleConfig {
+        &self.config
+    }
+
+    /// The stage the next poll that finds nothing waits in
+    pub fn stage(&self) -> IdleStage {
+        self.config.stage(self.polls)
+    }
+
+    pub fn stats(&self) -> IdleStats {
+        self.stats
+    }
+
+    /// A poll found work: back to hot spinning
+    #[inline]
+    pub fn reset(&mut self) {
+        self.polls = 0;
+    }
+
+    /// Wait after a poll that found nothing, in the current stage. Once sleeping, `ready`
+    /// is asked whether work arrived before the sleep began. Returns the stage waited in.
+    #[inline]
+    pub fn idle(&mut self, ready: impl FnOnce() -> bool) -> IdleStage {
+        self.idle_within(Duration::MAX, ready)
+    }
+
+    /// [`Idler::idle`], sleeping no longer than `limit`, e.g. the time to a deadline
+    pub fn idle_within(&mut self, limit: Duration, ready: impl FnOnce() -> bool) -> IdleStage {
+        let stage = self.stage();
+        self.polls = self.polls.saturating_add(1);
+        match stage {
+            IdleStage::Spin => {
+                self.stats.spins += 1;
+                std::hint::spin_loop();
+            }
+            IdleStage::Pause => {
+                self.stats.pauses += 1;
+                for _ in 0..PAUSE_BURST {
+                    std::hint::spin_loop();
+                }
+            }
+            IdleStage::Yield => {
+                self.stats.yields += 1;
+                thread::yield_now();
+            }
+            IdleStage::Sleep => {
+                self.stats.sleeps += 1;
+                self.doorbell.sleep(self.config.max_sleep.min(limit), ready);
+            }
+        }
+        stage
+    }
+}
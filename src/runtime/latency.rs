@@ -0,0 +1,68 @@
+/// Collects latency samples (in nanoseconds) and reports percentiles.
+///
+/// Samples are stored raw; sorting happens once in `summary()`, outside any measured path.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyRecorder {
+    samples: Vec<u64>,
+}
+
+/// Percentile summary of a set of latency samples, in nanoseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub count: usize,
+    pub min: u64,
+    pub p50: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub max: u64,
+}
+
+impl LatencyRecorder {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            samples: Vec::with_capacity(capacity),
+        }
+    }
+
+    #[inline(always)]
+    pub fn record(&mut self, nanos: u64) {
+        self.samples.push(nanos);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn summary(&mut self) -> LatencySummary {
+        if self.samples.is_empty() {
+            return LatencySummary::default();
+        }
+        self.samples.sort_unstable();
+        LatencySummary {
+            count: self.samples.len(),
+            min: self.samples[0],
+            p50: percentile(&self.samples, 50.0),
+            p99: percentile(&self.samples, 99.0),
+            p999: percentile(&self.samples, 99.9),
+            max: self.samples[self.samples.len() - 1],
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice. Returns 0 for an empty slice.
+pub fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    // The epsilon keeps e.g. 99.9% of 1000 at rank 999 despite float rounding
+    let rank = ((p / 100.0) * sorted.len() as f64 - 1e-9).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
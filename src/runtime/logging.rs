@@ -0,0 +1,183 @@
+//! Logging behind the `log` facade, with a level per subsystem and a writer thread.
+//!
+//! A record's subsystem is the crate module it comes from (`game`, `input`, `ipc`, `net`,
+//! `runtime`), or `main` for the runner. Levels are given as `<default>,<subsystem>=<level>,...`,
+//! e.g. `info,game=debug,ipc=off`, through the runner's `--log` flag or `HFS_LOG`.
+//!
+//! The calling thread formats the line and hands it to a bounded channel without waiting;
+//! when the channel is full the line is dropped and counted. Only the writer thread ever
+//! touches the output, so a slow terminal or disk can never stall a pinned thread.
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread;
+use std::time::Instant;
+
+/// Lines waiting for the writer before new ones are dropped
+pub const LOG_QUEUE_LINES: usize = 4096;
+/// Environment variable read by the runner when `--log` is not given
+pub const LOG_ENV: &str = "HFS_LOG";
+
+const CRATE_PREFIX: &str = "high_frequency_snake";
+
+/// Level per subsystem, falling back to a default
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLevels {
+    pub default: LevelFilter,
+    pub subsystems: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        Self {
+            default: LevelFilter::Info,
+            subsystems: Vec::new(),
+        }
+    }
+}
+
+impl LogLevels {
+    /// Parse `<level>` and `<subsystem>=<level>` items separated by commas; later items win
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut levels = Self::default();
+        for item in spec.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let parse_level = |level: &str| level.parse().map_err(|_| format!("invalid log level: {}", level));
+            match item.split_once('=') {
+                Some((subsystem, level)) => {
+                    let level = parse_level(level)?;
+                    levels.subsystems.retain(|(name, _)| name != subsystem);
+                    levels.subsystems.push((subsystem.to_string(), level));
+                }
+                None => levels.default = parse_level(item)?,
+            }
+        }
+        Ok(levels)
+    }
+
+    /// Level applying to records with `target`
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        let subsystem = subsystem(target);
+        self.subsystems
+            .iter()
+            .find(|(name, _)| name == subsystem)
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// Most verbose level of any subsystem
+    pub fn max(&self) -> LevelFilter {
+        self.subsystems.iter().map(|(_, level)| *level).fold(self.default, Ord::max)
+    }
+}
+
+/// Subsystem of a record target: the crate's top-level module, `main` for the crate root
+/// (the runner), anything else as given
+pub fn subsystem(target: &str) -> &str {
+    match target.strip_prefix(CRATE_PREFIX) {
+        Some("") => "main",
+        Some(path) => path.trim_start_matches("::").split("::").next().unwrap_or(path),
+        None => target,
+    }
+}
+
+enum Message {
+    Line(String),
+    // Answered once every line before it is written
+    Flush(SyncSender<()>),
+}
+
+/// A [`Log`] implementation queueing formatted lines for a writer thread
+pub struct Logger {
+    levels: LogLevels,
+    sender: SyncSender<Message>,
+    dropped: AtomicU64,
+    start: Instant,
+}
+
+impl Logger {
+    /// A logger and the writer thread draining its queue of `capacity` lines into `output`.
+    /// The thread ends when the logger is dropped.
+    pub fn new<W: Write + Send + 'static>(levels: LogLevels, capacity: usize, output: W) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        thread::Builder::new()
+            .name("log-writer".to_string())
+            .spawn(move || write_lines(receiver, output))?;
+        Ok(Self {
+            levels,
+            sender,
+            dropped: AtomicU64::new(0),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn levels(&self) -> &LogLevels {
+        &self.levels
+    }
+
+    /// Lines dropped so far because the writer fell behind
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.levels.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let elapsed = self.start.elapsed();
+        let line = format!(
+            "{:>6}.{:03} {:<5} {}: {}\n",
+            elapsed.as_secs(),
+            elapsed.subsec_millis(),
+            record.level(),
+            subsystem(record.target()),
+            record.args()
+        );
+        match self.sender.try_send(Message::Line(line)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Wait until the writer has written every line queued so far. Blocks, so pinned
+    /// threads should leave it to the runner at exit.
+    fn flush(&self) {
+        let (done, wait) = mpsc::sync_channel(1);
+        if self.sender.send(Message::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+}
+
+/// Install a [`Logger`] writing to stderr as the global logger
+pub fn init(levels: LogLevels) -> Result<(), SetLoggerError> {
+    let max = levels.max();
+    let logger = Logger::new(levels, LOG_QUEUE_LINES, io::stderr()).expect("failed to spawn log writer");
+    log::set_boxed_logger(Box::new(logger))?;
+    log::set_max_level(max);
+    Ok(())
+}
+
+fn write_lines<W: Write>(receiver: Receiver<Message>, mut output: W) {
+    for message in receiver {
+        match message {
+            // Nowhere left to report a failed write
+            Message::Line(line) => {
+                let _ = output.write_all(line.as_bytes());
+            }
+            Message::Flush(done) => {
+                let _ = output.flush();
+                let _ = done.send(());
+            }
+        }
+    }
+    let _ = output.flush();
+}
@@ -0,0 +1,145 @@
+//! Capture of slow ticks with enough context to explain them.
+//!
+//! A rare 10x tick is invisible in averages and gone by the time anyone looks. With phase
+//! timing on (`GameState::set_phase_timing`), [`OutlierCapture::observe`] keeps a report
+//! of every tick over the threshold: where the time went per phase, how much input it
+//! handled and queued up meanwhile, and optionally a snapshot of the state it left. The
+//! last `capacity` reports are kept in memory until [`OutlierCapture::flush`].
+
+use crate::game::engine::GameState;
+use crate::game::metrics::{PhaseTimings, TICK_PHASES};
+use crate::game::snapshot::save_snapshot;
+use crate::runtime::platform::TIMESTAMP_UNIT;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct OutlierConfig {
+    /// Ticks taking longer than this, in `platform::TIMESTAMP_UNIT`s, are captured
+    pub threshold: u64,
+    /// Reports kept in memory; the oldest is dropped when full
+    pub capacity: usize,
+    /// Save a snapshot of the state after each outlier tick here, as `outlier-<tick>.snap`.
+    /// Writing one takes far longer than a tick, so the next ticks will be slow too.
+    pub snapshot_dir: Option<PathBuf>,
+    /// Snapshots written at most, so a slow machine doesn't fill the disk
+    pub max_snapshots: usize,
+}
+
+impl Default for OutlierConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 1_000_000,
+            capacity: 64,
+            snapshot_dir: None,
+            max_snapshots: 4,
+        }
+    }
+}
+
+/// Context of one slow tick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlierReport {
+    pub tick: u64,
+    /// Whole tick, in `platform::TIMESTAMP_UNIT`s
+    pub duration: u64,
+    /// Per phase, see `metrics::TICK_PHASES`; all zero unless the state times phases
+    pub phases: PhaseTimings,
+    /// Inputs the tick applied
+    pub inputs: usize,
+    /// Inputs queued up by the time the tick finished
+    pub queue_depth: usize,
+    /// Snakes alive after the tick
+    pub alive: u32,
+    /// Snapshot of the state after the tick, when one was written
+    pub snapshot: Option<PathBuf>,
+}
+
+pub struct OutlierCapture {
+    config: OutlierConfig,
+    reports: VecDeque<OutlierReport>,
+    captured: u64,
+    snapshots: usize,
+}
+
+impl OutlierCapture {
+    pub fn new(config: OutlierConfig) -> Self {
+        Self {
+            reports: VecDeque::with_capacity(config.capacity),
+            config,
+            captured: 0,
+            snapshots: 0,
+        }
+    }
+
+    pub fn config(&self) -> &OutlierConfig {
+        &self.config
+    }
+
+    /// Look at a tick that just ran on `state` and took `duration`; keep a report if it was
+    /// an outlier. Returns whether it was.
+    pub fn observe(&mut self, tick: u64, duration: u64, state: &GameState, inputs: usize, queue_depth: usize) -> bool {
+        if duration <= self.config.threshold || self.config.capacity == 0 {
+            return false;
+        }
+
+        let snapshot = match &self.config.snapshot_dir {
+            Some(dir) if self.snapshots < self.config.max_snapshots => {
+                let path = dir.join(format!("outlier-{}.snap", tick));
+                // A report without its snapshot still beats no report
+                std::fs::create_dir_all(dir).and_then(|_| save_snapshot(state, tick, &path)).ok().map(|_| {
+                    self.snapshots += 1;
+                    path
+                })
+            }
+            _ => None,
+        };
+
+        if self.reports.len() == self.config.capacity {
+            self.reports.pop_front();
+        }
+        self.reports.push_back(OutlierReport {
+            tick,
+            duration,
+            phases: state.phase_timings,
+            inputs,
+            queue_depth,
+            alive: state.population.alive,
+            snapshot,
+        });
+        self.captured += 1;
+        true
+    }
+
+    /// Reports held in memory, oldest first
+    pub fn reports(&self) -> impl Iterator<Item = &OutlierReport> {
+        self.reports.iter()
+    }
+
+    /// Outliers seen so far, including reports since dropped or flushed
+    pub fn captured(&self) -> u64 {
+        self.captured
+    }
+
+    /// Write the held reports one per line, oldest first, and drop them. Returns the
+    /// number written.
+    pub fn flush<W: Write>(&mut self, writer: &mut W) -> io::Result<usize> {
+        let count = self.reports.len();
+        for report in self.reports.drain(..) {
+            write!(
+                writer,
+                "outlier tick={} unit={} duration={} applied_inputs={} queue_depth={} alive={}",
+                report.tick, TIMESTAMP_UNIT, report.duration, report.inputs, report.queue_depth, report.alive
+            )?;
+            for (name, time) in TICK_PHASES.iter().zip(report.phases) {
+                write!(writer, " {}={}", name, time)?;
+            }
+            if let Some(path) = &report.snapshot {
+                write!(writer, " snapshot={}", path.display())?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(count)
+    }
+}
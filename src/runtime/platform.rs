@@ -0,0 +1,130 @@
+//! Per-OS differences of the runner, kept in one place.
+//!
+//! Linux is the measured platform. Windows and macOS get best-effort fallbacks so the
+//! binary and benches run on dev machines, with numbers that are not comparable.
+
+use core_affinity::CoreId;
+use std::io;
+use std::sync::atomic::AtomicU32;
+use std::time::Duration;
+
+/// Page size assumed when touching memory page by page; the smallest on every target
+pub const PAGE_SIZE: usize = 4096;
+
+/// Unit of [`timestamp`] on this target
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-fallbacks")))]
+pub const TIMESTAMP_UNIT: &str = "cycles";
+#[cfg(not(all(target_arch = "x86_64", not(feature = "safe-fallbacks"))))]
+pub const TIMESTAMP_UNIT: &str = "ns";
+
+/// Cores threads may be pinned to; empty when the OS won't enumerate them
+pub fn core_ids() -> Vec<CoreId> {
+    core_affinity::get_core_ids().unwrap_or_default()
+}
+
+/// Pin the calling thread to `core`. Returns false when the OS refused.
+///
+/// A hard pin on Linux and Windows. macOS only takes it as an affinity hint for the
+/// scheduler, so threads may still migrate there.
+pub fn pin_current(core: CoreId) -> bool {
+    core_affinity::set_for_current(core)
+}
+
+/// The first two cores for the (producer, game) thread pair, if there are two
+pub fn core_pair() -> Option<(CoreId, CoreId)> {
+    match core_ids()[..] {
+        [first, second, ..] => Some((first, second)),
+        _ => None,
+    }
+}
+
+/// Cheap monotonic timestamp for profiling, in [`TIMESTAMP_UNIT`]s.
+///
+/// The TSC on x86_64 (unless built with `safe-fallbacks`). Elsewhere nanoseconds from
+/// `Instant`, which is backed by QueryPerformanceCounter on Windows and the mach timebase
+/// on macOS.
+#[cfg(all(target_arch = "x86_64", not(feature = "safe-fallbacks")))]
+#[inline(always)]
+pub fn timestamp() -> u64 {
+    // SAFETY: rdtsc is available on every x86_64 CPU
+    unsafe { std::arch::x86_64::_rdtsc() }
+}
+
+#[cfg(not(all(target_arch = "x86_64", not(feature = "safe-fallbacks"))))]
+#[inline(always)]
+pub fn timestamp() -> u64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+/// Lock the `len` bytes at `start` into RAM so they are never swapped out. Returns `len`.
+///
+/// mlock on Unix, which faults the pages in as a side effect; it fails with `ENOMEM` or
+/// `EPERM` beyond `RLIMIT_MEMLOCK` (see `ulimit -l`). Unsupported elsewhere and under
+/// `safe-fallbacks`.
+#[cfg(all(unix, not(feature = "safe-fallbacks")))]
+pub fn lock_memory(start: *const u8, len: usize) -> io::Result<usize> {
+    if len == 0 {
+        return Ok(0);
+    }
+    // SAFETY: mlock only changes how the range is paged; an unmapped range is an error
+    match unsafe { libc::mlock(start.cast(), len) } {
+        0 => Ok(len),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(not(all(unix, not(feature = "safe-fallbacks"))))]
+pub fn lock_memory(_start: *const u8, _len: usize) -> io::Result<usize> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "memory locking needs mlock"))
+}
+
+/// [`lock_memory`] over the whole allocation of `vec`, spare capacity included
+pub fn lock_vec<T>(vec: &Vec<T>) -> io::Result<usize> {
+    lock_memory(vec.as_ptr().cast(), vec.capacity() * std::mem::size_of::<T>())
+}
+
+/// Sleep while `word` holds `expected`, for at most `timeout`; returns early when
+/// [`futex_wake`] is called on `word`, and may return spuriously.
+///
+/// A private futex on Linux. Elsewhere, and under `safe-fallbacks`, a plain sleep of
+/// `timeout` when `word` still holds `expected`, so wake-ups come late by up to `timeout`.
+#[cfg(all(target_os = "linux", not(feature = "safe-fallbacks")))]
+pub fn futex_wait(word: &AtomicU32, expected: u32, timeout: Duration) {
+    let timeout = libc::timespec {
+        tv_sec: timeout.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+        tv_nsec: timeout.subsec_nanos() as libc::c_long,
+    };
+    // SAFETY: `word` is a live, aligned u32 for the whole call; FUTEX_WAIT only reads it
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            word.as_ptr(),
+            libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+            expected,
+            &timeout as *const libc::timespec,
+        );
+    }
+}
+
+#[cfg(not(all(target_os = "linux", not(feature = "safe-fallbacks"))))]
+pub fn futex_wait(word: &AtomicU32, expected: u32, timeout: Duration) {
+    if word.load(std::sync::atomic::Ordering::Acquire) == expected {
+        std::thread::sleep(timeout);
+    }
+}
+
+/// Wake every thread in [`futex_wait`] on `word`; nothing to do off Linux
+#[cfg(all(target_os = "linux", not(feature = "safe-fallbacks")))]
+pub fn futex_wake(word: &AtomicU32) {
+    // SAFETY: FUTEX_WAKE only uses the address of `word` as a key
+    unsafe {
+        libc::syscall(libc::SYS_futex, word.as_ptr(), libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG, i32::MAX);
+    }
+}
+
+#[cfg(not(all(target_os = "linux", not(feature = "safe-fallbacks"))))]
+pub fn futex_wake(_word: &AtomicU32) {}
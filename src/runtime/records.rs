@@ -0,0 +1,373 @@
+//! Match history, high scores and career stats that outlive the process.
+//!
+//! A [`MatchTracker`] follows a running state tick by tick (apples, kills, deaths, lengths
+//! per snake) and [`MatchTracker::finish`] turns that into a [`MatchResult`]. A
+//! [`RecordStore`] appends every result, and leaderboard standings taken along the way, to
+//! a text file, one record per line, and replays the file when opened, so careers carry
+//! on across restarts. A crash in the middle of an append leaves at most a torn last
+//! line, which opening skips and cuts off.
+//!
+//! ```text
+//! # high-frequency-snake records v1
+//! match <id> <first tick> <end tick> <snake>:<apples>:<kills>:<deaths>:<best score>:<longest> ...
+//! standings <tick> <snake>:<score> ...
+//! ```
+//!
+//! Snakes are known by id only, so a career is that of an id across matches.
+
+use crate::game::engine::GameState;
+use crate::game::leaderboard::{APPLE_POINTS, KILL_POINTS, Standing};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+const HEADER: &str = "# high-frequency-snake records v1";
+
+/// One snake's share of a match
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SnakeResult {
+    pub snake_id: u32,
+    pub apples: u64,
+    pub kills: u64,
+    pub deaths: u64,
+    /// Best score of one life, scored as the leaderboard does
+    pub best_score: u64,
+    /// Longest body seen at a death or at the end
+    pub longest: u64,
+}
+
+/// A finished match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchResult {
+    /// Assigned by [`RecordStore::record_match`], counting from 1
+    pub id: u64,
+    /// `GameState::now` when tracking started and when it finished
+    pub first_tick: u64,
+    pub end_tick: u64,
+    /// Every snake that scored, died or was alive at the end, by id
+    pub snakes: Vec<SnakeResult>,
+}
+
+impl MatchResult {
+    /// Snake with the best score, the lowest id among equals; none if nobody scored
+    pub fn winner(&self) -> Option<&SnakeResult> {
+        self.snakes
+            .iter()
+            .filter(|snake| snake.best_score > 0)
+            .max_by_key(|snake| (snake.best_score, std::cmp::Reverse(snake.snake_id)))
+    }
+}
+
+/// Follows a state through a match, see the module docs
+#[derive(Debug, Clone, Default)]
+pub struct MatchTracker {
+    first_tick: u64,
+    snakes: BTreeMap<u32, SnakeResult>,
+    // Score of the current life by snake
+    lives: BTreeMap<u32, u64>,
+}
+
+impl MatchTracker {
+    /// Start tracking `state` from its next tick
+    pub fn start(state: &GameState) -> Self {
+        Self {
+            first_tick: state.now(),
+            ..Self::default()
+        }
+    }
+
+    /// Take in the tick `state` just ran
+    pub fn observe(&mut self, state: &GameState) {
+        for &(_, eater) in state.eaten_apples() {
+            self.entry(eater).apples += 1;
+            *self.lives.entry(eater).or_default() += APPLE_POINTS;
+        }
+        for kill in &state.kills {
+            self.entry(kill.killer).kills += 1;
+            *self.lives.entry(kill.killer).or_default() += KILL_POINTS;
+        }
+        for &id in &state.report().deaths {
+            let length = state.snakes.get(id as usize).map_or(0, |snake| snake.body().len() as u64);
+            let life = self.lives.remove(&id).unwrap_or(0);
+            let snake = self.entry(id);
+            snake.deaths += 1;
+            snake.best_score = snake.best_score.max(life);
+            snake.longest = snake.longest.max(length);
+        }
+    }
+
+    /// The match as it stands, live snakes counted at their current length and score
+    pub fn finish(mut self, state: &GameState) -> MatchResult {
+        for snake in state.snakes.iter().filter(|snake| snake.is_alive()) {
+            let life = self.lives.get(&snake.id()).copied().unwrap_or(0);
+            let length = snake.body().len() as u64;
+            let result = self.entry(snake.id());
+            result.best_score = result.best_score.max(life);
+            result.longest = result.longest.max(length);
+        }
+        MatchResult {
+            id: 0,
+            first_tick: self.first_tick,
+            end_tick: state.now(),
+            snakes: self.snakes.into_values().collect(),
+        }
+    }
+
+    fn entry(&mut self, id: u32) -> &mut SnakeResult {
+        self.snakes.entry(id).or_insert(SnakeResult {
+            snake_id: id,
+            ..SnakeResult::default()
+        })
+    }
+}
+
+/// Totals of one snake id over every recorded match
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Career {
+    pub snake_id: u32,
+    pub matches: u64,
+    pub wins: u64,
+    pub apples: u64,
+    pub kills: u64,
+    pub deaths: u64,
+    pub best_score: u64,
+    pub longest: u64,
+}
+
+impl Career {
+    fn add(&mut self, snake: &SnakeResult, won: bool) {
+        self.matches += 1;
+        self.wins += won as u64;
+        self.apples += snake.apples;
+        self.kills += snake.kills;
+        self.deaths += snake.deaths;
+        self.best_score = self.best_score.max(snake.best_score);
+        self.longest = self.longest.max(snake.longest);
+    }
+}
+
+/// Standings taken at one tick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StandingsRecord {
+    pub tick: u64,
+    pub standings: Vec<Standing>,
+}
+
+/// Append-only file of matches and standings, with everything in it held in memory
+pub struct RecordStore {
+    path: PathBuf,
+    file: File,
+    matches: Vec<MatchResult>,
+    standings: Vec<StandingsRecord>,
+    careers: BTreeMap<u32, Career>,
+    skipped: usize,
+}
+
+impl RecordStore {
+    /// Open the store at `path`, creating it if missing, and replay its records
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(&path)?;
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        let mut store = Self {
+            path,
+            file,
+            matches: Vec::new(),
+            standings: Vec::new(),
+            careers: BTreeMap::new(),
+            skipped: 0,
+        };
+        // Every record ends in a newline; anything after the last one was torn mid-write
+        let complete = text.rfind('\n').map_or("", |end| &text[..=end]);
+        if complete.len() < text.len() {
+            store.skipped += 1;
+        }
+        for line in complete.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            match parse_line(line) {
+                Some(Record::Match(result)) => store.remember(result),
+                Some(Record::Standings(record)) => store.standings.push(record),
+                None => store.skipped += 1,
+            }
+        }
+        if text.is_empty() {
+            store.append(HEADER)?;
+        } else if complete.len() < text.len() {
+            // Drop the torn line: left in place, it would swallow the next record or be
+            // completed into one that was never written
+            store.file.set_len(complete.len() as u64)?;
+        }
+        Ok(store)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `result` under the next match id and return that id
+    pub fn record_match(&mut self, mut result: MatchResult) -> io::Result<u64> {
+        result.id = self.matches.last().map_or(1, |last| last.id + 1);
+        let mut line = format!("match {} {} {}", result.id, result.first_tick, result.end_tick);
+        for snake in &result.snakes {
+            let _ = write!(
+                line,
+                " {}:{}:{}:{}:{}:{}",
+                snake.snake_id, snake.apples, snake.kills, snake.deaths, snake.best_score, snake.longest
+            );
+        }
+        self.append(&line)?;
+        let id = result.id;
+        self.remember(result);
+        Ok(id)
+    }
+
+    /// Append the leaderboard's `standings` as of `tick`
+    pub fn record_standings(&mut self, tick: u64, standings: &[Standing]) -> io::Result<()> {
+        let mut line = format!("standings {}", tick);
+        for standing in standings {
+            let _ = write!(line, " {}:{}", standing.snake_id, standing.score);
+        }
+        self.append(&line)?;
+        self.standings.push(StandingsRecord {
+            tick,
+            standings: standings.to_vec(),
+        });
+        Ok(())
+    }
+
+    pub fn matches(&self) -> &[MatchResult] {
+        &self.matches
+    }
+
+    /// Standings in the order recorded
+    pub fn standings_history(&self) -> &[StandingsRecord] {
+        &self.standings
+    }
+
+    pub fn career(&self, snake_id: u32) -> Option<&Career> {
+        self.careers.get(&snake_id)
+    }
+
+    /// Careers by snake id
+    pub fn careers(&self) -> impl Iterator<Item = &Career> {
+        self.careers.values()
+    }
+
+    /// The `count` best single-match scores as (match id, snake), best first
+    pub fn high_scores(&self, count: usize) -> Vec<(u64, SnakeResult)> {
+        let mut scores: Vec<(u64, SnakeResult)> = self
+            .matches
+            .iter()
+            .flat_map(|result| result.snakes.iter().map(|snake| (result.id, *snake)))
+            .filter(|(_, snake)| snake.best_score > 0)
+            .collect();
+        scores.sort_by_key(|(id, snake)| (std::cmp::Reverse(snake.best_score), *id, snake.snake_id));
+        scores.truncate(count);
+        scores
+    }
+
+    /// Lines that could not be read back when opening, e.g. one torn by a crash
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Summary in plain text: match count, the `top` high scores and career leaders
+    pub fn render(&self, top: usize) -> String {
+        let mut text = format!("matches {}\nstandings_records {}\n", self.matches.len(), self.standings.len());
+        text += "high scores (match snake score apples kills longest):\n";
+        for (id, snake) in self.high_scores(top) {
+            let _ = writeln!(text, "  {} {} {} {} {} {}", id, snake.snake_id, snake.best_score, snake.apples, snake.kills, snake.longest);
+        }
+        let mut careers: Vec<&Career> = self.careers.values().collect();
+        careers.sort_by_key(|career| (std::cmp::Reverse((career.wins, career.best_score)), career.snake_id));
+        text += "careers (snake matches wins best apples kills deaths longest):\n";
+        for career in careers.into_iter().take(top) {
+            let _ = writeln!(text, "  {}", render_career(career));
+        }
+        text
+    }
+
+    fn remember(&mut self, result: MatchResult) {
+        let winner = result.winner().map(|snake| snake.snake_id);
+        for snake in &result.snakes {
+            self.careers
+                .entry(snake.snake_id)
+                .or_insert(Career {
+                    snake_id: snake.snake_id,
+                    ..Career::default()
+                })
+                .add(snake, winner == Some(snake.snake_id));
+        }
+        self.matches.push(result);
+    }
+
+    // One whole line per write, so a crash tears at most the line being written
+    fn append(&mut self, line: &str) -> io::Result<()> {
+        self.file.write_all(format!("{}\n", line).as_bytes())?;
+        self.file.sync_data()
+    }
+}
+
+/// One career as `<snake> <matches> <wins> <best> <apples> <kills> <deaths> <longest>`
+pub fn render_career(career: &Career) -> String {
+    format!(
+        "{} {} {} {} {} {} {} {}",
+        career.snake_id,
+        career.matches,
+        career.wins,
+        career.best_score,
+        career.apples,
+        career.kills,
+        career.deaths,
+        career.longest
+    )
+}
+
+enum Record {
+    Match(MatchResult),
+    Standings(StandingsRecord),
+}
+
+fn parse_line(line: &str) -> Option<Record> {
+    let mut words = line.split_whitespace();
+    match words.next()? {
+        "match" => {
+            let id = words.next()?.parse().ok()?;
+            let first_tick = words.next()?.parse().ok()?;
+            let end_tick = words.next()?.parse().ok()?;
+            let snakes = words
+                .map(|word| {
+                    let fields: Vec<u64> = word.split(':').map(str::parse).collect::<Result<_, _>>().ok()?;
+                    match fields[..] {
+                        [snake_id, apples, kills, deaths, best_score, longest] => Some(SnakeResult {
+                            snake_id: u32::try_from(snake_id).ok()?,
+                            apples,
+                            kills,
+                            deaths,
+                            best_score,
+                            longest,
+                        }),
+                        _ => None,
+                    }
+                })
+                .collect::<Option<_>>()?;
+            Some(Record::Match(MatchResult { id, first_tick, end_tick, snakes }))
+        }
+        "standings" => {
+            let tick = words.next()?.parse().ok()?;
+            let standings = words
+                .map(|word| {
+                    let (snake_id, score) = word.split_once(':')?;
+                    Some(Standing {
+                        snake_id: snake_id.parse().ok()?,
+                        score: score.parse().ok()?,
+                    })
+                })
+                .collect::<Option<_>>()?;
+            Some(Record::Standings(StandingsRecord { tick, standings }))
+        }
+        _ => None,
+    }
+}
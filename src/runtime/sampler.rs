@@ -0,0 +1,139 @@
+//! Sampling self-profiler (the `phase-sampler` build).
+//!
+//! With a [`PhaseBeacon`] attached (`GameState::set_phase_beacon`), the engine stores
+//! which of `TICK_PHASES` it is running at every phase boundary of every `every_ticks`-th
+//! tick: one relaxed store each, no timestamp reads. A [`PhaseSampler`] thread reads the
+//! beacon at a fixed interval and counts what it finds. Over many samples the counts
+//! approximate where tick time goes, without perf tooling, so it can stay on in
+//! production.
+//!
+//! Between ticks, and throughout ticks that are not sampled, the beacon reads idle. The
+//! sampler's wake-ups follow the OS timer, so short runs give noisy shares.
+//!
+//! The beacon and the sampler always build; only the engine's marking is behind the
+//! feature, so a default build pays nothing per tick.
+
+use crate::game::metrics::TICK_PHASES;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const IDLE: u8 = u8::MAX;
+
+/// The phase a game thread is in, as last marked by the engine
+#[derive(Debug)]
+pub struct PhaseBeacon(AtomicU8);
+
+impl PhaseBeacon {
+    pub fn new() -> Self {
+        Self(AtomicU8::new(IDLE))
+    }
+
+    /// Now running `phase` (an index into `TICK_PHASES`), or outside any sampled phase
+    #[inline(always)]
+    pub fn mark(&self, phase: Option<usize>) {
+        self.0.store(phase.map_or(IDLE, |phase| phase as u8), Ordering::Relaxed);
+    }
+
+    pub fn current(&self) -> Option<usize> {
+        let phase = self.0.load(Ordering::Relaxed);
+        (phase != IDLE).then_some(phase as usize)
+    }
+}
+
+impl Default for PhaseBeacon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Samples per tick phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PhaseHistogram {
+    /// Indexed like `TICK_PHASES`
+    pub phases: [u64; TICK_PHASES.len()],
+    /// Samples that found the game thread outside a sampled tick
+    pub idle: u64,
+}
+
+impl PhaseHistogram {
+    /// Samples taken inside a sampled tick
+    pub fn busy(&self) -> u64 {
+        self.phases.iter().sum()
+    }
+}
+
+impl fmt::Display for PhaseHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let busy = self.busy().max(1);
+        for (name, &samples) in TICK_PHASES.iter().zip(&self.phases) {
+            writeln!(f, "{:<8} {:>10} samples {:>5.1}%", name, samples, samples as f64 * 100.0 / busy as f64)?;
+        }
+        writeln!(f, "{:<8} {:>10} samples", "idle", self.idle)
+    }
+}
+
+// One counter per phase, then idle
+type Counters = [AtomicU64; TICK_PHASES.len() + 1];
+
+/// Background thread counting the phase a [`PhaseBeacon`] shows, once per interval
+pub struct PhaseSampler {
+    counters: Arc<Counters>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PhaseSampler {
+    /// Start sampling `beacon` every `interval` on a thread of its own
+    pub fn start(beacon: Arc<PhaseBeacon>, interval: Duration) -> io::Result<Self> {
+        let counters: Arc<Counters> = Arc::new(std::array::from_fn(|_| AtomicU64::new(0)));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let counters = Arc::clone(&counters);
+            let stop = Arc::clone(&stop);
+            thread::Builder::new().name("phase-sampler".to_string()).spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let slot = beacon.current().unwrap_or(TICK_PHASES.len());
+                    counters[slot].fetch_add(1, Ordering::Relaxed);
+                    thread::sleep(interval);
+                }
+            })?
+        };
+        Ok(Self {
+            counters,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Samples so far
+    pub fn histogram(&self) -> PhaseHistogram {
+        let count = |slot: usize| self.counters[slot].load(Ordering::Relaxed);
+        PhaseHistogram {
+            phases: std::array::from_fn(count),
+            idle: count(TICK_PHASES.len()),
+        }
+    }
+
+    /// Stop sampling and return every sample taken
+    pub fn stop(mut self) -> PhaseHistogram {
+        self.join();
+        self.histogram()
+    }
+
+    fn join(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for PhaseSampler {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
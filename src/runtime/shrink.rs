@@ -0,0 +1,174 @@
+//! Minimise a failing [`Scenario`] into a small reproducer.
+//!
+//! The failure is a black box: `still_fails` gets a candidate scenario and reports whether
+//! the bug still shows (an invariant check via [`Scenario::first_failure`], a comparison
+//! against a second engine, ...). It must be deterministic. The shrinker then
+//!
+//! 1. binary-searches the shortest run that still fails,
+//! 2. binary-searches the latest start, re-capturing the state at that tick,
+//! 3. drops snakes, inputs, apples and power-ups in halving chunks while the failure persists,
+//!
+//! and repeats 3 until nothing more can go. Save the result with [`Scenario::save`].
+
+use crate::game::scenario::Scenario;
+use crate::game::types::Input;
+use crate::input::replay::Replay;
+
+#[derive(Debug, Clone)]
+pub struct ShrinkOutcome {
+    pub scenario: Scenario,
+    /// Times `still_fails` was called
+    pub oracle_runs: usize,
+}
+
+/// Minimise `scenario`, which must fail to begin with (otherwise it is returned unchanged)
+pub fn shrink<F>(scenario: Scenario, mut still_fails: F) -> ShrinkOutcome
+where
+    F: FnMut(&Scenario) -> bool,
+{
+    let mut oracle_runs = 1;
+    if !still_fails(&scenario) {
+        return ShrinkOutcome { scenario, oracle_runs };
+    }
+    let mut fails = |candidate: &Scenario| {
+        oracle_runs += 1;
+        still_fails(candidate)
+    };
+
+    let mut scenario = trim_end(scenario, &mut fails);
+    scenario = trim_start(scenario, &mut fails);
+    loop {
+        let size = |scenario: &Scenario| {
+            (scenario.snakes.len(), scenario.replay.input_count(), scenario.apples.len(), scenario.powerups.len())
+        };
+        let before = size(&scenario);
+        scenario = drop_snakes(scenario, &mut fails);
+        scenario = drop_inputs(scenario, &mut fails);
+        scenario = drop_apples(scenario, &mut fails);
+        scenario = drop_powerups(scenario, &mut fails);
+        if before == size(&scenario) {
+            break;
+        }
+    }
+    let scenario = trim_end(scenario, &mut fails);
+
+    ShrinkOutcome { scenario, oracle_runs }
+}
+
+/// Shortest prefix of the run that still fails
+fn trim_end(scenario: Scenario, fails: &mut impl FnMut(&Scenario) -> bool) -> Scenario {
+    let (mut low, mut high) = (1, scenario.ticks);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if fails(&truncate(&scenario, middle)) {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    truncate(&scenario, low)
+}
+
+/// Latest tick the run can start from and still fail
+fn trim_start(scenario: Scenario, fails: &mut impl FnMut(&Scenario) -> bool) -> Scenario {
+    let (mut low, mut high) = (0, scenario.ticks.saturating_sub(1));
+    let mut best = scenario.clone();
+    while low < high {
+        let middle = low + (high - low).div_ceil(2);
+        let candidate = advance(&scenario, middle);
+        if fails(&candidate) {
+            low = middle;
+            best = candidate;
+        } else {
+            high = middle - 1;
+        }
+    }
+    best
+}
+
+fn drop_snakes(scenario: Scenario, fails: &mut impl FnMut(&Scenario) -> bool) -> Scenario {
+    let indices: Vec<usize> = (0..scenario.snakes.len()).collect();
+    let kept = reduce(indices, |keep| fails(&keep_snakes(&scenario, keep)));
+    keep_snakes(&scenario, &kept)
+}
+
+fn drop_inputs(scenario: Scenario, fails: &mut impl FnMut(&Scenario) -> bool) -> Scenario {
+    let steps: Vec<(u64, Input)> = scenario.replay.steps().collect();
+    let with_steps = |steps: &[(u64, Input)]| Scenario {
+        replay: Replay::from_steps(steps.iter().copied()),
+        ..scenario.clone()
+    };
+    let kept = reduce(steps, |steps| fails(&with_steps(steps)));
+    with_steps(&kept)
+}
+
+fn drop_apples(scenario: Scenario, fails: &mut impl FnMut(&Scenario) -> bool) -> Scenario {
+    let with_apples = |apples: &[_]| Scenario {
+        apples: apples.to_vec(),
+        ..scenario.clone()
+    };
+    let kept = reduce(scenario.apples.clone(), |apples| fails(&with_apples(apples)));
+    with_apples(&kept)
+}
+
+fn drop_powerups(scenario: Scenario, fails: &mut impl FnMut(&Scenario) -> bool) -> Scenario {
+    let with_powerups = |powerups: &[_]| Scenario {
+        powerups: powerups.to_vec(),
+        ..scenario.clone()
+    };
+    let kept = reduce(scenario.powerups.clone(), |powerups| fails(&with_powerups(powerups)));
+    with_powerups(&kept)
+}
+
+fn truncate(scenario: &Scenario, ticks: u64) -> Scenario {
+    Scenario {
+        replay: scenario.replay.window(0, ticks),
+        ticks,
+        ..scenario.clone()
+    }
+}
+
+/// The scenario as it stands after its first `ticks` ticks
+fn advance(scenario: &Scenario, ticks: u64) -> Scenario {
+    let state = scenario.state_after(ticks);
+    Scenario {
+        events: scenario.events.window(ticks, scenario.ticks - ticks),
+        ..Scenario::from_state(&state, ticks, &scenario.replay, scenario.ticks - ticks)
+    }
+}
+
+/// Keep the snakes at `keep` (ascending indices), renumbering them and their inputs
+fn keep_snakes(scenario: &Scenario, keep: &[usize]) -> Scenario {
+    let mut new_ids = vec![None; scenario.snakes.len()];
+    for (new_id, &old_id) in keep.iter().enumerate() {
+        new_ids[old_id] = Some(new_id as u32);
+    }
+    let steps = scenario.replay.steps().filter_map(|(tick, input)| {
+        new_ids[input.snake_id as usize].map(|snake_id| (tick, Input { snake_id, ..input }))
+    });
+    Scenario {
+        snakes: keep.iter().map(|&id| scenario.snakes[id].clone()).collect(),
+        replay: Replay::from_steps(steps),
+        ..scenario.clone()
+    }
+}
+
+/// Remove chunks of `items` (halving the chunk size down to single items) as long as
+/// `fails` keeps holding for what remains
+fn reduce<T: Clone>(mut items: Vec<T>, mut fails: impl FnMut(&[T]) -> bool) -> Vec<T> {
+    let mut chunk = items.len().div_ceil(2);
+    while chunk > 0 {
+        let mut start = 0;
+        while start < items.len() {
+            let end = (start + chunk).min(items.len());
+            let candidate: Vec<T> = items[..start].iter().chain(&items[end..]).cloned().collect();
+            if fails(&candidate) {
+                items = candidate;
+            } else {
+                start = end;
+            }
+        }
+        chunk /= 2;
+    }
+    items
+}
@@ -0,0 +1,181 @@
+use crate::game::engine::GameState;
+use crate::game::snapshot::save_snapshot;
+use crate::game::types::Input;
+use crate::input::replay::{Replay, ReplayFrame};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+
+/// Process exit status used when a supervised worker panicked (EX_SOFTWARE)
+pub const EXIT_WORKER_PANIC: i32 = 70;
+
+/// Shared stop signal for supervised workers.
+///
+/// Workers poll [`Shutdown::is_requested`] in their loops; the supervisor requests
+/// shutdown on behalf of any worker that panics, and marks it as a failure.
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+    failed: Arc<AtomicBool>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every worker to stop
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::Release);
+    }
+
+    #[inline(always)]
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::Relaxed)
+    }
+
+    /// True if shutdown was triggered by a worker panic
+    pub fn is_failure(&self) -> bool {
+        self.failed.load(Ordering::Acquire)
+    }
+
+    /// The raw stop flag, for APIs such as `LoadGenerator::run` that take an `AtomicBool`
+    pub fn flag(&self) -> &AtomicBool {
+        &self.requested
+    }
+
+    fn fail(&self) {
+        self.failed.store(true, Ordering::Release);
+        self.request();
+    }
+}
+
+/// A worker that panicked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerPanic {
+    pub worker: String,
+    pub message: String,
+}
+
+/// Runs worker threads so that one panicking tears the others down instead of
+/// leaving them spinning and `join()` hanging forever.
+#[derive(Default)]
+pub struct Supervisor {
+    shutdown: Shutdown,
+    workers: Vec<(String, JoinHandle<Result<(), String>>)>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn shutdown(&self) -> &Shutdown {
+        &self.shutdown
+    }
+
+    /// Spawn a named worker. A panic inside `work` is caught and turns into a failure
+    /// shutdown for every worker.
+    pub fn spawn<F>(&mut self, name: &str, work: F) -> io::Result<()>
+    where
+        F: FnOnce(Shutdown) + Send + 'static,
+    {
+        let shutdown = self.shutdown.clone();
+        let handle = thread::Builder::new().name(name.to_string()).spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| work(shutdown.clone())));
+            result.map_err(|payload| {
+                shutdown.fail();
+                panic_message(payload.as_ref())
+            })
+        })?;
+        self.workers.push((name.to_string(), handle));
+        Ok(())
+    }
+
+    /// Wait for every worker to finish. Returns the first panic in spawn order, if any.
+    pub fn join(self) -> Result<(), WorkerPanic> {
+        let mut first_panic = None;
+        for (worker, handle) in self.workers {
+            // The worker body is unwind-caught, so join only fails if that machinery does
+            let result = handle.join().unwrap_or_else(|payload| Err(panic_message(payload.as_ref())));
+            if let Err(message) = result
+                && first_panic.is_none()
+            {
+                first_panic = Some(WorkerPanic { worker, message });
+            }
+        }
+        first_panic.map_or(Ok(()), Err)
+    }
+}
+
+/// The last `capacity` non-empty input batches, kept for crash dumps.
+/// Frame buffers are recycled once full, so steady-state recording does not allocate.
+#[derive(Debug, Clone)]
+pub struct InputHistory {
+    frames: VecDeque<ReplayFrame>,
+    capacity: usize,
+}
+
+impl InputHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, inputs: &[Input]) {
+        if inputs.is_empty() || self.capacity == 0 {
+            return;
+        }
+        let mut frame = if self.frames.len() == self.capacity {
+            self.frames.pop_front().expect("history is full")
+        } else {
+            ReplayFrame { tick, inputs: Vec::new() }
+        };
+        frame.tick = tick;
+        frame.inputs.clear();
+        frame.inputs.extend_from_slice(inputs);
+        self.frames.push_back(frame);
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn to_replay(&self) -> Replay {
+        Replay {
+            frames: self.frames.iter().cloned().collect(),
+        }
+    }
+}
+
+/// Write `crash-<tick>.snap` and `crash-<tick>.replay` into `dir` and return the snapshot path.
+/// The state may be mid-tick if the game thread itself panicked.
+pub fn write_crash_dump(dir: &Path, state: &GameState, tick: u64, history: &InputHistory) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let snapshot_path = dir.join(format!("crash-{:020}.snap", tick));
+    save_snapshot(state, tick, &snapshot_path)?;
+    history.to_replay().save(snapshot_path.with_extension("replay"))?;
+    Ok(snapshot_path)
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
@@ -0,0 +1,187 @@
+use crate::game::config::GameConfig;
+use crate::game::engine::GameState;
+use crate::game::types::Input;
+use crate::input::load::{ArrivalProcess, LoadConfig, LoadGenerator};
+use crate::ipc::spsc::Spsc;
+use crate::runtime::latency::{LatencyRecorder, LatencySummary};
+use crate::runtime::threads::{self, ThreadRole};
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SWEEP_QUEUE_CAPACITY: usize = 1024;
+
+/// An input carrying its scheduled send time, so apply latency can be measured end to end
+#[derive(Debug, Clone, Copy)]
+pub struct TimedInput {
+    pub input: Input,
+    pub sent_at: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct SweepConfig {
+    /// Offered rate of the first step (inputs/sec)
+    pub min_rate: f64,
+    /// Offered rate of the last step (inputs/sec)
+    pub max_rate: f64,
+    /// Number of steps, spaced geometrically between min and max
+    pub steps: usize,
+    /// Measurement time per step
+    pub step_duration: Duration,
+    pub arrival: ArrivalProcess,
+    pub game: GameConfig,
+    pub seed: u64,
+    /// Pin producer and game loop to these cores when set
+    pub cores: Option<(core_affinity::CoreId, core_affinity::CoreId)>,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self {
+            min_rate: 10_000.0,
+            max_rate: 10_000_000.0,
+            steps: 10,
+            step_duration: Duration::from_secs(2),
+            arrival: ArrivalProcess::Poisson,
+            game: GameConfig::default(),
+            seed: 42,
+            cores: None,
+        }
+    }
+}
+
+/// Measurements for one offered-load step
+#[derive(Debug, Clone, Copy)]
+pub struct SweepPoint {
+    pub offered_rate: f64,
+    /// Inputs actually applied per second
+    pub achieved_rate: f64,
+    pub dropped: u64,
+    /// Time from scheduled send to the end of the tick that applied the input
+    pub apply_latency: LatencySummary,
+    pub tick_duration: LatencySummary,
+}
+
+impl SweepConfig {
+    /// Offered rate of each step
+    pub fn rates(&self) -> Vec<f64> {
+        if self.steps <= 1 {
+            return vec![self.min_rate];
+        }
+        let ratio = (self.max_rate / self.min_rate).powf(1.0 / (self.steps - 1) as f64);
+        (0..self.steps)
+            .map(|step| self.min_rate * ratio.powi(step as i32))
+            .collect()
+    }
+}
+
+/// Run every step of the sweep against one game state and return the measured curve
+pub fn run_sweep(config: &SweepConfig) -> Vec<SweepPoint> {
+    let mut game_state = GameState::random_with_config(config.game);
+    config
+        .rates()
+        .into_iter()
+        .map(|rate| run_step(config, rate, &mut game_state))
+        .collect()
+}
+
+fn run_step(config: &SweepConfig, rate: f64, game_state: &mut GameState) -> SweepPoint {
+    let (mut producer_queue, mut queue) = Spsc::<TimedInput, SWEEP_QUEUE_CAPACITY>::new().split();
+    let done = Arc::new(AtomicBool::new(false));
+    let producer_done = Arc::clone(&done);
+
+    let load_config = LoadConfig {
+        target_rate: rate,
+        arrival: config.arrival,
+        num_snakes: game_state.snakes.len() as u32,
+        seed: config.seed,
+        ..LoadConfig::default()
+    };
+    let step_duration = config.step_duration;
+    let producer_core = config.cores.map(|(producer, _)| producer);
+
+    let producer = thread::spawn(move || {
+        let _registration = threads::register(ThreadRole::InputGen, producer_core);
+        let never_stop = AtomicBool::new(false);
+        let stats = LoadGenerator::new(load_config).run(&never_stop, Some(step_duration), |sent_at, input| {
+            producer_queue.produce(TimedInput { input, sent_at })
+        });
+        producer_done.store(true, Ordering::Release);
+        stats
+    });
+
+    let _registration = threads::register(ThreadRole::Game, config.cores.map(|(_, game)| game));
+
+    let expected_inputs = (rate * step_duration.as_secs_f64()) as usize;
+    let mut apply_latency = LatencyRecorder::with_capacity(expected_inputs);
+    let mut tick_duration = LatencyRecorder::default();
+    let mut timed_inputs = Vec::with_capacity(SWEEP_QUEUE_CAPACITY);
+    let mut inputs = Vec::with_capacity(SWEEP_QUEUE_CAPACITY);
+    let start = Instant::now();
+
+    loop {
+        // Check before draining so nothing published before `done` is missed
+        let producer_finished = done.load(Ordering::Acquire);
+        while let Some(timed) = queue.consume() {
+            timed_inputs.push(timed);
+        }
+
+        if timed_inputs.is_empty() {
+            if producer_finished {
+                break;
+            }
+            std::hint::spin_loop();
+            continue;
+        }
+
+        inputs.extend(timed_inputs.iter().map(|timed| timed.input));
+        let tick_start = Instant::now();
+        game_state.tick(&inputs);
+        let tick_end = Instant::now();
+
+        tick_duration.record((tick_end - tick_start).as_nanos() as u64);
+        for timed in &timed_inputs {
+            apply_latency.record(tick_end.saturating_duration_since(timed.sent_at).as_nanos() as u64);
+        }
+        timed_inputs.clear();
+        inputs.clear();
+    }
+
+    let elapsed = start.elapsed();
+    let stats = producer.join().expect("load generator thread panicked");
+
+    SweepPoint {
+        offered_rate: rate,
+        achieved_rate: apply_latency.len() as f64 / elapsed.as_secs_f64(),
+        dropped: stats.dropped,
+        apply_latency: apply_latency.summary(),
+        tick_duration: tick_duration.summary(),
+    }
+}
+
+/// Write the throughput-vs-latency curve as CSV (latencies in microseconds)
+pub fn write_csv<W: Write>(points: &[SweepPoint], writer: &mut W) -> io::Result<()> {
+    writeln!(
+        writer,
+        "offered_rate,achieved_rate,dropped,ticks,apply_p50_us,apply_p99_us,apply_p999_us,tick_p50_us,tick_p99_us,tick_p999_us"
+    )?;
+    for point in points {
+        writeln!(
+            writer,
+            "{:.0},{:.0},{},{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3}",
+            point.offered_rate,
+            point.achieved_rate,
+            point.dropped,
+            point.tick_duration.count,
+            point.apply_latency.p50 as f64 / 1000.0,
+            point.apply_latency.p99 as f64 / 1000.0,
+            point.apply_latency.p999 as f64 / 1000.0,
+            point.tick_duration.p50 as f64 / 1000.0,
+            point.tick_duration.p99 as f64 / 1000.0,
+            point.tick_duration.p999 as f64 / 1000.0,
+        )?;
+    }
+    Ok(())
+}
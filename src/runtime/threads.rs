@@ -0,0 +1,145 @@
+//! Registry of the runner's threads: name, role, core and OS thread id.
+//!
+//! Each long-lived thread calls [`register`] once it starts. Registering pins the thread
+//! to its assigned core and records what it is, so `perf`, `top -H` or a debugger can be
+//! pointed at the right tid without guessing which of half a dozen pinned threads is
+//! which. The entry goes away when the returned [`Registration`] is dropped. [`list`]
+//! and [`render`] read the registry from any thread; the async admin endpoint serves it.
+
+use crate::runtime::platform;
+use core_affinity::CoreId;
+use std::fmt;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadRole {
+    /// Runs `GameState::tick`
+    Game,
+    /// Generates load into the input queue
+    InputGen,
+    /// Publishes or records metrics
+    Metrics,
+    /// Receives inputs from outside the process
+    NetRx,
+}
+
+impl ThreadRole {
+    pub fn name(self) -> &'static str {
+        match self {
+            ThreadRole::Game => "game",
+            ThreadRole::InputGen => "inputgen",
+            ThreadRole::Metrics => "metrics",
+            ThreadRole::NetRx => "net-rx",
+        }
+    }
+}
+
+impl fmt::Display for ThreadRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Where a thread was meant to run and where it does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Unpinned,
+    Pinned(usize),
+    /// The OS refused to pin the thread to this core
+    Refused(usize),
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placement::Unpinned => write!(f, "any core"),
+            Placement::Pinned(core) => write!(f, "core {}", core),
+            Placement::Refused(core) => write!(f, "any core (pinning to {} refused)", core),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadInfo {
+    /// The thread's name, or its role's if it has none
+    pub name: String,
+    pub role: ThreadRole,
+    pub placement: Placement,
+    /// Kernel thread id, as `perf -t` and `top -H` show it; Linux only
+    pub tid: Option<u64>,
+    id: ThreadId,
+}
+
+static REGISTRY: Mutex<Vec<ThreadInfo>> = Mutex::new(Vec::new());
+
+/// Keeps the calling thread listed until dropped
+#[derive(Debug)]
+pub struct Registration {
+    id: ThreadId,
+    placement: Placement,
+}
+
+impl Registration {
+    pub fn placement(&self) -> Placement {
+        self.placement
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let mut threads = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        threads.retain(|thread| thread.id != self.id);
+    }
+}
+
+/// Pin the calling thread to `core`, if given, and list it under `role`
+pub fn register(role: ThreadRole, core: Option<CoreId>) -> Registration {
+    let placement = match core {
+        Some(core) if platform::pin_current(core) => Placement::Pinned(core.id),
+        Some(core) => Placement::Refused(core.id),
+        None => Placement::Unpinned,
+    };
+    let current = thread::current();
+    let info = ThreadInfo {
+        name: current.name().unwrap_or(role.name()).to_string(),
+        role,
+        placement,
+        tid: os_thread_id(),
+        id: current.id(),
+    };
+    let mut threads = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    threads.retain(|thread| thread.id != info.id);
+    threads.push(info);
+    Registration { id: current.id(), placement }
+}
+
+/// Every registered thread, in order of registration
+pub fn list() -> Vec<ThreadInfo> {
+    REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// `threads` in `thread <name> role <role> core <core|any> tid <tid|?>` lines
+pub fn render(threads: &[ThreadInfo]) -> String {
+    let mut out = String::new();
+    for thread in threads {
+        let core = match thread.placement {
+            Placement::Pinned(core) => core.to_string(),
+            Placement::Unpinned | Placement::Refused(_) => "any".to_string(),
+        };
+        let tid = thread.tid.map_or("?".to_string(), |tid| tid.to_string());
+        out.push_str(&format!("thread {} role {} core {} tid {}\n", thread.name, thread.role, core, tid));
+    }
+    out
+}
+
+#[cfg(all(target_os = "linux", not(feature = "safe-fallbacks")))]
+fn os_thread_id() -> Option<u64> {
+    // SAFETY: gettid has no preconditions
+    Some(unsafe { libc::gettid() } as u64)
+}
+
+#[cfg(not(all(target_os = "linux", not(feature = "safe-fallbacks"))))]
+fn os_thread_id() -> Option<u64> {
+    None
+}
@@ -0,0 +1,233 @@
+//! Spectator time-shift: pause a live game, scrub back through its recent past and return.
+//!
+//! A [`SnapshotHistory`] is fed the live state after every tick and keeps the last
+//! `retain_ticks` of it as a seekable chain: an encoded snapshot (keyframe) every
+//! `keyframe_interval` ticks and an encoded [`SnapshotDelta`] for every tick in between.
+//! Any retained tick is rebuilt from the keyframe at or before it plus the deltas after
+//! that. To compute deltas the history mirrors the live state, so it holds a second copy
+//! of the grid.
+//!
+//! A [`Spectator`] is one client's position in the history: live, or paused on a tick of
+//! its own copy of the state, which it can step forward delta by delta, rewind, or drop
+//! to go back to live. The simulation never waits for either.
+
+use crate::game::codec::GridCodec;
+use crate::game::delta::SnapshotDelta;
+use crate::game::engine::GameState;
+use crate::game::snapshot::{read_snapshot, write_snapshot_with};
+use std::collections::VecDeque;
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeShiftConfig {
+    /// Ticks behind the latest one that stay seekable
+    pub retain_ticks: u64,
+    /// Ticks between keyframes; seeking replays at most this many deltas
+    pub keyframe_interval: u64,
+    pub codec: GridCodec,
+}
+
+impl Default for TimeShiftConfig {
+    fn default() -> Self {
+        Self {
+            retain_ticks: 10_000,
+            keyframe_interval: 500,
+            codec: GridCodec::Bitmask,
+        }
+    }
+}
+
+impl TimeShiftConfig {
+    /// Keep `seconds` of a game running at `ticks_per_second`
+    pub fn for_seconds(seconds: u64, ticks_per_second: u64) -> Self {
+        Self {
+            retain_ticks: seconds * ticks_per_second,
+            ..Self::default()
+        }
+    }
+}
+
+/// Retained keyframes and per-tick deltas of a live game, see the module docs
+pub struct SnapshotHistory {
+    config: TimeShiftConfig,
+    // The state as of the latest tick published, rebuilt from the chain
+    mirror: GameState,
+    // (tick, encoded snapshot), oldest first
+    keyframes: VecDeque<(u64, Vec<u8>)>,
+    // (tick, encoded delta from tick - 1), oldest first, all after the first keyframe
+    deltas: VecDeque<(u64, Vec<u8>)>,
+}
+
+impl SnapshotHistory {
+    /// Start the history at `state`'s current tick
+    pub fn new(state: &GameState, config: TimeShiftConfig) -> io::Result<Self> {
+        let mut keyframe = Vec::new();
+        write_snapshot_with(state, state.now(), config.codec, &mut keyframe)?;
+        let (_, mirror) = read_snapshot(&mut keyframe.as_slice())?;
+        Ok(Self {
+            config: TimeShiftConfig {
+                keyframe_interval: config.keyframe_interval.max(1),
+                ..config
+            },
+            mirror,
+            keyframes: VecDeque::from([(state.now(), keyframe)]),
+            deltas: VecDeque::new(),
+        })
+    }
+
+    pub fn config(&self) -> TimeShiftConfig {
+        self.config
+    }
+
+    /// Record `state` after a tick. Ticks must be published in order, none skipped.
+    pub fn publish(&mut self, state: &GameState) -> io::Result<()> {
+        let tick = state.now();
+        if tick != self.latest() + 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("published tick {} after {}", tick, self.latest()),
+            ));
+        }
+        let delta = SnapshotDelta::between(&self.mirror, state);
+        delta.apply(&mut self.mirror);
+        let mut encoded = Vec::new();
+        delta.write_to(&mut encoded)?;
+        self.deltas.push_back((tick, encoded));
+
+        if tick.is_multiple_of(self.config.keyframe_interval) {
+            let mut keyframe = Vec::new();
+            write_snapshot_with(state, tick, self.config.codec, &mut keyframe)?;
+            self.keyframes.push_back((tick, keyframe));
+        }
+        self.evict();
+        Ok(())
+    }
+
+    /// Latest tick published
+    pub fn latest(&self) -> u64 {
+        self.deltas.back().or(self.keyframes.back()).map_or(0, |(tick, _)| *tick)
+    }
+
+    /// Earliest tick that can still be rebuilt
+    pub fn oldest(&self) -> u64 {
+        self.keyframes.front().map_or(0, |(tick, _)| *tick)
+    }
+
+    /// Bytes held by keyframes and deltas
+    pub fn retained_bytes(&self) -> usize {
+        self.keyframes.iter().chain(&self.deltas).map(|(_, bytes)| bytes.len()).sum()
+    }
+
+    /// Encoded delta turning the state at `tick - 1` into the state at `tick`, if retained
+    pub fn delta(&self, tick: u64) -> Option<&[u8]> {
+        let first = self.deltas.front()?.0;
+        let (stored, bytes) = self.deltas.get(tick.checked_sub(first)? as usize)?;
+        debug_assert_eq!(*stored, tick);
+        Some(bytes)
+    }
+
+    /// Rebuild the state at `tick`, which must lie within `oldest()..=latest()`
+    pub fn state_at(&self, tick: u64) -> io::Result<GameState> {
+        if tick < self.oldest() || tick > self.latest() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("tick {} is outside the history ({}..={})", tick, self.oldest(), self.latest()),
+            ));
+        }
+        let (start, keyframe) = self.keyframes.iter().rev().find(|(start, _)| *start <= tick).expect("oldest keyframe");
+        let (_, mut state) = read_snapshot(&mut keyframe.as_slice())?;
+        for step in start + 1..=tick {
+            apply_encoded(&mut state, self.delta(step).expect("delta within the history"))?;
+        }
+        Ok(state)
+    }
+
+    // Drop the oldest keyframe and its deltas once the next keyframe alone covers the window
+    fn evict(&mut self) {
+        let horizon = self.latest().saturating_sub(self.config.retain_ticks);
+        while self.keyframes.len() > 1 && self.keyframes[1].0 <= horizon {
+            self.keyframes.pop_front();
+            let start = self.oldest();
+            while self.deltas.front().is_some_and(|(tick, _)| *tick <= start) {
+                self.deltas.pop_front();
+            }
+        }
+    }
+}
+
+/// One spectator's position in a [`SnapshotHistory`]
+#[derive(Default)]
+pub struct Spectator {
+    // None while following the live game
+    paused: Option<GameState>,
+}
+
+impl Spectator {
+    /// A spectator following the live game
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.paused.is_none()
+    }
+
+    /// Tick on screen: the paused one, or the latest published
+    pub fn tick(&self, history: &SnapshotHistory) -> u64 {
+        self.paused.as_ref().map_or(history.latest(), GameState::now)
+    }
+
+    /// The state on screen while paused; live spectators show the live state
+    pub fn paused_state(&self) -> Option<&GameState> {
+        self.paused.as_ref()
+    }
+
+    /// Freeze on the latest published tick
+    pub fn pause(&mut self, history: &SnapshotHistory) -> io::Result<()> {
+        if self.paused.is_none() {
+            self.paused = Some(history.state_at(history.latest())?);
+        }
+        Ok(())
+    }
+
+    /// Pause on `tick`, clamped to the retained ticks. Returns the tick shown.
+    pub fn seek(&mut self, history: &SnapshotHistory, tick: u64) -> io::Result<u64> {
+        let tick = tick.clamp(history.oldest(), history.latest());
+        self.paused = Some(history.state_at(tick)?);
+        Ok(tick)
+    }
+
+    /// Pause `ticks` before the tick on screen, as far back as the history goes
+    pub fn rewind(&mut self, history: &SnapshotHistory, ticks: u64) -> io::Result<u64> {
+        self.seek(history, self.tick(history).saturating_sub(ticks))
+    }
+
+    /// Advance a paused spectator one tick, applying the next delta. A spectator whose
+    /// tick has left the history jumps to the oldest one retained. Returns false when
+    /// already live or on the latest tick.
+    pub fn step(&mut self, history: &SnapshotHistory) -> io::Result<bool> {
+        let Some(state) = &mut self.paused else {
+            return Ok(false);
+        };
+        let next = state.now() + 1;
+        if next > history.latest() {
+            return Ok(false);
+        }
+        if next <= history.oldest() {
+            self.seek(history, history.oldest())?;
+            return Ok(true);
+        }
+        apply_encoded(state, history.delta(next).expect("delta within the history"))?;
+        Ok(true)
+    }
+
+    /// Back to following the live game
+    pub fn go_live(&mut self) {
+        self.paused = None;
+    }
+}
+
+fn apply_encoded(state: &mut GameState, mut bytes: &[u8]) -> io::Result<()> {
+    SnapshotDelta::read_from(&mut bytes)?.apply(state);
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! Warm-up before measurement.
+//!
+//! The first ticks of a run pay for page faults, cold caches and untrained branch
+//! predictors, and measuring them says nothing about the steady state. [`warmup`]
+//! pre-faults the grid and then ticks until per-tick latency settles, or until a tick
+//! limit, so benches and the runner start measuring from the same place.
+
+use crate::game::engine::GameState;
+use crate::runtime::platform::timestamp;
+
+#[derive(Debug, Clone)]
+pub struct WarmupConfig {
+    /// Ticks per window; latency is compared window to window
+    pub window: usize,
+    /// Settled once a window's mean absolute deviation is within this percentage of its
+    /// mean, and its mean within this percentage of the previous window's
+    pub tolerance_percent: u64,
+    /// Give up waiting for latency to settle after this many ticks
+    pub max_ticks: usize,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            window: 32,
+            tolerance_percent: 10,
+            max_ticks: 1024,
+        }
+    }
+}
+
+/// How a warm-up ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmupReport {
+    /// Ticks run
+    pub ticks: usize,
+    /// Whether latency settled before `max_ticks`
+    pub steady: bool,
+    /// Mean tick latency of the last full window, in `platform::TIMESTAMP_UNIT`s
+    pub mean: u64,
+}
+
+/// Steady-state detection over a stream of tick durations
+#[derive(Debug, Clone)]
+pub struct SteadyState {
+    config: WarmupConfig,
+    window: Vec<u64>,
+    previous_mean: Option<u64>,
+    samples: usize,
+}
+
+impl SteadyState {
+    pub fn new(config: WarmupConfig) -> Self {
+        Self {
+            window: Vec::with_capacity(config.window),
+            config,
+            previous_mean: None,
+            samples: 0,
+        }
+    }
+
+    /// Add the duration of one tick. Returns true once latency has settled.
+    pub fn push(&mut self, duration: u64) -> bool {
+        self.samples += 1;
+        self.window.push(duration);
+        if self.window.len() < self.config.window.max(1) {
+            return false;
+        }
+
+        let len = self.window.len() as u64;
+        let mean = self.window.iter().sum::<u64>() / len;
+        let deviation = self.window.iter().map(|&d| d.abs_diff(mean)).sum::<u64>() / len;
+        let tolerance = mean * self.config.tolerance_percent / 100;
+        let steady = deviation <= tolerance && self.previous_mean.is_some_and(|previous| previous.abs_diff(mean) <= tolerance);
+
+        self.previous_mean = Some(mean);
+        self.window.clear();
+        steady
+    }
+
+    /// Mean of the last full window, 0 before the first
+    pub fn mean(&self) -> u64 {
+        self.previous_mean.unwrap_or(0)
+    }
+
+    /// Durations pushed so far
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+}
+
+/// Pre-fault the grid of `state`, then call `tick` until its latency settles or
+/// `config.max_ticks` ticks have run. `tick` gets the state and the warm-up tick number
+/// and should run one tick the way the measured code will.
+pub fn warmup<F: FnMut(&mut GameState, usize)>(state: &mut GameState, config: &WarmupConfig, mut tick: F) -> WarmupReport {
+    state.grid.prefault();
+
+    let mut detector = SteadyState::new(config.clone());
+    let mut steady = false;
+    while !steady && detector.samples() < config.max_ticks {
+        let start = timestamp();
+        tick(state, detector.samples());
+        steady = detector.push(timestamp() - start);
+    }
+
+    WarmupReport {
+        ticks: detector.samples(),
+        steady,
+        mean: detector.mean(),
+    }
+}
@@ -0,0 +1,207 @@
+use crate::game::apple::AppleScaling;
+use crate::game::config::GameConfig;
+use crate::runtime::balance::{BalanceConfig, PopulationBalancer};
+use crate::game::engine::GameState;
+use crate::game::codec::GridCodec;
+use crate::game::snapshot::{load_snapshot, save_snapshot_with};
+use crate::game::types::Input;
+use crate::input::replay::Replay;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const CHECKPOINT_PREFIX: &str = "checkpoint-";
+const CHECKPOINT_EXTENSION: &str = ".snap";
+const INPUT_LOG_PREFIX: &str = "inputs-";
+const INPUT_LOG_EXTENSION: &str = ".replay";
+
+#[derive(Debug, Clone)]
+pub struct WorldConfig {
+    /// Directory holding checkpoints and input logs
+    pub dir: PathBuf,
+    /// Ticks between checkpoints
+    pub checkpoint_interval: u64,
+    /// Checkpoints (and input log segments) kept on disk; older ones are deleted
+    pub keep_checkpoints: usize,
+    /// Dead snakes brought back per tick
+    pub respawns_per_tick: usize,
+    /// Hold the alive count near a target instead; replaces `respawns_per_tick`
+    pub balance: Option<BalanceConfig>,
+    /// Grid compression of checkpoints; any codec reads back regardless of this setting
+    pub snapshot_codec: GridCodec,
+    /// Used only when no checkpoint exists yet
+    pub game: GameConfig,
+    /// Apple scaling of a fresh world (see `GameState::set_apple_scaling`); checkpoints
+    /// carry their own
+    pub apple_scaling: Option<AppleScaling>,
+    /// Leaderboard size of a fresh world (see `GameState::set_leaderboard`); checkpoints
+    /// carry their own board and scores
+    pub leaderboard: Option<usize>,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("world"),
+            checkpoint_interval: 100_000,
+            keep_checkpoints: 3,
+            respawns_per_tick: 16,
+            balance: None,
+            snapshot_codec: GridCodec::default(),
+            game: GameConfig::default(),
+            apple_scaling: None,
+            leaderboard: None,
+        }
+    }
+}
+
+/// A simulation that runs indefinitely, respawning dead snakes and checkpointing to disk.
+///
+/// Every `checkpoint_interval` ticks the full state (including the engine RNG) is written
+/// as `checkpoint-<tick>.snap`, together with `inputs-<start>-<end>.replay` holding the inputs
+/// applied since the previous checkpoint. Reopening the directory resumes from the latest
+/// checkpoint, and feeding the same inputs reproduces the original run exactly.
+pub struct PersistentWorld {
+    config: WorldConfig,
+    state: GameState,
+    // Inputs applied since the last checkpoint
+    input_log: Replay,
+    log_start: u64,
+    balancer: Option<PopulationBalancer>,
+}
+
+impl PersistentWorld {
+    /// Resume from the latest checkpoint in `config.dir`, or start a fresh random world
+    /// (checkpointed immediately at tick 0) if there is none.
+    pub fn open(config: WorldConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        match latest_checkpoint(&config.dir)? {
+            Some(path) => {
+                let (_, state) = load_snapshot(path)?;
+                Ok(Self::from_state(config, state))
+            }
+            None => {
+                let mut state = GameState::random_with_config(config.game);
+                state.set_apple_scaling(config.apple_scaling);
+                state.set_leaderboard(config.leaderboard);
+                let mut world = Self::from_state(config, state);
+                world.checkpoint()?;
+                Ok(world)
+            }
+        }
+    }
+
+    /// Carry on with `state`, e.g. one handed off by another process (see
+    /// [`crate::ipc::handoff`]). Checkpoints follow from its tick; the previous owner is
+    /// expected to have checkpointed up to it.
+    pub fn resume(config: WorldConfig, state: GameState) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        Ok(Self::from_state(config, state))
+    }
+
+    fn from_state(config: WorldConfig, state: GameState) -> Self {
+        Self {
+            balancer: config.balance.map(PopulationBalancer::new),
+            config,
+            log_start: state.now(),
+            state,
+            input_log: Replay::new(),
+        }
+    }
+
+    pub fn config(&self) -> &WorldConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// The population balancer, when `config.balance` is set
+    pub fn balancer(&self) -> Option<&PopulationBalancer> {
+        self.balancer.as_ref()
+    }
+
+    /// Number of ticks simulated since the world was created (the state's clock)
+    pub fn tick(&self) -> u64 {
+        self.state.now()
+    }
+
+    /// Advance one tick, respawn dead snakes and checkpoint when due
+    pub fn step(&mut self, inputs: &[Input]) -> io::Result<()> {
+        self.input_log.record(self.tick(), inputs);
+        self.state.tick(inputs);
+        match &mut self.balancer {
+            Some(balancer) => {
+                balancer.balance(&mut self.state);
+            }
+            None => {
+                self.state.respawn_dead(self.config.respawns_per_tick);
+            }
+        }
+
+        if self.config.checkpoint_interval > 0 && self.tick().is_multiple_of(self.config.checkpoint_interval) {
+            self.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    /// Write a checkpoint of the current tick and the input log segment leading to it,
+    /// then delete segments beyond `keep_checkpoints`.
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        if self.tick() > self.log_start {
+            let log_path = self.config.dir.join(format!(
+                "{}{:020}-{:020}{}",
+                INPUT_LOG_PREFIX, self.log_start, self.tick(), INPUT_LOG_EXTENSION
+            ));
+            self.input_log.save(log_path)?;
+        }
+
+        // Write then rename, so a crash mid-write never leaves a truncated latest checkpoint
+        let path = self.config.dir.join(format!("{}{:020}{}", CHECKPOINT_PREFIX, self.tick(), CHECKPOINT_EXTENSION));
+        let partial = path.with_extension("partial");
+        save_snapshot_with(&self.state, self.tick(), self.config.snapshot_codec, &partial)?;
+        fs::rename(&partial, &path)?;
+
+        log::debug!("checkpoint written at tick {}", self.tick());
+        self.input_log = Replay::new();
+        self.log_start = self.tick();
+        self.rotate()
+    }
+
+    fn rotate(&self) -> io::Result<()> {
+        let keep = self.config.keep_checkpoints.max(1);
+        for (prefix, extension) in [
+            (CHECKPOINT_PREFIX, CHECKPOINT_EXTENSION),
+            (INPUT_LOG_PREFIX, INPUT_LOG_EXTENSION),
+        ] {
+            let files = list_files(&self.config.dir, prefix, extension)?;
+            for path in files.iter().take(files.len().saturating_sub(keep)) {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Most recent checkpoint in `dir`, if any
+pub fn latest_checkpoint(dir: &Path) -> io::Result<Option<PathBuf>> {
+    Ok(list_files(dir, CHECKPOINT_PREFIX, CHECKPOINT_EXTENSION)?.pop())
+}
+
+// Matching files sorted oldest first (tick numbers are zero padded)
+fn list_files(dir: &Path, prefix: &str, extension: &str) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let matches = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(prefix) && name.ends_with(extension));
+        if matches {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,115 @@
+//! Browser bindings (feature `wasm`, target wasm32) for the demo page in `web/`.
+//!
+//! ```text
+//! wasm-pack build --target web --out-dir web/pkg -- --features wasm
+//! ```
+
+use crate::game::config::GameConfig;
+use crate::game::engine::GameState;
+use crate::game::grid::{GRID_HEIGHT, GRID_WIDTH};
+use crate::game::snakemeta::SnakeMeta;
+use crate::game::types::{Direction, Input};
+use wasm_bindgen::prelude::*;
+
+/// A game driven from JavaScript. Directions use the replay encoding:
+/// 0 up, 1 down, 2 left, 3 right. Cells: 0 empty, 1 snake, 2 apple, 3 split power-up.
+#[wasm_bindgen]
+pub struct Simulator {
+    state: GameState,
+    inputs: Vec<Input>,
+}
+
+#[wasm_bindgen]
+impl Simulator {
+    /// Seeded random placement, so a seed always produces the same world. Throws if
+    /// `snake_capacity` is more than the engine can tick.
+    #[wasm_bindgen(constructor)]
+    pub fn new(snake_capacity: u32, apple_capacity: u32, seed: u32) -> Result<Simulator, JsError> {
+        let config = GameConfig {
+            snake_capacity: snake_capacity as usize,
+            apple_capacity: apple_capacity as usize,
+            rng_seed: seed as u64,
+            ..GameConfig::default()
+        };
+        config.validate()?;
+        Ok(Simulator {
+            state: GameState::seeded(config),
+            inputs: Vec::new(),
+        })
+    }
+
+    /// Queue an input for the next tick. Returns false for an unknown snake or direction.
+    pub fn push_input(&mut self, snake_id: u32, direction: u8) -> bool {
+        match Direction::from_u8(direction) {
+            Some(direction) if (snake_id as usize) < self.state.snakes.len() => {
+                self.inputs.push(Input { snake_id, direction });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.state.tick(&self.inputs);
+        self.inputs.clear();
+    }
+
+    /// Ticks so far (a JS number; exact up to 2^53)
+    #[wasm_bindgen(getter)]
+    pub fn tick_count(&self) -> f64 {
+        self.state.now() as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn snake_count(&self) -> u32 {
+        self.state.snakes.len() as u32
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn alive(&self) -> u32 {
+        self.state.population.alive
+    }
+
+    /// `[x, y]` of the snake's head, or undefined for an unknown id
+    pub fn snake_head(&self, snake_id: u32) -> Option<Vec<u16>> {
+        let head = self.state.snakes.get(snake_id as usize)?.head()?;
+        Some(vec![head.x, head.y])
+    }
+
+    /// Give a snake its palette color, name and team; see [`crate::game::snakemeta`]
+    pub fn set_snake_meta(&mut self, snake_id: u32, color: u8, name: &str, team: u16) {
+        self.state.set_snake_meta(snake_id, Some(SnakeMeta::new(color, name, team)));
+    }
+
+    /// Palette index below 16 to draw the snake in, from its id unless set
+    pub fn snake_color(&self, snake_id: u32) -> u8 {
+        self.state.snake_meta(snake_id).color
+    }
+
+    /// Empty unless set
+    pub fn snake_name(&self, snake_id: u32) -> String {
+        self.state.snake_meta(snake_id).name
+    }
+
+    /// 0 for no team
+    pub fn snake_team(&self, snake_id: u32) -> u16 {
+        self.state.snake_meta(snake_id).team
+    }
+
+    /// Row-major cell codes of a `width` x `height` window at `(x, y)`, wrapping at the edges
+    pub fn view(&self, x: u32, y: u32, width: u32, height: u32) -> Vec<u8> {
+        let mut cells = Vec::new();
+        self.state
+            .grid
+            .write_view(x as usize, y as usize, width as usize, height as usize, &mut cells);
+        cells
+    }
+
+    pub fn grid_width() -> u32 {
+        GRID_WIDTH as u32
+    }
+
+    pub fn grid_height() -> u32 {
+        GRID_HEIGHT as u32
+    }
+}
@@ -0,0 +1,104 @@
+//! The threaded pipeline of `main.rs` end to end: a load generator thread feeding the
+//! SPSC queue, and a game thread draining it alongside bots, both under a `Supervisor`.
+
+use high_frequency_snake::game::config::GameConfig;
+use high_frequency_snake::game::engine::GameState;
+use high_frequency_snake::game::fixedpoint::Fixed;
+use high_frequency_snake::game::grid::{Cell, GRID_HEIGHT};
+use high_frequency_snake::game::metrics::PopulationMetrics;
+use high_frequency_snake::game::types::Input;
+use high_frequency_snake::input::bot::{Difficulty, DifficultyMix, TieredBots};
+use high_frequency_snake::input::load::{LoadConfig, LoadGenerator, LoadStats};
+use high_frequency_snake::input::source::{InputSource, QueueSource};
+use high_frequency_snake::ipc::spsc::Spsc;
+use high_frequency_snake::runtime::supervisor::Supervisor;
+use std::sync::mpsc;
+
+const QUEUE_CAPACITY: usize = 1024;
+const TICKS: u64 = 2000;
+// Snakes 0..LOADED take inputs from the load generator, the rest are bots
+const LOADED: u32 = 100;
+const SNAKES: usize = 250;
+
+/// What the game thread saw, sent back once it is done
+struct GameReport {
+    drained: u64,
+    initial_longest: usize,
+    longest: usize,
+    deaths: u64,
+}
+
+/// Alive counters agree with the snakes, and every live segment is on the grid
+fn check_snakes(state: &GameState, tick: u64) {
+    let scanned = PopulationMetrics::from_snakes(&state.snakes);
+    assert_eq!(state.population.alive, scanned.alive, "alive count drifted by tick {}", tick);
+    assert_eq!(state.population.total_body_cells, scanned.total_body_cells, "body cells drifted by tick {}", tick);
+    assert_eq!(state.population.length_histogram, scanned.length_histogram, "length histogram drifted by tick {}", tick);
+    for snake in state.snakes.iter().filter(|snake| snake.is_alive()) {
+        for segment in snake.segments() {
+            assert_eq!(state.grid.get_cell(&segment), Cell::Snake, "snake {} off the grid at tick {}", snake.id(), tick);
+        }
+    }
+}
+
+fn longest(state: &GameState) -> usize {
+    state.snakes.iter().filter(|snake| snake.is_alive()).map(|snake| snake.body().len()).max().unwrap_or(0)
+}
+
+#[test]
+fn test_pipeline_runs_bots_and_load_to_a_clean_shutdown() {
+    let (mut producer_queue, consumer_queue) = Spsc::<Input, QUEUE_CAPACITY>::new().split();
+    let (load_sender, load_stats) = mpsc::channel::<(LoadStats, usize)>();
+    let (game_sender, game_report) = mpsc::channel::<GameReport>();
+    let mut supervisor = Supervisor::new();
+
+    supervisor
+        .spawn("input", move |shutdown| {
+            let config = LoadConfig { target_rate: 200_000.0, num_snakes: LOADED, ..LoadConfig::default() };
+            let stats = LoadGenerator::new(config).run(shutdown.flag(), None, |_, input| producer_queue.produce(input));
+            // The game thread drained its last batch before requesting shutdown
+            load_sender.send((stats, producer_queue.len())).unwrap();
+        })
+        .unwrap();
+
+    supervisor
+        .spawn("game", move |shutdown| {
+            // Dense apples so greedy bots find some within their sight
+            let mut state = GameState::seeded(GameConfig { snake_capacity: SNAKES, apple_capacity: 500_000, ..GameConfig::default() });
+            let mix = DifficultyMix { random: 1, greedy: 2, lookahead: 2 };
+            let mut bots = TieredBots::new(LOADED..SNAKES as u32, mix, Fixed::from_ratio(1, 10), 7);
+            assert!(bots.tiers().iter().any(|(_, tier)| *tier == Difficulty::Greedy));
+            let mut source = QueueSource::new(consumer_queue);
+            let mut inputs = Vec::with_capacity(QUEUE_CAPACITY);
+            let mut report = GameReport { drained: 0, initial_longest: longest(&state), longest: 0, deaths: 0 };
+
+            for tick in 0..TICKS {
+                inputs.clear();
+                source.next_batch(tick, &mut inputs);
+                report.drained += inputs.len() as u64;
+                bots.decide(state.view(), &mut inputs);
+                state.tick(&inputs);
+                state.respawn_dead(8);
+                check_snakes(&state, tick);
+                report.longest = report.longest.max(longest(&state));
+            }
+            report.deaths = state.population.total_deaths;
+
+            let apples = (0..GRID_HEIGHT).map(|y| state.grid.row(y).iter().filter(|cell| **cell == Cell::Apple).count() as u64).sum::<u64>();
+            assert_eq!(apples, state.num_apples, "apple count drifted");
+            game_sender.send(report).unwrap();
+            shutdown.request();
+        })
+        .unwrap();
+
+    supervisor.join().expect("no worker panicked");
+    let (load, queued) = load_stats.recv().expect("the load generator stopped on shutdown");
+    let game = game_report.recv().expect("the game thread finished its ticks");
+
+    // Every input the queue accepted was either applied or is still queued
+    assert!(game.drained > 0, "no inputs made it through the queue");
+    assert_eq!(load.sent, game.drained + queued as u64);
+    // Bots ate: some snake outgrew every snake of the starting state
+    assert!(game.longest > game.initial_longest, "no snake grew past length {}", game.initial_longest);
+    assert!(game.deaths > 0, "nothing ever collided");
+}
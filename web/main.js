@@ -0,0 +1,71 @@
+// Build the package first:
+//   wasm-pack build --target web --out-dir web/pkg -- --features wasm
+// then serve this directory, e.g. `python3 -m http.server -d web`.
+import init, { Simulator } from "./pkg/high_frequency_snake.js";
+
+const VIEW_WIDTH = 160;
+const VIEW_HEIGHT = 120;
+const SCALE = 4;
+const SNAKES = 2048;
+const APPLES = 4096;
+const SEED = 1;
+const TICKS_PER_FRAME = 4;
+// 0 empty, 1 snake, 2 apple, 3 split power-up
+const COLORS = [[17, 17, 17], [80, 220, 100], [230, 60, 60], [80, 140, 255]];
+const KEYS = {
+  ArrowUp: 0, ArrowDown: 1, ArrowLeft: 2, ArrowRight: 3,
+  w: 0, s: 1, a: 2, d: 3, W: 0, S: 1, A: 2, D: 3,
+};
+
+await init();
+const sim = new Simulator(SNAKES, APPLES, SEED);
+// The player's snake, chosen with ?snake=<id>
+const requested = Number.parseInt(new URLSearchParams(location.search).get("snake") ?? "0", 10);
+const PLAYER = requested >= 0 && requested < sim.snake_count ? requested : 0;
+
+const canvas = document.getElementById("board");
+canvas.width = VIEW_WIDTH;
+canvas.height = VIEW_HEIGHT;
+canvas.style.width = `${VIEW_WIDTH * SCALE}px`;
+canvas.style.height = `${VIEW_HEIGHT * SCALE}px`;
+const context = canvas.getContext("2d");
+const image = context.createImageData(VIEW_WIDTH, VIEW_HEIGHT);
+const stats = document.getElementById("stats");
+
+addEventListener("keydown", (event) => {
+  if (event.key in KEYS) {
+    sim.push_input(PLAYER, KEYS[event.key]);
+    event.preventDefault();
+  }
+});
+
+// Keep the window centred on the player's snake while it lives
+let [centerX, centerY] = [0, 0];
+
+function frame() {
+  for (let i = 0; i < TICKS_PER_FRAME; i++) {
+    for (let j = 0; j < 32; j++) {
+      // Bots steer every snake but the player's
+      const id = (PLAYER + 1 + Math.floor(Math.random() * (sim.snake_count - 1))) % sim.snake_count;
+      sim.push_input(id, Math.floor(Math.random() * 4));
+    }
+    sim.tick();
+  }
+
+  const head = sim.snake_head(PLAYER);
+  if (head) {
+    [centerX, centerY] = head;
+  }
+  const x = (centerX - VIEW_WIDTH / 2 + Simulator.grid_width()) % Simulator.grid_width();
+  const y = (centerY - VIEW_HEIGHT / 2 + Simulator.grid_height()) % Simulator.grid_height();
+  const cells = sim.view(x, y, VIEW_WIDTH, VIEW_HEIGHT);
+  for (let i = 0; i < cells.length; i++) {
+    const [r, g, b] = COLORS[cells[i]];
+    image.data.set([r, g, b, 255], i * 4);
+  }
+  context.putImageData(image, 0, 0);
+  stats.textContent = `tick ${sim.tick_count}  alive ${sim.alive}/${sim.snake_count}  you: snake ${PLAYER}${head ? "" : " (dead)"}`;
+  requestAnimationFrame(frame);
+}
+
+requestAnimationFrame(frame);